@@ -205,12 +205,10 @@ fn create_user_tables(conn: &Connection) -> Result<()> {
         [],
     )?;
 
-    // Tabela de metadados do banco
+    // Versão do banco, na mesma tabela usada pelo app (src-tauri/src/db.rs)
     conn.execute(
-        "CREATE TABLE IF NOT EXISTS database_metadata (
-            key TEXT PRIMARY KEY,
-            value TEXT NOT NULL,
-            updated_at TEXT NOT NULL
+        "CREATE TABLE IF NOT EXISTS database_version (
+            version INTEGER PRIMARY KEY
         )",
         [],
     )?;
@@ -243,10 +241,9 @@ fn create_indexes(conn: &Connection) -> Result<()> {
 }
 
 fn set_database_version(conn: &Connection, version: i32) -> Result<()> {
-    let now = chrono::Utc::now().to_rfc3339();
     conn.execute(
-        "INSERT OR REPLACE INTO database_metadata (key, value, updated_at) VALUES (?1, ?2, ?3)",
-        params!["version", version.to_string(), now],
+        "INSERT OR REPLACE INTO database_version (version) VALUES (?1)",
+        params![version],
     )?;
     Ok(())
 }
@@ -306,7 +303,7 @@ mod tests {
         let tables = vec![
             "courses", "modules", "videos", "video_progress",
             "user_notes", "video_bookmarks", "user_settings", 
-            "activity_log", "database_metadata"
+            "activity_log", "database_version"
         ];
         
         for table in tables {
@@ -0,0 +1,125 @@
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use anyhow::{Result, anyhow};
+use chrono::{DateTime, Local, NaiveDate, TimeZone, Utc};
+use serde::Serialize;
+use crate::db::{ActivityDetails, ActivityLog, Database};
+use crate::error::AppError;
+
+// Atividade como gravada no arquivo, com `details` já interpretado
+#[derive(Debug, Serialize)]
+struct ExportedActivity {
+    id: String,
+    activity_type: String,
+    entity_id: String,
+    entity_type: String,
+    details: Option<ActivityDetails>,
+    created_at: DateTime<Utc>,
+}
+
+impl From<ActivityLog> for ExportedActivity {
+    fn from(activity: ActivityLog) -> Self {
+        Self {
+            details: activity.parsed_details(),
+            id: activity.id,
+            activity_type: activity.activity_type,
+            entity_id: activity.entity_id,
+            entity_type: activity.entity_type,
+            created_at: activity.created_at,
+        }
+    }
+}
+
+// Grava em `out_path` um array JSON com as atividades de `from` a `to`
+// inclusive (dias no fuso local), em ordem cronológica. As linhas são
+// escritas à medida que são lidas. Retorna quantas foram gravadas
+pub fn export_activity_log(db: &Database, from: NaiveDate, to: NaiveDate, out_path: &Path) -> Result<usize> {
+    export_activity_log_in(db, from, to, out_path, &Local)
+}
+
+fn export_activity_log_in<Tz: TimeZone>(db: &Database, from: NaiveDate, to: NaiveDate, out_path: &Path, tz: &Tz) -> Result<usize> {
+    if from > to {
+        return Err(AppError::InvalidInput(format!("Período inválido: {} é depois de {}", from, to)).into());
+    }
+    let since = start_of_day(from, tz);
+    let until = start_of_day(to.succ_opt().unwrap_or(to), tz);
+
+    let file = std::fs::File::create(out_path)
+        .map_err(|e| anyhow!("Erro ao criar {}: {}", out_path.display(), e))?;
+    let mut out = BufWriter::new(file);
+    let mut count = 0;
+
+    out.write_all(b"[")?;
+    db.for_each_activity_between(since, until, |activity| -> Result<()> {
+        out.write_all(if count == 0 { b"\n" } else { b",\n" })?;
+        serde_json::to_writer(&mut out, &ExportedActivity::from(activity))?;
+        count += 1;
+        Ok(())
+    })?;
+    out.write_all(b"\n]\n")?;
+    out.flush()
+        .map_err(|e| anyhow!("Erro ao gravar {}: {}", out_path.display(), e))?;
+
+    log::info!("📤 {} atividades de {} a {} exportadas para {}", count, from, to, out_path.display());
+    Ok(count)
+}
+
+// Meia-noite local do dia, em UTC. Num dia em que o horário de verão pula a
+// meia-noite, vale o primeiro instante existente
+fn start_of_day<Tz: TimeZone>(day: NaiveDate, tz: &Tz) -> DateTime<Utc> {
+    let midnight = day.and_hms_opt(0, 0, 0).unwrap();
+    tz.from_local_datetime(&midnight)
+        .earliest()
+        .or_else(|| tz.from_local_datetime(&(midnight + chrono::Duration::hours(1))).earliest())
+        .map(|start| start.with_timezone(&Utc))
+        .unwrap_or_else(|| midnight.and_utc())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_export_writes_only_activities_in_range() {
+        let temp_dir = TempDir::new().unwrap();
+        let db = Database::new(&temp_dir.path().join("test.db")).unwrap();
+
+        // UTC-3: 02:00 UTC do dia 11 ainda é dia 10 no horário local
+        let tz = chrono::FixedOffset::west_opt(3 * 3600).unwrap();
+        let activities = [
+            ("antes", "2024-03-09T20:00:00+00:00", Some(r#"{"percent":100.0}"#)),
+            ("depois", "2024-03-13T12:00:00+00:00", None),
+            ("segunda", "2024-03-11T02:00:00+00:00", Some("texto livre")),
+            ("primeira", "2024-03-10T03:00:00+00:00", Some(r#"{"percent":80.0}"#)),
+            ("terceira", "2024-03-12T23:00:00+00:00", None),
+        ];
+        for (id, created_at, details) in activities {
+            db.log_activity(&ActivityLog {
+                id: id.to_string(),
+                activity_type: "video_completed".to_string(),
+                entity_id: "video-1".to_string(),
+                entity_type: "video".to_string(),
+                details: details.map(str::to_string),
+                created_at: DateTime::parse_from_rfc3339(created_at).unwrap().with_timezone(&Utc),
+            }).unwrap();
+        }
+
+        let out_path = temp_dir.path().join("atividades.json");
+        let day = |d: u32| NaiveDate::from_ymd_opt(2024, 3, d).unwrap();
+        assert_eq!(export_activity_log_in(&db, day(10), day(12), &out_path, &tz).unwrap(), 3);
+
+        let exported: Vec<serde_json::Value> = serde_json::from_str(&std::fs::read_to_string(&out_path).unwrap()).unwrap();
+        let ids: Vec<&str> = exported.iter().map(|a| a["id"].as_str().unwrap()).collect();
+        assert_eq!(ids, vec!["primeira", "segunda", "terceira"]);
+        assert_eq!(exported[0]["details"], serde_json::json!({ "video_completed": { "percent": 80.0 } }));
+        assert_eq!(exported[1]["details"], serde_json::json!({ "raw": "texto livre" }));
+        assert!(exported[2]["details"].is_null());
+
+        // Período vazio ainda gera um array válido
+        assert_eq!(export_activity_log_in(&db, day(1), day(2), &out_path, &tz).unwrap(), 0);
+        let exported: Vec<serde_json::Value> = serde_json::from_str(&std::fs::read_to_string(&out_path).unwrap()).unwrap();
+        assert!(exported.is_empty());
+        assert!(export_activity_log_in(&db, day(12), day(10), &out_path, &tz).is_err());
+    }
+}
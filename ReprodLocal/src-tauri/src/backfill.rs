@@ -0,0 +1,195 @@
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Mutex};
+use anyhow::{Result, anyhow};
+use serde::Serialize;
+use crate::db::Database;
+use crate::error::AppError;
+use crate::media;
+
+// Evento emitido a cada vídeo processado pelo preenchimento de durações
+pub const BACKFILL_PROGRESS_EVENT: &str = "backfill://progress";
+
+// Máximo de processos do ffprobe rodando ao mesmo tempo
+const BACKFILL_WORKERS: usize = 4;
+
+// Lê a duração de um arquivo de vídeo; trocado por um falso nos testes
+pub trait DurationProber: Sync {
+    fn probe(&self, video_path: &Path) -> std::result::Result<f64, AppError>;
+}
+
+pub struct FfprobeProber {
+    pub ffprobe: PathBuf,
+}
+
+impl DurationProber for FfprobeProber {
+    fn probe(&self, video_path: &Path) -> std::result::Result<f64, AppError> {
+        media::probe_duration(&self.ffprobe, video_path)
+    }
+}
+
+#[derive(Debug, Serialize, Clone, Default, PartialEq)]
+pub struct BackfillProgress {
+    pub total: usize,
+    pub processed: usize,
+    pub updated: usize,
+    pub failed: usize,
+    pub cancelled: bool,
+}
+
+// Preenche a duração dos vídeos que ainda não a têm. As leituras rodam em
+// paralelo e cada resultado é gravado assim que chega, segurando o banco só
+// durante a gravação; falhas individuais são contadas e ignoradas
+pub fn backfill_durations<F>(
+    db: &Mutex<Database>,
+    course_id: Option<&str>,
+    prober: &dyn DurationProber,
+    cancel: &AtomicBool,
+    mut on_progress: F,
+) -> Result<BackfillProgress>
+where
+    F: FnMut(&BackfillProgress),
+{
+    let videos = db
+        .lock()
+        .map_err(|e| anyhow!("Erro ao acessar banco: {}", e))?
+        .get_videos_without_duration(course_id)?;
+
+    let mut progress = BackfillProgress { total: videos.len(), ..Default::default() };
+    if videos.is_empty() {
+        return Ok(progress);
+    }
+    log::info!("⏱️ Preenchendo duração de {} vídeos", videos.len());
+
+    let queue = Mutex::new(videos.into_iter());
+    let (tx, rx) = mpsc::channel();
+
+    std::thread::scope(|scope| {
+        for _ in 0..BACKFILL_WORKERS.min(progress.total) {
+            let tx = tx.clone();
+            let queue = &queue;
+            scope.spawn(move || loop {
+                if cancel.load(Ordering::Relaxed) {
+                    break;
+                }
+                let Some(video) = queue.lock().ok().and_then(|mut q| q.next()) else {
+                    break;
+                };
+                let result = prober.probe(Path::new(&video.path));
+                if tx.send((video, result)).is_err() {
+                    break;
+                }
+            });
+        }
+        drop(tx);
+
+        for (video, result) in rx {
+            progress.processed += 1;
+            match result {
+                Ok(duration) => {
+                    let saved = db
+                        .lock()
+                        .map_err(|e| e.to_string())
+                        .and_then(|db| db.set_video_duration(&video.id, duration).map_err(|e| e.to_string()));
+                    match saved {
+                        Ok(()) => progress.updated += 1,
+                        Err(e) => {
+                            log::warn!("⚠️ Erro ao gravar duração de {}: {}", video.path, e);
+                            progress.failed += 1;
+                        }
+                    }
+                }
+                Err(e) => {
+                    log::warn!("⚠️ Não foi possível ler a duração de {}: {}", video.path, e);
+                    progress.failed += 1;
+                }
+            }
+            on_progress(&progress);
+        }
+    });
+
+    progress.cancelled = cancel.load(Ordering::Relaxed) && progress.processed < progress.total;
+    log::info!(
+        "⏱️ Preenchimento de durações concluído: {} atualizados, {} falhas, {} de {} processados",
+        progress.updated, progress.failed, progress.processed, progress.total
+    );
+    Ok(progress)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{Course, Module, Video};
+    use chrono::Utc;
+
+    // Prober falso: duração vem do nome do arquivo e "quebrado" falha
+    struct FakeProber;
+
+    impl DurationProber for FakeProber {
+        fn probe(&self, video_path: &Path) -> std::result::Result<f64, AppError> {
+            let stem = video_path.file_stem().unwrap().to_string_lossy();
+            stem.parse::<f64>()
+                .map_err(|_| AppError::ToolFailed(format!("ffprobe não conseguiu ler {}", video_path.display())))
+        }
+    }
+
+    fn seeded_db(videos: &[(&str, Option<f64>)]) -> Mutex<Database> {
+        let db = Database::new(Path::new(":memory:")).unwrap();
+        db.insert_course(&Course {
+            id: "course-1".to_string(),
+            name: "Curso".to_string(),
+            path: "/cursos/curso".to_string(),
+            created_at: Utc::now(),
+            last_accessed: None,
+            cover_path: None,
+            display_name: None,
+            description: None,
+        }).unwrap();
+        db.insert_module(&Module {
+            id: "module-1".to_string(),
+            course_id: "course-1".to_string(),
+            name: "Módulo".to_string(),
+            path: "/cursos/curso".to_string(),
+            order_index: 0,
+        }).unwrap();
+        for (index, (name, duration)) in videos.iter().enumerate() {
+            db.insert_video(&Video {
+                id: format!("video-{}", index),
+                module_id: "module-1".to_string(),
+                course_id: "course-1".to_string(),
+                name: name.to_string(),
+                path: format!("/cursos/curso/{}.mp4", name),
+                duration: *duration,
+                order_index: index as i32,
+            }).unwrap();
+        }
+        Mutex::new(db)
+    }
+
+    #[test]
+    fn test_backfill_fills_missing_durations_only() {
+        let db = seeded_db(&[("120", None), ("45.5", None), ("quebrado", None), ("300", Some(999.0))]);
+        let cancel = AtomicBool::new(false);
+        let mut events = Vec::new();
+
+        let progress = backfill_durations(&db, Some("course-1"), &FakeProber, &cancel, |p| events.push(p.clone())).unwrap();
+        assert_eq!(progress, BackfillProgress { total: 3, processed: 3, updated: 2, failed: 1, cancelled: false });
+        assert_eq!(events.len(), 3);
+        assert_eq!(events.last().unwrap(), &progress);
+
+        let db = db.lock().unwrap();
+        let durations: Vec<Option<f64>> = db.get_module_videos("module-1").unwrap().iter().map(|v| v.duration).collect();
+        assert_eq!(durations, vec![Some(120.0), Some(45.5), None, Some(999.0)]);
+    }
+
+    #[test]
+    fn test_backfill_stops_when_cancelled() {
+        let db = seeded_db(&[("10", None), ("20", None)]);
+        let cancel = AtomicBool::new(true);
+
+        let progress = backfill_durations(&db, None, &FakeProber, &cancel, |_| {}).unwrap();
+        assert_eq!(progress.processed, 0);
+        assert!(progress.cancelled);
+        assert_eq!(db.lock().unwrap().get_videos_without_duration(None).unwrap().len(), 2);
+    }
+}
@@ -0,0 +1,186 @@
+use std::path::{Path, PathBuf};
+use anyhow::{Result, anyhow};
+use chrono::{DateTime, Duration, NaiveDateTime, Utc};
+use crate::db::Database;
+
+// Backups ficam em `backups/` ao lado do banco, como database-AAAAMMDD-HHMMSS.db.
+// Outro backup no mesmo segundo ganha um sufixo (database-AAAAMMDD-HHMMSS-2.db),
+// já que `VACUUM INTO` não sobrescreve arquivos
+const BACKUP_DIR_NAME: &str = "backups";
+const BACKUP_PREFIX: &str = "database-";
+const BACKUP_TIMESTAMP_FORMAT: &str = "%Y%m%d-%H%M%S";
+
+const DEFAULT_INTERVAL_HOURS: i64 = 24;
+const DEFAULT_KEEP_COUNT: usize = 7;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct BackupPolicy {
+    pub interval: Duration,
+    pub keep_count: usize,
+}
+
+impl BackupPolicy {
+    // Configurações `backup_interval_hours` e `backup_keep_count`; valores
+    // inválidos voltam ao padrão
+    pub fn from_settings(db: &Database) -> Result<Self> {
+        let value = |key: &str| -> Result<Option<String>> {
+            Ok(db.get_user_setting(key)?.map(|setting| setting.setting_value.trim().to_string()))
+        };
+
+        let hours = value("backup_interval_hours")?
+            .and_then(|v| v.parse::<i64>().ok())
+            .filter(|hours| *hours >= 0)
+            .unwrap_or(DEFAULT_INTERVAL_HOURS);
+        let keep_count = value("backup_keep_count")?
+            .and_then(|v| v.parse::<usize>().ok())
+            .filter(|count| *count >= 1)
+            .unwrap_or(DEFAULT_KEEP_COUNT);
+
+        Ok(Self { interval: Duration::hours(hours), keep_count })
+    }
+}
+
+// Pasta de backups do banco; None para bancos em memória
+pub fn backup_dir(db: &Database) -> Option<PathBuf> {
+    let db_path = PathBuf::from(db.path()?);
+    Some(db_path.parent()?.join(BACKUP_DIR_NAME))
+}
+
+// Faz um backup se o último for mais antigo que o intervalo e remove os que
+// passam de `keep_count`. Retorna o caminho do backup criado
+pub fn backup_if_due(db: &Database, dir: &Path, policy: &BackupPolicy, now: DateTime<Utc>) -> Result<Option<PathBuf>> {
+    if let Some((last, _)) = list_backups(dir)?.last() {
+        if now - *last < policy.interval {
+            log::debug!("💾 Último backup de {}, ainda dentro do intervalo", last);
+            return Ok(None);
+        }
+    }
+
+    let path = create_backup(db, dir, now)?;
+    prune_backups(dir, policy.keep_count)?;
+    Ok(Some(path))
+}
+
+pub fn create_backup(db: &Database, dir: &Path, now: DateTime<Utc>) -> Result<PathBuf> {
+    std::fs::create_dir_all(dir)
+        .map_err(|e| anyhow!("Erro ao criar pasta de backups {}: {}", dir.display(), e))?;
+    let timestamp = now.format(BACKUP_TIMESTAMP_FORMAT).to_string();
+    let mut path = dir.join(format!("{}{}.db", BACKUP_PREFIX, timestamp));
+    let mut counter = 1;
+    while path.exists() {
+        counter += 1;
+        path = dir.join(format!("{}{}-{}.db", BACKUP_PREFIX, timestamp, counter));
+    }
+
+    db.backup_to(&path)?;
+    log::info!("💾 Backup do banco criado: {}", path.display());
+    Ok(path)
+}
+
+// Remove os backups mais antigos além de `keep_count`; retorna quantos saíram
+pub fn prune_backups(dir: &Path, keep_count: usize) -> Result<usize> {
+    let backups = list_backups(dir)?;
+    let excess = backups.len().saturating_sub(keep_count);
+
+    for (_, path) in &backups[..excess] {
+        std::fs::remove_file(path)
+            .map_err(|e| anyhow!("Erro ao remover backup antigo {}: {}", path.display(), e))?;
+        log::info!("🗑️ Backup antigo removido: {}", path.display());
+    }
+    Ok(excess)
+}
+
+// Backups da pasta, do mais antigo ao mais recente; outros arquivos são ignorados
+fn list_backups(dir: &Path) -> Result<Vec<(DateTime<Utc>, PathBuf)>> {
+    if !dir.is_dir() {
+        return Ok(Vec::new());
+    }
+
+    let mut backups = Vec::new();
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        let Some(stem) = path.file_stem().and_then(|s| s.to_str()) else {
+            continue;
+        };
+        let parsed = stem
+            .strip_prefix(BACKUP_PREFIX)
+            .filter(|_| path.extension().is_some_and(|ext| ext == "db"))
+            .and_then(parse_backup_stem);
+        if let Some((created_at, counter)) = parsed {
+            backups.push((created_at.and_utc(), counter, path));
+        }
+    }
+    backups.sort();
+    Ok(backups.into_iter().map(|(created_at, _, path)| (created_at, path)).collect())
+}
+
+// "AAAAMMDD-HHMMSS" ou "AAAAMMDD-HHMMSS-N"; o primeiro do segundo conta como 1
+fn parse_backup_stem(stem: &str) -> Option<(NaiveDateTime, u32)> {
+    if let Ok(created_at) = NaiveDateTime::parse_from_str(stem, BACKUP_TIMESTAMP_FORMAT) {
+        return Some((created_at, 1));
+    }
+    let (timestamp, counter) = stem.rsplit_once('-')?;
+    Some((NaiveDateTime::parse_from_str(timestamp, BACKUP_TIMESTAMP_FORMAT).ok()?, counter.parse().ok()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use tempfile::TempDir;
+
+    fn at(day: u32, hour: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 3, day, hour, 0, 0).unwrap()
+    }
+
+    #[test]
+    fn test_backup_respects_interval() {
+        let temp_dir = TempDir::new().unwrap();
+        let db = Database::new(&temp_dir.path().join("database.db")).unwrap();
+        let dir = backup_dir(&db).unwrap();
+        assert_eq!(dir, temp_dir.path().join("backups"));
+        let policy = BackupPolicy { interval: Duration::hours(24), keep_count: 7 };
+
+        let first = backup_if_due(&db, &dir, &policy, at(1, 8)).unwrap().unwrap();
+        assert!(first.ends_with("database-20240301-080000.db"));
+        assert!(Database::new(&first).is_ok());
+
+        assert!(backup_if_due(&db, &dir, &policy, at(2, 7)).unwrap().is_none());
+        assert!(backup_if_due(&db, &dir, &policy, at(2, 8)).unwrap().is_some());
+        assert_eq!(list_backups(&dir).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_rotation_keeps_newest_backups() {
+        let temp_dir = TempDir::new().unwrap();
+        let db = Database::new(&temp_dir.path().join("database.db")).unwrap();
+        let dir = temp_dir.path().join("backups");
+        let policy = BackupPolicy { interval: Duration::hours(0), keep_count: 3 };
+
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("anotacoes.txt"), "não é backup").unwrap();
+        for day in 1..=5 {
+            backup_if_due(&db, &dir, &policy, at(day, 12)).unwrap().unwrap();
+        }
+
+        let days: Vec<DateTime<Utc>> = list_backups(&dir).unwrap().into_iter().map(|(created_at, _)| created_at).collect();
+        assert_eq!(days, vec![at(3, 12), at(4, 12), at(5, 12)]);
+        assert!(dir.join("anotacoes.txt").exists());
+    }
+
+    #[test]
+    fn test_backups_in_the_same_second_get_a_suffix() {
+        let temp_dir = TempDir::new().unwrap();
+        let db = Database::new(&temp_dir.path().join("database.db")).unwrap();
+        let dir = temp_dir.path().join("backups");
+
+        let paths: Vec<PathBuf> = (0..3).map(|_| create_backup(&db, &dir, at(1, 8)).unwrap()).collect();
+        assert!(paths[0].ends_with("database-20240301-080000.db"));
+        assert!(paths[1].ends_with("database-20240301-080000-2.db"));
+        assert!(paths[2].ends_with("database-20240301-080000-3.db"));
+
+        // O mais antigo continua sendo o primeiro a sair na rotação
+        assert_eq!(prune_backups(&dir, 2).unwrap(), 1);
+        assert!(!paths[0].exists() && paths[1].exists() && paths[2].exists());
+    }
+}
@@ -0,0 +1,159 @@
+use std::collections::HashMap;
+use std::sync::{RwLock, RwLockReadGuard, RwLockWriteGuard};
+use std::time::{Duration, Instant};
+use crate::db::{Course, Module};
+
+// A barra lateral pede as listas várias vezes seguidas; o prazo só limita o
+// quanto uma escrita que não passou por `invalidate` pode ficar escondida
+pub const LIST_CACHE_TTL: Duration = Duration::from_secs(30);
+
+struct Cached<T> {
+    value: T,
+    loaded_at: Instant,
+}
+
+// Cache das listas de cursos e de módulos por curso. Quem altera cursos ou
+// módulos chama `invalidate` ainda com o banco travado, então nenhuma leitura
+// recarrega a lista antiga entre a escrita e a invalidação
+pub struct ListCache {
+    ttl: Duration,
+    courses: RwLock<Option<Cached<Vec<Course>>>>,
+    modules: RwLock<HashMap<String, Cached<Vec<Module>>>>,
+}
+
+impl ListCache {
+    pub fn new(ttl: Duration) -> Self {
+        Self { ttl, courses: RwLock::new(None), modules: RwLock::new(HashMap::new()) }
+    }
+
+    // Lista de cursos do cache ou, se vencida, de `load`
+    pub fn courses<F, E>(&self, load: F) -> Result<Vec<Course>, E>
+    where
+        F: FnOnce() -> Result<Vec<Course>, E>,
+    {
+        if let Some(cached) = read(&self.courses).as_ref().filter(|c| c.loaded_at.elapsed() < self.ttl) {
+            return Ok(cached.value.clone());
+        }
+
+        let courses = load()?;
+        *write(&self.courses) = Some(Cached { value: courses.clone(), loaded_at: Instant::now() });
+        Ok(courses)
+    }
+
+    pub fn course_modules<F, E>(&self, course_id: &str, load: F) -> Result<Vec<Module>, E>
+    where
+        F: FnOnce() -> Result<Vec<Module>, E>,
+    {
+        if let Some(cached) = read(&self.modules).get(course_id).filter(|c| c.loaded_at.elapsed() < self.ttl) {
+            return Ok(cached.value.clone());
+        }
+
+        let modules = load()?;
+        write(&self.modules).insert(
+            course_id.to_string(),
+            Cached { value: modules.clone(), loaded_at: Instant::now() },
+        );
+        Ok(modules)
+    }
+
+    pub fn invalidate(&self) {
+        *write(&self.courses) = None;
+        write(&self.modules).clear();
+    }
+}
+
+impl Default for ListCache {
+    fn default() -> Self {
+        Self::new(LIST_CACHE_TTL)
+    }
+}
+
+// Um pânico com o lock na mão não deixa o cache inconsistente (os valores são
+// trocados inteiros), então o envenenamento é ignorado
+fn read<T>(lock: &RwLock<T>) -> RwLockReadGuard<'_, T> {
+    lock.read().unwrap_or_else(|e| e.into_inner())
+}
+
+fn write<T>(lock: &RwLock<T>) -> RwLockWriteGuard<'_, T> {
+    lock.write().unwrap_or_else(|e| e.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+    use chrono::Utc;
+    use tempfile::TempDir;
+    use crate::db::Database;
+
+    fn seed(db: &Database) {
+        db.insert_course(&Course {
+            id: "course-1".to_string(),
+            name: "Rust".to_string(),
+            path: "/cursos/Rust".to_string(),
+            created_at: Utc::now(),
+            last_accessed: None,
+            cover_path: None,
+            display_name: None,
+            description: None,
+        }).unwrap();
+        db.insert_module(&Module {
+            id: "module-1".to_string(),
+            course_id: "course-1".to_string(),
+            name: "Básico".to_string(),
+            path: "/cursos/Rust/Básico".to_string(),
+            order_index: 0,
+        }).unwrap();
+    }
+
+    #[test]
+    fn test_cached_lists_skip_queries_until_invalidated() {
+        let temp_dir = TempDir::new().unwrap();
+        let db = Database::new(&temp_dir.path().join("test.db")).unwrap();
+        seed(&db);
+        let cache = ListCache::default();
+        let queries = Cell::new(0);
+        let courses = || cache.courses(|| {
+            queries.set(queries.get() + 1);
+            db.get_all_courses()
+        }).unwrap();
+        let modules = || cache.course_modules("course-1", || {
+            queries.set(queries.get() + 1);
+            db.get_course_modules("course-1")
+        }).unwrap();
+
+        assert_eq!(courses()[0].display_name, None);
+        assert_eq!(modules().len(), 1);
+        assert_eq!(queries.get(), 2);
+        courses();
+        modules();
+        assert_eq!(queries.get(), 2);
+
+        db.rename_course("course-1", "Rust Avançado").unwrap();
+        db.insert_module(&Module {
+            id: "module-2".to_string(),
+            course_id: "course-1".to_string(),
+            name: "Traits".to_string(),
+            path: "/cursos/Rust/Traits".to_string(),
+            order_index: 1,
+        }).unwrap();
+        cache.invalidate();
+
+        assert_eq!(courses()[0].display_name.as_deref(), Some("Rust Avançado"));
+        assert_eq!(modules().len(), 2);
+        assert_eq!(queries.get(), 4);
+    }
+
+    #[test]
+    fn test_expired_entries_are_reloaded() {
+        let cache = ListCache::new(Duration::ZERO);
+        let queries = Cell::new(0);
+        for _ in 0..2 {
+            cache.courses(|| {
+                queries.set(queries.get() + 1);
+                Ok::<_, ()>(Vec::new())
+            }).unwrap();
+        }
+        assert_eq!(queries.get(), 2);
+    }
+}
@@ -0,0 +1,122 @@
+use std::path::Path;
+use anyhow::{Result, anyhow};
+use crate::db::{Database, UserNote};
+use crate::error::AppError;
+
+// Quanto tempo cada anotação fica na tela, em segundos
+pub const DEFAULT_CUE_SECONDS: f64 = 5.0;
+
+// Grava as anotações do vídeo como legendas WebVTT em `out_path`, para
+// sobrepor ao vídeo em qualquer player. Retorna quantas legendas saíram
+pub fn export_notes_vtt(db: &Database, video_id: &str, out_path: &Path, cue_seconds: f64) -> Result<usize> {
+    if !cue_seconds.is_finite() || cue_seconds <= 0.0 {
+        return Err(AppError::InvalidInput(format!("Duração da legenda inválida: {}", cue_seconds)).into());
+    }
+    if db.get_video_by_id(video_id)?.is_none() {
+        return Err(AppError::NotFound(format!("Vídeo não encontrado: {}", video_id)).into());
+    }
+
+    let notes = db.get_notes_by_video(video_id)?;
+    let (vtt, cue_count) = notes_to_vtt(&notes, cue_seconds);
+    std::fs::write(out_path, vtt)
+        .map_err(|e| anyhow!("Erro ao gravar {}: {}", out_path.display(), e))?;
+
+    log::info!("📤 {} anotações do vídeo {} exportadas para {}", cue_count, video_id, out_path.display());
+    Ok(cue_count)
+}
+
+// Cada anotação com timestamp vira uma legenda de `cue_seconds`, cortada no
+// início da seguinte para não haver sobreposição. Anotações no mesmo instante
+// dividem a mesma legenda
+pub fn notes_to_vtt(notes: &[UserNote], cue_seconds: f64) -> (String, usize) {
+    let mut timed: Vec<(f64, &UserNote)> = notes.iter()
+        .filter_map(|note| note.timestamp.filter(|t| t.is_finite()).map(|t| (t.max(0.0), note)))
+        .collect();
+    timed.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+    let mut cues: Vec<(f64, Vec<String>)> = Vec::new();
+    for (start, note) in timed {
+        let text = cue_text(note);
+        match cues.last_mut() {
+            Some((last_start, texts)) if *last_start == start => texts.push(text),
+            _ => cues.push((start, vec![text])),
+        }
+    }
+
+    let mut vtt = String::from("WEBVTT\n");
+    for (index, (start, texts)) in cues.iter().enumerate() {
+        let mut end = start + cue_seconds;
+        if let Some((next_start, _)) = cues.get(index + 1) {
+            end = end.min(*next_start);
+        }
+        vtt.push_str(&format!(
+            "\n{}\n{} --> {}\n{}\n",
+            index + 1, vtt_timestamp(*start), vtt_timestamp(end), texts.join("\n")
+        ));
+    }
+    (vtt, cues.len())
+}
+
+// Título e conteúdo, sem linhas em branco (que encerram a legenda) e com os
+// caracteres reservados do WebVTT escapados
+fn cue_text(note: &UserNote) -> String {
+    [note.title.as_str(), note.content.as_str()]
+        .iter()
+        .flat_map(|text| text.lines())
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(|line| line.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;"))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+// HH:MM:SS.mmm
+fn vtt_timestamp(seconds: f64) -> String {
+    let millis = (seconds * 1000.0).round() as u64;
+    format!(
+        "{:02}:{:02}:{:02}.{:03}",
+        millis / 3_600_000, millis / 60_000 % 60, millis / 1000 % 60, millis % 1000
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    fn note(timestamp: Option<f64>, title: &str, content: &str) -> UserNote {
+        UserNote {
+            id: uuid::Uuid::new_v4().to_string(),
+            video_id: Some("video-1".to_string()),
+            course_id: Some("course-1".to_string()),
+            module_id: Some("module-1".to_string()),
+            timestamp,
+            title: title.to_string(),
+            content: content.to_string(),
+            note_type: "video".to_string(),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            color: None,
+            bookmark_id: None,
+        }
+    }
+
+    #[test]
+    fn test_notes_become_ordered_non_overlapping_cues() {
+        let notes = vec![
+            note(Some(3723.5), "Lifetimes", "Ver 'a e 'static"),
+            note(None, "Resumo geral", "sem timestamp"),
+            note(Some(12.0), "Ownership", "Move <> cópia\n\nrever depois"),
+            note(Some(14.25), "Borrow", "&T e &mut T"),
+        ];
+
+        let (vtt, cue_count) = notes_to_vtt(&notes, DEFAULT_CUE_SECONDS);
+        assert_eq!(cue_count, 3);
+        assert_eq!(vtt, concat!(
+            "WEBVTT\n",
+            "\n1\n00:00:12.000 --> 00:00:14.250\nOwnership\nMove &lt;&gt; cópia\nrever depois\n",
+            "\n2\n00:00:14.250 --> 00:00:19.250\nBorrow\n&amp;T e &amp;mut T\n",
+            "\n3\n01:02:03.500 --> 01:02:08.500\nLifetimes\nVer 'a e 'static\n",
+        ));
+    }
+}
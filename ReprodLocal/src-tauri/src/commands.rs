@@ -1,47 +1,252 @@
-use crate::db::{Database, Course, Module, Video, VideoProgress, UserNote, VideoBookmark, UserSettings, ActivityLog};
-use crate::fs::{FileSystemScanner, get_default_course_directories};
-use tauri::State;
-use std::path::PathBuf;
+use crate::activity_export;
+use crate::backfill::{self, BackfillProgress, FfprobeProber, BACKFILL_PROGRESS_EVENT};
+use crate::backup::{self, BackupPolicy};
+use crate::cache::ListCache;
+use crate::captions;
+use crate::db::{Database, Course, CourseBadges, CourseActivitySummary, CourseDuration, CourseTimeRemaining, GlobalSummary, HomeCourse, ArchiveCandidate, Module, ModuleNode, OutlineItem, Video, VideoLocation, VideoProgress, UserNote, VideoBookmark, AdjacentBookmarks, UserSettings, ActivityLog, ActivityDetails, VideoRecommendation, PlaybackSettings, Theme, ProgressImport, ChecklistImport, FavoriteVideo, RatedVideo, AnnotatedVideo, ResetScope, VideoDetail, StreakStatus, BulkNoteUpdate, ClearedAnnotations, InconsistentProgress, OrphanReport, ProgressRepair, VideoOrganization, validate_note_color};
+use crate::fs::{self, DuplicateVideoGroup, FileMetadata, FileSystemScanner, RevealAction, ScanPreview, ScanReport, file_metadata, find_suspect_videos, get_default_course_directories, reveal_action};
+use crate::logging;
+use crate::media::{self, ClipExport, ClipRange};
+use crate::sprites::{self, FfmpegExtractor, ScrubSprites};
+use crate::structure::{self, CourseStructure};
+use crate::video_player::{ExternalPlayerConfig, VideoPlayer};
+use tauri::{AppHandle, Emitter, State};
+use std::path::{Path, PathBuf};
 use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
 use anyhow::Result;
 use uuid::Uuid;
-use chrono::Utc;
+use chrono::{DateTime, NaiveDate, Utc};
 
 pub struct AppState {
     pub db: Mutex<Database>,
+    pub player: Mutex<VideoPlayer>,
+    pub backfill_cancel: AtomicBool, // Pede a parada de `backfill_durations`
+    pub cache: ListCache, // Listas de cursos e módulos da barra lateral
+}
+
+#[tauri::command]
+pub async fn get_app_info(state: State<'_, AppState>) -> Result<AppInfo, String> {
+    build_app_info(&state)
+}
+
+fn build_app_info(state: &AppState) -> Result<AppInfo, String> {
+    let db = state.db.lock().map_err(|e| format!("Erro ao acessar banco: {}", e))?;
+
+    let db_version = db.get_database_version().map_err(|e| format!("Erro ao ler versão do banco: {}", e))?;
+    let (total_courses, total_videos) = db.get_library_counts()
+        .map_err(|e| format!("Erro ao contar cursos e vídeos: {}", e))?;
+
+    Ok(AppInfo {
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        db_version,
+        db_path: db.path(),
+        total_courses,
+        total_videos,
+    })
+}
+
+#[tauri::command]
+pub async fn get_schema_version(state: State<'_, AppState>) -> Result<i32, String> {
+    let db = state.db.lock().map_err(|e| format!("Erro ao acessar banco: {}", e))?;
+    db.get_database_version().map_err(|e| format!("Erro ao ler versão do banco: {}", e))
+}
+
+// Uso administrativo: reexecuta as migrações a partir da versão gravada
+#[tauri::command]
+pub async fn force_migrate(state: State<'_, AppState>) -> Result<i32, String> {
+    let db = state.db.lock().map_err(|e| format!("Erro ao acessar banco: {}", e))?;
+    db.force_migrate().map_err(|e| format!("Erro ao migrar banco: {}", e))
 }
 
 #[tauri::command]
 pub async fn scan_courses(state: State<'_, AppState>) -> Result<Vec<Course>, String> {
-    println!("🔍 Iniciando escaneamento de cursos...");
+    log::info!("🔍 Iniciando escaneamento de cursos...");
     let db = state.db.lock().map_err(|e| format!("Erro ao acessar banco: {}", e))?;
+    state.cache.invalidate();
     let scanner = FileSystemScanner::new(&*db);
     
     let default_dirs = get_default_course_directories();
-    println!("📁 Diretórios a serem escaneados: {:?}", default_dirs);
+    log::debug!("📁 Diretórios a serem escaneados: {:?}", default_dirs);
     
     let courses = scanner.rescan_courses(&default_dirs).map_err(|e| e.to_string())?;
-    println!("✅ Escaneamento concluído. {} cursos encontrados", courses.len());
+    log::info!("✅ Escaneamento concluído. {} cursos encontrados", courses.len());
     
     Ok(courses)
 }
 
 #[tauri::command]
 pub async fn get_all_courses(state: State<'_, AppState>) -> Result<Vec<Course>, String> {
-    println!("📚 Carregando todos os cursos do banco...");
+    log::debug!("📚 Carregando todos os cursos do banco...");
     let db = state.db.lock().map_err(|e| format!("Erro ao acessar banco: {}", e))?;
-    let courses = db.get_all_courses().map_err(|e| e.to_string())?;
-    println!("📚 {} cursos carregados do banco", courses.len());
+    let courses = state.cache.courses(|| db.get_all_courses()).map_err(|e| e.to_string())?;
+    log::debug!("📚 {} cursos carregados do banco", courses.len());
     Ok(courses)
 }
 
+#[tauri::command]
+pub async fn get_courses_with_progress(state: State<'_, AppState>) -> Result<Vec<(Course, i32, i32, f64)>, String> {
+    let db = state.db.lock().map_err(|e| format!("Erro ao acessar banco: {}", e))?;
+    db.get_courses_with_progress().map_err(|e| format!("Erro ao buscar progresso dos cursos: {}", e))
+}
+
+// Cursos da tela inicial, dos mais estudados agora para os parados ou concluídos
+#[tauri::command]
+pub async fn get_home_courses(
+    limit: Option<usize>,
+    state: State<'_, AppState>
+) -> Result<Vec<HomeCourse>, String> {
+    let db = state.db.lock().map_err(|e| format!("Erro ao acessar banco: {}", e))?;
+    db.get_home_courses(limit.unwrap_or(10)).map_err(|e| format!("Erro ao buscar cursos da tela inicial: {}", e))
+}
+
+// Cursos concluídos e sem acesso há mais de `archive_suggestion_days` dias
+#[tauri::command]
+pub async fn get_archive_candidates(state: State<'_, AppState>) -> Result<Vec<ArchiveCandidate>, String> {
+    let db = state.db.lock().map_err(|e| format!("Erro ao acessar banco: {}", e))?;
+    db.get_archive_candidates().map_err(|e| format!("Erro ao buscar cursos para arquivar: {}", e))
+}
+
 #[tauri::command]
 pub async fn get_course_modules(
     course_id: String,
     state: State<'_, AppState>
 ) -> Result<Vec<Module>, String> {
     let db = state.db.lock().map_err(|e| format!("Erro ao acessar banco: {}", e))?;
-    db.get_course_modules(&course_id).map_err(|e| e.to_string())
+    state.cache.course_modules(&course_id, || db.get_course_modules(&course_id)).map_err(|e| e.to_string())
+}
+
+// Mesmos módulos de `get_course_modules`, aninhados por pasta
+#[tauri::command]
+pub async fn get_course_module_tree(
+    course_id: String,
+    state: State<'_, AppState>
+) -> Result<Vec<ModuleNode>, String> {
+    let db = state.db.lock().map_err(|e| format!("Erro ao acessar banco: {}", e))?;
+    db.get_course_module_tree(&course_id).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_course_modules_with_counts(
+    course_id: String,
+    state: State<'_, AppState>
+) -> Result<Vec<(Module, i32, i32)>, String> {
+    let db = state.db.lock().map_err(|e| format!("Erro ao acessar banco: {}", e))?;
+    db.get_course_modules_with_counts(&course_id).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_course_outline(
+    course_id: String,
+    state: State<'_, AppState>
+) -> Result<Vec<OutlineItem>, String> {
+    let db = state.db.lock().map_err(|e| format!("Erro ao acessar banco: {}", e))?;
+    db.get_course_outline(&course_id).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_video_location(
+    video_id: String,
+    state: State<'_, AppState>
+) -> Result<VideoLocation, String> {
+    let db = state.db.lock().map_err(|e| format!("Erro ao acessar banco: {}", e))?;
+    db.get_video_location(&video_id).map_err(|e| e.to_string())
+}
+
+// Próximo módulo a liberar e a fração já concluída dele; None com o curso concluído
+#[tauri::command]
+pub async fn get_recommended_next_module(
+    course_id: String,
+    state: State<'_, AppState>
+) -> Result<Option<(Module, f64)>, String> {
+    let db = state.db.lock().map_err(|e| format!("Erro ao acessar banco: {}", e))?;
+    db.get_recommended_next_module(&course_id).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn normalize_ordering(
+    course_id: String,
+    state: State<'_, AppState>
+) -> Result<usize, String> {
+    let db = state.db.lock().map_err(|e| format!("Erro ao acessar banco: {}", e))?;
+    state.cache.invalidate();
+    db.normalize_ordering(&course_id).map_err(|e| format!("Erro ao reordenar curso: {}", e))
+}
+
+#[tauri::command]
+pub async fn merge_modules(
+    course_id: String,
+    keep_module_id: String,
+    merge_module_id: String,
+    state: State<'_, AppState>
+) -> Result<usize, String> {
+    let db = state.db.lock().map_err(|e| format!("Erro ao acessar banco: {}", e))?;
+    state.cache.invalidate();
+
+    let moved = db.merge_modules(&course_id, &keep_module_id, &merge_module_id).map_err(|e| e.to_string())?;
+
+    // Log da atividade
+    let activity = ActivityLog {
+        id: Uuid::new_v4().to_string(),
+        activity_type: "modules_merged".to_string(),
+        entity_id: keep_module_id,
+        entity_type: "module".to_string(),
+        details: Some(ActivityDetails::ModulesMerged { merged_module_id: merge_module_id, videos_moved: moved }.to_details()),
+        created_at: Utc::now(),
+    };
+    db.log_activity(&activity).ok();
+
+    Ok(moved)
+}
+
+#[tauri::command]
+pub async fn move_video_to_module(
+    video_id: String,
+    target_module_id: String,
+    state: State<'_, AppState>
+) -> Result<Video, String> {
+    let db = state.db.lock().map_err(|e| format!("Erro ao acessar banco: {}", e))?;
+
+    let from_module_id = db.get_video_by_id(&video_id)
+        .map_err(|e| format!("Erro ao buscar vídeo: {}", e))?
+        .map(|video| video.module_id)
+        .unwrap_or_default();
+    let moved = db.move_video_to_module(&video_id, &target_module_id).map_err(|e| e.to_string())?;
+
+    // Log da atividade
+    let activity = ActivityLog {
+        id: Uuid::new_v4().to_string(),
+        activity_type: "video_moved".to_string(),
+        entity_id: video_id,
+        entity_type: "video".to_string(),
+        details: Some(ActivityDetails::VideoMoved { from_module_id, to_module_id: target_module_id }.to_details()),
+        created_at: Utc::now(),
+    };
+    db.log_activity(&activity).ok();
+
+    Ok(moved)
+}
+
+// Reagrupa os vídeos de um curso em módulos a partir do nome dos arquivos,
+// ex.: `^(Section \d+) - Lesson (\d+)` com os grupos 1 (módulo) e 2 (ordem)
+#[tauri::command]
+pub async fn organize_videos_by_pattern(
+    course_id: String,
+    regex: String,
+    module_group: usize,
+    order_group: usize,
+    state: State<'_, AppState>
+) -> Result<VideoOrganization, String> {
+    let db = state.db.lock().map_err(|e| format!("Erro ao acessar banco: {}", e))?;
+    state.cache.invalidate();
+    let result = db.organize_videos_by_pattern(&course_id, &regex, module_group, order_group)
+        .map_err(|e| e.to_string())?;
+
+    log::info!(
+        "🗂️ Curso {} reorganizado: {} módulos criados, {} vídeos movidos, {} sem correspondência",
+        course_id, result.modules_created, result.videos_moved, result.unmatched
+    );
+    Ok(result)
 }
 
 #[tauri::command]
@@ -84,208 +289,736 @@ pub async fn update_video_progress(
     db.update_video_progress(&progress).map_err(|e| e.to_string())
 }
 
+#[tauri::command]
+pub async fn import_progress_from(
+    db_path: String,
+    state: State<'_, AppState>
+) -> Result<ProgressImport, String> {
+    let db = state.db.lock().map_err(|e| format!("Erro ao acessar banco: {}", e))?;
+
+    let result = db.import_progress_from(Path::new(&db_path)).map_err(|e| e.to_string())?;
+    log::info!(
+        "📥 Progresso importado de {}: {} encontrados, {} atualizados, {} ignorados",
+        db_path, result.matched, result.updated, result.skipped
+    );
+
+    Ok(result)
+}
+
+#[tauri::command]
+pub async fn import_completion_checklist(
+    course_id: String,
+    file_path: String,
+    state: State<'_, AppState>
+) -> Result<ChecklistImport, String> {
+    let db = state.db.lock().map_err(|e| format!("Erro ao acessar banco: {}", e))?;
+
+    let result = db.import_completion_checklist(&course_id, Path::new(&file_path)).map_err(|e| e.to_string())?;
+    log::info!(
+        "📥 Lista {} importada: {} linhas encontradas, {} vídeos alterados, {} sem correspondência",
+        file_path, result.matched, result.changed, result.unmatched.len()
+    );
+
+    Ok(result)
+}
+
 #[tauri::command]
 pub async fn get_recent_videos(
     limit: usize,
+    include_completed: Option<bool>,
     state: State<'_, AppState>
 ) -> Result<Vec<(Video, VideoProgress)>, String> {
     let db = state.db.lock().map_err(|e| format!("Erro ao acessar banco: {}", e))?;
-    db.get_recent_videos(limit).map_err(|e| e.to_string())
+    db.get_recent_videos(limit, include_completed.unwrap_or(false)).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_progress_since(
+    since: DateTime<Utc>,
+    course_id: Option<String>,
+    state: State<'_, AppState>
+) -> Result<Vec<(Course, Video, VideoProgress)>, String> {
+    let db = state.db.lock().map_err(|e| format!("Erro ao acessar banco: {}", e))?;
+    db.get_progress_since(since, course_id.as_deref()).map_err(|e| format!("Erro ao buscar progresso recente: {}", e))
+}
+
+#[tauri::command]
+pub async fn get_last_watched_per_course(
+    state: State<'_, AppState>
+) -> Result<Vec<(Course, Video, VideoProgress)>, String> {
+    let db = state.db.lock().map_err(|e| format!("Erro ao acessar banco: {}", e))?;
+    db.get_last_watched_per_course().map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_completion_timeline(
+    weeks: usize,
+    state: State<'_, AppState>
+) -> Result<Vec<(NaiveDate, i64)>, String> {
+    let db = state.db.lock().map_err(|e| format!("Erro ao acessar banco: {}", e))?;
+    db.get_completion_timeline(weeks).map_err(|e| format!("Erro ao buscar conclusões por semana: {}", e))
+}
+
+#[tauri::command]
+pub async fn get_watch_history(
+    from: NaiveDate,
+    to: NaiveDate,
+    include_empty_days: Option<bool>,
+    state: State<'_, AppState>
+) -> Result<Vec<(NaiveDate, i32, f64)>, String> {
+    let db = state.db.lock().map_err(|e| format!("Erro ao acessar banco: {}", e))?;
+    db.get_watch_history(from, to, include_empty_days.unwrap_or(false))
+        .map_err(|e| format!("Erro ao buscar histórico: {}", e))
+}
+
+#[tauri::command]
+pub async fn estimate_daily_watch_time(
+    from: NaiveDate,
+    to: NaiveDate,
+    state: State<'_, AppState>
+) -> Result<Vec<(NaiveDate, f64)>, String> {
+    let db = state.db.lock().map_err(|e| format!("Erro ao acessar banco: {}", e))?;
+    db.estimate_daily_watch_time(from, to).map_err(|e| format!("Erro ao estimar tempo assistido: {}", e))
+}
+
+#[tauri::command]
+pub async fn get_streak_status(state: State<'_, AppState>) -> Result<StreakStatus, String> {
+    let db = state.db.lock().map_err(|e| format!("Erro ao acessar banco: {}", e))?;
+    db.get_streak_status().map_err(|e| format!("Erro ao calcular sequência: {}", e))
+}
+
+#[tauri::command]
+pub async fn get_recommendations(
+    limit: usize,
+    state: State<'_, AppState>
+) -> Result<Vec<VideoRecommendation>, String> {
+    let db = state.db.lock().map_err(|e| format!("Erro ao acessar banco: {}", e))?;
+    db.get_recommendations(limit).map_err(|e| format!("Erro ao gerar recomendações: {}", e))
 }
 
 #[tauri::command]
 pub async fn play_video(
     video_path: String,
     start_time: Option<f64>,
-    _state: State<'_, AppState>
+    state: State<'_, AppState>
 ) -> Result<(), String> {
-    // Implementação simplificada - apenas log por enquanto
-    println!("Reproduzindo vídeo: {} (tempo: {:?})", video_path, start_time);
-    Ok(())
+    log::info!("Reproduzindo vídeo: {} (tempo: {:?})", video_path, start_time);
+
+    let player_config = {
+        let db = state.db.lock().map_err(|e| format!("Erro ao acessar banco: {}", e))?;
+        load_external_player_config(&db).map_err(|e| format!("Erro ao buscar configuração do player: {}", e))?
+    };
+
+    let mut player = state.player.lock().map_err(|e| format!("Erro ao acessar player: {}", e))?;
+    player.set_external_player(player_config);
+    player.play(&video_path, start_time).map_err(|e| e.to_string())
+}
+
+fn load_external_player_config(db: &Database) -> rusqlite::Result<ExternalPlayerConfig> {
+    let setting_value = |key: &str| -> rusqlite::Result<Option<String>> {
+        Ok(db.get_user_setting(key)?
+            .map(|s| s.setting_value)
+            .filter(|v| !v.trim().is_empty()))
+    };
+
+    Ok(ExternalPlayerConfig {
+        path: setting_value("external_player_path")?,
+        args: setting_value("external_player_args")?,
+    })
 }
 
 // ===== COMANDOS DE CONCLUSÃO DE VÍDEOS =====
 
 #[tauri::command]
-pub async fn mark_video_completed(
-    video_id: String,
+pub async fn mark_video_completed(
+    video_id: String,
+    state: State<'_, AppState>
+) -> Result<(), String> {
+    let db = state.db.lock().map_err(|e| format!("Erro ao acessar banco: {}", e))?;
+    
+    db.mark_video_completed(&video_id, true)
+        .map_err(|e| format!("Erro ao marcar vídeo como concluído: {}", e))?;
+    
+    // Registrar atividade
+    let activity = ActivityLog {
+        id: Uuid::new_v4().to_string(),
+        activity_type: "video_completed".to_string(),
+        entity_id: video_id,
+        entity_type: "video".to_string(),
+        details: Some(ActivityDetails::VideoCompleted { percent: 100.0 }.to_details()),
+        created_at: Utc::now(),
+    };
+    
+    db.log_activity(&activity)
+        .map_err(|e| format!("Erro ao registrar atividade: {}", e))?;
+    
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn mark_video_incomplete(
+    video_id: String,
+    state: State<'_, AppState>
+) -> Result<(), String> {
+    let db = state.db.lock().map_err(|e| format!("Erro ao acessar banco: {}", e))?;
+    
+    db.mark_video_completed(&video_id, false)
+        .map_err(|e| format!("Erro ao marcar vídeo como incompleto: {}", e))?;
+    
+    // Registrar atividade
+    let activity = ActivityLog {
+        id: Uuid::new_v4().to_string(),
+        activity_type: "video_marked_incomplete".to_string(),
+        entity_id: video_id,
+        entity_type: "video".to_string(),
+        details: Some(ActivityDetails::VideoMarkedIncomplete {}.to_details()),
+        created_at: Utc::now(),
+    };
+    
+    db.log_activity(&activity)
+        .map_err(|e| format!("Erro ao registrar atividade: {}", e))?;
+    
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn reset_progress(
+    scope: ResetScope,
+    state: State<'_, AppState>
+) -> Result<usize, String> {
+    let db = state.db.lock().map_err(|e| format!("Erro ao acessar banco: {}", e))?;
+
+    let cleared = db.reset_progress(&scope)
+        .map_err(|e| format!("Erro ao redefinir progresso: {}", e))?;
+
+    // Log da atividade
+    let (entity_id, entity_type) = scope.entity();
+    let activity = ActivityLog {
+        id: Uuid::new_v4().to_string(),
+        activity_type: "progress_reset".to_string(),
+        entity_id: entity_id.to_string(),
+        entity_type: entity_type.to_string(),
+        details: Some(ActivityDetails::ProgressReset { cleared }.to_details()),
+        created_at: Utc::now(),
+    };
+    db.log_activity(&activity).ok();
+
+    Ok(cleared)
+}
+
+#[tauri::command]
+pub async fn find_orphans(state: State<'_, AppState>) -> Result<OrphanReport, String> {
+    let db = state.db.lock().map_err(|e| format!("Erro ao acessar banco: {}", e))?;
+    db.find_orphans().map_err(|e| format!("Erro ao procurar registros órfãos: {}", e))
+}
+
+// Limpeza única de módulos e vídeos sem pai; retorna (módulos, vídeos) removidos
+#[tauri::command]
+pub async fn delete_orphans(state: State<'_, AppState>) -> Result<(usize, usize), String> {
+    let db = state.db.lock().map_err(|e| format!("Erro ao acessar banco: {}", e))?;
+    state.cache.invalidate();
+    let (modules, videos) = db.delete_orphans().map_err(|e| format!("Erro ao remover registros órfãos: {}", e))?;
+    log::info!("🗑️ Registros órfãos removidos: {} módulos, {} vídeos", modules, videos);
+    Ok((modules, videos))
+}
+
+#[tauri::command]
+pub async fn find_inconsistent_progress(state: State<'_, AppState>) -> Result<Vec<InconsistentProgress>, String> {
+    let db = state.db.lock().map_err(|e| format!("Erro ao acessar banco: {}", e))?;
+    db.find_inconsistent_progress().map_err(|e| format!("Erro ao verificar progresso: {}", e))
+}
+
+#[tauri::command]
+pub async fn find_dangling_note_timestamps(state: State<'_, AppState>) -> Result<Vec<UserNote>, String> {
+    let db = state.db.lock().map_err(|e| format!("Erro ao acessar banco: {}", e))?;
+    db.find_dangling_note_timestamps().map_err(|e| format!("Erro ao verificar anotações: {}", e))
+}
+
+#[tauri::command]
+pub async fn clamp_note_timestamps(state: State<'_, AppState>) -> Result<usize, String> {
+    let db = state.db.lock().map_err(|e| format!("Erro ao acessar banco: {}", e))?;
+    let clamped = db.clamp_note_timestamps().map_err(|e| format!("Erro ao corrigir anotações: {}", e))?;
+    log::info!("🔧 {} timestamps de anotações limitados à duração do vídeo", clamped);
+    Ok(clamped)
+}
+
+#[tauri::command]
+pub async fn repair_progress_durations(state: State<'_, AppState>) -> Result<ProgressRepair, String> {
+    let db = state.db.lock().map_err(|e| format!("Erro ao acessar banco: {}", e))?;
+    let repair = db.repair_progress_durations().map_err(|e| format!("Erro ao corrigir progresso: {}", e))?;
+    log::info!(
+        "🔧 Progresso corrigido: {} durações provisórias, {} posições limitadas",
+        repair.durations_fixed, repair.positions_clamped
+    );
+    Ok(repair)
+}
+
+#[tauri::command]
+pub async fn get_course_intro_video(
+    course_id: String,
+    state: State<'_, AppState>
+) -> Result<Option<Video>, String> {
+    let db = state.db.lock().map_err(|e| format!("Erro ao acessar banco: {}", e))?;
+    db.get_course_intro_video(&course_id).map_err(|e| format!("Erro ao buscar vídeo de introdução: {}", e))
+}
+
+// Vídeo e posição (em segundos) para retomar o curso; None se já foi concluído
+#[tauri::command]
+pub async fn resume_course(
+    course_id: String,
+    state: State<'_, AppState>
+) -> Result<Option<(Video, f64)>, String> {
+    let db = state.db.lock().map_err(|e| format!("Erro ao acessar banco: {}", e))?;
+    db.resume_course(&course_id).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_next_incomplete_video(
+    current_video_id: String,
+    state: State<'_, AppState>
+) -> Result<Option<Video>, String> {
+    let db = state.db.lock().map_err(|e| format!("Erro ao acessar banco: {}", e))?;
+    db.get_next_incomplete_video(&current_video_id).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_completed_videos(
+    course_id: Option<String>,
+    state: State<'_, AppState>
+) -> Result<Vec<(Video, VideoProgress)>, String> {
+    let db = state.db.lock().map_err(|e| format!("Erro ao acessar banco: {}", e))?;
+    
+    db.get_completed_videos(course_id.as_deref())
+        .map_err(|e| format!("Erro ao buscar vídeos concluídos: {}", e))
+}
+
+#[tauri::command]
+pub async fn get_incomplete_videos(
+    course_id: Option<String>,
+    state: State<'_, AppState>
+) -> Result<Vec<(Video, Option<VideoProgress>)>, String> {
+    let db = state.db.lock().map_err(|e| format!("Erro ao acessar banco: {}", e))?;
+    
+    db.get_incomplete_videos(course_id.as_deref())
+        .map_err(|e| format!("Erro ao buscar vídeos incompletos: {}", e))
+}
+
+#[tauri::command]
+pub async fn get_course_completion_stats(
+    course_id: String,
+    state: State<'_, AppState>
+) -> Result<(i32, i32, i32), String> {
+    let db = state.db.lock().map_err(|e| format!("Erro ao acessar banco: {}", e))?;
+    
+    db.get_course_completion_stats(&course_id)
+        .map_err(|e| format!("Erro ao obter estatísticas de conclusão: {}", e))
+}
+
+#[tauri::command]
+pub async fn get_course_badges(
+    course_id: String,
+    state: State<'_, AppState>
+) -> Result<CourseBadges, String> {
+    let db = state.db.lock().map_err(|e| format!("Erro ao acessar banco: {}", e))?;
+    db.get_course_badges(&course_id).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_course_activity_summary(
+    course_id: String,
+    state: State<'_, AppState>
+) -> Result<CourseActivitySummary, String> {
+    let db = state.db.lock().map_err(|e| format!("Erro ao acessar banco: {}", e))?;
+    db.get_course_activity_summary(&course_id).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_video_by_path(
+    video_path: String,
+    state: State<'_, AppState>
+) -> Result<Option<Video>, String> {
+    let db = state.db.lock().map_err(|e| format!("Erro ao acessar banco: {}", e))?;
+    
+    db.get_video_by_normalized_path(&video_path)
+        .map_err(|e| format!("Erro ao buscar vídeo por caminho: {}", e))
+}
+
+
+
+#[tauri::command]
+pub async fn pause_video(state: State<'_, AppState>) -> Result<(), String> {
+    log::debug!("Pausando vídeo");
+    let player = state.player.lock().map_err(|e| format!("Erro ao acessar player: {}", e))?;
+    player.pause().map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn resume_video(state: State<'_, AppState>) -> Result<(), String> {
+    log::debug!("Retomando vídeo");
+    let player = state.player.lock().map_err(|e| format!("Erro ao acessar player: {}", e))?;
+    player.resume().map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn seek_video(time: f64, state: State<'_, AppState>) -> Result<(), String> {
+    log::debug!("Buscando posição: {}", time);
+    let mut player = state.player.lock().map_err(|e| format!("Erro ao acessar player: {}", e))?;
+    player.seek(time).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn stop_video(state: State<'_, AppState>) -> Result<(), String> {
+    log::debug!("Parando vídeo");
+    let mut player = state.player.lock().map_err(|e| format!("Erro ao acessar player: {}", e))?;
+    player.stop().map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_video_status(state: State<'_, AppState>) -> Result<Option<VideoStatus>, String> {
+    let player = state.player.lock().map_err(|e| format!("Erro ao acessar player: {}", e))?;
+    player.get_status().map(Some).map_err(|e| e.to_string())
+}
+
+// Lê com o ffprobe a duração dos vídeos que ainda não a têm, emitindo
+// `backfill://progress` a cada vídeo; `cancel_backfill` interrompe
+#[tauri::command]
+pub async fn backfill_durations(
+    course_id: Option<String>,
+    app: AppHandle,
+    state: State<'_, AppState>
+) -> Result<BackfillProgress, String> {
+    let ffprobe = media::find_executable("ffprobe").map_err(|e| e.to_string())?;
+    state.backfill_cancel.store(false, Ordering::Relaxed);
+
+    backfill::backfill_durations(
+        &state.db,
+        course_id.as_deref(),
+        &FfprobeProber { ffprobe },
+        &state.backfill_cancel,
+        |progress| {
+            app.emit(BACKFILL_PROGRESS_EVENT, progress.clone()).ok();
+        },
+    )
+    .map_err(|e| format!("Erro ao preencher durações: {}", e))
+}
+
+#[tauri::command]
+pub async fn cancel_backfill(state: State<'_, AppState>) -> Result<(), String> {
+    state.backfill_cancel.store(true, Ordering::Relaxed);
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_suspect_videos(
+    course_id: Option<String>,
+    state: State<'_, AppState>
+) -> Result<Vec<(Video, u64, String)>, String> {
+    let db = state.db.lock().map_err(|e| format!("Erro ao acessar banco: {}", e))?;
+
+    let min_bytes = db.get_user_setting("min_video_bytes")
+        .map_err(|e| format!("Erro ao buscar configuração: {}", e))?
+        .and_then(|setting| setting.setting_value.trim().parse::<u64>().ok())
+        .unwrap_or(1024);
+    // ffprobe é opcional: sem ele a verificação fica só no tamanho
+    let ffprobe = media::find_executable("ffprobe").ok();
+
+    find_suspect_videos(&db, course_id.as_deref(), min_bytes, ffprobe.as_deref())
+        .map_err(|e| format!("Erro ao verificar vídeos: {}", e))
+}
+
+#[tauri::command]
+pub async fn find_duplicate_videos_by_name(state: State<'_, AppState>) -> Result<Vec<DuplicateVideoGroup>, String> {
+    let db = state.db.lock().map_err(|e| format!("Erro ao acessar banco: {}", e))?;
+    fs::find_duplicate_videos_by_name(&db).map_err(|e| format!("Erro ao procurar vídeos duplicados: {}", e))
+}
+
+#[tauri::command]
+pub async fn get_file_metadata(path: String) -> Result<FileMetadata, String> {
+    file_metadata(Path::new(&path)).map_err(|e| e.to_string())
+}
+
+// Calcula o hash do arquivo (fora do lock do banco) e, se ele for um vídeo
+// cadastrado, guarda o resultado em `videos.content_hash`
+#[tauri::command]
+pub async fn compute_file_hash(
+    path: String,
+    algorithm: String,
+    state: State<'_, AppState>
+) -> Result<String, String> {
+    let digest = fs::compute_file_hash(Path::new(&path), &algorithm).map_err(|e| e.to_string())?;
+
+    let db = state.db.lock().map_err(|e| format!("Erro ao acessar banco: {}", e))?;
+    if let Some(video) = db.get_video_by_path(&path).map_err(|e| format!("Erro ao buscar vídeo: {}", e))? {
+        db.set_video_content_hash(&video.id, &format!("{}:{}", algorithm, digest))
+            .map_err(|e| format!("Erro ao salvar hash do vídeo: {}", e))?;
+    }
+
+    Ok(digest)
+}
+
+#[tauri::command]
+pub async fn open_containing_folder(video_path: String) -> Result<(), String> {
+    let action = reveal_action(Path::new(&video_path), std::env::consts::OS).map_err(|e| e.to_string())?;
+
+    match action {
+        RevealAction::Command { program, args } => {
+            std::process::Command::new(&program)
+                .args(&args)
+                .spawn()
+                .map_err(|e| format!("Erro ao abrir pasta com {}: {}", program, e))?;
+        }
+        RevealAction::OpenDirectory(dir) => {
+            tauri_plugin_opener::open_path(&dir, None::<&str>)
+                .map_err(|e| format!("Erro ao abrir pasta {}: {}", dir.display(), e))?;
+        }
+    }
+
+    Ok(())
+}
+
+// Caminho do banco, para quem quer copiar o `database.db` à mão
+#[tauri::command]
+pub async fn get_database_location() -> Result<String, String> {
+    Ok(get_db_path().to_string_lossy().to_string())
+}
+
+#[tauri::command]
+pub async fn open_database_folder() -> Result<(), String> {
+    let db_path = get_db_path();
+    let dir = match db_path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir.to_path_buf(),
+        _ => PathBuf::from("."),
+    };
+
+    tauri_plugin_opener::open_path(&dir, None::<&str>)
+        .map_err(|e| format!("Erro ao abrir pasta {}: {}", dir.display(), e))
+}
+
+#[tauri::command]
+pub async fn select_course_directory(app: tauri::AppHandle) -> Result<Option<String>, String> {
+    use tauri_plugin_dialog::DialogExt;
+    use std::sync::mpsc;
+    use std::time::Duration;
+    
+    let (tx, rx) = mpsc::channel();
+    
+    app.dialog()
+        .file()
+        .set_title("Selecionar Diretório de Cursos")
+        .pick_folder(move |path| {
+            let _ = tx.send(path);
+        });
+    
+    // Aguarda o resultado com timeout
+    match rx.recv_timeout(Duration::from_secs(60)) {
+        Ok(Some(path)) => {
+            Ok(Some(path.to_string()))
+        },
+        Ok(None) => {
+            Ok(None)
+        },
+        Err(_) => {
+            Err("Timeout ao selecionar diretório".to_string())
+        }
+    }
+}
+
+
+
+#[tauri::command]
+pub async fn scan_custom_directory(
+    directory_path: String,
+    state: State<'_, AppState>
+) -> Result<Vec<Course>, String> {
+    let db = state.db.lock().map_err(|e| format!("Erro ao acessar banco: {}", e))?;
+    state.cache.invalidate();
+    let scanner = FileSystemScanner::new(&*db);
+    
+    let path = PathBuf::from(directory_path);
+    let courses = scanner.scan_directory(&path).map_err(|e| e.to_string())?;
+    
+    Ok(courses)
+}
+
+// Como `scan_custom_directory`, incluindo as entradas que não puderam ser lidas
+#[tauri::command]
+pub async fn scan_custom_directory_with_report(
+    directory_path: String,
+    state: State<'_, AppState>
+) -> Result<ScanReport, String> {
+    let db = state.db.lock().map_err(|e| format!("Erro ao acessar banco: {}", e))?;
+    state.cache.invalidate();
+    let scanner = FileSystemScanner::new(&db);
+
+    scanner.scan_directory_with_report(&PathBuf::from(directory_path)).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn preview_scan(
+    directory_path: String,
+    state: State<'_, AppState>
+) -> Result<ScanPreview, String> {
+    let db = state.db.lock().map_err(|e| format!("Erro ao acessar banco: {}", e))?;
+    let scanner = FileSystemScanner::new(&db);
+
+    scanner.preview_scan(&PathBuf::from(directory_path)).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn rescan_course(
+    course_id: String,
+    mark_accessed: Option<bool>,
+    state: State<'_, AppState>
+) -> Result<Course, String> {
+    let db = state.db.lock().map_err(|e| format!("Erro ao acessar banco: {}", e))?;
+    state.cache.invalidate();
+    let scanner = FileSystemScanner::new(&db);
+
+    scanner.rescan_course(&course_id, mark_accessed.unwrap_or(false)).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn rename_course(
+    course_id: String,
+    name: String,
     state: State<'_, AppState>
 ) -> Result<(), String> {
     let db = state.db.lock().map_err(|e| format!("Erro ao acessar banco: {}", e))?;
-    
-    db.mark_video_completed(&video_id, true)
-        .map_err(|e| format!("Erro ao marcar vídeo como concluído: {}", e))?;
-    
-    // Registrar atividade
+    state.cache.invalidate();
+
+    db.rename_course(&course_id, &name).map_err(|e| e.to_string())?;
+
+    // Log da atividade
     let activity = ActivityLog {
         id: Uuid::new_v4().to_string(),
-        activity_type: "video_completed".to_string(),
-        entity_id: video_id,
-        entity_type: "video".to_string(),
-        details: Some("Vídeo marcado como concluído manualmente".to_string()),
+        activity_type: "course_renamed".to_string(),
+        entity_id: course_id,
+        entity_type: "course".to_string(),
+        details: Some(ActivityDetails::CourseRenamed { name: name.trim().to_string() }.to_details()),
         created_at: Utc::now(),
     };
-    
-    db.log_activity(&activity)
-        .map_err(|e| format!("Erro ao registrar atividade: {}", e))?;
-    
+    db.log_activity(&activity).ok();
+
     Ok(())
 }
 
+// Descrição escrita pelo usuário; texto vazio remove
 #[tauri::command]
-pub async fn mark_video_incomplete(
-    video_id: String,
+pub async fn set_course_description(
+    course_id: String,
+    description: String,
     state: State<'_, AppState>
 ) -> Result<(), String> {
     let db = state.db.lock().map_err(|e| format!("Erro ao acessar banco: {}", e))?;
-    
-    db.mark_video_completed(&video_id, false)
-        .map_err(|e| format!("Erro ao marcar vídeo como incompleto: {}", e))?;
-    
-    // Registrar atividade
-    let activity = ActivityLog {
-        id: Uuid::new_v4().to_string(),
-        activity_type: "video_marked_incomplete".to_string(),
-        entity_id: video_id,
-        entity_type: "video".to_string(),
-        details: Some("Vídeo marcado como incompleto".to_string()),
-        created_at: Utc::now(),
-    };
-    
-    db.log_activity(&activity)
-        .map_err(|e| format!("Erro ao registrar atividade: {}", e))?;
-    
-    Ok(())
+    state.cache.invalidate();
+    db.set_course_description(&course_id, &description).map_err(|e| e.to_string())
 }
 
 #[tauri::command]
-pub async fn get_completed_videos(
-    course_id: Option<String>,
+pub async fn get_course_detail(
+    course_id: String,
     state: State<'_, AppState>
-) -> Result<Vec<(Video, VideoProgress)>, String> {
+) -> Result<Course, String> {
     let db = state.db.lock().map_err(|e| format!("Erro ao acessar banco: {}", e))?;
-    
-    db.get_completed_videos(course_id.as_deref())
-        .map_err(|e| format!("Erro ao buscar vídeos concluídos: {}", e))
+    db.get_course_by_id(&course_id)
+        .map_err(|e| format!("Erro ao buscar curso: {}", e))?
+        .ok_or_else(|| format!("Curso não encontrado: {}", course_id))
 }
 
 #[tauri::command]
-pub async fn get_incomplete_videos(
-    course_id: Option<String>,
+pub async fn get_video_detail(
+    video_id: String,
     state: State<'_, AppState>
-) -> Result<Vec<(Video, Option<VideoProgress>)>, String> {
+) -> Result<VideoDetail, String> {
     let db = state.db.lock().map_err(|e| format!("Erro ao acessar banco: {}", e))?;
-    
-    db.get_incomplete_videos(course_id.as_deref())
-        .map_err(|e| format!("Erro ao buscar vídeos incompletos: {}", e))
+    db.get_video_detail(&video_id).map_err(|e| e.to_string())
 }
 
 #[tauri::command]
-pub async fn get_course_completion_stats(
-    course_id: String,
+pub async fn set_selected_subtitle(
+    video_id: String,
+    subtitle_path: Option<String>,
     state: State<'_, AppState>
-) -> Result<(i32, i32, i32), String> {
+) -> Result<(), String> {
     let db = state.db.lock().map_err(|e| format!("Erro ao acessar banco: {}", e))?;
-    
-    db.get_course_completion_stats(&course_id)
-        .map_err(|e| format!("Erro ao obter estatísticas de conclusão: {}", e))
+    db.set_selected_subtitle(&video_id, subtitle_path.as_deref()).map_err(|e| e.to_string())
 }
 
 #[tauri::command]
-pub async fn get_video_by_path(
-    video_path: String,
+pub async fn toggle_video_favorite(
+    video_id: String,
     state: State<'_, AppState>
-) -> Result<Option<Video>, String> {
+) -> Result<bool, String> {
     let db = state.db.lock().map_err(|e| format!("Erro ao acessar banco: {}", e))?;
-    
-    db.get_video_by_path(&video_path)
-        .map_err(|e| format!("Erro ao buscar vídeo por caminho: {}", e))
+    db.toggle_video_favorite(&video_id).map_err(|e| e.to_string())
 }
 
-
-
 #[tauri::command]
-pub async fn pause_video(_state: State<'_, AppState>) -> Result<(), String> {
-    // Implementação simplificada
-    println!("Pausando vídeo");
-    Ok(())
+pub async fn get_favorite_videos(state: State<'_, AppState>) -> Result<Vec<FavoriteVideo>, String> {
+    let db = state.db.lock().map_err(|e| format!("Erro ao acessar banco: {}", e))?;
+    db.get_favorite_videos().map_err(|e| e.to_string())
 }
 
 #[tauri::command]
-pub async fn resume_video(_state: State<'_, AppState>) -> Result<(), String> {
-    // Implementação simplificada
-    println!("Retomando vídeo");
-    Ok(())
+pub async fn get_largest_videos(
+    limit: Option<usize>,
+    state: State<'_, AppState>
+) -> Result<Vec<(Video, u64, String)>, String> {
+    let db = state.db.lock().map_err(|e| format!("Erro ao acessar banco: {}", e))?;
+    db.get_largest_videos(limit.unwrap_or(20)).map_err(|e| e.to_string())
 }
 
 #[tauri::command]
-pub async fn seek_video(time: f64, _state: State<'_, AppState>) -> Result<(), String> {
-    // Implementação simplificada
-    println!("Buscando posição: {}", time);
-    Ok(())
+pub async fn set_video_rating(
+    video_id: String,
+    rating: u8,
+    state: State<'_, AppState>
+) -> Result<(), String> {
+    let db = state.db.lock().map_err(|e| format!("Erro ao acessar banco: {}", e))?;
+    db.set_video_rating(&video_id, rating).map_err(|e| e.to_string())
 }
 
 #[tauri::command]
-pub async fn stop_video(_state: State<'_, AppState>) -> Result<(), String> {
-    // Implementação simplificada
-    println!("Parando vídeo");
-    Ok(())
+pub async fn get_top_rated_videos(
+    limit: Option<usize>,
+    state: State<'_, AppState>
+) -> Result<Vec<RatedVideo>, String> {
+    let db = state.db.lock().map_err(|e| format!("Erro ao acessar banco: {}", e))?;
+    db.get_top_rated_videos(limit.unwrap_or(20)).map_err(|e| e.to_string())
 }
 
 #[tauri::command]
-pub async fn get_video_status(_state: State<'_, AppState>) -> Result<Option<VideoStatus>, String> {
-    // Implementação simplificada
-    Ok(Some(VideoStatus {
-        is_playing: false,
-        current_time: 0.0,
-        duration: 0.0,
-        volume: 1.0,
-    }))
+pub async fn get_course_cover(
+    course_id: String,
+    state: State<'_, AppState>
+) -> Result<Option<String>, String> {
+    let db = state.db.lock().map_err(|e| format!("Erro ao acessar banco: {}", e))?;
+    db.get_course_cover(&course_id).map_err(|e| format!("Erro ao buscar capa do curso: {}", e))
 }
 
 #[tauri::command]
-pub async fn select_course_directory(app: tauri::AppHandle) -> Result<Option<String>, String> {
-    use tauri_plugin_dialog::DialogExt;
-    use std::sync::mpsc;
-    use std::time::Duration;
-    
-    let (tx, rx) = mpsc::channel();
-    
-    app.dialog()
-        .file()
-        .set_title("Selecionar Diretório de Cursos")
-        .pick_folder(move |path| {
-            let _ = tx.send(path);
-        });
-    
-    // Aguarda o resultado com timeout
-    match rx.recv_timeout(Duration::from_secs(60)) {
-        Ok(Some(path)) => {
-            Ok(Some(path.to_string()))
-        },
-        Ok(None) => {
-            Ok(None)
-        },
-        Err(_) => {
-            Err("Timeout ao selecionar diretório".to_string())
-        }
-    }
+pub async fn get_course_duration(
+    course_id: String,
+    state: State<'_, AppState>
+) -> Result<CourseDuration, String> {
+    let db = state.db.lock().map_err(|e| format!("Erro ao acessar banco: {}", e))?;
+    db.get_course_duration(&course_id).map_err(|e| format!("Erro ao calcular duração do curso: {}", e))
 }
 
-
+// Totais de todos os cursos de uma vez, para a barra de status
+#[tauri::command]
+pub async fn get_global_summary(state: State<'_, AppState>) -> Result<GlobalSummary, String> {
+    let db = state.db.lock().map_err(|e| format!("Erro ao acessar banco: {}", e))?;
+    db.get_global_summary().map_err(|e| format!("Erro ao calcular resumo: {}", e))
+}
 
 #[tauri::command]
-pub async fn scan_custom_directory(
-    directory_path: String,
+pub async fn get_course_time_remaining(
+    course_id: String,
     state: State<'_, AppState>
-) -> Result<Vec<Course>, String> {
+) -> Result<CourseTimeRemaining, String> {
     let db = state.db.lock().map_err(|e| format!("Erro ao acessar banco: {}", e))?;
-    let scanner = FileSystemScanner::new(&*db);
-    
-    let path = PathBuf::from(directory_path);
-    let courses = scanner.scan_directory(&path).map_err(|e| e.to_string())?;
-    
-    Ok(courses)
+    db.get_course_time_remaining(&course_id).map_err(|e| format!("Erro ao calcular tempo restante: {}", e))
 }
 
 #[tauri::command]
@@ -294,6 +1027,7 @@ pub async fn update_course_last_accessed(
     state: State<'_, AppState>
 ) -> Result<(), String> {
     let db = state.db.lock().map_err(|e| format!("Erro ao acessar banco: {}", e))?;
+    state.cache.invalidate();
     db.update_course_last_accessed(&course_id).map_err(|e| e.to_string())
 }
 
@@ -306,27 +1040,78 @@ pub struct VideoStatus {
     pub volume: f64,
 }
 
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct AppInfo {
+    pub version: String,
+    pub db_version: i32,
+    pub db_path: Option<String>,
+    pub total_courses: i64,
+    pub total_videos: i64,
+}
+
 fn get_db_path() -> PathBuf {
-    if let Some(data_dir) = dirs::data_dir() {
-        let app_dir = data_dir.join("ReprodLocal");
-        std::fs::create_dir_all(&app_dir).ok();
-        app_dir.join("database.db")
+    let db_path = db_path_in(dirs::data_dir());
+    if let Some(app_dir) = db_path.parent() {
+        std::fs::create_dir_all(app_dir).ok();
+    }
+    db_path
+}
+
+// Sem pasta de dados do sistema, o banco fica no diretório atual
+fn db_path_in(data_dir: Option<PathBuf>) -> PathBuf {
+    match data_dir {
+        Some(data_dir) => data_dir.join("ReprodLocal").join("database.db"),
+        None => PathBuf::from("database.db"),
+    }
+}
+
+fn get_sprite_cache_dir() -> PathBuf {
+    match dirs::cache_dir() {
+        Some(cache_dir) => cache_dir.join("ReprodLocal").join("sprites"),
+        None => PathBuf::from("sprites"),
+    }
+}
+
+// Senha do banco criptografado (feature `cipher`), informada pelo usuário
+// na variável de ambiente REPRODLOCAL_DB_KEY
+fn get_db_key() -> Option<String> {
+    if cfg!(feature = "cipher") {
+        std::env::var("REPRODLOCAL_DB_KEY").ok().filter(|key| !key.is_empty())
     } else {
-        PathBuf::from("database.db")
+        None
     }
 }
 
 pub fn create_app_state() -> Result<AppState> {
     let db_path = get_db_path();
-    let db = Database::new(&db_path)?;
+    let db = match get_db_key() {
+        Some(key) => Database::new_with_key(&db_path, Some(&key))?,
+        None => Database::new(&db_path)?,
+    };
     
     // Inicializar configurações padrão se necessário
     if let Err(e) = db.initialize_default_settings() {
-        eprintln!("⚠️ Aviso: Erro ao inicializar configurações padrão: {}", e);
+        log::warn!("⚠️ Aviso: Erro ao inicializar configurações padrão: {}", e);
+    }
+
+    if let Ok(Some(setting)) = db.get_user_setting("log_level") {
+        logging::apply_user_level(&setting.setting_value);
+    }
+
+    // Backup periódico; uma falha aqui não impede a abertura do app
+    if let Some(dir) = backup::backup_dir(&db) {
+        let result = BackupPolicy::from_settings(&db)
+            .and_then(|policy| backup::backup_if_due(&db, &dir, &policy, Utc::now()));
+        if let Err(e) = result {
+            log::warn!("⚠️ Erro ao fazer backup do banco: {}", e);
+        }
     }
     
     Ok(AppState {
         db: Mutex::new(db),
+        player: Mutex::new(VideoPlayer::new()),
+        backfill_cancel: AtomicBool::new(false),
+        cache: ListCache::default(),
     })
 }
 
@@ -335,7 +1120,7 @@ pub async fn scan_folder_content(
     folder_path: String,
     state: State<'_, AppState>
 ) -> Result<FolderContent, String> {
-    println!("🔍 Escaneando conteúdo da pasta: {}", folder_path);
+    log::debug!("🔍 Escaneando conteúdo da pasta: {}", folder_path);
     
     let path = std::path::Path::new(&folder_path);
     if !path.exists() {
@@ -352,6 +1137,7 @@ pub async fn scan_folder_content(
     for entry in walkdir::WalkDir::new(path)
         .follow_links(false)
         .into_iter()
+        .filter_entry(|e| e.depth() == 0 || !scanner.is_ignored(e.path()))
         .filter_map(|e| e.ok()) 
     {
         let entry_path = entry.path();
@@ -383,7 +1169,7 @@ pub async fn scan_folder_content(
     
     let total_files = media_files.len();
     
-    println!("✅ Escaneamento concluído. {} arquivos de mídia e {} subpastas encontrados", 
+    log::info!("✅ Escaneamento concluído. {} arquivos de mídia e {} subpastas encontrados", 
              total_files, subfolders.len());
     
     Ok(FolderContent {
@@ -394,12 +1180,93 @@ pub async fn scan_folder_content(
     })
 }
 
+// Versão paginada de `scan_folder_content` para árvores grandes: cada chamada
+// percorre a pasta só até completar a página seguinte ao `page_token`
+#[tauri::command]
+pub async fn scan_folder_page(
+    folder_path: String,
+    page_token: Option<String>,
+    page_size: usize,
+    state: State<'_, AppState>
+) -> Result<FolderPage, String> {
+    let path = Path::new(&folder_path);
+    if !path.is_dir() {
+        return Err(format!("Pasta não encontrada: {}", folder_path));
+    }
+
+    let db = state.db.lock().map_err(|e| format!("Erro ao acessar banco: {}", e))?;
+    build_folder_page(path, &FileSystemScanner::new(&db), page_token.as_deref(), page_size)
+}
+
+// O percurso em profundidade com as entradas ordenadas por nome segue a mesma
+// ordem da comparação de caminhos por componente, então retomar é pular tudo
+// até o token (e as pastas inteiras que ficam antes dele)
+fn build_folder_page(
+    dir: &Path,
+    scanner: &FileSystemScanner,
+    page_token: Option<&str>,
+    page_size: usize,
+) -> Result<FolderPage, String> {
+    if page_size == 0 {
+        return Err("O tamanho da página deve ser maior que zero".to_string());
+    }
+    let after = page_token.map(PathBuf::from);
+    let relative = |path: &Path| path.strip_prefix(dir).unwrap_or(path).to_path_buf();
+
+    let mut files = walkdir::WalkDir::new(dir)
+        .follow_links(false)
+        .sort_by_file_name()
+        .into_iter()
+        .filter_entry(|e| {
+            if e.depth() == 0 {
+                return true;
+            }
+            if scanner.is_ignored(e.path()) {
+                return false;
+            }
+            match &after {
+                Some(after) if e.file_type().is_dir() => {
+                    let rel = relative(e.path());
+                    rel > *after || after.starts_with(&rel)
+                }
+                _ => true,
+            }
+        })
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file() && scanner.is_video_file(e.path()))
+        .filter(|e| after.as_ref().is_none_or(|after| relative(e.path()) > *after));
+
+    let mut media_files = Vec::new();
+    for entry in files.by_ref().take(page_size) {
+        media_files.push(MediaFile {
+            name: entry.file_name().to_string_lossy().to_string(),
+            path: entry.path().to_string_lossy().to_string(),
+            file_type: get_file_type(entry.path()),
+            size: entry.metadata().map(|m| m.len()).unwrap_or(0),
+            duration: None,
+        });
+    }
+
+    let next_page_token = match (files.next(), media_files.last()) {
+        (Some(_), Some(last)) => Some(relative(Path::new(&last.path)).to_string_lossy().to_string()),
+        _ => None,
+    };
+    Ok(FolderPage {
+        path: dir.to_string_lossy().to_string(),
+        media_files,
+        next_page_token,
+    })
+}
+
+// Com `only_incomplete`, os vídeos já concluídos ficam de fora; arquivos que
+// não estão no banco contam como não assistidos
 #[tauri::command]
 pub async fn get_folder_playlist(
     folder_path: String,
+    only_incomplete: Option<bool>,
     state: State<'_, AppState>
 ) -> Result<Vec<MediaFile>, String> {
-    println!("🎵 Criando playlist para pasta: {}", folder_path);
+    log::debug!("🎵 Criando playlist para pasta: {}", folder_path);
     
     let path = std::path::Path::new(&folder_path);
     if !path.exists() {
@@ -407,7 +1274,14 @@ pub async fn get_folder_playlist(
     }
     
     let db = state.db.lock().map_err(|e| format!("Erro ao acessar banco: {}", e))?;
-    let scanner = FileSystemScanner::new(&*db);
+    let playlist = build_folder_playlist(&db, path, only_incomplete.unwrap_or(false))?;
+    
+    log::info!("✅ Playlist criada com {} arquivos", playlist.len());
+    Ok(playlist)
+}
+
+fn build_folder_playlist(db: &Database, path: &Path, only_incomplete: bool) -> Result<Vec<MediaFile>, String> {
+    let scanner = FileSystemScanner::new(db);
     
     let mut playlist = Vec::new();
     
@@ -421,9 +1295,13 @@ pub async fn get_folder_playlist(
         
         if entry_path.is_file() && scanner.is_video_file(entry_path) {
             if let Some(file_name) = entry_path.file_name().and_then(|n| n.to_str()) {
+                let path = entry_path.to_string_lossy().to_string();
+                if only_incomplete && is_video_completed(db, &path)? {
+                    continue;
+                }
                 playlist.push(MediaFile {
                     name: file_name.to_string(),
-                    path: entry_path.to_string_lossy().to_string(),
+                    path,
                     file_type: get_file_type(entry_path),
                     size: entry.metadata().map(|m| m.len()).unwrap_or(0),
                     duration: None,
@@ -434,11 +1312,17 @@ pub async fn get_folder_playlist(
     
     // Ordenar playlist por caminho para manter ordem hierárquica
     playlist.sort_by(|a, b| a.path.cmp(&b.path));
-    
-    println!("✅ Playlist criada com {} arquivos", playlist.len());
     Ok(playlist)
 }
 
+fn is_video_completed(db: &Database, video_path: &str) -> Result<bool, String> {
+    let Some(video) = db.get_video_by_path(video_path).map_err(|e| format!("Erro ao buscar vídeo: {}", e))? else {
+        return Ok(false);
+    };
+    let progress = db.get_video_progress(&video.id).map_err(|e| format!("Erro ao buscar progresso: {}", e))?;
+    Ok(progress.is_some_and(|p| p.completed))
+}
+
 #[derive(serde::Serialize, serde::Deserialize)]
 pub struct FolderContent {
     pub path: String,
@@ -463,6 +1347,85 @@ pub struct SubFolder {
     pub media_count: usize,
 }
 
+// Página de `scan_folder_page`. O token é o caminho (relativo à pasta) do
+// último arquivo entregue; None quando não há mais arquivos
+#[derive(serde::Serialize, serde::Deserialize)]
+pub struct FolderPage {
+    pub path: String,
+    pub media_files: Vec<MediaFile>,
+    pub next_page_token: Option<String>,
+}
+
+// Pasta de um curso com seus vídeos e subpastas; só entram pastas com
+// vídeos nelas ou em alguma subpasta
+#[derive(serde::Serialize, serde::Deserialize)]
+pub struct FolderNode {
+    pub name: String,
+    pub path: String,
+    pub media_files: Vec<MediaFile>,
+    pub children: Vec<FolderNode>,
+}
+
+#[tauri::command]
+pub async fn get_course_folder_tree(
+    course_id: String,
+    state: State<'_, AppState>
+) -> Result<Option<FolderNode>, String> {
+    let db = state.db.lock().map_err(|e| format!("Erro ao acessar banco: {}", e))?;
+    let course = db.get_course_by_id(&course_id)
+        .map_err(|e| format!("Erro ao buscar curso: {}", e))?
+        .ok_or("Curso não encontrado")?;
+
+    let path = Path::new(&course.path);
+    if !path.is_dir() {
+        return Err(format!("Pasta do curso não encontrada: {}", course.path));
+    }
+    build_folder_tree(path, &FileSystemScanner::new(&db))
+}
+
+fn build_folder_tree(dir: &Path, scanner: &FileSystemScanner) -> Result<Option<FolderNode>, String> {
+    let entries = std::fs::read_dir(dir).map_err(|e| format!("Erro ao ler pasta {}: {}", dir.display(), e))?;
+
+    let mut media_files = Vec::new();
+    let mut children = Vec::new();
+    for entry in entries.filter_map(|e| e.ok()) {
+        let entry_path = entry.path();
+        // `file_type` não segue links: um link para uma pasta acima faria a
+        // recursão não terminar, então pastas por link ficam de fora
+        let Ok(file_type) = entry.file_type() else {
+            continue;
+        };
+        if file_type.is_dir() {
+            if let Some(child) = build_folder_tree(&entry_path, scanner)? {
+                children.push(child);
+            }
+        } else if file_type.is_symlink() && entry_path.is_dir() {
+            log::debug!("🔗 Link para pasta ignorado: {}", entry_path.display());
+        } else if scanner.is_video_file(&entry_path) {
+            media_files.push(MediaFile {
+                name: entry.file_name().to_string_lossy().to_string(),
+                path: entry_path.to_string_lossy().to_string(),
+                file_type: get_file_type(&entry_path),
+                size: entry.metadata().map(|m| m.len()).unwrap_or(0),
+                duration: None,
+            });
+        }
+    }
+
+    if media_files.is_empty() && children.is_empty() {
+        return Ok(None);
+    }
+    media_files.sort_by(|a, b| a.name.cmp(&b.name));
+    children.sort_by(|a, b| a.name.cmp(&b.name));
+
+    Ok(Some(FolderNode {
+        name: dir.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default(),
+        path: dir.to_string_lossy().to_string(),
+        media_files,
+        children,
+    }))
+}
+
 fn get_file_type(path: &std::path::Path) -> String {
     path.extension()
         .and_then(|ext| ext.to_str())
@@ -474,6 +1437,7 @@ fn count_media_files_in_folder(folder_path: &std::path::Path, scanner: &FileSyst
     walkdir::WalkDir::new(folder_path)
         .follow_links(false)
         .into_iter()
+        .filter_entry(|e| e.depth() == 0 || !scanner.is_ignored(e.path()))
         .filter_map(|e| e.ok())
         .filter(|entry| entry.path().is_file() && scanner.is_video_file(entry.path()))
         .count()
@@ -483,38 +1447,56 @@ fn count_media_files_in_folder(folder_path: &std::path::Path, scanner: &FileSyst
 
 #[tauri::command]
 pub async fn create_user_note(
-    video_id: String,
-    course_id: String,
-    module_id: String,
-    timestamp: f64,
+    video_id: Option<String>, // Associações exigidas conforme o `note_type`
+    course_id: Option<String>,
+    module_id: Option<String>,
+    timestamp: Option<f64>,
     title: String,
     content: String,
     note_type: String,
+    color: Option<String>,
+    bookmark_id: Option<String>, // Liga a anotação a um bookmark do mesmo vídeo
     state: State<'_, AppState>
 ) -> Result<String, String> {
-    println!("🔍 Backend create_user_note - Parâmetros recebidos:");
-    println!("   video_id: {}", video_id);
-    println!("   course_id: {}", course_id);
-    println!("   module_id: {}", module_id);
-    println!("   timestamp: {}", timestamp);
-    println!("   title: {}", title);
-    println!("   content: {}", content);
-    println!("   note_type: {}", note_type);
+    log::debug!("🔍 Backend create_user_note - Parâmetros recebidos:");
+    log::debug!("   video_id: {:?}", video_id);
+    log::debug!("   course_id: {:?}", course_id);
+    log::debug!("   module_id: {:?}", module_id);
+    log::debug!("   timestamp: {:?}", timestamp);
+    log::debug!("   title: {}", title);
+    log::debug!("   content: {}", content);
+    log::debug!("   note_type: {}", note_type);
+    log::debug!("   color: {:?}", color);
+    log::debug!("   bookmark_id: {:?}", bookmark_id);
+
+    validate_note_color(color.as_deref()).map_err(|e| e.to_string())?;
 
     let db = state.db.lock().map_err(|e| format!("Erro ao acessar banco: {}", e))?;
+
+    if let Some(bookmark_id) = &bookmark_id {
+        let bookmark = db.get_video_bookmark(bookmark_id)
+            .map_err(|e| format!("Erro ao buscar bookmark: {}", e))?
+            .ok_or("Bookmark não encontrado")?;
+        if video_id.as_deref() != Some(bookmark.video_id.as_str()) {
+            return Err("O bookmark pertence a outro vídeo".to_string());
+        }
+    }
     
-    let note = UserNote {
+    let mut note = UserNote {
         id: Uuid::new_v4().to_string(),
-        video_id: Some(video_id),
-        course_id: Some(course_id),
-        module_id: Some(module_id),
-        timestamp: Some(timestamp),
+        video_id,
+        course_id,
+        module_id,
+        timestamp,
         title,
         content,
         note_type,
         created_at: Utc::now(),
         updated_at: Utc::now(),
+        color,
+        bookmark_id,
     };
+    db.resolve_note_associations(&mut note).map_err(|e| e.to_string())?;
     
     db.create_user_note(&note).map_err(|e| format!("Erro ao criar anotação: {}", e))?;
     
@@ -524,12 +1506,12 @@ pub async fn create_user_note(
         activity_type: "note_created".to_string(),
         entity_id: note.id.clone(),
         entity_type: "note".to_string(),
-        details: Some(format!("Anotação criada: {}", note.title)),
+        details: Some(ActivityDetails::NoteCreated { title: note.title.clone(), duplicated_from: None }.to_details()),
         created_at: Utc::now(),
     };
     db.log_activity(&activity).ok(); // Não falhar se o log der erro
     
-    println!("✅ Backend create_user_note - Anotação criada com sucesso! ID: {}", note.id);
+    log::info!("✅ Backend create_user_note - Anotação criada com sucesso! ID: {}", note.id);
     Ok(note.id)
 }
 
@@ -538,8 +1520,14 @@ pub async fn update_user_note(
     note_id: String,
     title: String,
     content: String,
+    color: Option<String>, // Sem valor mantém a cor atual; "" remove a cor
     state: State<'_, AppState>
 ) -> Result<(), String> {
+    let color = color.map(|c| Some(c).filter(|c| !c.is_empty()));
+    if let Some(new_color) = &color {
+        validate_note_color(new_color.as_deref()).map_err(|e| e.to_string())?;
+    }
+
     let db = state.db.lock().map_err(|e| format!("Erro ao acessar banco: {}", e))?;
     
     // Buscar a nota existente para manter os outros campos
@@ -550,17 +1538,60 @@ pub async fn update_user_note(
     
     note.title = title;
     note.content = content;
+    if let Some(new_color) = color {
+        note.color = new_color;
+    }
     note.updated_at = Utc::now();
     
-    db.update_user_note(&note).map_err(|e| format!("Erro ao atualizar anotação: {}", e))?;
+    db.update_user_note(&note).map_err(|e| format!("Erro ao atualizar anotação: {}", e))?;
+    
+    // Log da atividade
+    let activity = ActivityLog {
+        id: Uuid::new_v4().to_string(),
+        activity_type: "note_updated".to_string(),
+        entity_id: note.id,
+        entity_type: "note".to_string(),
+        details: Some(ActivityDetails::NoteUpdated { title: note.title.clone() }.to_details()),
+        created_at: Utc::now(),
+    };
+    db.log_activity(&activity).ok();
+    
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn bulk_update_notes(
+    note_ids: Vec<String>,
+    note_type: Option<String>,
+    color: Option<String>, // Sem valor mantém a cor atual; "" remove a cor
+    state: State<'_, AppState>
+) -> Result<BulkNoteUpdate, String> {
+    let color = color.as_deref().map(|c| Some(c).filter(|c| !c.is_empty()));
+    let db = state.db.lock().map_err(|e| format!("Erro ao acessar banco: {}", e))?;
+
+    let result = db.bulk_update_notes(&note_ids, note_type.as_deref(), color).map_err(|e| e.to_string())?;
+    if !result.missing_ids.is_empty() {
+        log::warn!("⚠️ Anotações não encontradas na atualização em lote: {:?}", result.missing_ids);
+    }
+    Ok(result)
+}
+
+#[tauri::command]
+pub async fn delete_user_note(
+    note_id: String,
+    state: State<'_, AppState>
+) -> Result<(), String> {
+    let db = state.db.lock().map_err(|e| format!("Erro ao acessar banco: {}", e))?;
+    
+    db.delete_user_note(&note_id).map_err(|e| format!("Erro ao deletar anotação: {}", e))?;
     
     // Log da atividade
     let activity = ActivityLog {
         id: Uuid::new_v4().to_string(),
-        activity_type: "note_updated".to_string(),
-        entity_id: note.id,
+        activity_type: "note_deleted".to_string(),
+        entity_id: note_id,
         entity_type: "note".to_string(),
-        details: Some(format!("Anotação atualizada: {}", note.title)),
+        details: Some(ActivityDetails::NoteDeleted {}.to_details()),
         created_at: Utc::now(),
     };
     db.log_activity(&activity).ok();
@@ -569,28 +1600,56 @@ pub async fn update_user_note(
 }
 
 #[tauri::command]
-pub async fn delete_user_note(
+pub async fn restore_note(
     note_id: String,
     state: State<'_, AppState>
 ) -> Result<(), String> {
     let db = state.db.lock().map_err(|e| format!("Erro ao acessar banco: {}", e))?;
-    
-    db.delete_user_note(&note_id).map_err(|e| format!("Erro ao deletar anotação: {}", e))?;
-    
+
+    db.restore_user_note(&note_id).map_err(|e| e.to_string())?;
+
     // Log da atividade
     let activity = ActivityLog {
         id: Uuid::new_v4().to_string(),
-        activity_type: "note_deleted".to_string(),
+        activity_type: "note_restored".to_string(),
         entity_id: note_id,
         entity_type: "note".to_string(),
-        details: Some("Anotação deletada".to_string()),
+        details: Some(ActivityDetails::NoteRestored {}.to_details()),
         created_at: Utc::now(),
     };
     db.log_activity(&activity).ok();
-    
+
     Ok(())
 }
 
+#[tauri::command]
+pub async fn clear_course_annotations(
+    course_id: String,
+    include_bookmarks: bool,
+    state: State<'_, AppState>
+) -> Result<ClearedAnnotations, String> {
+    let db = state.db.lock().map_err(|e| format!("Erro ao acessar banco: {}", e))?;
+
+    let cleared = db.clear_course_annotations(&course_id, include_bookmarks)
+        .map_err(|e| format!("Erro ao limpar anotações do curso: {}", e))?;
+
+    // Log da atividade
+    let activity = ActivityLog {
+        id: Uuid::new_v4().to_string(),
+        activity_type: "course_annotations_cleared".to_string(),
+        entity_id: course_id,
+        entity_type: "course".to_string(),
+        details: Some(ActivityDetails::CourseAnnotationsCleared {
+            notes_deleted: cleared.notes_deleted,
+            bookmarks_deleted: cleared.bookmarks_deleted,
+        }.to_details()),
+        created_at: Utc::now(),
+    };
+    db.log_activity(&activity).ok();
+
+    Ok(cleared)
+}
+
 #[tauri::command]
 pub async fn get_notes_by_video(
     video_id: String,
@@ -600,6 +1659,25 @@ pub async fn get_notes_by_video(
     db.get_notes_by_video(&video_id).map_err(|e| format!("Erro ao buscar anotações: {}", e))
 }
 
+#[tauri::command]
+pub async fn search_notes_in_video(
+    video_id: String,
+    query: String,
+    state: State<'_, AppState>
+) -> Result<Vec<UserNote>, String> {
+    let db = state.db.lock().map_err(|e| format!("Erro ao acessar banco: {}", e))?;
+    db.search_notes_in_video(&video_id, &query).map_err(|e| format!("Erro ao buscar anotações: {}", e))
+}
+
+#[tauri::command]
+pub async fn get_notes_for_bookmark(
+    bookmark_id: String,
+    state: State<'_, AppState>
+) -> Result<Vec<UserNote>, String> {
+    let db = state.db.lock().map_err(|e| format!("Erro ao acessar banco: {}", e))?;
+    db.get_notes_for_bookmark(&bookmark_id).map_err(|e| format!("Erro ao buscar anotações: {}", e))
+}
+
 #[tauri::command]
 pub async fn get_notes_by_course(
     course_id: String,
@@ -609,12 +1687,90 @@ pub async fn get_notes_by_course(
     db.get_notes_by_course(&course_id).map_err(|e| format!("Erro ao buscar anotações: {}", e))
 }
 
+#[tauri::command]
+pub async fn get_notes_by_module(
+    module_id: String,
+    state: State<'_, AppState>
+) -> Result<Vec<UserNote>, String> {
+    let db = state.db.lock().map_err(|e| format!("Erro ao acessar banco: {}", e))?;
+    db.get_notes_by_module(&module_id).map_err(|e| format!("Erro ao buscar anotações: {}", e))
+}
+
+#[tauri::command]
+pub async fn get_course_level_notes(
+    course_id: String,
+    state: State<'_, AppState>
+) -> Result<Vec<UserNote>, String> {
+    let db = state.db.lock().map_err(|e| format!("Erro ao acessar banco: {}", e))?;
+    db.get_course_level_notes(&course_id).map_err(|e| format!("Erro ao buscar anotações: {}", e))
+}
+
 #[tauri::command]
 pub async fn get_all_notes(state: State<'_, AppState>) -> Result<Vec<UserNote>, String> {
     let db = state.db.lock().map_err(|e| format!("Erro ao acessar banco: {}", e))?;
     db.get_all_notes().map_err(|e| format!("Erro ao buscar anotações: {}", e))
 }
 
+#[tauri::command]
+pub async fn get_notes_by_color(
+    color: String,
+    state: State<'_, AppState>
+) -> Result<Vec<UserNote>, String> {
+    validate_note_color(Some(&color)).map_err(|e| e.to_string())?;
+
+    let db = state.db.lock().map_err(|e| format!("Erro ao acessar banco: {}", e))?;
+    db.get_notes_by_color(&color).map_err(|e| format!("Erro ao buscar anotações: {}", e))
+}
+
+#[tauri::command]
+pub async fn get_most_annotated_videos(
+    limit: usize,
+    state: State<'_, AppState>
+) -> Result<Vec<AnnotatedVideo>, String> {
+    let db = state.db.lock().map_err(|e| format!("Erro ao acessar banco: {}", e))?;
+    db.get_most_annotated_videos(limit).map_err(|e| format!("Erro ao buscar vídeos anotados: {}", e))
+}
+
+#[tauri::command]
+pub async fn get_note_type_counts(state: State<'_, AppState>) -> Result<Vec<(String, i64)>, String> {
+    let db = state.db.lock().map_err(|e| format!("Erro ao acessar banco: {}", e))?;
+    db.get_note_type_counts().map_err(|e| format!("Erro ao contar tipos de anotação: {}", e))
+}
+
+#[tauri::command]
+pub async fn get_note_keyword_frequencies(
+    top_n: usize,
+    min_word_length: Option<usize>,
+    state: State<'_, AppState>
+) -> Result<Vec<(String, i64)>, String> {
+    let db = state.db.lock().map_err(|e| format!("Erro ao acessar banco: {}", e))?;
+    db.get_note_keyword_frequencies(top_n, min_word_length.unwrap_or(3))
+        .map_err(|e| format!("Erro ao contar palavras das anotações: {}", e))
+}
+
+#[tauri::command]
+pub async fn clone_note(
+    note_id: String,
+    state: State<'_, AppState>
+) -> Result<String, String> {
+    let db = state.db.lock().map_err(|e| format!("Erro ao acessar banco: {}", e))?;
+
+    let note = db.clone_note(&note_id).map_err(|e| e.to_string())?;
+
+    // Log da atividade
+    let activity = ActivityLog {
+        id: Uuid::new_v4().to_string(),
+        activity_type: "note_created".to_string(),
+        entity_id: note.id.clone(),
+        entity_type: "note".to_string(),
+        details: Some(ActivityDetails::NoteCreated { title: note.title.clone(), duplicated_from: Some(note_id) }.to_details()),
+        created_at: Utc::now(),
+    };
+    db.log_activity(&activity).ok();
+
+    Ok(note.id)
+}
+
 // ========== COMANDOS PARA BOOKMARKS ==========
 
 #[tauri::command]
@@ -644,7 +1800,7 @@ pub async fn create_video_bookmark(
         activity_type: "bookmark_created".to_string(),
         entity_id: bookmark.id.clone(),
         entity_type: "bookmark".to_string(),
-        details: Some(format!("Bookmark criado: {}", bookmark.title)),
+        details: Some(ActivityDetails::BookmarkCreated { title: bookmark.title.clone() }.to_details()),
         created_at: Utc::now(),
     };
     db.log_activity(&activity).ok();
@@ -667,7 +1823,7 @@ pub async fn delete_video_bookmark(
         activity_type: "bookmark_deleted".to_string(),
         entity_id: bookmark_id,
         entity_type: "bookmark".to_string(),
-        details: Some("Bookmark deletado".to_string()),
+        details: Some(ActivityDetails::BookmarkDeleted {}.to_details()),
         created_at: Utc::now(),
     };
     db.log_activity(&activity).ok();
@@ -675,6 +1831,38 @@ pub async fn delete_video_bookmark(
     Ok(())
 }
 
+#[tauri::command]
+pub async fn restore_bookmark(
+    bookmark_id: String,
+    state: State<'_, AppState>
+) -> Result<(), String> {
+    let db = state.db.lock().map_err(|e| format!("Erro ao acessar banco: {}", e))?;
+
+    db.restore_video_bookmark(&bookmark_id).map_err(|e| e.to_string())?;
+
+    // Log da atividade
+    let activity = ActivityLog {
+        id: Uuid::new_v4().to_string(),
+        activity_type: "bookmark_restored".to_string(),
+        entity_id: bookmark_id,
+        entity_type: "bookmark".to_string(),
+        details: Some(ActivityDetails::BookmarkRestored {}.to_details()),
+        created_at: Utc::now(),
+    };
+    db.log_activity(&activity).ok();
+
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn purge_deleted(
+    older_than_days: i64,
+    state: State<'_, AppState>
+) -> Result<usize, String> {
+    let db = state.db.lock().map_err(|e| format!("Erro ao acessar banco: {}", e))?;
+    db.purge_deleted(older_than_days).map_err(|e| format!("Erro ao limpar itens excluídos: {}", e))
+}
+
 #[tauri::command]
 pub async fn get_video_bookmarks(
     video_id: String,
@@ -684,6 +1872,139 @@ pub async fn get_video_bookmarks(
     db.get_video_bookmarks(&video_id).map_err(|e| format!("Erro ao buscar bookmarks: {}", e))
 }
 
+#[tauri::command]
+pub async fn get_adjacent_bookmarks(
+    video_id: String,
+    current_time: f64,
+    state: State<'_, AppState>
+) -> Result<AdjacentBookmarks, String> {
+    let db = state.db.lock().map_err(|e| format!("Erro ao acessar banco: {}", e))?;
+    db.get_adjacent_bookmarks(&video_id, current_time).map_err(|e| format!("Erro ao buscar bookmarks: {}", e))
+}
+
+#[tauri::command]
+pub async fn get_course_bookmarks(
+    course_id: String,
+    state: State<'_, AppState>
+) -> Result<Vec<(Video, VideoBookmark)>, String> {
+    let db = state.db.lock().map_err(|e| format!("Erro ao acessar banco: {}", e))?;
+    db.get_course_bookmarks(&course_id).map_err(|e| format!("Erro ao buscar bookmarks do curso: {}", e))
+}
+
+#[tauri::command]
+pub async fn export_bookmark_clip(
+    bookmark_id: String,
+    pre_seconds: f64,
+    post_seconds: f64,
+    out_path: String,
+    state: State<'_, AppState>
+) -> Result<ClipExport, String> {
+    let range_and_path = {
+        let db = state.db.lock().map_err(|e| format!("Erro ao acessar banco: {}", e))?;
+
+        let bookmark = db.get_video_bookmark(&bookmark_id)
+            .map_err(|e| format!("Erro ao buscar bookmark: {}", e))?
+            .ok_or("Bookmark não encontrado")?;
+        let video = db.get_video_by_id(&bookmark.video_id)
+            .map_err(|e| format!("Erro ao buscar vídeo: {}", e))?
+            .ok_or("Vídeo do bookmark não encontrado")?;
+
+        let range = ClipRange::around(bookmark.timestamp, pre_seconds, post_seconds).map_err(|e| e.to_string())?;
+        (range, video.path)
+    };
+
+    // O ffmpeg roda fora do lock do banco
+    let (range, video_path) = range_and_path;
+    log::info!("🎞️ Exportando trecho de {} ({:.1}s a partir de {:.1}s)", video_path, range.duration, range.start);
+    media::export_clip(&video_path, &range, &out_path).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn export_course_structure(
+    course_id: String,
+    out_path: String,
+    state: State<'_, AppState>
+) -> Result<CourseStructure, String> {
+    let db = state.db.lock().map_err(|e| format!("Erro ao acessar banco: {}", e))?;
+    structure::export_course_structure(&db, &course_id, Path::new(&out_path)).map_err(|e| e.to_string())
+}
+
+// Anotações do vídeo como legendas WebVTT; `cue_seconds` padrão de 5s.
+// Retorna quantas legendas foram gravadas
+#[tauri::command]
+pub async fn export_notes_vtt(
+    video_id: String,
+    out_path: String,
+    cue_seconds: Option<f64>,
+    state: State<'_, AppState>
+) -> Result<usize, String> {
+    let db = state.db.lock().map_err(|e| format!("Erro ao acessar banco: {}", e))?;
+    let cue_seconds = cue_seconds.unwrap_or(captions::DEFAULT_CUE_SECONDS);
+    captions::export_notes_vtt(&db, &video_id, Path::new(&out_path), cue_seconds).map_err(|e| e.to_string())
+}
+
+// Miniaturas para a prévia ao passar o mouse na barra de progresso. A
+// duração vem do banco ou, se ainda não for conhecida, do ffprobe
+#[tauri::command]
+pub async fn generate_scrub_sprites(
+    video_id: String,
+    interval_seconds: f64,
+    state: State<'_, AppState>
+) -> Result<ScrubSprites, String> {
+    let (video, duration) = {
+        let db = state.db.lock().map_err(|e| format!("Erro ao acessar banco: {}", e))?;
+        let video = db.get_video_by_id(&video_id)
+            .map_err(|e| format!("Erro ao buscar vídeo: {}", e))?
+            .ok_or("Vídeo não encontrado")?;
+        let duration = match video.duration {
+            Some(duration) => duration,
+            None => {
+                let ffprobe = media::find_executable("ffprobe").map_err(|e| e.to_string())?;
+                let duration = media::probe_duration(&ffprobe, Path::new(&video.path)).map_err(|e| e.to_string())?;
+                db.set_video_duration(&video.id, duration).ok();
+                duration
+            }
+        };
+        (video, duration)
+    };
+
+    // O ffmpeg roda fora do lock do banco
+    let ffmpeg = media::find_executable("ffmpeg").map_err(|e| e.to_string())?;
+    sprites::generate_scrub_sprites(
+        &FfmpegExtractor { ffmpeg },
+        &video.id,
+        Path::new(&video.path),
+        duration,
+        interval_seconds,
+        &get_sprite_cache_dir(),
+    )
+    .map_err(|e| e.to_string())
+}
+
+// Backup manual, fora do intervalo configurado; retorna o caminho do arquivo
+#[tauri::command]
+pub async fn backup_database(state: State<'_, AppState>) -> Result<String, String> {
+    let db = state.db.lock().map_err(|e| format!("Erro ao acessar banco: {}", e))?;
+    let dir = backup::backup_dir(&db).ok_or("Banco em memória não tem backup")?;
+    let policy = BackupPolicy::from_settings(&db).map_err(|e| e.to_string())?;
+
+    let path = backup::create_backup(&db, &dir, Utc::now()).map_err(|e| format!("Erro ao fazer backup: {}", e))?;
+    backup::prune_backups(&dir, policy.keep_count).map_err(|e| e.to_string())?;
+    Ok(path.to_string_lossy().to_string())
+}
+
+// ========== COMANDOS PARA CRIPTOGRAFIA ==========
+
+#[tauri::command]
+pub async fn change_database_key(
+    old_key: String,
+    new_key: String,
+    state: State<'_, AppState>
+) -> Result<(), String> {
+    let db = state.db.lock().map_err(|e| format!("Erro ao acessar banco: {}", e))?;
+    db.change_key(&old_key, &new_key).map_err(|e| format!("Erro ao alterar senha do banco: {}", e))
+}
+
 // ========== COMANDOS PARA CONFIGURAÇÕES ==========
 
 #[tauri::command]
@@ -704,10 +2025,20 @@ pub async fn set_user_setting(
     };
     
     db.set_user_setting(&setting).map_err(|e| format!("Erro ao salvar configuração: {}", e))?;
+
+    if setting.setting_key == "log_level" {
+        logging::apply_user_level(&setting.setting_value);
+    }
     
     Ok(())
 }
 
+// Confere uma configuração sem gravar, para validar o formulário antes de salvar
+#[tauri::command]
+pub async fn validate_setting(key: String, value: String, setting_type: String) -> Result<(), String> {
+    crate::db::validate_setting(&key, &value, &setting_type).map_err(|e| e.to_string())
+}
+
 #[tauri::command]
 pub async fn get_user_setting(
     key: String,
@@ -717,12 +2048,50 @@ pub async fn get_user_setting(
     db.get_user_setting(&key).map_err(|e| format!("Erro ao buscar configuração: {}", e))
 }
 
+// Configurações do tipo json trafegam como valor JSON, sem o texto intermediário
+#[tauri::command]
+pub async fn get_json_setting(
+    key: String,
+    state: State<'_, AppState>
+) -> Result<Option<serde_json::Value>, String> {
+    let db = state.db.lock().map_err(|e| format!("Erro ao acessar banco: {}", e))?;
+    db.get_setting_json(&key).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn set_json_setting(
+    key: String,
+    value: serde_json::Value,
+    state: State<'_, AppState>
+) -> Result<(), String> {
+    let db = state.db.lock().map_err(|e| format!("Erro ao acessar banco: {}", e))?;
+    db.set_setting_json(&key, &value).map_err(|e| format!("Erro ao salvar configuração: {}", e))
+}
+
 #[tauri::command]
 pub async fn get_all_user_settings(state: State<'_, AppState>) -> Result<Vec<UserSettings>, String> {
     let db = state.db.lock().map_err(|e| format!("Erro ao acessar banco: {}", e))?;
     db.get_all_user_settings().map_err(|e| format!("Erro ao buscar configurações: {}", e))
 }
 
+#[tauri::command]
+pub async fn get_playback_settings(state: State<'_, AppState>) -> Result<PlaybackSettings, String> {
+    let db = state.db.lock().map_err(|e| format!("Erro ao acessar banco: {}", e))?;
+    db.get_playback_settings().map_err(|e| format!("Erro ao buscar configurações de reprodução: {}", e))
+}
+
+#[tauri::command]
+pub async fn set_theme(theme: Theme, state: State<'_, AppState>) -> Result<(), String> {
+    let db = state.db.lock().map_err(|e| format!("Erro ao acessar banco: {}", e))?;
+    db.set_theme(theme).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_theme(state: State<'_, AppState>) -> Result<Theme, String> {
+    let db = state.db.lock().map_err(|e| format!("Erro ao acessar banco: {}", e))?;
+    db.get_theme().map_err(|e| e.to_string())
+}
+
 #[tauri::command]
 pub async fn initialize_default_settings(state: State<'_, AppState>) -> Result<(), String> {
     let db = state.db.lock().map_err(|e| format!("Erro ao acessar banco: {}", e))?;
@@ -740,6 +2109,19 @@ pub async fn get_recent_activities(
     db.get_recent_activities(limit).map_err(|e| format!("Erro ao buscar atividades: {}", e))
 }
 
+// Atividades de `from` a `to` (dias no fuso local) como array JSON em `out_path`.
+// Retorna quantas foram gravadas
+#[tauri::command]
+pub async fn export_activity_log(
+    from: NaiveDate,
+    to: NaiveDate,
+    out_path: String,
+    state: State<'_, AppState>
+) -> Result<usize, String> {
+    let db = state.db.lock().map_err(|e| format!("Erro ao acessar banco: {}", e))?;
+    activity_export::export_activity_log(&db, from, to, Path::new(&out_path)).map_err(|e| e.to_string())
+}
+
 #[tauri::command]
 pub async fn get_activities_by_type(
     activity_type: String,
@@ -774,4 +2156,203 @@ pub async fn log_user_activity(
     db.log_activity(&activity).map_err(|e| format!("Erro ao registrar atividade: {}", e))?;
     
     Ok(())
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::DATABASE_VERSION;
+
+    #[test]
+    fn test_app_info_reports_version_and_counts() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let db = Database::new(&temp_dir.path().join("database.db")).unwrap();
+
+        for course_id in ["course-1", "course-2"] {
+            db.insert_course(&Course {
+                id: course_id.to_string(),
+                name: course_id.to_string(),
+                path: format!("/cursos/{}", course_id),
+                cover_path: None,
+                display_name: None,
+                description: None,
+                created_at: Utc::now(),
+                last_accessed: None,
+            }).unwrap();
+        }
+        db.insert_module(&Module {
+            id: "module-1".to_string(),
+            course_id: "course-1".to_string(),
+            name: "Módulo".to_string(),
+            path: "/cursos/course-1/modulo".to_string(),
+            order_index: 0,
+        }).unwrap();
+        for (index, video_id) in ["video-1", "video-2", "video-3"].iter().enumerate() {
+            db.insert_video(&Video {
+                id: video_id.to_string(),
+                module_id: "module-1".to_string(),
+                course_id: "course-1".to_string(),
+                name: video_id.to_string(),
+                path: format!("/cursos/course-1/modulo/{}.mp4", video_id),
+                duration: None,
+                order_index: index as i32,
+            }).unwrap();
+        }
+
+        let state = AppState {
+            db: Mutex::new(db),
+            player: Mutex::new(VideoPlayer::new()),
+            backfill_cancel: AtomicBool::new(false),
+            cache: ListCache::default(),
+        };
+        let info = build_app_info(&state).unwrap();
+
+        assert_eq!(info.version, env!("CARGO_PKG_VERSION"));
+        assert_eq!(info.db_version, DATABASE_VERSION);
+        assert_eq!(info.total_courses, 2);
+        assert_eq!(info.total_videos, 3);
+        assert!(info.db_path.unwrap().ends_with("database.db"));
+    }
+
+    #[test]
+    fn test_database_location_is_under_app_data_dir() {
+        let data_dir = PathBuf::from("/home/aluno/.local/share");
+        assert_eq!(
+            db_path_in(Some(data_dir.clone())),
+            data_dir.join("ReprodLocal").join("database.db")
+        );
+        assert_eq!(db_path_in(None), PathBuf::from("database.db"));
+
+        // Sem chamar `get_db_path`, que cria a pasta
+        if let Some(data_dir) = dirs::data_dir() {
+            let db_path = db_path_in(Some(data_dir.clone()));
+            assert!(db_path.starts_with(&data_dir));
+            assert!(db_path.parent().unwrap().ends_with("ReprodLocal"));
+        }
+    }
+
+    #[test]
+    fn test_folder_tree_prunes_branches_without_media() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let course_dir = temp_dir.path().join("Curso");
+        for dir in ["01 Básico/Extras", "02 Avançado/Projeto/Final", "Materiais/PDFs", "Vazia"] {
+            std::fs::create_dir_all(course_dir.join(dir)).unwrap();
+        }
+        for file in ["intro.mp4", "01 Básico/b.mp4", "01 Básico/a.mkv", "02 Avançado/Projeto/Final/entrega.mp4"] {
+            std::fs::write(course_dir.join(file), "fake video content").unwrap();
+        }
+        std::fs::write(course_dir.join("01 Básico/Extras/slides.pdf"), "pdf").unwrap();
+        std::fs::write(course_dir.join("Materiais/PDFs/apostila.pdf"), "pdf").unwrap();
+
+        let db = Database::new(&temp_dir.path().join("test.db")).unwrap();
+        let tree = build_folder_tree(&course_dir, &FileSystemScanner::new(&db)).unwrap().unwrap();
+
+        fn shape(node: &FolderNode) -> String {
+            let files: Vec<&str> = node.media_files.iter().map(|f| f.name.as_str()).collect();
+            let children: Vec<String> = node.children.iter().map(shape).collect();
+            format!("{}[{}]{{{}}}", node.name, files.join(","), children.join(","))
+        }
+        assert_eq!(
+            shape(&tree),
+            "Curso[intro.mp4]{01 Básico[a.mkv,b.mp4]{},02 Avançado[]{Projeto[]{Final[entrega.mp4]{}}}}"
+        );
+
+        assert!(build_folder_tree(&course_dir.join("Materiais"), &FileSystemScanner::new(&db)).unwrap().is_none());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_folder_tree_skips_symlinked_folders() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let course_dir = temp_dir.path().join("Curso");
+        std::fs::create_dir_all(course_dir.join("Módulo")).unwrap();
+        std::fs::write(course_dir.join("Módulo/aula.mp4"), "fake video content").unwrap();
+        // Link de volta para a pasta do curso, que antes recursava sem parar
+        std::os::unix::fs::symlink(&course_dir, course_dir.join("Módulo/voltar")).unwrap();
+
+        let db = Database::new(&temp_dir.path().join("test.db")).unwrap();
+        let tree = build_folder_tree(&course_dir, &FileSystemScanner::new(&db)).unwrap().unwrap();
+        assert_eq!(tree.children.len(), 1);
+        assert!(tree.children[0].children.is_empty());
+        assert_eq!(tree.children[0].media_files.len(), 1);
+    }
+
+    #[test]
+    fn test_folder_pages_visit_each_file_once() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let root = temp_dir.path().join("Biblioteca");
+        let mut expected = Vec::new();
+        for index in 0..120 {
+            // Pastas e arquivos misturados em vários níveis
+            let dir = match index % 4 {
+                0 => root.clone(),
+                1 => root.join(format!("Modulo {}", index % 3)),
+                2 => root.join("Modulo 1").join(format!("Parte {}", index % 5)),
+                _ => root.join("a").join("b"),
+            };
+            std::fs::create_dir_all(&dir).unwrap();
+            let file = dir.join(format!("aula{:03}.mp4", index));
+            std::fs::write(&file, "fake video content").unwrap();
+            expected.push(file.to_string_lossy().to_string());
+        }
+        std::fs::write(root.join("notas.txt"), "texto").unwrap();
+
+        let db = Database::new(&temp_dir.path().join("test.db")).unwrap();
+        let scanner = FileSystemScanner::new(&db);
+        let mut visited = Vec::new();
+        let mut page_sizes = Vec::new();
+        let mut token: Option<String> = None;
+        loop {
+            let page = build_folder_page(&root, &scanner, token.as_deref(), 50).unwrap();
+            page_sizes.push(page.media_files.len());
+            visited.extend(page.media_files.into_iter().map(|f| f.path));
+            token = page.next_page_token;
+            if token.is_none() {
+                break;
+            }
+        }
+
+        assert_eq!(page_sizes, vec![50, 50, 20]);
+        let unique: std::collections::HashSet<&String> = visited.iter().collect();
+        assert_eq!(unique.len(), visited.len());
+        expected.sort();
+        visited.sort();
+        assert_eq!(visited, expected);
+        assert!(build_folder_page(&root, &scanner, None, 0).is_err());
+    }
+
+    #[test]
+    fn test_playlist_only_incomplete_skips_completed_videos() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let course_dir = temp_dir.path().join("Curso");
+        std::fs::create_dir_all(course_dir.join("02 Avançado")).unwrap();
+        for file in ["01-intro.mp4", "02-tipos.mp4", "02 Avançado/01-traits.mp4"] {
+            std::fs::write(course_dir.join(file), "fake video content").unwrap();
+        }
+
+        let db = Database::new(&temp_dir.path().join("test.db")).unwrap();
+        FileSystemScanner::new(&db).scan_directory(temp_dir.path()).unwrap();
+        // Arquivo novo, ainda não escaneado
+        std::fs::write(course_dir.join("03-extra.mp4"), "fake video content").unwrap();
+
+        for (file, completed) in [("01-intro.mp4", true), ("02-tipos.mp4", false), ("02 Avançado/01-traits.mp4", true)] {
+            let video = db.get_video_by_path(&course_dir.join(file).to_string_lossy()).unwrap().unwrap();
+            db.update_video_progress(&VideoProgress {
+                id: Uuid::new_v4().to_string(),
+                video_id: video.id,
+                current_time: 10.0,
+                duration: 60.0,
+                completed,
+                last_watched: Utc::now(),
+            }).unwrap();
+        }
+
+        let names = |only_incomplete| -> Vec<String> {
+            build_folder_playlist(&db, &course_dir, only_incomplete).unwrap()
+                .into_iter()
+                .map(|file| file.name)
+                .collect()
+        };
+        assert_eq!(names(false), vec!["01-intro.mp4", "01-traits.mp4", "02-tipos.mp4", "03-extra.mp4"]);
+        assert_eq!(names(true), vec!["02-tipos.mp4", "03-extra.mp4"]);
+    }
+}
@@ -1,10 +1,27 @@
-use rusqlite::{Connection, Result, params};
+use rusqlite::{Connection, OpenFlags, Result, params};
 use serde::{Deserialize, Serialize};
-use chrono::{DateTime, Utc};
+use serde::de::DeserializeOwned;
+use chrono::{DateTime, Datelike, Local, NaiveDate, TimeZone, Utc};
+use std::collections::HashSet;
 use std::path::Path;
+use regex::Regex;
+use std::thread;
+use std::time::Duration;
+use crate::error::AppError;
 
 // Versão atual do esquema do banco de dados
-const DATABASE_VERSION: i32 = 2;
+pub const DATABASE_VERSION: i32 = 20;
+
+// Módulos `m` das listas planas: fica de fora a pasta sem vídeos que só
+// agrupa submódulos, que aparece apenas em `get_course_module_tree`
+const FLAT_MODULE_CONDITION: &str = "(EXISTS (SELECT 1 FROM videos fv WHERE fv.module_id = m.id) 
+     OR NOT EXISTS (SELECT 1 FROM modules sub WHERE sub.parent_module_id = m.id))";
+
+// Colunas lidas por `map_notes_from_query`, na mesma ordem
+const NOTE_COLUMNS: &str = "id, video_id, course_id, module_id, timestamp, title, content, note_type, created_at, updated_at, color, bookmark_id";
+
+// Colunas lidas por `course_from_row`, na mesma ordem
+const COURSE_COLUMNS: &str = "id, name, path, created_at, last_accessed, cover_path, course_display_name, description";
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Course {
@@ -13,6 +30,10 @@ pub struct Course {
     pub path: String,
     pub created_at: DateTime<Utc>,
     pub last_accessed: Option<DateTime<Utc>>,
+    pub cover_path: Option<String>, // Imagem de capa detectada na pasta do curso
+    pub display_name: Option<String>, // Nome escolhido pelo usuário; sem ele vale `name` (da pasta)
+    #[serde(default)]
+    pub description: Option<String>, // Escrita pelo usuário; o escaneamento não altera
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -24,6 +45,33 @@ pub struct Module {
     pub order_index: i32,
 }
 
+// Linha do sumário do curso: o cabeçalho de um módulo ou um vídeo dele
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub enum OutlineItem {
+    Module { module: Module, video_count: i32 },
+    Video { video: Video, completed: bool, current_time: f64 },
+}
+
+// Posição do vídeo no curso para o cabeçalho do player ("Módulo 3 · Aula 7
+// de 12"). Índices a partir de 1
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct VideoLocation {
+    pub module_index: i32,
+    pub module_count: i32,
+    pub video_index_in_module: i32,
+    pub videos_in_module: i32,
+    pub global_index: i32, // Posição entre todos os vídeos do curso
+    pub global_count: i32,
+}
+
+// Módulo com seus submódulos, em `order_index`
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ModuleNode {
+    pub module: Module,
+    pub parent_module_id: Option<String>,
+    pub children: Vec<ModuleNode>,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Video {
     pub id: String,
@@ -57,6 +105,88 @@ pub struct UserNote {
     pub note_type: String, // "video", "course", "module", "general"
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    pub color: Option<String>, // Cor no formato #RRGGBB
+    #[serde(default)]
+    pub bookmark_id: Option<String>, // Bookmark ao qual a anotação está ligada
+}
+
+// Aceita cores no formato #RRGGBB (ou nenhuma cor)
+pub fn validate_note_color(color: Option<&str>) -> std::result::Result<(), AppError> {
+    match color {
+        None => Ok(()),
+        Some(c) if c.len() == 7 && c.starts_with('#') && c[1..].chars().all(|ch| ch.is_ascii_hexdigit()) => Ok(()),
+        Some(c) => Err(AppError::InvalidInput(format!("Cor inválida (use #RRGGBB): {}", c))),
+    }
+}
+
+// Tipos de anotação conhecidos
+pub const NOTE_TYPES: &[&str] = &["video", "course", "module", "general"];
+
+pub fn validate_note_type(note_type: &str) -> std::result::Result<(), AppError> {
+    if NOTE_TYPES.contains(&note_type) {
+        Ok(())
+    } else {
+        Err(AppError::InvalidInput(format!(
+            "Tipo de anotação inválido (use {}): {}", NOTE_TYPES.join(", "), note_type
+        )))
+    }
+}
+
+// Configurações que só aceitam alguns valores
+const SETTING_ALLOWED_VALUES: &[(&str, &[&str])] = &[
+    ("theme", &["dark", "light", "system"]),
+    ("log_level", &["error", "warn", "info", "debug", "trace", "off"]),
+];
+
+// Confere se o valor é válido para o `setting_type` ("string", "number",
+// "boolean" ou "json") e, nas configurações conhecidas, se é um valor aceito
+pub fn validate_setting(key: &str, value: &str, setting_type: &str) -> std::result::Result<(), AppError> {
+    let invalid = |expected: &str| Err(AppError::InvalidInput(format!(
+        "Valor inválido para {} (esperado {}): {}", key, expected, value
+    )));
+
+    match setting_type {
+        "string" => {}
+        "number" => {
+            if !value.trim().parse::<f64>().is_ok_and(f64::is_finite) {
+                return invalid("um número");
+            }
+        }
+        "boolean" => {
+            if value != "true" && value != "false" {
+                return invalid("true ou false");
+            }
+        }
+        "json" => {
+            if serde_json::from_str::<serde_json::Value>(value).is_err() {
+                return invalid("JSON");
+            }
+        }
+        other => {
+            return Err(AppError::InvalidInput(format!(
+                "Tipo de configuração inválido (use string, number, boolean ou json): {}", other
+            )));
+        }
+    }
+
+    if let Some((_, allowed)) = SETTING_ALLOWED_VALUES.iter().find(|(allowed_key, _)| *allowed_key == key) {
+        if !allowed.contains(&value) {
+            return invalid(&allowed.join(", "));
+        }
+    }
+    Ok(())
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct BulkNoteUpdate {
+    pub updated: usize,
+    pub missing_ids: Vec<String>, // Ids inexistentes ou de anotações excluídas
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ClearedAnnotations {
+    pub notes_deleted: usize,
+    pub bookmarks_deleted: usize,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -69,6 +199,13 @@ pub struct VideoBookmark {
     pub created_at: DateTime<Utc>,
 }
 
+// Marcadores em volta da posição atual, para os botões de pular no player
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AdjacentBookmarks {
+    pub previous: Option<VideoBookmark>,
+    pub next: Option<VideoBookmark>,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct UserSettings {
     pub id: String,
@@ -84,17 +221,368 @@ pub struct ActivityLog {
     pub activity_type: String, // "video_watched", "course_started", "note_created", etc.
     pub entity_id: String, // ID do vídeo, curso, etc.
     pub entity_type: String, // "video", "course", "module"
-    pub details: Option<String>, // JSON com detalhes adicionais
+    pub details: Option<String>, // JSON de `ActivityDetails` conforme o `activity_type`
     pub created_at: DateTime<Utc>,
 }
 
+impl ActivityLog {
+    // Interpreta `details` de acordo com o `activity_type`; registros antigos
+    // (texto livre) ou de tipos desconhecidos voltam como `Raw`
+    pub fn parsed_details(&self) -> Option<ActivityDetails> {
+        let details = self.details.as_ref()?;
+        let parsed = serde_json::from_str::<serde_json::Value>(details)
+            .ok()
+            .filter(|payload| payload.is_object())
+            .and_then(|payload| {
+                let mut tagged = serde_json::Map::new();
+                tagged.insert(self.activity_type.clone(), payload);
+                serde_json::from_value(serde_json::Value::Object(tagged)).ok()
+            });
+        Some(parsed.unwrap_or_else(|| ActivityDetails::Raw(details.clone())))
+    }
+}
+
+// Detalhes de cada tipo de atividade. O nome da variante em snake_case é o
+// `activity_type`; em `details` fica gravado só o objeto com os campos
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ActivityDetails {
+    VideoCompleted { percent: f64 },
+    VideoMarkedIncomplete {},
+    CourseRenamed { name: String },
+    ModulesMerged { merged_module_id: String, videos_moved: usize },
+    VideoMoved { from_module_id: String, to_module_id: String },
+    NoteCreated {
+        title: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        duplicated_from: Option<String>, // Anotação de origem, quando duplicada
+    },
+    NoteUpdated { title: String },
+    NoteDeleted {},
+    NoteRestored {},
+    BookmarkCreated { title: String },
+    BookmarkDeleted {},
+    BookmarkRestored {},
+    ProgressReset { cleared: usize },
+    CourseAnnotationsCleared { notes_deleted: usize, bookmarks_deleted: usize },
+    Raw(String), // Texto livre, sem estrutura conhecida
+}
+
+impl ActivityDetails {
+    // Valor a gravar em `ActivityLog.details`
+    pub fn to_details(&self) -> String {
+        if let ActivityDetails::Raw(text) = self {
+            return text.clone();
+        }
+        match serde_json::to_value(self) {
+            Ok(serde_json::Value::Object(tagged)) => tagged
+                .into_iter()
+                .next()
+                .map(|(_, payload)| payload.to_string())
+                .unwrap_or_default(),
+            _ => String::new(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct CourseDuration {
+    pub total_seconds: f64,
+    pub unknown_duration_count: i32, // Vídeos ainda sem duração conhecida
+}
+
+// Resumo de todo o acervo para a barra de status
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct GlobalSummary {
+    pub total_videos: i64,
+    pub completed_videos: i64,
+    pub percent: f64,
+    pub total_watch_seconds: f64, // Soma das posições salvas, como em `get_watch_history`
+    pub course_count: i64,
+}
+
+// Tempo que falta para terminar o curso, já dividido pela velocidade de reprodução
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct CourseTimeRemaining {
+    pub remaining_seconds: f64,
+    pub unknown_duration_count: i32, // Vídeos não concluídos sem duração conhecida, fora da soma
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct VideoRecommendation {
+    pub video: Video,
+    pub score: f64,
+    pub reason: String, // Explicação dos pesos que compuseram a pontuação
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AnnotatedVideo {
+    pub video: Video,
+    pub course_name: String,
+    pub note_count: i64,
+    pub bookmark_count: i64,
+}
+
+// Amostras de progresso mantidas por vídeo; as mais antigas são descartadas
+// a cada gravação, então a estimativa de tempo assistido só cobre as recentes
+const MAX_PROGRESS_SAMPLES_PER_VIDEO: i64 = 500;
+
+// Só o registro de progresso mais recente de cada vídeo, já que o comando
+// `update_video_progress` grava um novo a cada salvamento. Usado como tabela
+const LATEST_PROGRESS: &str = "(SELECT * FROM (
+        SELECT p.*, ROW_NUMBER() OVER (PARTITION BY p.video_id ORDER BY p.last_watched DESC, p.rowid DESC) AS latest_rank 
+        FROM video_progress p
+     ) WHERE latest_rank = 1)";
+
+// Duração fictícia gravada por `mark_video_completed` antes do vídeo ser reproduzido
+const PLACEHOLDER_PROGRESS_DURATION: f64 = 100.0;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct InconsistentProgress {
+    pub video: Video,
+    pub progress: VideoProgress,
+    pub reason: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ProgressRepair {
+    pub durations_fixed: usize,
+    pub positions_clamped: usize,
+}
+
+// Módulos sem curso e vídeos sem módulo ou curso, de antes das chaves
+// estrangeiras serem aplicadas. Traz só alguns exemplos de cada
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct OrphanReport {
+    pub module_count: usize,
+    pub video_count: usize,
+    pub sample_modules: Vec<Module>,
+    pub sample_videos: Vec<Video>,
+}
+
+const ORPHAN_SAMPLE_LIMIT: usize = 10;
+const ORPHAN_MODULE_CONDITION: &str = "NOT EXISTS (SELECT 1 FROM courses c WHERE c.id = modules.course_id)";
+const ORPHAN_VIDEO_CONDITION: &str = "NOT EXISTS (SELECT 1 FROM modules m INNER JOIN courses c ON c.id = m.course_id WHERE m.id = videos.module_id) 
+     OR NOT EXISTS (SELECT 1 FROM courses c WHERE c.id = videos.course_id)";
+
+// Alcance de `reset_progress`
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub enum ResetScope {
+    Video(String),
+    Module(String),
+    Course(String),
+}
+
+impl ResetScope {
+    // Id e tipo da entidade, no formato usado pelo log de atividades
+    pub fn entity(&self) -> (&str, &str) {
+        match self {
+            ResetScope::Video(id) => (id, "video"),
+            ResetScope::Module(id) => (id, "module"),
+            ResetScope::Course(id) => (id, "course"),
+        }
+    }
+}
+
+// Tudo o que o player precisa para abrir um vídeo
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct VideoDetail {
+    pub video: Video,
+    pub description: Option<String>, // Lida do arquivo de metadados ao escanear
+    pub module: Module,
+    pub course: Course,
+    pub progress: Option<VideoProgress>,
+    pub bookmarks: Vec<VideoBookmark>,
+    pub notes: Vec<UserNote>,
+    pub selected_subtitle_path: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct VideoOrganization {
+    pub modules_created: usize,
+    pub videos_moved: usize,
+    pub unmatched: usize, // Vídeos cujo nome não casa com o padrão, mantidos onde estavam
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct FavoriteVideo {
+    pub video: Video,
+    pub course_name: String, // Nome de exibição do curso, se houver
+}
+
+// Maior nota aceita por `set_video_rating`
+pub const MAX_VIDEO_RATING: u8 = 5;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RatedVideo {
+    pub video: Video,
+    pub course_name: String, // Nome de exibição do curso, se houver
+    pub rating: u8,
+}
+
+// Meia-vida padrão do peso de um acesso na tela inicial
+const DEFAULT_HOME_HALF_LIFE_DAYS: f64 = 7.0;
+// Peso de um curso concluído em relação a um não iniciado acessado na mesma época
+const HOME_FINISHED_WEIGHT: f64 = 0.1;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct HomeCourse {
+    pub course: Course,
+    pub percent: f64,
+    pub score: f64,
+}
+
+// Dias sem acesso, por padrão, para um curso concluído ser sugerido para arquivar
+const DEFAULT_ARCHIVE_SUGGESTION_DAYS: i64 = 90;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ArchiveCandidate {
+    pub course: Course,
+    pub percent: f64,
+    pub days_since_access: i64,
+}
+
+// Marcos de conclusão de um curso, para as conquistas da interface
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct CourseBadges {
+    pub total_videos: i32,
+    pub completed_videos: i32,
+    pub percent: f64,
+    pub started: bool, // Algum vídeo concluído ou em andamento
+    pub halfway: bool, // Pelo menos 50% concluído
+    pub completed: bool,
+    pub started_at: Option<DateTime<Utc>>, // Primeira atividade registrada nos vídeos do curso
+    pub completed_at: Option<DateTime<Utc>>, // Última conclusão de vídeo, com o curso concluído
+}
+
+// Números da página de detalhes do curso. Sem atividade, contagens zeradas e datas None
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct CourseActivitySummary {
+    pub note_count: i64,
+    pub bookmark_count: i64,
+    pub first_activity: Option<DateTime<Utc>>, // Registro mais antigo no log ou no progresso
+    pub last_activity: Option<DateTime<Utc>>,
+    pub percent: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct StreakStatus {
+    pub current_streak: i32, // Dias seguidos com vídeos assistidos, até hoje ou ontem
+    pub last_active_date: Option<NaiveDate>,
+    pub at_risk: bool, // Há sequência, mas nada foi assistido hoje
+}
+
+// Tentativas de `retry_on_busy` e espera inicial, dobrada a cada nova tentativa
+const BUSY_RETRY_ATTEMPTS: u32 = 5;
+const BUSY_RETRY_INITIAL_DELAY: Duration = Duration::from_millis(20);
+
+// Trechos de nome que indicam o vídeo de abertura de um curso
+const INTRO_VIDEO_KEYWORDS: &[&str] = &[
+    "intro", "welcome", "overview", "syllabus", "boas-vindas", "boas vindas", "bem-vindo", "bem vindo",
+    "apresentação", "apresentacao", "visão geral", "visao geral",
+];
+
+// Palavras comuns demais para a nuvem de palavras das anotações
+const NOTE_KEYWORD_STOPWORDS: &[&str] = &[
+    "a", "o", "as", "os", "um", "uma", "uns", "umas", "de", "do", "da", "dos", "das", "em", "no", "na",
+    "nos", "nas", "por", "para", "pra", "com", "sem", "e", "ou", "que", "se", "não", "nao", "mais", "mas",
+    "como", "ao", "aos", "é", "ser", "foi", "são", "isso", "isto", "esse", "essa", "este", "esta", "ele",
+    "ela", "eles", "elas", "seu", "sua", "muito", "também", "tambem", "já", "the", "and", "of", "to", "in",
+    "is", "it", "for", "on", "with",
+];
+
+// Pesos das recomendações (ver `get_recommendations`)
+const RECENT_COURSE_WEIGHT: f64 = 3.0;
+const MODULE_MOMENTUM_WEIGHT: f64 = 2.0;
+const IN_PROGRESS_WEIGHT: f64 = 1.0;
+
+// Tema da interface; "system" segue a preferência do sistema operacional
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Theme {
+    #[default]
+    Dark,
+    Light,
+    System,
+}
+
+impl Theme {
+    // Valor gravado na configuração `theme`
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Theme::Dark => "dark",
+            Theme::Light => "light",
+            Theme::System => "system",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "dark" => Some(Theme::Dark),
+            "light" => Some(Theme::Light),
+            "system" => Some(Theme::System),
+            _ => None,
+        }
+    }
+}
+
+// Configurações de reprodução lidas de uma vez pelo player; valores
+// inválidos no banco são trocados pelos padrões
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct PlaybackSettings {
+    pub auto_play_next: bool,
+    pub playback_speed: f64,
+    pub volume: f64,
+    pub auto_save_progress: bool,
+    pub auto_save_interval_seconds: u32,
+    pub completion_threshold: f64, // Fração do vídeo a partir da qual ele conta como concluído
+}
+
+impl Default for PlaybackSettings {
+    fn default() -> Self {
+        Self {
+            auto_play_next: true,
+            playback_speed: 1.0,
+            volume: 0.8,
+            auto_save_progress: true,
+            auto_save_interval_seconds: 10,
+            completion_threshold: 0.9,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+pub struct ProgressImport {
+    pub matched: usize, // Vídeos encontrados aqui pelo caminho
+    pub updated: usize, // Progressos gravados por serem mais recentes que os locais
+    pub skipped: usize, // Vídeos do outro banco que não existem aqui ou com data ilegível
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+pub struct ChecklistImport {
+    pub matched: usize, // Linhas que correspondem a um vídeo do curso
+    pub changed: usize, // Vídeos cuja conclusão foi alterada
+    pub unmatched: Vec<String>, // Nomes das linhas sem vídeo correspondente
+}
+
 pub struct Database {
     conn: Connection,
 }
 
 impl Database {
     pub fn new(db_path: &Path) -> Result<Self> {
+        Self::new_with_key(db_path, None)
+    }
+
+    // Abre o banco com uma chave SQLCipher opcional. A chave só tem efeito
+    // quando compilado com a feature `cipher`
+    pub fn new_with_key(db_path: &Path, key: Option<&str>) -> Result<Self> {
         let conn = Connection::open(db_path)?;
+        if let Some(key) = key {
+            conn.pragma_update(None, "key", key)?;
+            // Com a chave errada a primeira leitura falha com "file is not a database"
+            conn.query_row("SELECT COUNT(*) FROM sqlite_master", [], |row| row.get::<_, i64>(0))?;
+        }
         let db = Database { conn };
         
         // Inicializar ou migrar o banco de dados
@@ -103,6 +591,30 @@ impl Database {
         Ok(db)
     }
 
+    #[cfg(feature = "cipher")]
+    pub fn change_key(&self, old_key: &str, new_key: &str) -> std::result::Result<(), AppError> {
+        if new_key.is_empty() {
+            return Err(AppError::InvalidInput("A nova senha não pode ser vazia".to_string()));
+        }
+
+        // Confere a senha atual abrindo uma segunda conexão com ela
+        let db_path = self.conn.path()
+            .ok_or_else(|| AppError::InvalidInput("Banco em memória não pode ser criptografado".to_string()))?;
+        let check = Connection::open(db_path)?;
+        check.pragma_update(None, "key", old_key)?;
+        check.query_row("SELECT COUNT(*) FROM sqlite_master", [], |row| row.get::<_, i64>(0))
+            .map_err(|_| AppError::InvalidInput("Senha atual incorreta".to_string()))?;
+        drop(check);
+
+        self.conn.pragma_update(None, "rekey", new_key)?;
+        Ok(())
+    }
+
+    #[cfg(not(feature = "cipher"))]
+    pub fn change_key(&self, _old_key: &str, _new_key: &str) -> std::result::Result<(), AppError> {
+        Err(AppError::InvalidInput("Criptografia do banco não disponível nesta versão (feature `cipher`)".to_string()))
+    }
+
     fn initialize_database(&self) -> Result<()> {
         // Criar tabela de versão se não existir
         self.conn.execute(
@@ -111,6 +623,7 @@ impl Database {
             )",
             [],
         )?;
+        self.reconcile_version_tables()?;
 
         // Verificar versão atual
         let current_version = self.get_database_version()?;
@@ -127,7 +640,41 @@ impl Database {
         Ok(())
     }
 
-    fn get_database_version(&self) -> Result<i32> {
+    // Versões antigas do script de instalação (scripts/init_database.rs) gravavam
+    // a versão em `database_metadata`. Ela é trazida para `database_version`,
+    // a única fonte da versão, para o banco não ser tratado como novo
+    fn reconcile_version_tables(&self) -> Result<()> {
+        let has_metadata: bool = self.conn.query_row(
+            "SELECT EXISTS (SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'database_metadata')",
+            [],
+            |row| row.get(0),
+        )?;
+        if !has_metadata {
+            return Ok(());
+        }
+
+        let metadata_version = match self.conn.query_row(
+            "SELECT value FROM database_metadata WHERE key = 'version'",
+            [],
+            |row| row.get::<_, String>(0),
+        ) {
+            Ok(value) => value.trim().parse::<i32>().ok(),
+            Err(rusqlite::Error::QueryReturnedNoRows) => return Ok(()),
+            Err(e) => return Err(e),
+        };
+
+        if let Some(version) = metadata_version {
+            let current_version = self.get_database_version()?;
+            if version > current_version {
+                log::info!("🔧 Versão {} lida de database_metadata (database_version: {})", version, current_version);
+                self.set_database_version(version)?;
+            }
+        }
+        self.conn.execute("DELETE FROM database_metadata WHERE key = 'version'", [])?;
+        Ok(())
+    }
+
+    pub fn get_database_version(&self) -> Result<i32> {
         match self.conn.query_row(
             "SELECT version FROM database_version ORDER BY version DESC LIMIT 1",
             [],
@@ -138,6 +685,23 @@ impl Database {
         }
     }
 
+    // Reexecuta as migrações a partir da versão gravada, para recuperar um banco
+    // depois de uma atualização interrompida. As DDL usam IF NOT EXISTS e
+    // `add_column_if_missing`, então rodar de novo não altera um banco em dia.
+    // Retorna a versão final
+    pub fn force_migrate(&self) -> Result<i32> {
+        let stored_version = self.get_database_version()?;
+        log::warn!("🔧 Migração forçada a partir da versão {}", stored_version);
+
+        if stored_version == 0 {
+            self.create_tables()?;
+            self.set_database_version(DATABASE_VERSION)?;
+        } else {
+            self.migrate_database(stored_version, DATABASE_VERSION.max(stored_version))?;
+        }
+        self.get_database_version()
+    }
+
     fn set_database_version(&self, version: i32) -> Result<()> {
         self.conn.execute(
             "INSERT OR REPLACE INTO database_version (version) VALUES (?1)",
@@ -147,16 +711,106 @@ impl Database {
     }
 
     fn migrate_database(&self, from_version: i32, to_version: i32) -> Result<()> {
-        println!("🔄 Migrando banco de dados da versão {} para {}", from_version, to_version);
+        log::info!("🔄 Migrando banco de dados da versão {} para {}", from_version, to_version);
         
         // Migração da versão 1 para 2 (adicionar novas tabelas)
         if from_version < 2 {
             self.create_new_tables_v2()?;
         }
 
+        // Migração da versão 2 para 3 (capa dos cursos)
+        if from_version < 3 {
+            self.migrate_to_v3()?;
+        }
+
+        // Migração da versão 3 para 4 (cache da duração total do curso)
+        if from_version < 4 {
+            self.migrate_to_v4()?;
+        }
+
+        // Migração da versão 4 para 5 (nome de exibição dos cursos)
+        if from_version < 5 {
+            self.migrate_to_v5()?;
+        }
+
+        // Migração da versão 5 para 6 (exclusão reversível de anotações e bookmarks)
+        if from_version < 6 {
+            self.migrate_to_v6()?;
+        }
+
+        // Migração da versão 6 para 7 (cor das anotações)
+        if from_version < 7 {
+            self.migrate_to_v7()?;
+        }
+
+        // Migração da versão 7 para 8 (vídeos favoritos)
+        if from_version < 8 {
+            self.migrate_to_v8()?;
+        }
+
+        // Migração da versão 8 para 9 (descrição dos vídeos)
+        if from_version < 9 {
+            self.migrate_to_v9()?;
+        }
+
+        // Migração da versão 9 para 10 (anotações ligadas a bookmarks)
+        if from_version < 10 {
+            self.migrate_to_v10()?;
+        }
+
+        // Migração da versão 10 para 11 (legenda escolhida por vídeo)
+        if from_version < 11 {
+            self.migrate_to_v11()?;
+        }
+
+        // Migração da versão 11 para 12 (hash do conteúdo dos vídeos)
+        if from_version < 12 {
+            self.migrate_to_v12()?;
+        }
+
+        // Migração da versão 12 para 13 (conclusões marcadas à mão)
+        if from_version < 13 {
+            self.migrate_to_v13()?;
+        }
+
+        // Migração da versão 13 para 14 (submódulos)
+        if from_version < 14 {
+            self.migrate_to_v14()?;
+        }
+
+        // Migração da versão 14 para 15 (descrição dos cursos)
+        if from_version < 15 {
+            self.migrate_to_v15()?;
+        }
+
+        // Migração da versão 15 para 16 (avaliação dos vídeos)
+        if from_version < 16 {
+            self.migrate_to_v16()?;
+        }
+
+        // Migração da versão 16 para 17 (amostras de progresso)
+        if from_version < 17 {
+            self.migrate_to_v17()?;
+        }
+
+        // Migração da versão 17 para 18 (associações opcionais das anotações)
+        if from_version < 18 {
+            self.migrate_to_v18()?;
+        }
+
+        // Migração da versão 18 para 19 (tamanho dos arquivos de vídeo)
+        if from_version < 19 {
+            self.migrate_to_v19()?;
+        }
+
+        // Migração da versão 19 para 20 (limpeza das amostras de progresso por vídeo)
+        if from_version < 20 {
+            self.migrate_to_v20()?;
+        }
+
         // Atualizar versão
         self.set_database_version(to_version)?;
-        println!("✅ Migração concluída com sucesso!");
+        log::info!("✅ Migração concluída com sucesso!");
         
         Ok(())
     }
@@ -216,6 +870,26 @@ impl Database {
         // Criar novas tabelas da versão 2
         self.create_new_tables_v2()?;
 
+        // Aplicar alterações de colunas das versões seguintes
+        self.migrate_to_v3()?;
+        self.migrate_to_v4()?;
+        self.migrate_to_v5()?;
+        self.migrate_to_v6()?;
+        self.migrate_to_v7()?;
+        self.migrate_to_v8()?;
+        self.migrate_to_v9()?;
+        self.migrate_to_v10()?;
+        self.migrate_to_v11()?;
+        self.migrate_to_v12()?;
+        self.migrate_to_v13()?;
+        self.migrate_to_v14()?;
+        self.migrate_to_v15()?;
+        self.migrate_to_v16()?;
+        self.migrate_to_v17()?;
+        self.migrate_to_v18()?;
+        self.migrate_to_v19()?;
+        self.migrate_to_v20()?;
+
         Ok(())
     }
 
@@ -285,53 +959,243 @@ impl Database {
         Ok(())
     }
 
-    fn create_indexes(&self) -> Result<()> {
-        // Índices para melhor performance nas consultas
-        self.conn.execute("CREATE INDEX IF NOT EXISTS idx_user_notes_video_id ON user_notes(video_id)", [])?;
-        self.conn.execute("CREATE INDEX IF NOT EXISTS idx_user_notes_course_id ON user_notes(course_id)", [])?;
-        self.conn.execute("CREATE INDEX IF NOT EXISTS idx_user_notes_module_id ON user_notes(module_id)", [])?;
-        self.conn.execute("CREATE INDEX IF NOT EXISTS idx_user_notes_type ON user_notes(note_type)", [])?;
-        
-        self.conn.execute("CREATE INDEX IF NOT EXISTS idx_video_bookmarks_video_id ON video_bookmarks(video_id)", [])?;
-        self.conn.execute("CREATE INDEX IF NOT EXISTS idx_video_bookmarks_timestamp ON video_bookmarks(timestamp)", [])?;
-        
-        self.conn.execute("CREATE INDEX IF NOT EXISTS idx_user_settings_key ON user_settings(setting_key)", [])?;
-        
-        self.conn.execute("CREATE INDEX IF NOT EXISTS idx_activity_log_type ON activity_log(activity_type)", [])?;
-        self.conn.execute("CREATE INDEX IF NOT EXISTS idx_activity_log_entity ON activity_log(entity_id, entity_type)", [])?;
-        self.conn.execute("CREATE INDEX IF NOT EXISTS idx_activity_log_created_at ON activity_log(created_at)", [])?;
+    fn migrate_to_v3(&self) -> Result<()> {
+        self.add_column_if_missing("courses", "cover_path", "TEXT")
+    }
+
+    fn migrate_to_v4(&self) -> Result<()> {
+        self.add_column_if_missing("courses", "total_duration", "REAL")
+    }
+
+    fn migrate_to_v5(&self) -> Result<()> {
+        self.add_column_if_missing("courses", "course_display_name", "TEXT")
+    }
+
+    fn migrate_to_v6(&self) -> Result<()> {
+        self.add_column_if_missing("user_notes", "deleted_at", "TEXT")?;
+        self.add_column_if_missing("video_bookmarks", "deleted_at", "TEXT")
+    }
+
+    fn migrate_to_v7(&self) -> Result<()> {
+        self.add_column_if_missing("user_notes", "color", "TEXT")
+    }
+
+    fn migrate_to_v8(&self) -> Result<()> {
+        self.add_column_if_missing("videos", "is_favorite", "BOOLEAN DEFAULT 0")
+    }
+
+    fn migrate_to_v9(&self) -> Result<()> {
+        self.add_column_if_missing("videos", "description", "TEXT")
+    }
 
+    // Bookmark removido de vez desfaz a ligação, sem apagar a anotação
+    fn migrate_to_v10(&self) -> Result<()> {
+        self.add_column_if_missing("user_notes", "bookmark_id", "TEXT REFERENCES video_bookmarks(id) ON DELETE SET NULL")?;
+        self.conn.execute("CREATE INDEX IF NOT EXISTS idx_user_notes_bookmark ON user_notes(bookmark_id)", [])?;
         Ok(())
     }
 
-    pub fn insert_course(&self, course: &Course) -> Result<()> {
+    // Tabela própria: o upsert de `video_progress` substitui a linha inteira
+    fn migrate_to_v11(&self) -> Result<()> {
         self.conn.execute(
-            "INSERT OR REPLACE INTO courses (id, name, path, created_at, last_accessed) 
-             VALUES (?1, ?2, ?3, ?4, ?5)",
-            params![
-                course.id,
-                course.name,
-                course.path,
-                course.created_at.to_rfc3339(),
-                course.last_accessed.map(|dt| dt.to_rfc3339())
-            ],
+            "CREATE TABLE IF NOT EXISTS video_subtitle_choice (
+                video_id TEXT PRIMARY KEY,
+                subtitle_path TEXT NOT NULL,
+                updated_at TEXT NOT NULL,
+                FOREIGN KEY (video_id) REFERENCES videos (id) ON DELETE CASCADE
+            )",
+            [],
         )?;
         Ok(())
     }
 
-    pub fn insert_module(&self, module: &Module) -> Result<()> {
+    // Guardado como "algoritmo:hex", ex.: "sha256:d7a8..."
+    fn migrate_to_v12(&self) -> Result<()> {
+        self.add_column_if_missing("videos", "content_hash", "TEXT")
+    }
+
+    // Progresso criado só pelo "marcar como concluído", com posição e duração
+    // fictícias; o upsert de `update_video_progress` volta a coluna para 0
+    fn migrate_to_v13(&self) -> Result<()> {
+        self.add_column_if_missing("video_progress", "completed_manually", "BOOLEAN NOT NULL DEFAULT 0")
+    }
+
+    // Módulo pai, para pastas dentro de pastas; NULL nos módulos de primeiro nível
+    fn migrate_to_v14(&self) -> Result<()> {
+        self.add_column_if_missing("modules", "parent_module_id", "TEXT REFERENCES modules(id) ON DELETE SET NULL")?;
+        self.conn.execute("CREATE INDEX IF NOT EXISTS idx_modules_parent ON modules(parent_module_id)", [])?;
+        Ok(())
+    }
+
+    // Bancos criados pelo script de instalação já têm a coluna
+    fn migrate_to_v15(&self) -> Result<()> {
+        self.add_column_if_missing("courses", "description", "TEXT")
+    }
+
+    // Nota de 1 a 5; NULL enquanto o vídeo não foi avaliado
+    fn migrate_to_v16(&self) -> Result<()> {
+        self.add_column_if_missing("videos", "rating", "INTEGER")
+    }
+
+    // Uma linha por chamada de `update_video_progress`, já que `video_progress`
+    // só guarda a posição mais recente de cada vídeo
+    fn migrate_to_v17(&self) -> Result<()> {
         self.conn.execute(
-            "INSERT OR REPLACE INTO modules (id, course_id, name, path, order_index) 
-             VALUES (?1, ?2, ?3, ?4, ?5)",
-            params![module.id, module.course_id, module.name, module.path, module.order_index],
+            "CREATE TABLE IF NOT EXISTS progress_samples (
+                video_id TEXT NOT NULL,
+                position REAL NOT NULL,
+                recorded_at TEXT NOT NULL,
+                FOREIGN KEY (video_id) REFERENCES videos (id) ON DELETE CASCADE
+            )",
+            [],
         )?;
+        self.conn.execute("CREATE INDEX IF NOT EXISTS idx_progress_samples_recorded_at ON progress_samples(recorded_at)", [])?;
         Ok(())
     }
 
-    pub fn insert_video(&self, video: &Video) -> Result<()> {
-        self.conn.execute(
-            "INSERT OR REPLACE INTO videos (id, module_id, course_id, name, path, duration, order_index) 
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+    // Anotações de curso ou gerais não têm vídeo (nem módulo, nem posição).
+    // SQLite não remove NOT NULL de uma coluna, então a tabela é recriada e os
+    // valores vazios gravados até aqui viram NULL
+    fn migrate_to_v18(&self) -> Result<()> {
+        let video_id_required: bool = self.conn.query_row(
+            "SELECT \"notnull\" FROM pragma_table_info('user_notes') WHERE name = 'video_id'",
+            [],
+            |row| row.get(0),
+        )?;
+        if !video_id_required {
+            return Ok(());
+        }
+
+        let foreign_keys: bool = self.conn.query_row("PRAGMA foreign_keys", [], |row| row.get(0))?;
+        self.conn.execute("PRAGMA foreign_keys = OFF", [])?;
+        let rebuilt = self.conn.execute_batch(
+            "BEGIN;
+             CREATE TABLE user_notes_v18 (
+                id TEXT PRIMARY KEY,
+                video_id TEXT,
+                course_id TEXT,
+                module_id TEXT,
+                timestamp REAL,
+                title TEXT NOT NULL,
+                content TEXT NOT NULL,
+                note_type TEXT NOT NULL DEFAULT 'general',
+                created_at TEXT NOT NULL,
+                updated_at TEXT NOT NULL,
+                deleted_at TEXT,
+                color TEXT,
+                bookmark_id TEXT REFERENCES video_bookmarks(id) ON DELETE SET NULL,
+                FOREIGN KEY(video_id) REFERENCES videos(id),
+                FOREIGN KEY(course_id) REFERENCES courses(id),
+                FOREIGN KEY(module_id) REFERENCES modules(id)
+             );
+             INSERT INTO user_notes_v18 (id, video_id, course_id, module_id, timestamp, title, content, note_type, 
+                                         created_at, updated_at, deleted_at, color, bookmark_id) 
+                SELECT id, NULLIF(video_id, ''), NULLIF(course_id, ''), NULLIF(module_id, ''), timestamp, title, content, note_type, 
+                       created_at, updated_at, deleted_at, color, bookmark_id 
+                FROM user_notes;
+             DROP TABLE user_notes;
+             ALTER TABLE user_notes_v18 RENAME TO user_notes;
+             CREATE INDEX IF NOT EXISTS idx_user_notes_video_id ON user_notes(video_id);
+             CREATE INDEX IF NOT EXISTS idx_user_notes_course_id ON user_notes(course_id);
+             CREATE INDEX IF NOT EXISTS idx_user_notes_module_id ON user_notes(module_id);
+             CREATE INDEX IF NOT EXISTS idx_user_notes_type ON user_notes(note_type);
+             CREATE INDEX IF NOT EXISTS idx_user_notes_bookmark ON user_notes(bookmark_id);
+             COMMIT;"
+        );
+        if rebuilt.is_err() {
+            self.conn.execute_batch("ROLLBACK").ok();
+        }
+        self.conn.pragma_update(None, "foreign_keys", foreign_keys)?;
+        rebuilt
+    }
+
+    // Em bytes, gravado pelo scan; NULL até o próximo scan do curso
+    fn migrate_to_v19(&self) -> Result<()> {
+        self.add_column_if_missing("videos", "file_size", "INTEGER")
+    }
+
+    // `update_video_progress` descarta as amostras antigas de cada vídeo
+    fn migrate_to_v20(&self) -> Result<()> {
+        self.conn.execute("CREATE INDEX IF NOT EXISTS idx_progress_samples_video ON progress_samples(video_id, recorded_at)", [])?;
+        Ok(())
+    }
+
+    // ALTER TABLE não tem IF NOT EXISTS, então verificamos a coluna antes
+    fn add_column_if_missing(&self, table: &str, column: &str, definition: &str) -> Result<()> {
+        let mut stmt = self.conn.prepare(&format!("PRAGMA table_info({})", table))?;
+        let exists = stmt
+            .query_map([], |row| row.get::<_, String>(1))?
+            .filter_map(|name| name.ok())
+            .any(|name| name == column);
+
+        if !exists {
+            self.conn.execute(
+                &format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, definition),
+                [],
+            )?;
+        }
+        Ok(())
+    }
+
+    fn create_indexes(&self) -> Result<()> {
+        // Índices para melhor performance nas consultas
+        self.conn.execute("CREATE INDEX IF NOT EXISTS idx_user_notes_video_id ON user_notes(video_id)", [])?;
+        self.conn.execute("CREATE INDEX IF NOT EXISTS idx_user_notes_course_id ON user_notes(course_id)", [])?;
+        self.conn.execute("CREATE INDEX IF NOT EXISTS idx_user_notes_module_id ON user_notes(module_id)", [])?;
+        self.conn.execute("CREATE INDEX IF NOT EXISTS idx_user_notes_type ON user_notes(note_type)", [])?;
+        
+        self.conn.execute("CREATE INDEX IF NOT EXISTS idx_video_bookmarks_video_id ON video_bookmarks(video_id)", [])?;
+        self.conn.execute("CREATE INDEX IF NOT EXISTS idx_video_bookmarks_timestamp ON video_bookmarks(timestamp)", [])?;
+        
+        self.conn.execute("CREATE INDEX IF NOT EXISTS idx_user_settings_key ON user_settings(setting_key)", [])?;
+        
+        self.conn.execute("CREATE INDEX IF NOT EXISTS idx_activity_log_type ON activity_log(activity_type)", [])?;
+        self.conn.execute("CREATE INDEX IF NOT EXISTS idx_activity_log_entity ON activity_log(entity_id, entity_type)", [])?;
+        self.conn.execute("CREATE INDEX IF NOT EXISTS idx_activity_log_created_at ON activity_log(created_at)", [])?;
+
+        Ok(())
+    }
+
+    pub fn insert_course(&self, course: &Course) -> Result<()> {
+        retry_on_busy(|| self.conn.execute(
+            "INSERT INTO courses (id, name, path, created_at, last_accessed, cover_path) 
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+             ON CONFLICT(id) DO UPDATE SET 
+                name = excluded.name, path = excluded.path, 
+                last_accessed = excluded.last_accessed, cover_path = excluded.cover_path",
+            params![
+                course.id,
+                course.name,
+                course.path,
+                course.created_at.to_rfc3339(),
+                course.last_accessed.map(|dt| dt.to_rfc3339()),
+                course.cover_path
+            ],
+        ))?;
+        Ok(())
+    }
+
+    pub fn insert_module(&self, module: &Module) -> Result<()> {
+        retry_on_busy(|| self.conn.execute(
+            "INSERT INTO modules (id, course_id, name, path, order_index) 
+             VALUES (?1, ?2, ?3, ?4, ?5)
+             ON CONFLICT(id) DO UPDATE SET 
+                course_id = excluded.course_id, name = excluded.name, 
+                path = excluded.path, order_index = excluded.order_index",
+            params![module.id, module.course_id, module.name, module.path, module.order_index],
+        ))?;
+        Ok(())
+    }
+
+    pub fn insert_video(&self, video: &Video) -> Result<()> {
+        retry_on_busy(|| self.conn.execute(
+            "INSERT INTO videos (id, module_id, course_id, name, path, duration, order_index) 
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+             ON CONFLICT(id) DO UPDATE SET 
+                module_id = excluded.module_id, course_id = excluded.course_id, 
+                name = excluded.name, path = excluded.path, 
+                duration = COALESCE(excluded.duration, videos.duration), 
+                order_index = excluded.order_index",
             params![
                 video.id,
                 video.module_id,
@@ -341,12 +1205,102 @@ impl Database {
                 video.duration,
                 video.order_index
             ],
+        ))?;
+
+        // A duração total em cache do curso deixa de valer
+        retry_on_busy(|| self.conn.execute(
+            "UPDATE courses SET total_duration = NULL WHERE id = ?1",
+            params![video.course_id],
+        ))?;
+        Ok(())
+    }
+
+    // Vídeos ainda sem duração conhecida, de um curso ou de todos
+    pub fn get_videos_without_duration(&self, course_id: Option<&str>) -> Result<Vec<Video>> {
+        Ok(self.get_videos(course_id)?
+            .into_iter()
+            .filter(|video| video.duration.is_none())
+            .collect())
+    }
+
+    // Grava a duração lida do arquivo, sem sobrescrever uma já conhecida
+    pub fn set_video_duration(&self, video_id: &str, duration: f64) -> Result<()> {
+        let updated = retry_on_busy(|| self.conn.execute(
+            "UPDATE videos SET duration = ?1 WHERE id = ?2 AND duration IS NULL",
+            params![duration, video_id],
+        ))?;
+
+        if updated > 0 {
+            // A duração total em cache do curso deixa de valer
+            retry_on_busy(|| self.conn.execute(
+                "UPDATE courses SET total_duration = NULL WHERE id = (SELECT course_id FROM videos WHERE id = ?1)",
+                params![video_id],
+            ))?;
+        }
+        Ok(())
+    }
+
+    pub fn set_video_content_hash(&self, video_id: &str, content_hash: &str) -> Result<()> {
+        self.conn.execute(
+            "UPDATE videos SET content_hash = ?1 WHERE id = ?2",
+            params![content_hash, video_id],
         )?;
         Ok(())
     }
 
+    pub fn set_video_description(&self, video_id: &str, description: Option<&str>) -> Result<()> {
+        retry_on_busy(|| self.conn.execute(
+            "UPDATE videos SET description = ?1 WHERE id = ?2",
+            params![description, video_id],
+        ))?;
+        Ok(())
+    }
+
+    pub fn set_video_file_size(&self, video_id: &str, file_size: Option<u64>) -> Result<()> {
+        retry_on_busy(|| self.conn.execute(
+            "UPDATE videos SET file_size = ?1 WHERE id = ?2",
+            params![file_size.map(|size| size as i64), video_id],
+        ))?;
+        Ok(())
+    }
+
+    // Maiores arquivos do acervo, para liberar espaço. Vídeos sem tamanho
+    // conhecido (ainda não escaneados de novo) vêm por último, com tamanho 0
+    pub fn get_largest_videos(&self, limit: usize) -> Result<Vec<(Video, u64, String)>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT v.id, v.module_id, v.course_id, v.name, v.path, v.duration, v.order_index, 
+                    COALESCE(v.file_size, 0), COALESCE(c.course_display_name, c.name) 
+             FROM videos v 
+             INNER JOIN courses c ON c.id = v.course_id 
+             ORDER BY v.file_size IS NULL, v.file_size DESC, v.path 
+             LIMIT ?1"
+        )?;
+
+        let video_iter = stmt.query_map(params![limit as i64], |row| {
+            Ok((
+                Video {
+                    id: row.get(0)?,
+                    module_id: row.get(1)?,
+                    course_id: row.get(2)?,
+                    name: row.get(3)?,
+                    path: row.get(4)?,
+                    duration: row.get(5)?,
+                    order_index: row.get(6)?,
+                },
+                row.get::<_, i64>(7)?.max(0) as u64,
+                row.get(8)?,
+            ))
+        })?;
+
+        let mut videos = Vec::new();
+        for video in video_iter {
+            videos.push(video?);
+        }
+        Ok(videos)
+    }
+
     pub fn update_video_progress(&self, progress: &VideoProgress) -> Result<()> {
-        self.conn.execute(
+        retry_on_busy(|| self.conn.execute(
             "INSERT OR REPLACE INTO video_progress (id, video_id, current_time, duration, completed, last_watched) 
              VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
             params![
@@ -357,28 +1311,62 @@ impl Database {
                 progress.completed,
                 progress.last_watched.to_rfc3339()
             ],
-        )?;
+        ))?;
+        retry_on_busy(|| self.conn.execute(
+            "INSERT INTO progress_samples (video_id, position, recorded_at) VALUES (?1, ?2, ?3)",
+            params![progress.video_id, progress.current_time, progress.last_watched.to_rfc3339()],
+        ))?;
+        retry_on_busy(|| self.conn.execute(
+            "DELETE FROM progress_samples WHERE video_id = ?1 AND rowid NOT IN (
+                SELECT rowid FROM progress_samples WHERE video_id = ?1 
+                ORDER BY recorded_at DESC, rowid DESC LIMIT ?2
+             )",
+            params![progress.video_id, MAX_PROGRESS_SAMPLES_PER_VIDEO],
+        ))?;
         Ok(())
     }
 
     pub fn get_all_courses(&self) -> Result<Vec<Course>> {
         let mut stmt = self.conn.prepare(
-            "SELECT id, name, path, created_at, last_accessed FROM courses ORDER BY last_accessed DESC, name"
+            &format!("SELECT {} FROM courses ORDER BY last_accessed DESC, COALESCE(course_display_name, name)", COURSE_COLUMNS)
         )?;
         
+        let course_iter = stmt.query_map([], Self::course_from_row)?;
+
+        let mut courses = Vec::new();
+        for course in course_iter {
+            courses.push(course?);
+        }
+        Ok(courses)
+    }
+
+    // Cursos com total de vídeos, concluídos e percentual, numa única consulta
+    pub fn get_courses_with_progress(&self) -> Result<Vec<(Course, i32, i32, f64)>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {}, COALESCE(stats.total, 0), COALESCE(stats.completed, 0)
+             FROM courses
+             LEFT JOIN (
+                SELECT v.course_id,
+                       COUNT(DISTINCT v.id) AS total,
+                       COUNT(DISTINCT CASE WHEN vp.completed = 1 THEN v.id END) AS completed
+                FROM videos v
+                LEFT JOIN video_progress vp ON vp.video_id = v.id
+                GROUP BY v.course_id
+             ) stats ON stats.course_id = courses.id
+             ORDER BY last_accessed DESC, COALESCE(course_display_name, name)",
+            COURSE_COLUMNS
+        ))?;
+
         let course_iter = stmt.query_map([], |row| {
-            Ok(Course {
-                id: row.get(0)?,
-                name: row.get(1)?,
-                path: row.get(2)?,
-                created_at: DateTime::parse_from_rfc3339(&row.get::<_, String>(3)?)
-                    .map_err(|_| rusqlite::Error::InvalidColumnType(3, "created_at".to_string(), rusqlite::types::Type::Text))?
-                    .with_timezone(&Utc),
-                last_accessed: row.get::<_, Option<String>>(4)?
-                    .map(|s| DateTime::parse_from_rfc3339(&s).ok())
-                    .flatten()
-                    .map(|dt| dt.with_timezone(&Utc)),
-            })
+            let course = Self::course_from_row(row)?;
+            let total: i32 = row.get(8)?;
+            let completed: i32 = row.get(9)?;
+            let percent = if total > 0 {
+                completed as f64 * 100.0 / total as f64
+            } else {
+                0.0
+            };
+            Ok((course, total, completed, percent))
         })?;
 
         let mut courses = Vec::new();
@@ -388,10 +1376,170 @@ impl Database {
         Ok(courses)
     }
 
-    pub fn get_course_modules(&self, course_id: &str) -> Result<Vec<Module>> {
-        let mut stmt = self.conn.prepare(
-            "SELECT id, course_id, name, path, order_index FROM modules WHERE course_id = ?1 ORDER BY order_index"
+    // Cursos da tela inicial: o acesso recente pesa com decaimento exponencial
+    // (meia-vida na configuração `home_decay_half_life_days`) e o progresso
+    // ajusta o peso, para que os cursos em andamento subam e os concluídos desçam
+    pub fn get_home_courses(&self, limit: usize) -> Result<Vec<HomeCourse>> {
+        let half_life_days = self.get_user_setting("home_decay_half_life_days")?
+            .and_then(|setting| setting.setting_value.trim().parse::<f64>().ok())
+            .filter(|days| days.is_finite() && *days > 0.0)
+            .unwrap_or(DEFAULT_HOME_HALF_LIFE_DAYS);
+        self.get_home_courses_at(Utc::now(), limit, half_life_days)
+    }
+
+    fn get_home_courses_at(&self, now: DateTime<Utc>, limit: usize, half_life_days: f64) -> Result<Vec<HomeCourse>> {
+        let mut courses: Vec<HomeCourse> = self.get_courses_with_progress()?
+            .into_iter()
+            .map(|(course, total, completed, percent)| {
+                let recency = course.last_accessed.map_or(0.0, |accessed| {
+                    let age_days = (now - accessed).num_seconds().max(0) as f64 / 86_400.0;
+                    0.5_f64.powf(age_days / half_life_days)
+                });
+                let progress_weight = if total > 0 && completed == total {
+                    HOME_FINISHED_WEIGHT
+                } else {
+                    1.0 + percent / 100.0
+                };
+                HomeCourse { course, percent, score: recency * progress_weight }
+            })
+            .collect();
+
+        // Ordenação estável: empates mantêm a ordem de `get_courses_with_progress`
+        courses.sort_by(|a, b| b.score.total_cmp(&a.score));
+        courses.truncate(limit);
+        Ok(courses)
+    }
+
+    // Cursos concluídos sem acesso há mais de `archive_suggestion_days` dias,
+    // os mais esquecidos primeiro. Curso nunca aberto conta desde a criação
+    pub fn get_archive_candidates(&self) -> Result<Vec<ArchiveCandidate>> {
+        let min_days = self.get_user_setting("archive_suggestion_days")?
+            .and_then(|setting| setting.setting_value.trim().parse::<i64>().ok())
+            .filter(|days| *days >= 0)
+            .unwrap_or(DEFAULT_ARCHIVE_SUGGESTION_DAYS);
+        self.get_archive_candidates_at(Utc::now(), min_days)
+    }
+
+    fn get_archive_candidates_at(&self, now: DateTime<Utc>, min_days: i64) -> Result<Vec<ArchiveCandidate>> {
+        let mut candidates: Vec<ArchiveCandidate> = self.get_courses_with_progress()?
+            .into_iter()
+            .filter(|(_, total, completed, _)| *total > 0 && completed == total)
+            .filter_map(|(course, _, _, percent)| {
+                let accessed = course.last_accessed.unwrap_or(course.created_at);
+                let days_since_access = (now - accessed).num_days();
+                (days_since_access > min_days).then_some(ArchiveCandidate { course, percent, days_since_access })
+            })
+            .collect();
+
+        candidates.sort_by_key(|c| std::cmp::Reverse(c.days_since_access));
+        Ok(candidates)
+    }
+
+    pub fn get_course_by_id(&self, course_id: &str) -> Result<Option<Course>> {
+        let result = self.conn.query_row(
+            &format!("SELECT {} FROM courses WHERE id = ?1", COURSE_COLUMNS),
+            params![course_id],
+            Self::course_from_row,
+        );
+
+        match result {
+            Ok(course) => Ok(Some(course)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    pub fn get_course_by_path(&self, course_path: &str) -> Result<Option<Course>> {
+        let result = self.conn.query_row(
+            &format!("SELECT {} FROM courses WHERE path = ?1", COURSE_COLUMNS),
+            params![course_path],
+            Self::course_from_row,
+        );
+
+        match result {
+            Ok(course) => Ok(Some(course)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn course_from_row(row: &rusqlite::Row) -> Result<Course> {
+        Ok(Course {
+            id: row.get(0)?,
+            name: row.get(1)?,
+            path: row.get(2)?,
+            created_at: DateTime::parse_from_rfc3339(&row.get::<_, String>(3)?)
+                .map_err(|_| rusqlite::Error::InvalidColumnType(3, "created_at".to_string(), rusqlite::types::Type::Text))?
+                .with_timezone(&Utc),
+            last_accessed: row.get::<_, Option<String>>(4)?
+                .and_then(|s| DateTime::parse_from_rfc3339(&s).ok())
+                .map(|dt| dt.with_timezone(&Utc)),
+            cover_path: row.get(5)?,
+            display_name: row.get(6)?,
+            description: row.get(7)?,
+        })
+    }
+
+    // Descrição do curso; vazia remove
+    pub fn set_course_description(&self, course_id: &str, description: &str) -> std::result::Result<(), AppError> {
+        let description = Some(description.trim()).filter(|text| !text.is_empty());
+        let updated = self.conn.execute(
+            "UPDATE courses SET description = ?1 WHERE id = ?2",
+            params![description, course_id],
         )?;
+        if updated == 0 {
+            return Err(AppError::NotFound(format!("Curso não encontrado: {}", course_id)));
+        }
+        Ok(())
+    }
+
+    // Define o nome de exibição do curso; vazio volta a usar o nome da pasta
+    pub fn rename_course(&self, course_id: &str, display_name: &str) -> std::result::Result<(), AppError> {
+        let display_name = Some(display_name.trim()).filter(|name| !name.is_empty());
+        let updated = self.conn.execute(
+            "UPDATE courses SET course_display_name = ?1 WHERE id = ?2",
+            params![display_name, course_id],
+        )?;
+
+        if updated == 0 {
+            return Err(AppError::NotFound(format!("Curso não encontrado: {}", course_id)));
+        }
+        Ok(())
+    }
+
+    pub fn get_module_by_id(&self, module_id: &str) -> Result<Option<Module>> {
+        let result = self.conn.query_row(
+            "SELECT id, course_id, name, path, order_index FROM modules WHERE id = ?1",
+            params![module_id],
+            |row| {
+                Ok(Module {
+                    id: row.get(0)?,
+                    course_id: row.get(1)?,
+                    name: row.get(2)?,
+                    path: row.get(3)?,
+                    order_index: row.get(4)?,
+                })
+            },
+        );
+
+        match result {
+            Ok(module) => Ok(Some(module)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    // Lista plana, sem as pastas que só agrupam submódulos
+    pub fn get_course_modules(&self, course_id: &str) -> Result<Vec<Module>> {
+        self.query_course_modules(course_id, true)
+    }
+
+    fn query_course_modules(&self, course_id: &str, flat: bool) -> Result<Vec<Module>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT m.id, m.course_id, m.name, m.path, m.order_index FROM modules m 
+             WHERE m.course_id = ?1 AND ({}) ORDER BY m.order_index",
+            if flat { FLAT_MODULE_CONDITION } else { "1" }
+        ))?;
         
         let module_iter = stmt.query_map([course_id], |row| {
             Ok(Module {
@@ -410,654 +1558,5468 @@ impl Database {
         Ok(modules)
     }
 
-    pub fn get_module_videos(&self, module_id: &str) -> Result<Vec<Video>> {
-        let mut stmt = self.conn.prepare(
-            "SELECT id, module_id, course_id, name, path, duration, order_index 
-             FROM videos WHERE module_id = ?1 ORDER BY order_index"
+    pub fn set_module_parent(&self, module_id: &str, parent_module_id: Option<&str>) -> Result<()> {
+        self.conn.execute(
+            "UPDATE modules SET parent_module_id = ?1 WHERE id = ?2",
+            params![parent_module_id, module_id],
         )?;
-        
-        let video_iter = stmt.query_map([module_id], |row| {
-            Ok(Video {
-                id: row.get(0)?,
-                module_id: row.get(1)?,
-                course_id: row.get(2)?,
-                name: row.get(3)?,
-                path: row.get(4)?,
-                duration: row.get(5)?,
-                order_index: row.get(6)?,
-            })
-        })?;
+        Ok(())
+    }
 
-        let mut videos = Vec::new();
-        for video in video_iter {
-            videos.push(video?);
+    // Módulos do curso em árvore, inclusive as pastas sem vídeos que só
+    // agrupam submódulos; um pai de outro curso (ou removido) deixa o módulo na raiz
+    pub fn get_course_module_tree(&self, course_id: &str) -> Result<Vec<ModuleNode>> {
+        let mut stmt = self.conn.prepare("SELECT id, parent_module_id FROM modules WHERE course_id = ?1")?;
+        let parents = stmt
+            .query_map([course_id], |row| Ok((row.get::<_, String>(0)?, row.get::<_, Option<String>>(1)?)))?
+            .collect::<Result<std::collections::HashMap<String, Option<String>>>>()?;
+
+        let modules = self.query_course_modules(course_id, false)?;
+        let mut children: std::collections::HashMap<Option<String>, Vec<Module>> = std::collections::HashMap::new();
+        for module in modules {
+            let parent = parents.get(&module.id).cloned().flatten()
+                .filter(|parent| parent != &module.id && parents.contains_key(parent));
+            children.entry(parent).or_default().push(module);
         }
-        Ok(videos)
+
+        fn build(
+            parent: Option<String>,
+            children: &mut std::collections::HashMap<Option<String>, Vec<Module>>,
+        ) -> Vec<ModuleNode> {
+            children.remove(&parent).unwrap_or_default()
+                .into_iter()
+                .map(|module| ModuleNode {
+                    children: build(Some(module.id.clone()), children),
+                    parent_module_id: parent.clone(),
+                    module,
+                })
+                .collect()
+        }
+        Ok(build(None, &mut children))
     }
 
-    pub fn get_video_progress(&self, video_id: &str) -> Result<Option<VideoProgress>> {
-        let mut stmt = self.conn.prepare(
-            "SELECT id, video_id, current_time, duration, completed, last_watched 
-             FROM video_progress WHERE video_id = ?1"
-        )?;
-        
-        let mut rows = stmt.query_map([video_id], |row| {
-            Ok(VideoProgress {
-                id: row.get(0)?,
-                video_id: row.get(1)?,
-                current_time: row.get(2)?,
-                duration: row.get(3)?,
-                completed: row.get(4)?,
-                last_watched: DateTime::parse_from_rfc3339(&row.get::<_, String>(5)?)
-                    .map_err(|_| rusqlite::Error::InvalidColumnType(5, "last_watched".to_string(), rusqlite::types::Type::Text))?
-                    .with_timezone(&Utc),
-            })
+    // Módulos do curso com o total de vídeos e de vídeos concluídos de cada um
+    pub fn get_course_modules_with_counts(&self, course_id: &str) -> Result<Vec<(Module, i32, i32)>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT m.id, m.course_id, m.name, m.path, m.order_index, 
+                    COUNT(DISTINCT v.id), 
+                    COUNT(DISTINCT CASE WHEN vp.completed = 1 THEN v.id END) 
+             FROM modules m 
+             LEFT JOIN videos v ON v.module_id = m.id 
+             LEFT JOIN video_progress vp ON vp.video_id = v.id 
+             WHERE m.course_id = ?1 AND {} 
+             GROUP BY m.id 
+             ORDER BY m.order_index",
+            FLAT_MODULE_CONDITION
+        ))?;
+
+        let module_iter = stmt.query_map([course_id], |row| {
+            Ok((
+                Module {
+                    id: row.get(0)?,
+                    course_id: row.get(1)?,
+                    name: row.get(2)?,
+                    path: row.get(3)?,
+                    order_index: row.get(4)?,
+                },
+                row.get(5)?,
+                row.get(6)?,
+            ))
         })?;
 
-        match rows.next() {
-            Some(row) => Ok(Some(row?)),
-            None => Ok(None),
+        let mut modules = Vec::new();
+        for module in module_iter {
+            modules.push(module?);
         }
+        Ok(modules)
     }
 
-    pub fn get_recent_videos(&self, limit: usize) -> Result<Vec<(Video, VideoProgress)>> {
-        let mut stmt = self.conn.prepare(
-            "SELECT v.id, v.module_id, v.course_id, v.name, v.path, v.duration, v.order_index,
-                    p.id, p.video_id, p.current_time, p.duration, p.completed, p.last_watched
-             FROM videos v
-             INNER JOIN video_progress p ON v.id = p.video_id
-             WHERE p.completed = 0
-             ORDER BY p.last_watched DESC
-             LIMIT ?1"
-        )?;
-        
-        let video_iter = stmt.query_map([limit], |row| {
-            let video = Video {
-                id: row.get(0)?,
-                module_id: row.get(1)?,
-                course_id: row.get(2)?,
-                name: row.get(3)?,
-                path: row.get(4)?,
-                duration: row.get(5)?,
-                order_index: row.get(6)?,
-            };
-            
-            let progress = VideoProgress {
-                id: row.get(7)?,
-                video_id: row.get(8)?,
-                current_time: row.get(9)?,
-                duration: row.get(10)?,
-                completed: row.get(11)?,
-                last_watched: DateTime::parse_from_rfc3339(&row.get::<_, String>(12)?)
-                    .map_err(|_| rusqlite::Error::InvalidColumnType(12, "last_watched".to_string(), rusqlite::types::Type::Text))?
-                    .with_timezone(&Utc),
-            };
-            
-            Ok((video, progress))
+    // Sumário do curso numa lista só: cada módulo seguido dos seus vídeos, em
+    // `order_index`. Vídeos nunca abertos aparecem não concluídos, na posição 0;
+    // concluídos à mão também ficam na posição 0, pois a gravada é fictícia
+    pub fn get_course_outline(&self, course_id: &str) -> Result<Vec<OutlineItem>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT v.id, v.module_id, v.course_id, v.name, v.path, v.duration, v.order_index, 
+                    COALESCE(vp.completed, 0), 
+                    CASE WHEN vp.completed_manually THEN 0 ELSE COALESCE(vp.current_time, 0) END 
+             FROM videos v 
+             LEFT JOIN {} vp ON vp.video_id = v.id 
+             WHERE v.course_id = ?1 
+             ORDER BY v.order_index, v.name",
+            LATEST_PROGRESS
+        ))?;
+        let rows = stmt.query_map([course_id], |row| {
+            Ok(OutlineItem::Video {
+                video: Video {
+                    id: row.get(0)?,
+                    module_id: row.get(1)?,
+                    course_id: row.get(2)?,
+                    name: row.get(3)?,
+                    path: row.get(4)?,
+                    duration: row.get(5)?,
+                    order_index: row.get(6)?,
+                },
+                completed: row.get(7)?,
+                current_time: row.get(8)?,
+            })
         })?;
 
-        let mut results = Vec::new();
-        for item in video_iter {
-            results.push(item?);
+        let mut videos_by_module: std::collections::HashMap<String, Vec<OutlineItem>> = std::collections::HashMap::new();
+        for item in rows {
+            let item = item?;
+            if let OutlineItem::Video { video, .. } = &item {
+                videos_by_module.entry(video.module_id.clone()).or_default().push(item);
+            }
         }
-        Ok(results)
+
+        let mut outline = Vec::new();
+        for (module, video_count, _) in self.get_course_modules_with_counts(course_id)? {
+            let videos = videos_by_module.remove(&module.id).unwrap_or_default();
+            outline.push(OutlineItem::Module { module, video_count });
+            outline.extend(videos);
+        }
+        Ok(outline)
     }
 
-    pub fn update_course_last_accessed(&self, course_id: &str) -> Result<()> {
-        self.conn.execute(
-            "UPDATE courses SET last_accessed = ?1 WHERE id = ?2",
-            params![Utc::now().to_rfc3339(), course_id],
-        )?;
-        Ok(())
+    // Mesma ordem do sumário: módulos por `order_index` e, dentro deles, os
+    // vídeos. Só contam os módulos com vídeos, não as pastas que os agrupam
+    pub fn get_video_location(&self, video_id: &str) -> std::result::Result<VideoLocation, AppError> {
+        let video = self.get_video_by_id(video_id)?
+            .ok_or_else(|| AppError::NotFound(format!("Vídeo não encontrado: {}", video_id)))?;
+        let videos = self.get_videos(Some(&video.course_id))?;
+        let mut seen = HashSet::new();
+        let modules: Vec<&str> = videos.iter().map(|v| v.module_id.as_str()).filter(|id| seen.insert(*id)).collect();
+        let in_module: Vec<&Video> = videos.iter().filter(|v| v.module_id == video.module_id).collect();
+
+        let position = |index: Option<usize>| index.map_or(0, |i| i as i32 + 1);
+        Ok(VideoLocation {
+            module_index: position(modules.iter().position(|m| *m == video.module_id)),
+            module_count: modules.len() as i32,
+            video_index_in_module: position(in_module.iter().position(|v| v.id == video.id)),
+            videos_in_module: in_module.len() as i32,
+            global_index: position(videos.iter().position(|v| v.id == video.id)),
+            global_count: videos.len() as i32,
+        })
     }
 
-    // ========== MÉTODOS PARA ANOTAÇÕES ==========
-    
-    pub fn create_user_note(&self, note: &UserNote) -> Result<()> {
+    // Primeiro módulo (na ordem do curso) ainda não concluído, com a fração já
+    // concluída, para a interface liberar os módulos em sequência. Módulos sem
+    // vídeos são pulados; None se o curso estiver todo concluído
+    pub fn get_recommended_next_module(&self, course_id: &str) -> Result<Option<(Module, f64)>> {
+        Ok(self.get_course_modules_with_counts(course_id)?
+            .into_iter()
+            .find(|(_, total, completed)| completed < total)
+            .map(|(module, total, completed)| (module, completed as f64 / total as f64)))
+    }
+
+    // Reescreve o order_index dos módulos do curso para 0..n e o dos vídeos de
+    // cada módulo para 0..m, mantendo a ordem atual (empates resolvidos pelo
+    // nome). Retorna quantas linhas mudaram
+    pub fn normalize_ordering(&self, course_id: &str) -> Result<usize> {
+        let tx = self.transaction()?;
+        let mut changed = 0;
+
+        let modules: Vec<(String, i32)> = {
+            let mut stmt = self.conn.prepare(
+                "SELECT id, order_index FROM modules WHERE course_id = ?1 ORDER BY order_index, name, id"
+            )?;
+            let rows = stmt.query_map([course_id], |row| Ok((row.get(0)?, row.get(1)?)))?;
+            rows.collect::<Result<_>>()?
+        };
+
+        for (index, (module_id, order_index)) in modules.iter().enumerate() {
+            if *order_index != index as i32 {
+                self.conn.execute(
+                    "UPDATE modules SET order_index = ?1 WHERE id = ?2",
+                    params![index as i32, module_id],
+                )?;
+                changed += 1;
+            }
+
+            let videos: Vec<(String, i32)> = {
+                let mut stmt = self.conn.prepare(
+                    "SELECT id, order_index FROM videos WHERE module_id = ?1 ORDER BY order_index, name, id"
+                )?;
+                let rows = stmt.query_map([module_id], |row| Ok((row.get(0)?, row.get(1)?)))?;
+                rows.collect::<Result<_>>()?
+            };
+            for (video_index, (video_id, video_order)) in videos.iter().enumerate() {
+                if *video_order != video_index as i32 {
+                    self.conn.execute(
+                        "UPDATE videos SET order_index = ?1 WHERE id = ?2",
+                        params![video_index as i32, video_id],
+                    )?;
+                    changed += 1;
+                }
+            }
+        }
+
+        tx.commit()?;
+        Ok(changed)
+    }
+
+    // Move os vídeos e anotações de `merge_module_id` para o fim de `keep_module_id`
+    // e apaga o módulo esvaziado. Retorna quantos vídeos foram movidos
+    pub fn merge_modules(&self, course_id: &str, keep_module_id: &str, merge_module_id: &str) -> std::result::Result<usize, AppError> {
+        if keep_module_id == merge_module_id {
+            return Err(AppError::InvalidInput("Não é possível mesclar um módulo com ele mesmo".to_string()));
+        }
+        for module_id in [keep_module_id, merge_module_id] {
+            let belongs: bool = self.conn.query_row(
+                "SELECT EXISTS(SELECT 1 FROM modules WHERE id = ?1 AND course_id = ?2)",
+                params![module_id, course_id],
+                |row| row.get(0),
+            )?;
+            if !belongs {
+                return Err(AppError::NotFound(format!("Módulo {} não encontrado no curso {}", module_id, course_id)));
+            }
+        }
+
+        let tx = self.transaction()?;
+
+        let next_order: i32 = self.conn.query_row(
+            "SELECT COALESCE(MAX(order_index) + 1, 0) FROM videos WHERE module_id = ?1",
+            params![keep_module_id],
+            |row| row.get(0),
+        )?;
+        let moved = self.get_module_videos(merge_module_id)?;
+        for (offset, video) in moved.iter().enumerate() {
+            self.conn.execute(
+                "UPDATE videos SET module_id = ?1, order_index = ?2 WHERE id = ?3",
+                params![keep_module_id, next_order + offset as i32, video.id],
+            )?;
+        }
+
         self.conn.execute(
-            "INSERT INTO user_notes (id, video_id, course_id, module_id, timestamp, title, content, note_type, created_at, updated_at) 
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
-            params![
-                note.id,
-                note.video_id.as_ref().unwrap_or(&"".to_string()),
-                note.course_id.as_ref().unwrap_or(&"".to_string()),
-                note.module_id.as_ref().unwrap_or(&"".to_string()),
-                note.timestamp.unwrap_or(0.0),
-                note.title,
-                note.content,
-                note.note_type,
-                note.created_at.to_rfc3339(),
-                note.updated_at.to_rfc3339()
-            ],
+            "UPDATE user_notes SET module_id = ?1 WHERE module_id = ?2",
+            params![keep_module_id, merge_module_id],
         )?;
-        Ok(())
+        self.conn.execute("DELETE FROM modules WHERE id = ?1", params![merge_module_id])?;
+
+        tx.commit()?;
+        Ok(moved.len())
     }
 
-    pub fn update_user_note(&self, note: &UserNote) -> Result<()> {
+    // Move o vídeo (e suas anotações) para o fim de outro módulo do mesmo curso
+    // e fecha o buraco deixado no módulo de origem. Retorna o vídeo atualizado
+    pub fn move_video_to_module(&self, video_id: &str, target_module_id: &str) -> std::result::Result<Video, AppError> {
+        let video = self.get_video_by_id(video_id)?
+            .ok_or_else(|| AppError::NotFound(format!("Vídeo não encontrado: {}", video_id)))?;
+        let target_course: Option<String> = match self.conn.query_row(
+            "SELECT course_id FROM modules WHERE id = ?1",
+            params![target_module_id],
+            |row| row.get(0),
+        ) {
+            Ok(course_id) => Some(course_id),
+            Err(rusqlite::Error::QueryReturnedNoRows) => None,
+            Err(e) => return Err(e.into()),
+        };
+        match target_course {
+            None => return Err(AppError::NotFound(format!("Módulo não encontrado: {}", target_module_id))),
+            Some(course_id) if course_id != video.course_id => {
+                return Err(AppError::InvalidInput("O módulo de destino pertence a outro curso".to_string()));
+            }
+            Some(_) => {}
+        }
+        if video.module_id == target_module_id {
+            return Err(AppError::InvalidInput("O vídeo já está nesse módulo".to_string()));
+        }
+
+        let tx = self.transaction()?;
+
+        let next_order: i32 = self.conn.query_row(
+            "SELECT COALESCE(MAX(order_index) + 1, 0) FROM videos WHERE module_id = ?1",
+            params![target_module_id],
+            |row| row.get(0),
+        )?;
         self.conn.execute(
-            "UPDATE user_notes SET title = ?1, content = ?2, updated_at = ?3 WHERE id = ?4",
-            params![note.title, note.content, note.updated_at.to_rfc3339(), note.id],
+            "UPDATE videos SET module_id = ?1, order_index = ?2 WHERE id = ?3",
+            params![target_module_id, next_order, video_id],
         )?;
-        Ok(())
+        self.conn.execute(
+            "UPDATE user_notes SET module_id = ?1 WHERE video_id = ?2",
+            params![target_module_id, video_id],
+        )?;
+        for (index, remaining) in self.get_module_videos(&video.module_id)?.iter().enumerate() {
+            if remaining.order_index != index as i32 {
+                self.conn.execute(
+                    "UPDATE videos SET order_index = ?1 WHERE id = ?2",
+                    params![index as i32, remaining.id],
+                )?;
+            }
+        }
+
+        tx.commit()?;
+        Ok(Video { module_id: target_module_id.to_string(), order_index: next_order, ..video })
     }
 
-    pub fn delete_user_note(&self, note_id: &str) -> Result<()> {
-        self.conn.execute("DELETE FROM user_notes WHERE id = ?1", params![note_id])?;
-        Ok(())
+    // Reorganiza os vídeos do curso pelo nome do arquivo: o grupo `module_group`
+    // do padrão dá o nome do módulo (criado se preciso) e `order_group` a
+    // posição do vídeo. Módulos que ficam vazios são removidos. Um novo
+    // escaneamento volta à organização das pastas
+    pub fn organize_videos_by_pattern(
+        &self,
+        course_id: &str,
+        pattern: &str,
+        module_group: usize,
+        order_group: usize,
+    ) -> std::result::Result<VideoOrganization, AppError> {
+        let regex = Regex::new(pattern)
+            .map_err(|e| AppError::InvalidInput(format!("Padrão inválido: {}", e)))?;
+        if module_group == 0 || order_group == 0 || module_group.max(order_group) >= regex.captures_len() {
+            return Err(AppError::InvalidInput(format!(
+                "O padrão tem {} grupos; os grupos do módulo e da ordem devem estar entre 1 e {}",
+                regex.captures_len() - 1, regex.captures_len() - 1
+            )));
+        }
+        let course = self.get_course_by_id(course_id)?
+            .ok_or_else(|| AppError::NotFound(format!("Curso não encontrado: {}", course_id)))?;
+
+        let mut result = VideoOrganization { modules_created: 0, videos_moved: 0, unmatched: 0 };
+        let mut placements: Vec<(Video, String, i32)> = Vec::new();
+        let modules = self.get_course_modules(course_id)?;
+        for module in &modules {
+            for video in self.get_module_videos(&module.id)? {
+                let file_name = Path::new(&video.path)
+                    .file_name()
+                    .map(|name| name.to_string_lossy().to_string())
+                    .unwrap_or_else(|| video.name.clone());
+                let placement = regex.captures(&file_name).and_then(|caps| {
+                    let module_name = caps.get(module_group)?.as_str().trim().to_string();
+                    let order = caps.get(order_group)?.as_str().trim().parse::<i32>().ok()?;
+                    Some((module_name, order)).filter(|(name, _)| !name.is_empty())
+                });
+                match placement {
+                    Some((module_name, order)) => placements.push((video, module_name, order)),
+                    None => result.unmatched += 1,
+                }
+            }
+        }
+
+        // Módulos novos entram depois dos existentes, ordenados pelo número no nome
+        let mut new_names: Vec<&str> = placements.iter()
+            .map(|(_, name, _)| name.as_str())
+            .filter(|name| !modules.iter().any(|m| m.name == *name))
+            .collect();
+        new_names.sort_by_key(|name| (leading_number(name), name.to_string()));
+        new_names.dedup();
+
+        let tx = self.transaction()?;
+        let mut module_ids: Vec<(String, String)> = modules.iter().map(|m| (m.name.clone(), m.id.clone())).collect();
+        let next_order = modules.iter().map(|m| m.order_index + 1).max().unwrap_or(0);
+        for (offset, name) in new_names.into_iter().enumerate() {
+            let module = Module {
+                id: uuid::Uuid::new_v4().to_string(),
+                course_id: course_id.to_string(),
+                name: name.to_string(),
+                path: Path::new(&course.path).join(name).to_string_lossy().to_string(),
+                order_index: next_order + offset as i32,
+            };
+            self.insert_module(&module)?;
+            module_ids.push((module.name, module.id));
+            result.modules_created += 1;
+        }
+
+        let mut emptied: HashSet<String> = HashSet::new();
+        for (video, module_name, order) in &placements {
+            let module_id = module_ids.iter()
+                .find(|(name, _)| name == module_name)
+                .map(|(_, id)| id.clone())
+                .unwrap_or_default();
+            if video.module_id == module_id && video.order_index == *order {
+                continue;
+            }
+            self.conn.execute(
+                "UPDATE videos SET module_id = ?1, order_index = ?2 WHERE id = ?3",
+                params![module_id, order, video.id],
+            )?;
+            self.conn.execute(
+                "UPDATE user_notes SET module_id = ?1 WHERE video_id = ?2",
+                params![module_id, video.id],
+            )?;
+            emptied.insert(video.module_id.clone());
+            result.videos_moved += 1;
+        }
+
+        for module_id in emptied {
+            self.conn.execute(
+                "DELETE FROM modules WHERE id = ?1 
+                 AND NOT EXISTS (SELECT 1 FROM videos WHERE module_id = ?1) 
+                 AND NOT EXISTS (SELECT 1 FROM user_notes WHERE module_id = ?1)",
+                params![module_id],
+            )?;
+        }
+
+        tx.commit()?;
+        Ok(result)
     }
 
-    pub fn get_notes_by_video(&self, video_id: &str) -> Result<Vec<UserNote>> {
-        let stmt = self.conn.prepare(
-            "SELECT id, video_id, course_id, module_id, timestamp, title, content, note_type, created_at, updated_at 
-             FROM user_notes WHERE video_id = ?1 ORDER BY timestamp ASC, created_at ASC"
-        )?;
-        
-        self.map_notes_from_query(stmt, params![video_id])
+    pub fn get_module_by_path(&self, course_id: &str, module_path: &str) -> Result<Option<Module>> {
+        let result = self.conn.query_row(
+            "SELECT id, course_id, name, path, order_index FROM modules WHERE course_id = ?1 AND path = ?2",
+            params![course_id, module_path],
+            |row| {
+                Ok(Module {
+                    id: row.get(0)?,
+                    course_id: row.get(1)?,
+                    name: row.get(2)?,
+                    path: row.get(3)?,
+                    order_index: row.get(4)?,
+                })
+            },
+        );
+
+        match result {
+            Ok(module) => Ok(Some(module)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e),
+        }
     }
 
-    pub fn get_notes_by_course(&self, course_id: &str) -> Result<Vec<UserNote>> {
-        let stmt = self.conn.prepare(
-            "SELECT id, video_id, course_id, module_id, timestamp, title, content, note_type, created_at, updated_at 
-             FROM user_notes WHERE course_id = ?1 ORDER BY created_at DESC"
+    pub fn get_module_videos(&self, module_id: &str) -> Result<Vec<Video>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, module_id, course_id, name, path, duration, order_index 
+             FROM videos WHERE module_id = ?1 ORDER BY order_index"
         )?;
         
-        self.map_notes_from_query(stmt, params![course_id])
+        let video_iter = stmt.query_map([module_id], |row| {
+            Ok(Video {
+                id: row.get(0)?,
+                module_id: row.get(1)?,
+                course_id: row.get(2)?,
+                name: row.get(3)?,
+                path: row.get(4)?,
+                duration: row.get(5)?,
+                order_index: row.get(6)?,
+            })
+        })?;
+
+        let mut videos = Vec::new();
+        for video in video_iter {
+            videos.push(video?);
+        }
+        Ok(videos)
     }
 
-    pub fn get_all_notes(&self) -> Result<Vec<UserNote>> {
-        let stmt = self.conn.prepare(
-            "SELECT id, video_id, course_id, module_id, timestamp, title, content, note_type, created_at, updated_at 
-             FROM user_notes ORDER BY created_at DESC"
+    // Vídeos de um curso (ou de todos), na ordem dos módulos
+    pub fn get_videos(&self, course_id: Option<&str>) -> Result<Vec<Video>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT v.id, v.module_id, v.course_id, v.name, v.path, v.duration, v.order_index 
+             FROM videos v 
+             INNER JOIN modules m ON m.id = v.module_id 
+             WHERE ?1 IS NULL OR v.course_id = ?1 
+             ORDER BY v.course_id, m.order_index, v.order_index"
         )?;
-        
-        self.map_notes_from_query(stmt, params![])
-    }
 
-    fn map_notes_from_query(&self, mut stmt: rusqlite::Statement, params: impl rusqlite::Params) -> Result<Vec<UserNote>> {
-        let note_iter = stmt.query_map(params, |row| {
-            Ok(UserNote {
+        let video_iter = stmt.query_map(params![course_id], |row| {
+            Ok(Video {
                 id: row.get(0)?,
-                video_id: row.get(1)?,
+                module_id: row.get(1)?,
                 course_id: row.get(2)?,
-                module_id: row.get(3)?,
-                timestamp: row.get(4)?,
-                title: row.get(5)?,
-                content: row.get(6)?,
-                note_type: row.get(7)?,
-                created_at: DateTime::parse_from_rfc3339(&row.get::<_, String>(8)?)
-                    .map_err(|_| rusqlite::Error::InvalidColumnType(8, "created_at".to_string(), rusqlite::types::Type::Text))?
-                    .with_timezone(&Utc),
-                updated_at: DateTime::parse_from_rfc3339(&row.get::<_, String>(9)?)
-                    .map_err(|_| rusqlite::Error::InvalidColumnType(9, "updated_at".to_string(), rusqlite::types::Type::Text))?
-                    .with_timezone(&Utc),
+                name: row.get(3)?,
+                path: row.get(4)?,
+                duration: row.get(5)?,
+                order_index: row.get(6)?,
             })
         })?;
 
-        let mut notes = Vec::new();
-        for note in note_iter {
-            notes.push(note?);
+        let mut videos = Vec::new();
+        for video in video_iter {
+            videos.push(video?);
         }
-        Ok(notes)
+        Ok(videos)
     }
 
-    // ========== MÉTODOS PARA BOOKMARKS ==========
-    
-    pub fn create_video_bookmark(&self, bookmark: &VideoBookmark) -> Result<()> {
-        self.conn.execute(
-            "INSERT INTO video_bookmarks (id, video_id, timestamp, title, description, created_at) 
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
-            params![
-                bookmark.id,
-                bookmark.video_id,
-                bookmark.timestamp,
-                bookmark.title,
-                bookmark.description,
-                bookmark.created_at.to_rfc3339()
-            ],
-        )?;
-        Ok(())
+    pub fn get_video_by_id(&self, video_id: &str) -> Result<Option<Video>> {
+        let result = self.conn.query_row(
+            "SELECT id, module_id, course_id, name, path, duration, order_index 
+             FROM videos WHERE id = ?1",
+            params![video_id],
+            |row| {
+                Ok(Video {
+                    id: row.get(0)?,
+                    module_id: row.get(1)?,
+                    course_id: row.get(2)?,
+                    name: row.get(3)?,
+                    path: row.get(4)?,
+                    duration: row.get(5)?,
+                    order_index: row.get(6)?,
+                })
+            },
+        );
+
+        match result {
+            Ok(video) => Ok(Some(video)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e),
+        }
     }
 
-    pub fn delete_video_bookmark(&self, bookmark_id: &str) -> Result<()> {
-        self.conn.execute("DELETE FROM video_bookmarks WHERE id = ?1", params![bookmark_id])?;
+    pub fn get_video_detail(&self, video_id: &str) -> std::result::Result<VideoDetail, AppError> {
+        let not_found = || AppError::NotFound(format!("Vídeo não encontrado: {}", video_id));
+
+        let result = self.conn.query_row(
+            "SELECT v.id, v.module_id, v.course_id, v.name, v.path, v.duration, v.order_index, 
+                    m.id, m.course_id, m.name, m.path, m.order_index, v.description 
+             FROM videos v 
+             INNER JOIN modules m ON m.id = v.module_id 
+             WHERE v.id = ?1",
+            params![video_id],
+            |row| {
+                Ok((
+                    Video {
+                        id: row.get(0)?,
+                        module_id: row.get(1)?,
+                        course_id: row.get(2)?,
+                        name: row.get(3)?,
+                        path: row.get(4)?,
+                        duration: row.get(5)?,
+                        order_index: row.get(6)?,
+                    },
+                    Module {
+                        id: row.get(7)?,
+                        course_id: row.get(8)?,
+                        name: row.get(9)?,
+                        path: row.get(10)?,
+                        order_index: row.get(11)?,
+                    },
+                    row.get(12)?,
+                ))
+            },
+        );
+        let (video, module, description) = match result {
+            Ok(found) => found,
+            Err(rusqlite::Error::QueryReturnedNoRows) => return Err(not_found()),
+            Err(e) => return Err(e.into()),
+        };
+        let course = self.get_course_by_id(&video.course_id)?.ok_or_else(not_found)?;
+
+        Ok(VideoDetail {
+            progress: self.get_video_progress(&video.id)?,
+            bookmarks: self.get_video_bookmarks(&video.id)?,
+            notes: self.get_notes_by_video(&video.id)?,
+            selected_subtitle_path: self.get_selected_subtitle(&video.id)?,
+            video,
+            description,
+            module,
+            course,
+        })
+    }
+
+    // Guarda a legenda escolhida para o vídeo; None volta à escolha automática
+    pub fn set_selected_subtitle(&self, video_id: &str, subtitle_path: Option<&str>) -> std::result::Result<(), AppError> {
+        if self.get_video_by_id(video_id)?.is_none() {
+            return Err(AppError::NotFound(format!("Vídeo não encontrado: {}", video_id)));
+        }
+
+        match subtitle_path {
+            Some(path) => {
+                if !Path::new(path).is_file() {
+                    return Err(AppError::InvalidInput(format!("Arquivo de legenda não encontrado: {}", path)));
+                }
+                self.conn.execute(
+                    "INSERT OR REPLACE INTO video_subtitle_choice (video_id, subtitle_path, updated_at) VALUES (?1, ?2, ?3)",
+                    params![video_id, path, Utc::now().to_rfc3339()],
+                )?;
+            }
+            None => {
+                self.conn.execute("DELETE FROM video_subtitle_choice WHERE video_id = ?1", params![video_id])?;
+            }
+        }
         Ok(())
     }
 
-    pub fn get_video_bookmarks(&self, video_id: &str) -> Result<Vec<VideoBookmark>> {
+    // Legenda escolhida, se o arquivo ainda existir
+    pub fn get_selected_subtitle(&self, video_id: &str) -> Result<Option<String>> {
+        let result = self.conn.query_row(
+            "SELECT subtitle_path FROM video_subtitle_choice WHERE video_id = ?1",
+            params![video_id],
+            |row| row.get::<_, String>(0),
+        );
+
+        match result {
+            Ok(path) => Ok(Some(path).filter(|path| Path::new(path).is_file())),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    // Inverte a marcação de favorito e retorna o novo estado. Como o upsert de
+    // `insert_video` não toca em `is_favorite`, a marcação sobrevive a rescans
+    pub fn toggle_video_favorite(&self, video_id: &str) -> std::result::Result<bool, AppError> {
+        let updated = self.conn.execute(
+            "UPDATE videos SET is_favorite = NOT COALESCE(is_favorite, 0) WHERE id = ?1",
+            params![video_id],
+        )?;
+
+        if updated == 0 {
+            return Err(AppError::NotFound(format!("Vídeo não encontrado: {}", video_id)));
+        }
+
+        let is_favorite = self.conn.query_row(
+            "SELECT is_favorite FROM videos WHERE id = ?1",
+            params![video_id],
+            |row| row.get(0),
+        )?;
+        Ok(is_favorite)
+    }
+
+    pub fn get_favorite_videos(&self) -> Result<Vec<FavoriteVideo>> {
         let mut stmt = self.conn.prepare(
-            "SELECT id, video_id, timestamp, title, description, created_at 
-             FROM video_bookmarks WHERE video_id = ?1 ORDER BY timestamp ASC"
+            "SELECT v.id, v.module_id, v.course_id, v.name, v.path, v.duration, v.order_index, 
+                    COALESCE(c.course_display_name, c.name) 
+             FROM videos v 
+             INNER JOIN courses c ON c.id = v.course_id 
+             INNER JOIN modules m ON m.id = v.module_id 
+             WHERE v.is_favorite = 1 
+             ORDER BY COALESCE(c.course_display_name, c.name) COLLATE NOCASE, c.id, m.order_index, v.order_index"
         )?;
-        
-        let bookmark_iter = stmt.query_map([video_id], |row| {
-            Ok(VideoBookmark {
-                id: row.get(0)?,
-                video_id: row.get(1)?,
-                timestamp: row.get(2)?,
-                title: row.get(3)?,
-                description: row.get(4)?,
-                created_at: DateTime::parse_from_rfc3339(&row.get::<_, String>(5)?)
-                    .map_err(|_| rusqlite::Error::InvalidColumnType(5, "created_at".to_string(), rusqlite::types::Type::Text))?
-                    .with_timezone(&Utc),
+
+        let favorite_iter = stmt.query_map([], |row| {
+            Ok(FavoriteVideo {
+                video: Video {
+                    id: row.get(0)?,
+                    module_id: row.get(1)?,
+                    course_id: row.get(2)?,
+                    name: row.get(3)?,
+                    path: row.get(4)?,
+                    duration: row.get(5)?,
+                    order_index: row.get(6)?,
+                },
+                course_name: row.get(7)?,
             })
         })?;
 
-        let mut bookmarks = Vec::new();
-        for bookmark in bookmark_iter {
-            bookmarks.push(bookmark?);
+        let mut favorites = Vec::new();
+        for favorite in favorite_iter {
+            favorites.push(favorite?);
         }
-        Ok(bookmarks)
+        Ok(favorites)
     }
 
-    // ========== MÉTODOS PARA CONFIGURAÇÕES ==========
-    
-    pub fn set_user_setting(&self, setting: &UserSettings) -> Result<()> {
-        self.conn.execute(
-            "INSERT OR REPLACE INTO user_settings (id, setting_key, setting_value, setting_type, updated_at) 
-             VALUES (?1, ?2, ?3, ?4, ?5)",
-            params![
-                setting.id,
-                setting.setting_key,
-                setting.setting_value,
-                setting.setting_type,
-                setting.updated_at.to_rfc3339()
-            ],
+    // Avalia o vídeo de 1 a 5; 0 remove a avaliação. Assim como `is_favorite`,
+    // a coluna não é tocada pelo upsert de `insert_video` e sobrevive a rescans
+    pub fn set_video_rating(&self, video_id: &str, rating: u8) -> std::result::Result<(), AppError> {
+        if rating > MAX_VIDEO_RATING {
+            return Err(AppError::InvalidInput(format!(
+                "Avaliação deve estar entre 1 e {} (0 remove): {}", MAX_VIDEO_RATING, rating
+            )));
+        }
+
+        let rating = (rating > 0).then_some(rating);
+        let updated = self.conn.execute(
+            "UPDATE videos SET rating = ?1 WHERE id = ?2",
+            params![rating, video_id],
         )?;
+
+        if updated == 0 {
+            return Err(AppError::NotFound(format!("Vídeo não encontrado: {}", video_id)));
+        }
         Ok(())
     }
 
-    pub fn get_user_setting(&self, key: &str) -> Result<Option<UserSettings>> {
+    // Vídeos avaliados, da maior nota para a menor; empates na ordem do curso
+    pub fn get_top_rated_videos(&self, limit: usize) -> Result<Vec<RatedVideo>> {
         let mut stmt = self.conn.prepare(
-            "SELECT id, setting_key, setting_value, setting_type, updated_at 
-             FROM user_settings WHERE setting_key = ?1"
+            "SELECT v.id, v.module_id, v.course_id, v.name, v.path, v.duration, v.order_index, 
+                    COALESCE(c.course_display_name, c.name), v.rating 
+             FROM videos v 
+             INNER JOIN courses c ON c.id = v.course_id 
+             INNER JOIN modules m ON m.id = v.module_id 
+             WHERE v.rating IS NOT NULL 
+             ORDER BY v.rating DESC, COALESCE(c.course_display_name, c.name) COLLATE NOCASE, c.id, m.order_index, v.order_index 
+             LIMIT ?1"
         )?;
-        
-        let mut rows = stmt.query_map([key], |row| {
-            Ok(UserSettings {
-                id: row.get(0)?,
-                setting_key: row.get(1)?,
-                setting_value: row.get(2)?,
-                setting_type: row.get(3)?,
-                updated_at: DateTime::parse_from_rfc3339(&row.get::<_, String>(4)?)
-                    .map_err(|_| rusqlite::Error::InvalidColumnType(4, "updated_at".to_string(), rusqlite::types::Type::Text))?
-                    .with_timezone(&Utc),
+
+        let rated_iter = stmt.query_map(params![limit as i64], |row| {
+            Ok(RatedVideo {
+                video: Video {
+                    id: row.get(0)?,
+                    module_id: row.get(1)?,
+                    course_id: row.get(2)?,
+                    name: row.get(3)?,
+                    path: row.get(4)?,
+                    duration: row.get(5)?,
+                    order_index: row.get(6)?,
+                },
+                course_name: row.get(7)?,
+                rating: row.get(8)?,
             })
         })?;
 
-        match rows.next() {
-            Some(row) => Ok(Some(row?)),
-            None => Ok(None),
+        let mut rated = Vec::new();
+        for video in rated_iter {
+            rated.push(video?);
         }
+        Ok(rated)
     }
 
-    pub fn get_all_user_settings(&self) -> Result<Vec<UserSettings>> {
+    // Vídeos com mais anotações e bookmarks (ignorando os excluídos)
+    pub fn get_most_annotated_videos(&self, limit: usize) -> Result<Vec<AnnotatedVideo>> {
         let mut stmt = self.conn.prepare(
-            "SELECT id, setting_key, setting_value, setting_type, updated_at 
-             FROM user_settings ORDER BY setting_key"
+            "SELECT v.id, v.module_id, v.course_id, v.name, v.path, v.duration, v.order_index, 
+                    COALESCE(c.course_display_name, c.name), 
+                    COALESCE(n.total, 0) AS note_count, 
+                    COALESCE(b.total, 0) AS bookmark_count 
+             FROM videos v 
+             INNER JOIN courses c ON c.id = v.course_id 
+             LEFT JOIN (SELECT video_id, COUNT(*) AS total FROM user_notes 
+                        WHERE deleted_at IS NULL AND video_id IS NOT NULL GROUP BY video_id) n ON n.video_id = v.id 
+             LEFT JOIN (SELECT video_id, COUNT(*) AS total FROM video_bookmarks 
+                        WHERE deleted_at IS NULL GROUP BY video_id) b ON b.video_id = v.id 
+             WHERE note_count + bookmark_count > 0 
+             ORDER BY note_count + bookmark_count DESC, note_count DESC, v.name 
+             LIMIT ?1"
         )?;
-        
-        let setting_iter = stmt.query_map([], |row| {
-            Ok(UserSettings {
-                id: row.get(0)?,
-                setting_key: row.get(1)?,
-                setting_value: row.get(2)?,
-                setting_type: row.get(3)?,
-                updated_at: DateTime::parse_from_rfc3339(&row.get::<_, String>(4)?)
-                    .map_err(|_| rusqlite::Error::InvalidColumnType(4, "updated_at".to_string(), rusqlite::types::Type::Text))?
-                    .with_timezone(&Utc),
+
+        let video_iter = stmt.query_map(params![limit as i64], |row| {
+            Ok(AnnotatedVideo {
+                video: Video {
+                    id: row.get(0)?,
+                    module_id: row.get(1)?,
+                    course_id: row.get(2)?,
+                    name: row.get(3)?,
+                    path: row.get(4)?,
+                    duration: row.get(5)?,
+                    order_index: row.get(6)?,
+                },
+                course_name: row.get(7)?,
+                note_count: row.get(8)?,
+                bookmark_count: row.get(9)?,
             })
         })?;
 
-        let mut settings = Vec::new();
-        for setting in setting_iter {
-            settings.push(setting?);
+        let mut videos = Vec::new();
+        for video in video_iter {
+            videos.push(video?);
         }
-        Ok(settings)
-    }
-
-    // ========== MÉTODOS PARA LOG DE ATIVIDADES ==========
-    
-    pub fn log_activity(&self, activity: &ActivityLog) -> Result<()> {
-        self.conn.execute(
-            "INSERT INTO activity_log (id, activity_type, entity_id, entity_type, details, created_at) 
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
-            params![
-                activity.id,
-                activity.activity_type,
-                activity.entity_id,
-                activity.entity_type,
-                activity.details,
-                activity.created_at.to_rfc3339()
-            ],
-        )?;
-        Ok(())
+        Ok(videos)
     }
 
-    pub fn get_recent_activities(&self, limit: usize) -> Result<Vec<ActivityLog>> {
+    pub fn get_video_progress(&self, video_id: &str) -> Result<Option<VideoProgress>> {
         let mut stmt = self.conn.prepare(
-            "SELECT id, activity_type, entity_id, entity_type, details, created_at 
-             FROM activity_log ORDER BY created_at DESC LIMIT ?1"
+            // `current_time` sem prefixo é a palavra-chave CURRENT_TIME do SQLite
+            "SELECT vp.id, vp.video_id, vp.current_time, vp.duration, vp.completed, vp.last_watched 
+             FROM video_progress vp WHERE vp.video_id = ?1"
         )?;
         
-        let activity_iter = stmt.query_map([limit], |row| {
-            Ok(ActivityLog {
+        let mut rows = stmt.query_map([video_id], |row| {
+            Ok(VideoProgress {
                 id: row.get(0)?,
-                activity_type: row.get(1)?,
-                entity_id: row.get(2)?,
-                entity_type: row.get(3)?,
-                details: row.get(4)?,
-                created_at: DateTime::parse_from_rfc3339(&row.get::<_, String>(5)?)
-                    .map_err(|_| rusqlite::Error::InvalidColumnType(5, "created_at".to_string(), rusqlite::types::Type::Text))?
+                video_id: row.get(1)?,
+                current_time: row.get(2)?,
+                duration: row.get(3)?,
+                completed: row.get(4)?,
+                last_watched: DateTime::parse_from_rfc3339(&row.get::<_, String>(5)?)
+                    .map_err(|_| rusqlite::Error::InvalidColumnType(5, "last_watched".to_string(), rusqlite::types::Type::Text))?
                     .with_timezone(&Utc),
             })
         })?;
 
-        let mut activities = Vec::new();
-        for activity in activity_iter {
-            activities.push(activity?);
+        match rows.next() {
+            Some(row) => Ok(Some(row?)),
+            None => Ok(None),
         }
-        Ok(activities)
     }
 
-    pub fn get_activities_by_type(&self, activity_type: &str, limit: usize) -> Result<Vec<ActivityLog>> {
+    // Vídeos assistidos por último; os concluídos só entram com `include_completed`
+    pub fn get_recent_videos(&self, limit: usize, include_completed: bool) -> Result<Vec<(Video, VideoProgress)>> {
         let mut stmt = self.conn.prepare(
-            "SELECT id, activity_type, entity_id, entity_type, details, created_at 
-             FROM activity_log WHERE activity_type = ?1 ORDER BY created_at DESC LIMIT ?2"
+            "SELECT v.id, v.module_id, v.course_id, v.name, v.path, v.duration, v.order_index,
+                    p.id, p.video_id, p.current_time, p.duration, p.completed, p.last_watched
+             FROM videos v
+             INNER JOIN video_progress p ON v.id = p.video_id
+             WHERE ?2 OR p.completed = 0
+             ORDER BY p.last_watched DESC
+             LIMIT ?1"
         )?;
         
-        let activity_iter = stmt.query_map(params![activity_type, limit], |row| {
-            Ok(ActivityLog {
+        let video_iter = stmt.query_map(params![limit as i64, include_completed], |row| {
+            let video = Video {
                 id: row.get(0)?,
-                activity_type: row.get(1)?,
-                entity_id: row.get(2)?,
-                entity_type: row.get(3)?,
-                details: row.get(4)?,
-                created_at: DateTime::parse_from_rfc3339(&row.get::<_, String>(5)?)
-                    .map_err(|_| rusqlite::Error::InvalidColumnType(5, "created_at".to_string(), rusqlite::types::Type::Text))?
+                module_id: row.get(1)?,
+                course_id: row.get(2)?,
+                name: row.get(3)?,
+                path: row.get(4)?,
+                duration: row.get(5)?,
+                order_index: row.get(6)?,
+            };
+            
+            let progress = VideoProgress {
+                id: row.get(7)?,
+                video_id: row.get(8)?,
+                current_time: row.get(9)?,
+                duration: row.get(10)?,
+                completed: row.get(11)?,
+                last_watched: DateTime::parse_from_rfc3339(&row.get::<_, String>(12)?)
+                    .map_err(|_| rusqlite::Error::InvalidColumnType(12, "last_watched".to_string(), rusqlite::types::Type::Text))?
                     .with_timezone(&Utc),
-            })
+            };
+            
+            Ok((video, progress))
         })?;
 
-        let mut activities = Vec::new();
-        for activity in activity_iter {
-            activities.push(activity?);
+        let mut results = Vec::new();
+        for item in video_iter {
+            results.push(item?);
         }
-        Ok(activities)
+        Ok(results)
     }
 
-    // ========== MÉTODOS UTILITÁRIOS ==========
-    
-    pub fn initialize_default_settings(&self) -> Result<()> {
-        let default_settings = vec![
-            ("theme", "dark", "string"),
-            ("auto_play_next", "true", "boolean"),
-            ("playback_speed", "1.0", "number"),
-            ("volume", "0.8", "number"),
-            ("auto_save_progress", "true", "boolean"),
-            ("show_subtitles", "false", "boolean"),
-            ("language", "pt-BR", "string"),
-        ];
+    // Mescla o progresso de outro banco (ex.: instalação anterior), casando
+    // vídeos pelo caminho e mantendo o `last_watched` mais recente
+    pub fn import_progress_from(&self, other_db_path: &Path) -> std::result::Result<ProgressImport, AppError> {
+        if !other_db_path.is_file() {
+            return Err(AppError::NotFound(format!("Banco não encontrado: {}", other_db_path.display())));
+        }
 
-        for (key, value, setting_type) in default_settings {
-            // Só criar se não existir
-            if self.get_user_setting(key)?.is_none() {
-                let setting = UserSettings {
-                    id: uuid::Uuid::new_v4().to_string(),
-                    setting_key: key.to_string(),
-                    setting_value: value.to_string(),
-                    setting_type: setting_type.to_string(),
-                    updated_at: Utc::now(),
-                };
-                self.set_user_setting(&setting)?;
+        let other = Connection::open_with_flags(other_db_path, OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+        let mut stmt = other.prepare(
+            "SELECT v.path, vp.current_time, vp.duration, vp.completed, vp.last_watched 
+             FROM video_progress vp 
+             INNER JOIN videos v ON v.id = vp.video_id"
+        )?;
+        let rows = stmt.query_map([], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, f64>(1)?,
+                row.get::<_, f64>(2)?,
+                row.get::<_, bool>(3)?,
+                row.get::<_, String>(4)?,
+            ))
+        })?;
+
+        let tx = self.transaction()?;
+        let mut result = ProgressImport::default();
+        for row in rows {
+            let (path, current_time, duration, completed, last_watched) = row?;
+            let last_watched = match DateTime::parse_from_rfc3339(&last_watched) {
+                Ok(dt) => dt.with_timezone(&Utc),
+                Err(e) => {
+                    log::warn!("⚠️ Progresso de {} ignorado, data inválida '{}': {}", path, last_watched, e);
+                    result.skipped += 1;
+                    continue;
+                }
+            };
+            let Some(video) = self.get_video_by_normalized_path(&path)? else {
+                result.skipped += 1;
+                continue;
+            };
+            result.matched += 1;
+
+            let local = self.get_video_progress(&video.id)?;
+            if local.as_ref().is_some_and(|p| p.last_watched >= last_watched) {
+                continue;
             }
+
+            self.update_video_progress(&VideoProgress {
+                id: local.map(|p| p.id).unwrap_or_else(|| uuid::Uuid::new_v4().to_string()),
+                video_id: video.id,
+                current_time,
+                duration,
+                completed,
+                last_watched,
+            })?;
+            result.updated += 1;
         }
+        tx.commit()?;
 
-        Ok(())
+        Ok(result)
     }
 
-    // Métodos para gerenciar conclusão de vídeos
-    pub fn mark_video_completed(&self, video_id: &str, completed: bool) -> Result<()> {
-        // Primeiro, verifica se já existe um registro de progresso
-        if let Some(mut progress) = self.get_video_progress(video_id)? {
-            // Atualiza o registro existente
-            progress.completed = completed;
-            progress.last_watched = Utc::now();
-            self.update_video_progress(&progress)?;
-        } else {
-            // Cria um novo registro de progresso
+    // Sincroniza a conclusão dos vídeos do curso com uma lista em texto, com
+    // linhas "[x] Nome da aula" ou "[ ] Nome da aula". O nome é comparado sem
+    // acentos, pontuação e numeração inicial; linhas em outro formato são ignoradas
+    pub fn import_completion_checklist(&self, course_id: &str, file_path: &Path) -> std::result::Result<ChecklistImport, AppError> {
+        if self.get_course_by_id(course_id)?.is_none() {
+            return Err(AppError::NotFound(format!("Curso não encontrado: {}", course_id)));
+        }
+        let text = std::fs::read_to_string(file_path).map_err(|e| match e.kind() {
+            std::io::ErrorKind::NotFound => AppError::NotFound(format!("Lista não encontrada: {}", file_path.display())),
+            _ => AppError::InvalidInput(format!("Erro ao ler {}: {}", file_path.display(), e)),
+        })?;
+
+        let videos: Vec<(Video, String)> = self.get_videos(Some(course_id))?
+            .into_iter()
+            .map(|video| {
+                let key = checklist_key(&video.name);
+                (video, key)
+            })
+            .collect();
+
+        let tx = self.transaction()?;
+        let mut result = ChecklistImport::default();
+        for (checked, name) in text.lines().filter_map(parse_checklist_line) {
+            let key = checklist_key(name);
+            let found = videos.iter()
+                .find(|(_, video_key)| *video_key == key)
+                .or_else(|| videos.iter().find(|(_, video_key)| strip_numbering(video_key) == strip_numbering(&key)));
+            let Some((video, _)) = found else {
+                result.unmatched.push(name.to_string());
+                continue;
+            };
+            result.matched += 1;
+
+            let completed = self.get_video_progress(&video.id)?.is_some_and(|p| p.completed);
+            if completed != checked {
+                self.mark_video_completed(&video.id, checked)?;
+                result.changed += 1;
+            }
+        }
+        tx.commit()?;
+
+        Ok(result)
+    }
+
+    // Último vídeo assistido de cada curso, para retomar do ponto onde parou;
+    // cursos sem progresso ficam de fora
+    pub fn get_last_watched_per_course(&self) -> Result<Vec<(Course, Video, VideoProgress)>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {}, 
+                    latest.v_id, latest.module_id, latest.v_course_id, latest.v_name, latest.v_path, latest.v_duration, latest.order_index, 
+                    latest.p_id, latest.p_current_time, latest.p_duration, latest.completed, latest.last_watched 
+             FROM courses 
+             INNER JOIN (
+                SELECT v.id AS v_id, v.module_id, v.course_id AS v_course_id, v.name AS v_name, v.path AS v_path, 
+                       v.duration AS v_duration, v.order_index, 
+                       p.id AS p_id, p.current_time AS p_current_time, p.duration AS p_duration, p.completed, p.last_watched, 
+                       ROW_NUMBER() OVER (PARTITION BY v.course_id ORDER BY p.last_watched DESC) AS position 
+                FROM video_progress p 
+                INNER JOIN videos v ON v.id = p.video_id
+             ) latest ON latest.v_course_id = courses.id AND latest.position = 1 
+             ORDER BY latest.last_watched DESC",
+            COURSE_COLUMNS
+        ))?;
+
+        let resume_iter = stmt.query_map([], |row| {
+            let course = Self::course_from_row(row)?;
+            let video = Video {
+                id: row.get(8)?,
+                module_id: row.get(9)?,
+                course_id: row.get(10)?,
+                name: row.get(11)?,
+                path: row.get(12)?,
+                duration: row.get(13)?,
+                order_index: row.get(14)?,
+            };
             let progress = VideoProgress {
-                id: uuid::Uuid::new_v4().to_string(),
-                video_id: video_id.to_string(),
-                current_time: if completed { 100.0 } else { 0.0 }, // Assume 100% se completo
-                duration: 100.0, // Valor padrão, será atualizado quando o vídeo for reproduzido
-                completed,
-                last_watched: Utc::now(),
+                id: row.get(15)?,
+                video_id: video.id.clone(),
+                current_time: row.get(16)?,
+                duration: row.get(17)?,
+                completed: row.get(18)?,
+                last_watched: DateTime::parse_from_rfc3339(&row.get::<_, String>(19)?)
+                    .map_err(|_| rusqlite::Error::InvalidColumnType(19, "last_watched".to_string(), rusqlite::types::Type::Text))?
+                    .with_timezone(&Utc),
             };
-            self.update_video_progress(&progress)?;
+            Ok((course, video, progress))
+        })?;
+
+        let mut results = Vec::new();
+        for item in resume_iter {
+            results.push(item?);
         }
-        Ok(())
+        Ok(results)
     }
 
-    pub fn get_completed_videos(&self, course_id: Option<&str>) -> Result<Vec<(Video, VideoProgress)>> {
-        let mut videos = Vec::new();
-        
-        if let Some(course_id) = course_id {
-            let mut stmt = self.conn.prepare(
-                "SELECT v.id, v.module_id, v.course_id, v.name, v.path, v.duration, v.order_index,
-                        vp.id, vp.video_id, vp.current_time, vp.duration, vp.completed, vp.last_watched
+    // Progresso salvo depois de `since` (opcionalmente só de um curso), do mais
+    // recente ao mais antigo. As datas são gravadas em RFC 3339 UTC, então a
+    // comparação de texto no SQL segue a ordem cronológica
+    pub fn get_progress_since(&self, since: DateTime<Utc>, course_id: Option<&str>) -> Result<Vec<(Course, Video, VideoProgress)>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {}, 
+                    watched.v_id, watched.module_id, watched.v_course_id, watched.v_name, watched.v_path, watched.v_duration, watched.order_index, 
+                    watched.p_id, watched.p_current_time, watched.p_duration, watched.completed, watched.last_watched 
+             FROM courses 
+             INNER JOIN (
+                SELECT v.id AS v_id, v.module_id, v.course_id AS v_course_id, v.name AS v_name, v.path AS v_path, 
+                       v.duration AS v_duration, v.order_index, 
+                       p.id AS p_id, p.current_time AS p_current_time, p.duration AS p_duration, p.completed, p.last_watched 
+                FROM video_progress p 
+                INNER JOIN videos v ON v.id = p.video_id
+             ) watched ON watched.v_course_id = courses.id 
+             WHERE watched.last_watched > ?2 AND (?1 IS NULL OR courses.id = ?1) 
+             ORDER BY watched.last_watched DESC",
+            COURSE_COLUMNS
+        ))?;
+
+        let progress_iter = stmt.query_map(params![course_id, since.to_rfc3339()], |row| {
+            let course = Self::course_from_row(row)?;
+            let video = Video {
+                id: row.get(8)?,
+                module_id: row.get(9)?,
+                course_id: row.get(10)?,
+                name: row.get(11)?,
+                path: row.get(12)?,
+                duration: row.get(13)?,
+                order_index: row.get(14)?,
+            };
+            let progress = VideoProgress {
+                id: row.get(15)?,
+                video_id: video.id.clone(),
+                current_time: row.get(16)?,
+                duration: row.get(17)?,
+                completed: row.get(18)?,
+                last_watched: DateTime::parse_from_rfc3339(&row.get::<_, String>(19)?)
+                    .map_err(|_| rusqlite::Error::InvalidColumnType(19, "last_watched".to_string(), rusqlite::types::Type::Text))?
+                    .with_timezone(&Utc),
+            };
+            Ok((course, video, progress))
+        })?;
+
+        let mut results = Vec::new();
+        for item in progress_iter {
+            results.push(item?);
+        }
+        Ok(results)
+    }
+
+    pub fn get_course_cover(&self, course_id: &str) -> Result<Option<String>> {
+        let result = self.conn.query_row(
+            "SELECT cover_path FROM courses WHERE id = ?1",
+            params![course_id],
+            |row| row.get::<_, Option<String>>(0),
+        );
+
+        match result {
+            Ok(cover_path) => Ok(cover_path),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    pub fn get_global_summary(&self) -> Result<GlobalSummary> {
+        let course_count: i64 = self.conn.query_row("SELECT COUNT(*) FROM courses", [], |row| row.get(0))?;
+        let (total_videos, completed_videos): (i64, i64) = self.conn.query_row(
+            "SELECT (SELECT COUNT(*) FROM videos), 
+                    (SELECT COUNT(DISTINCT vp.video_id) FROM video_progress vp 
+                     INNER JOIN videos v ON v.id = vp.video_id WHERE vp.completed = 1)",
+            [],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )?;
+        // Cada salvamento guarda a posição inteira, então só o último de cada vídeo soma
+        let total_watch_seconds: f64 = self.conn.query_row(
+            &format!("SELECT COALESCE(SUM(vp.current_time), 0) FROM {} vp WHERE vp.completed_manually = 0", LATEST_PROGRESS),
+            [],
+            |row| row.get(0),
+        )?;
+
+        let percent = if total_videos > 0 {
+            completed_videos as f64 * 100.0 / total_videos as f64
+        } else {
+            0.0
+        };
+        Ok(GlobalSummary { total_videos, completed_videos, percent, total_watch_seconds, course_count })
+    }
+
+    // Soma do que falta assistir dos vídeos não concluídos (o vídeo inteiro se
+    // não houver progresso), dividida pela `playback_speed` configurada
+    pub fn get_course_time_remaining(&self, course_id: &str) -> Result<CourseTimeRemaining> {
+        let (remaining, unknown_duration_count): (f64, i32) = self.conn.query_row(
+            &format!(
+                "SELECT COALESCE(SUM(MAX(v.duration - COALESCE(vp.current_time, 0), 0)), 0), 
+                        COUNT(CASE WHEN v.duration IS NULL THEN 1 END) 
                  FROM videos v 
-                 INNER JOIN video_progress vp ON v.id = vp.video_id 
-                 WHERE vp.completed = 1 AND v.course_id = ?
-                 ORDER BY vp.last_watched DESC"
+                 LEFT JOIN {} vp ON vp.video_id = v.id 
+                 WHERE v.course_id = ?1 AND COALESCE(vp.completed, 0) = 0",
+                LATEST_PROGRESS
+            ),
+            params![course_id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )?;
+
+        let speed = self.get_playback_settings()?.playback_speed;
+        Ok(CourseTimeRemaining {
+            remaining_seconds: remaining / speed,
+            unknown_duration_count,
+        })
+    }
+
+    // Soma das durações dos vídeos do curso (None conta como 0). O total fica
+    // em cache em courses.total_duration até o próximo escaneamento
+    pub fn get_course_duration(&self, course_id: &str) -> Result<CourseDuration> {
+        let unknown_duration_count: i32 = self.conn.query_row(
+            "SELECT COUNT(*) FROM videos WHERE course_id = ?1 AND duration IS NULL",
+            params![course_id],
+            |row| row.get(0),
+        )?;
+
+        let cached: Option<f64> = match self.conn.query_row(
+            "SELECT total_duration FROM courses WHERE id = ?1",
+            params![course_id],
+            |row| row.get(0),
+        ) {
+            Ok(total) => total,
+            Err(rusqlite::Error::QueryReturnedNoRows) => None,
+            Err(e) => return Err(e),
+        };
+
+        let total_seconds = match cached {
+            Some(total) => total,
+            None => {
+                let total: f64 = self.conn.query_row(
+                    "SELECT COALESCE(SUM(duration), 0) FROM videos WHERE course_id = ?1",
+                    params![course_id],
+                    |row| row.get(0),
+                )?;
+                self.conn.execute(
+                    "UPDATE courses SET total_duration = ?1 WHERE id = ?2",
+                    params![total, course_id],
+                )?;
+                total
+            }
+        };
+
+        Ok(CourseDuration { total_seconds, unknown_duration_count })
+    }
+
+    // Remove do curso os vídeos e módulos que não estão mais no disco,
+    // junto com progresso, bookmarks e anotações dos vídeos removidos.
+    // Retorna quantos vídeos foram removidos
+    pub fn remove_missing_course_entries(
+        &self,
+        course_id: &str,
+        kept_video_ids: &HashSet<String>,
+        kept_module_ids: &HashSet<String>,
+    ) -> Result<usize> {
+        let video_ids: Vec<String> = {
+            let mut stmt = self.conn.prepare("SELECT id FROM videos WHERE course_id = ?1")?;
+            let rows = stmt.query_map(params![course_id], |row| row.get(0))?;
+            rows.collect::<Result<Vec<String>>>()?
+        };
+        let module_ids: Vec<String> = {
+            let mut stmt = self.conn.prepare("SELECT id FROM modules WHERE course_id = ?1")?;
+            let rows = stmt.query_map(params![course_id], |row| row.get(0))?;
+            rows.collect::<Result<Vec<String>>>()?
+        };
+
+        let mut removed_videos = 0;
+        for video_id in video_ids.iter().filter(|id| !kept_video_ids.contains(*id)) {
+            self.conn.execute("DELETE FROM video_progress WHERE video_id = ?1", params![video_id])?;
+            self.conn.execute("DELETE FROM video_bookmarks WHERE video_id = ?1", params![video_id])?;
+            self.conn.execute("DELETE FROM user_notes WHERE video_id = ?1", params![video_id])?;
+            self.conn.execute("DELETE FROM videos WHERE id = ?1", params![video_id])?;
+            removed_videos += 1;
+        }
+
+        for module_id in module_ids.iter().filter(|id| !kept_module_ids.contains(*id)) {
+            self.conn.execute("DELETE FROM user_notes WHERE module_id = ?1", params![module_id])?;
+            self.conn.execute("DELETE FROM modules WHERE id = ?1", params![module_id])?;
+        }
+
+        if removed_videos > 0 {
+            self.conn.execute(
+                "UPDATE courses SET total_duration = NULL WHERE id = ?1",
+                params![course_id],
             )?;
-            
-            let video_iter = stmt.query_map(params![course_id], |row| {
-                Ok((
-                    Video {
-                        id: row.get(0)?,
-                        module_id: row.get(1)?,
-                        course_id: row.get(2)?,
-                        name: row.get(3)?,
-                        path: row.get(4)?,
-                        duration: row.get(5)?,
-                        order_index: row.get(6)?,
-                    },
-                    VideoProgress {
-                        id: row.get(7)?,
-                        video_id: row.get(8)?,
-                        current_time: row.get(9)?,
-                        duration: row.get(10)?,
-                        completed: row.get(11)?,
-                        last_watched: DateTime::parse_from_rfc3339(&row.get::<_, String>(12)?)
-                            .map_err(|_| rusqlite::Error::InvalidColumnType(12, "last_watched".to_string(), rusqlite::types::Type::Text))?
-                            .with_timezone(&Utc),
-                    },
-                ))
-            })?;
-            
-            for video in video_iter {
-                videos.push(video?);
-            }
-        } else {
+        }
+
+        Ok(removed_videos)
+    }
+
+    pub fn find_orphans(&self) -> Result<OrphanReport> {
+        let module_ids = self.orphan_ids("modules", ORPHAN_MODULE_CONDITION)?;
+        let video_ids = self.orphan_ids("videos", ORPHAN_VIDEO_CONDITION)?;
+
+        let mut sample_modules = Vec::new();
+        {
             let mut stmt = self.conn.prepare(
-                "SELECT v.id, v.module_id, v.course_id, v.name, v.path, v.duration, v.order_index,
-                        vp.id, vp.video_id, vp.current_time, vp.duration, vp.completed, vp.last_watched
-                 FROM videos v 
-                 INNER JOIN video_progress vp ON v.id = vp.video_id 
-                 WHERE vp.completed = 1
-                 ORDER BY vp.last_watched DESC"
+                "SELECT id, course_id, name, path, order_index FROM modules WHERE id = ?1"
             )?;
-            
-            let video_iter = stmt.query_map([], |row| {
-                Ok((
-                    Video {
+            for module_id in module_ids.iter().take(ORPHAN_SAMPLE_LIMIT) {
+                sample_modules.push(stmt.query_row([module_id], |row| {
+                    Ok(Module {
                         id: row.get(0)?,
-                        module_id: row.get(1)?,
-                        course_id: row.get(2)?,
-                        name: row.get(3)?,
-                        path: row.get(4)?,
-                        duration: row.get(5)?,
-                        order_index: row.get(6)?,
-                    },
-                    VideoProgress {
-                        id: row.get(7)?,
-                        video_id: row.get(8)?,
-                        current_time: row.get(9)?,
-                        duration: row.get(10)?,
-                        completed: row.get(11)?,
-                        last_watched: DateTime::parse_from_rfc3339(&row.get::<_, String>(12)?)
-                            .map_err(|_| rusqlite::Error::InvalidColumnType(12, "last_watched".to_string(), rusqlite::types::Type::Text))?
-                            .with_timezone(&Utc),
-                    },
-                ))
-            })?;
-            
-            for video in video_iter {
-                videos.push(video?);
+                        course_id: row.get(1)?,
+                        name: row.get(2)?,
+                        path: row.get(3)?,
+                        order_index: row.get(4)?,
+                    })
+                })?);
             }
         }
-        
-        Ok(videos)
+        let mut sample_videos = Vec::new();
+        for video_id in video_ids.iter().take(ORPHAN_SAMPLE_LIMIT) {
+            sample_videos.extend(self.get_video_by_id(video_id)?);
+        }
+
+        Ok(OrphanReport {
+            module_count: module_ids.len(),
+            video_count: video_ids.len(),
+            sample_modules,
+            sample_videos,
+        })
     }
 
-    pub fn get_incomplete_videos(&self, course_id: Option<&str>) -> Result<Vec<(Video, Option<VideoProgress>)>> {
-        let mut videos = Vec::new();
-        
-        if let Some(course_id) = course_id {
-            let mut stmt = self.conn.prepare(
-                "SELECT v.id, v.module_id, v.course_id, v.name, v.path, v.duration, v.order_index,
-                        vp.id, vp.video_id, vp.current_time, vp.duration, vp.completed, vp.last_watched
-                 FROM videos v 
-                 LEFT JOIN video_progress vp ON v.id = vp.video_id 
-                 WHERE (vp.completed IS NULL OR vp.completed = 0) AND v.course_id = ?
-                 ORDER BY v.order_index"
-            )?;
-            
-            let video_iter = stmt.query_map(params![course_id], |row| {
-                let progress = if row.get::<_, Option<String>>(7)?.is_some() {
-                    Some(VideoProgress {
-                        id: row.get(7)?,
-                        video_id: row.get(8)?,
-                        current_time: row.get(9)?,
-                        duration: row.get(10)?,
-                        completed: row.get(11)?,
-                        last_watched: DateTime::parse_from_rfc3339(&row.get::<_, String>(12)?)
-                            .map_err(|_| rusqlite::Error::InvalidColumnType(12, "last_watched".to_string(), rusqlite::types::Type::Text))?
-                            .with_timezone(&Utc),
-                    })
-                } else {
-                    None
-                };
+    // Remove os órfãos e o que depende deles (progresso, bookmarks, anotações)
+    // numa única transação. Retorna (módulos, vídeos) removidos
+    pub fn delete_orphans(&self) -> Result<(usize, usize)> {
+        let tx = self.transaction()?;
 
-                Ok((
-                    Video {
-                        id: row.get(0)?,
-                        module_id: row.get(1)?,
-                        course_id: row.get(2)?,
-                        name: row.get(3)?,
-                        path: row.get(4)?,
-                        duration: row.get(5)?,
-                        order_index: row.get(6)?,
-                    },
-                    progress,
-                ))
-            })?;
-            
-            for video in video_iter {
-                videos.push(video?);
+        let video_ids = self.orphan_ids("videos", ORPHAN_VIDEO_CONDITION)?;
+        for video_id in &video_ids {
+            self.conn.execute("DELETE FROM video_progress WHERE video_id = ?1", params![video_id])?;
+            self.conn.execute("DELETE FROM video_bookmarks WHERE video_id = ?1", params![video_id])?;
+            self.conn.execute("DELETE FROM user_notes WHERE video_id = ?1", params![video_id])?;
+            self.conn.execute("DELETE FROM videos WHERE id = ?1", params![video_id])?;
+        }
+
+        let module_ids = self.orphan_ids("modules", ORPHAN_MODULE_CONDITION)?;
+        for module_id in &module_ids {
+            self.conn.execute("DELETE FROM user_notes WHERE module_id = ?1", params![module_id])?;
+            self.conn.execute("DELETE FROM modules WHERE id = ?1", params![module_id])?;
+        }
+
+        tx.commit()?;
+        Ok((module_ids.len(), video_ids.len()))
+    }
+
+    fn orphan_ids(&self, table: &str, condition: &str) -> Result<Vec<String>> {
+        let mut stmt = self.conn.prepare(&format!("SELECT id FROM {} WHERE {} ORDER BY id", table, condition))?;
+        let rows = stmt.query_map([], |row| row.get(0))?;
+        rows.collect()
+    }
+
+    pub fn update_course_last_accessed(&self, course_id: &str) -> Result<()> {
+        self.conn.execute(
+            "UPDATE courses SET last_accessed = ?1 WHERE id = ?2",
+            params![Utc::now().to_rfc3339(), course_id],
+        )?;
+        Ok(())
+    }
+
+    // ========== MÉTODOS PARA ANOTAÇÕES ==========
+    
+    // Confere as associações da anotação antes de gravá-la: cada tipo exige a
+    // sua (vídeo, módulo ou curso), os ids informados precisam existir e
+    // pertencer uns aos outros, e os que faltam são completados a partir do
+    // vídeo ou do módulo. Ids vazios contam como não informados
+    pub fn resolve_note_associations(&self, note: &mut UserNote) -> std::result::Result<(), AppError> {
+        validate_note_type(&note.note_type)?;
+        for id in [&mut note.video_id, &mut note.module_id, &mut note.course_id] {
+            if id.as_deref().is_some_and(|id| id.trim().is_empty()) {
+                *id = None;
             }
-        } else {
-            let mut stmt = self.conn.prepare(
-                "SELECT v.id, v.module_id, v.course_id, v.name, v.path, v.duration, v.order_index,
-                        vp.id, vp.video_id, vp.current_time, vp.duration, vp.completed, vp.last_watched
-                 FROM videos v 
-                 LEFT JOIN video_progress vp ON v.id = vp.video_id 
-                 WHERE (vp.completed IS NULL OR vp.completed = 0)
-                 ORDER BY v.order_index"
-            )?;
-            
-            let video_iter = stmt.query_map([], |row| {
-                let progress = if row.get::<_, Option<String>>(7)?.is_some() {
-                    Some(VideoProgress {
-                        id: row.get(7)?,
-                        video_id: row.get(8)?,
-                        current_time: row.get(9)?,
-                        duration: row.get(10)?,
-                        completed: row.get(11)?,
-                        last_watched: DateTime::parse_from_rfc3339(&row.get::<_, String>(12)?)
-                            .map_err(|_| rusqlite::Error::InvalidColumnType(12, "last_watched".to_string(), rusqlite::types::Type::Text))?
-                            .with_timezone(&Utc),
-                    })
-                } else {
-                    None
-                };
+        }
 
-                Ok((
-                    Video {
-                        id: row.get(0)?,
-                        module_id: row.get(1)?,
-                        course_id: row.get(2)?,
-                        name: row.get(3)?,
-                        path: row.get(4)?,
-                        duration: row.get(5)?,
-                        order_index: row.get(6)?,
-                    },
-                    progress,
-                ))
-            })?;
-            
-            for video in video_iter {
-                videos.push(video?);
+        let mismatch = |what: &str, id: &str, expected: &str, found: &str| AppError::InvalidInput(format!(
+            "{} {} pertence a {}, não a {}", what, id, found, expected
+        ));
+
+        if let Some(video_id) = &note.video_id {
+            let video = self.get_video_by_id(video_id)?
+                .ok_or_else(|| AppError::NotFound(format!("Vídeo não encontrado: {}", video_id)))?;
+            match &note.module_id {
+                Some(module_id) if *module_id != video.module_id => {
+                    return Err(mismatch("O vídeo", video_id, module_id, &video.module_id));
+                }
+                _ => note.module_id = Some(video.module_id),
+            }
+            match &note.course_id {
+                Some(course_id) if *course_id != video.course_id => {
+                    return Err(mismatch("O vídeo", video_id, course_id, &video.course_id));
+                }
+                _ => note.course_id = Some(video.course_id),
             }
+        } else if note.timestamp.is_some() {
+            return Err(AppError::InvalidInput("Só anotações de vídeo têm posição".to_string()));
         }
-        
-        Ok(videos)
+
+        if let Some(module_id) = &note.module_id {
+            let module = self.get_module_by_id(module_id)?
+                .ok_or_else(|| AppError::NotFound(format!("Módulo não encontrado: {}", module_id)))?;
+            match &note.course_id {
+                Some(course_id) if *course_id != module.course_id => {
+                    return Err(mismatch("O módulo", module_id, course_id, &module.course_id));
+                }
+                _ => note.course_id = Some(module.course_id),
+            }
+        }
+
+        if let Some(course_id) = &note.course_id {
+            if self.get_course_by_id(course_id)?.is_none() {
+                return Err(AppError::NotFound(format!("Curso não encontrado: {}", course_id)));
+            }
+        }
+
+        let required = match note.note_type.as_str() {
+            "video" => note.video_id.is_none().then_some("vídeo"),
+            "module" => note.module_id.is_none().then_some("módulo"),
+            "course" => note.course_id.is_none().then_some("curso"),
+            _ => None,
+        };
+        if let Some(required) = required {
+            return Err(AppError::InvalidInput(format!(
+                "Anotação do tipo {} precisa de um {}", note.note_type, required
+            )));
+        }
+        Ok(())
     }
 
-    pub fn get_course_completion_stats(&self, course_id: &str) -> Result<(i32, i32, i32)> {
-        let total_videos: i32 = self.conn.query_row(
-            "SELECT COUNT(*) FROM videos WHERE course_id = ?",
-            params![course_id],
-            |row| row.get(0),
+    pub fn create_user_note(&self, note: &UserNote) -> Result<()> {
+        self.conn.execute(
+            "INSERT INTO user_notes (id, video_id, course_id, module_id, timestamp, title, content, note_type, created_at, updated_at, color, bookmark_id) 
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)",
+            params![
+                note.id,
+                note.video_id,
+                note.course_id,
+                note.module_id,
+                note.timestamp,
+                note.title,
+                note.content,
+                note.note_type,
+                note.created_at.to_rfc3339(),
+                note.updated_at.to_rfc3339(),
+                note.color,
+                note.bookmark_id
+            ],
         )?;
+        Ok(())
+    }
 
-        let completed_videos: i32 = self.conn.query_row(
-            "SELECT COUNT(*) FROM videos v INNER JOIN video_progress vp ON v.id = vp.video_id WHERE v.course_id = ? AND vp.completed = 1",
-            params![course_id],
-            |row| row.get(0),
+    pub fn update_user_note(&self, note: &UserNote) -> Result<()> {
+        self.conn.execute(
+            "UPDATE user_notes SET title = ?1, content = ?2, updated_at = ?3, color = ?4 WHERE id = ?5",
+            params![note.title, note.content, note.updated_at.to_rfc3339(), note.color, note.id],
         )?;
+        Ok(())
+    }
 
-        let in_progress_videos: i32 = self.conn.query_row(
-            "SELECT COUNT(*) FROM videos v INNER JOIN video_progress vp ON v.id = vp.video_id WHERE v.course_id = ? AND vp.completed = 0 AND vp.current_time > 0",
-            params![course_id],
-            |row| row.get(0),
-        )?;
+    // Altera tipo e/ou cor de várias anotações numa única transação. `color`
+    // None mantém a cor atual e Some(None) remove a cor
+    pub fn bulk_update_notes(
+        &self,
+        note_ids: &[String],
+        note_type: Option<&str>,
+        color: Option<Option<&str>>,
+    ) -> std::result::Result<BulkNoteUpdate, AppError> {
+        if let Some(note_type) = note_type {
+            validate_note_type(note_type)?;
+        }
+        if let Some(color) = color {
+            validate_note_color(color)?;
+        }
 
-        Ok((total_videos, completed_videos, in_progress_videos))
+        let tx = self.transaction()?;
+        let now = Utc::now().to_rfc3339();
+        let mut result = BulkNoteUpdate { updated: 0, missing_ids: Vec::new() };
+
+        for note_id in note_ids {
+            let updated = self.conn.execute(
+                "UPDATE user_notes 
+                 SET note_type = COALESCE(?1, note_type), 
+                     color = CASE WHEN ?2 THEN ?3 ELSE color END, 
+                     updated_at = ?4 
+                 WHERE id = ?5 AND deleted_at IS NULL",
+                params![note_type, color.is_some(), color.flatten(), now, note_id],
+            )?;
+            if updated == 0 {
+                result.missing_ids.push(note_id.clone());
+            } else {
+                result.updated += 1;
+            }
+        }
+
+        tx.commit()?;
+        Ok(result)
     }
 
-    pub fn get_video_by_path(&self, file_path: &str) -> Result<Option<Video>> {
-        let mut stmt = self.conn.prepare(
-            "SELECT id, module_id, course_id, name, path, duration, order_index 
-             FROM videos WHERE path = ?"
+    // Exclusão reversível: a anotação some das consultas até ser restaurada ou purgada
+    pub fn delete_user_note(&self, note_id: &str) -> Result<()> {
+        self.conn.execute(
+            "UPDATE user_notes SET deleted_at = ?1 WHERE id = ?2 AND deleted_at IS NULL",
+            params![Utc::now().to_rfc3339(), note_id],
+        )?;
+        Ok(())
+    }
+
+    pub fn restore_user_note(&self, note_id: &str) -> std::result::Result<(), AppError> {
+        let restored = self.conn.execute(
+            "UPDATE user_notes SET deleted_at = NULL WHERE id = ?1 AND deleted_at IS NOT NULL",
+            params![note_id],
         )?;
 
-        let result = stmt.query_row(params![file_path], |row| {
-            Ok(Video {
-                id: row.get(0)?,
-                module_id: row.get(1)?,
-                course_id: row.get(2)?,
-                name: row.get(3)?,
-                path: row.get(4)?,
-                duration: row.get(5)?,
-                order_index: row.get(6)?,
-            })
-        });
+        if restored == 0 {
+            return Err(AppError::NotFound(format!("Anotação excluída não encontrada: {}", note_id)));
+        }
+        Ok(())
+    }
 
-        match result {
-            Ok(video) => Ok(Some(video)),
-            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
-            Err(e) => Err(e),
+    // Exclui (de forma reversível, como `delete_user_note`) todas as anotações do
+    // curso e, se pedido, os bookmarks dos seus vídeos. O progresso não é tocado
+    pub fn clear_course_annotations(&self, course_id: &str, include_bookmarks: bool) -> std::result::Result<ClearedAnnotations, AppError> {
+        if self.get_course_by_id(course_id)?.is_none() {
+            return Err(AppError::NotFound(format!("Curso não encontrado: {}", course_id)));
         }
+
+        let tx = self.transaction()?;
+        let now = Utc::now().to_rfc3339();
+        let notes_deleted = self.conn.execute(
+            "UPDATE user_notes SET deleted_at = ?1 WHERE course_id = ?2 AND deleted_at IS NULL",
+            params![now, course_id],
+        )?;
+        let bookmarks_deleted = if include_bookmarks {
+            self.conn.execute(
+                "UPDATE video_bookmarks SET deleted_at = ?1 
+                 WHERE deleted_at IS NULL AND video_id IN (SELECT id FROM videos WHERE course_id = ?2)",
+                params![now, course_id],
+            )?
+        } else {
+            0
+        };
+        tx.commit()?;
+
+        Ok(ClearedAnnotations { notes_deleted, bookmarks_deleted })
+    }
+
+    pub fn get_notes_by_video(&self, video_id: &str) -> Result<Vec<UserNote>> {
+        let stmt = self.conn.prepare(&format!(
+            "SELECT {} FROM user_notes WHERE video_id = ?1 AND deleted_at IS NULL ORDER BY timestamp ASC, created_at ASC",
+            NOTE_COLUMNS
+        ))?;
+        
+        self.map_notes_from_query(stmt, params![video_id])
+    }
+
+    // Anotações ligadas ao bookmark. Com o bookmark na lixeira a ligação é
+    // mantida, para voltar junto se ele for restaurado
+    pub fn get_notes_for_bookmark(&self, bookmark_id: &str) -> Result<Vec<UserNote>> {
+        let stmt = self.conn.prepare(&format!(
+            "SELECT {} FROM user_notes WHERE bookmark_id = ?1 AND deleted_at IS NULL ORDER BY created_at ASC",
+            NOTE_COLUMNS
+        ))?;
+
+        self.map_notes_from_query(stmt, params![bookmark_id])
     }
-}
\ No newline at end of file
+
+    // Anotações do vídeo cujo título ou conteúdo contém `query`, sem diferenciar
+    // maiúsculas (inclusive acentuadas); consulta vazia retorna todas
+    pub fn search_notes_in_video(&self, video_id: &str, query: &str) -> Result<Vec<UserNote>> {
+        let notes = self.get_notes_by_video(video_id)?;
+        let query = query.trim().to_lowercase();
+        if query.is_empty() {
+            return Ok(notes);
+        }
+
+        Ok(notes
+            .into_iter()
+            .filter(|note| note.title.to_lowercase().contains(&query) || note.content.to_lowercase().contains(&query))
+            .collect())
+    }
+
+    pub fn get_notes_by_course(&self, course_id: &str) -> Result<Vec<UserNote>> {
+        let stmt = self.conn.prepare(&format!(
+            "SELECT {} FROM user_notes WHERE course_id = ?1 AND deleted_at IS NULL ORDER BY created_at DESC",
+            NOTE_COLUMNS
+        ))?;
+        
+        self.map_notes_from_query(stmt, params![course_id])
+    }
+
+    // Anotações ligadas ao módulo, inclusive as dos vídeos dele
+    pub fn get_notes_by_module(&self, module_id: &str) -> Result<Vec<UserNote>> {
+        let stmt = self.conn.prepare(&format!(
+            "SELECT {} FROM user_notes WHERE module_id = ?1 AND deleted_at IS NULL ORDER BY created_at DESC",
+            NOTE_COLUMNS
+        ))?;
+
+        self.map_notes_from_query(stmt, params![module_id])
+    }
+
+    // Só as anotações do curso em si, sem as de vídeos (vídeo vazio ou tipo 'course')
+    pub fn get_course_level_notes(&self, course_id: &str) -> Result<Vec<UserNote>> {
+        let stmt = self.conn.prepare(&format!(
+            "SELECT {} FROM user_notes 
+             WHERE course_id = ?1 AND deleted_at IS NULL 
+               AND (video_id IS NULL OR video_id = '' OR note_type = 'course') 
+             ORDER BY created_at DESC",
+            NOTE_COLUMNS
+        ))?;
+
+        self.map_notes_from_query(stmt, params![course_id])
+    }
+
+    pub fn get_all_notes(&self) -> Result<Vec<UserNote>> {
+        let stmt = self.conn.prepare(&format!(
+            "SELECT {} FROM user_notes WHERE deleted_at IS NULL ORDER BY created_at DESC",
+            NOTE_COLUMNS
+        ))?;
+        
+        self.map_notes_from_query(stmt, params![])
+    }
+
+    pub fn get_notes_by_color(&self, color: &str) -> Result<Vec<UserNote>> {
+        let stmt = self.conn.prepare(&format!(
+            "SELECT {} FROM user_notes WHERE color = ?1 COLLATE NOCASE AND deleted_at IS NULL ORDER BY created_at DESC",
+            NOTE_COLUMNS
+        ))?;
+
+        self.map_notes_from_query(stmt, params![color])
+    }
+
+    // Anotações com timestamp além da duração conhecida do vídeo, como as que
+    // sobram depois de um vídeo ser reencodado mais curto. Só leitura
+    pub fn find_dangling_note_timestamps(&self) -> Result<Vec<UserNote>> {
+        let stmt = self.conn.prepare(&format!(
+            "SELECT {} FROM user_notes n
+             WHERE n.deleted_at IS NULL
+               AND n.timestamp > (SELECT v.duration FROM videos v WHERE v.id = n.video_id AND v.duration > 0)
+             ORDER BY n.video_id, n.timestamp",
+            NOTE_COLUMNS
+        ))?;
+
+        self.map_notes_from_query(stmt, params![])
+    }
+
+    // Limita esses timestamps à duração do vídeo. Inclui as anotações na
+    // lixeira, para que voltem já corrigidas. Retorna quantas mudaram
+    pub fn clamp_note_timestamps(&self) -> Result<usize> {
+        self.conn.execute(
+            "UPDATE user_notes
+             SET timestamp = (SELECT v.duration FROM videos v WHERE v.id = user_notes.video_id)
+             WHERE timestamp > (SELECT v.duration FROM videos v WHERE v.id = user_notes.video_id AND v.duration > 0)",
+            [],
+        )
+    }
+
+    // Tipos de anotação em uso e quantas existem de cada, do mais usado ao menos usado
+    pub fn get_note_type_counts(&self) -> Result<Vec<(String, i64)>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT note_type, COUNT(*) FROM user_notes WHERE deleted_at IS NULL 
+             GROUP BY note_type ORDER BY COUNT(*) DESC, note_type ASC"
+        )?;
+
+        let count_iter = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+
+        let mut counts = Vec::new();
+        for count in count_iter {
+            counts.push(count?);
+        }
+        Ok(counts)
+    }
+
+    // Palavras mais frequentes no conteúdo das anotações, sem pontuação nem
+    // stopwords e com pelo menos `min_word_length` letras
+    pub fn get_note_keyword_frequencies(&self, top_n: usize, min_word_length: usize) -> Result<Vec<(String, i64)>> {
+        let mut counts: std::collections::HashMap<String, i64> = std::collections::HashMap::new();
+        for note in self.get_all_notes()? {
+            let content = note.content.to_lowercase();
+            let words = content
+                .split(|c: char| !c.is_alphanumeric())
+                .filter(|word| word.chars().count() >= min_word_length.max(1))
+                .filter(|word| !NOTE_KEYWORD_STOPWORDS.contains(word));
+            for word in words {
+                *counts.entry(word.to_string()).or_insert(0) += 1;
+            }
+        }
+
+        let mut frequencies: Vec<(String, i64)> = counts.into_iter().collect();
+        frequencies.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        frequencies.truncate(top_n);
+        Ok(frequencies)
+    }
+
+    pub fn get_note_by_id(&self, note_id: &str) -> Result<Option<UserNote>> {
+        let stmt = self.conn.prepare(&format!(
+            "SELECT {} FROM user_notes WHERE id = ?1 AND deleted_at IS NULL",
+            NOTE_COLUMNS
+        ))?;
+
+        Ok(self.map_notes_from_query(stmt, params![note_id])?.into_iter().next())
+    }
+
+    // Duplica uma anotação mantendo vídeo/curso/módulo/timestamp
+    pub fn clone_note(&self, note_id: &str) -> std::result::Result<UserNote, AppError> {
+        let source = self.get_note_by_id(note_id)?
+            .ok_or_else(|| AppError::NotFound(format!("Anotação não encontrada: {}", note_id)))?;
+
+        let now = Utc::now();
+        let clone = UserNote {
+            id: uuid::Uuid::new_v4().to_string(),
+            title: format!("{} (copy)", source.title),
+            created_at: now,
+            updated_at: now,
+            ..source
+        };
+
+        self.create_user_note(&clone)?;
+        Ok(clone)
+    }
+
+    fn map_notes_from_query(&self, mut stmt: rusqlite::Statement, params: impl rusqlite::Params) -> Result<Vec<UserNote>> {
+        let note_iter = stmt.query_map(params, |row| {
+            Ok(UserNote {
+                id: row.get(0)?,
+                video_id: row.get(1)?,
+                course_id: row.get(2)?,
+                module_id: row.get(3)?,
+                timestamp: row.get(4)?,
+                title: row.get(5)?,
+                content: row.get(6)?,
+                note_type: row.get(7)?,
+                created_at: DateTime::parse_from_rfc3339(&row.get::<_, String>(8)?)
+                    .map_err(|_| rusqlite::Error::InvalidColumnType(8, "created_at".to_string(), rusqlite::types::Type::Text))?
+                    .with_timezone(&Utc),
+                updated_at: DateTime::parse_from_rfc3339(&row.get::<_, String>(9)?)
+                    .map_err(|_| rusqlite::Error::InvalidColumnType(9, "updated_at".to_string(), rusqlite::types::Type::Text))?
+                    .with_timezone(&Utc),
+                color: row.get(10)?,
+                bookmark_id: row.get(11)?,
+            })
+        })?;
+
+        let mut notes = Vec::new();
+        for note in note_iter {
+            notes.push(note?);
+        }
+        Ok(notes)
+    }
+
+    // ========== MÉTODOS PARA BOOKMARKS ==========
+    
+    pub fn create_video_bookmark(&self, bookmark: &VideoBookmark) -> Result<()> {
+        self.conn.execute(
+            "INSERT INTO video_bookmarks (id, video_id, timestamp, title, description, created_at) 
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                bookmark.id,
+                bookmark.video_id,
+                bookmark.timestamp,
+                bookmark.title,
+                bookmark.description,
+                bookmark.created_at.to_rfc3339()
+            ],
+        )?;
+        Ok(())
+    }
+
+    // Exclusão reversível, como nas anotações
+    pub fn delete_video_bookmark(&self, bookmark_id: &str) -> Result<()> {
+        self.conn.execute(
+            "UPDATE video_bookmarks SET deleted_at = ?1 WHERE id = ?2 AND deleted_at IS NULL",
+            params![Utc::now().to_rfc3339(), bookmark_id],
+        )?;
+        Ok(())
+    }
+
+    pub fn restore_video_bookmark(&self, bookmark_id: &str) -> std::result::Result<(), AppError> {
+        let restored = self.conn.execute(
+            "UPDATE video_bookmarks SET deleted_at = NULL WHERE id = ?1 AND deleted_at IS NOT NULL",
+            params![bookmark_id],
+        )?;
+
+        if restored == 0 {
+            return Err(AppError::NotFound(format!("Bookmark excluído não encontrado: {}", bookmark_id)));
+        }
+        Ok(())
+    }
+
+    // Remove de vez anotações e bookmarks excluídos há mais de `older_than_days` dias.
+    // Retorna quantos registros foram apagados
+    pub fn purge_deleted(&self, older_than_days: i64) -> Result<usize> {
+        let cutoff = (Utc::now() - chrono::Duration::days(older_than_days.max(0))).to_rfc3339();
+        let notes = self.conn.execute(
+            "DELETE FROM user_notes WHERE deleted_at IS NOT NULL AND deleted_at <= ?1",
+            params![cutoff],
+        )?;
+        let bookmarks = self.conn.execute(
+            "DELETE FROM video_bookmarks WHERE deleted_at IS NOT NULL AND deleted_at <= ?1",
+            params![cutoff],
+        )?;
+        Ok(notes + bookmarks)
+    }
+
+    pub fn get_video_bookmarks(&self, video_id: &str) -> Result<Vec<VideoBookmark>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, video_id, timestamp, title, description, created_at 
+             FROM video_bookmarks WHERE video_id = ?1 AND deleted_at IS NULL ORDER BY timestamp ASC"
+        )?;
+        
+        let bookmark_iter = stmt.query_map([video_id], |row| {
+            Ok(VideoBookmark {
+                id: row.get(0)?,
+                video_id: row.get(1)?,
+                timestamp: row.get(2)?,
+                title: row.get(3)?,
+                description: row.get(4)?,
+                created_at: DateTime::parse_from_rfc3339(&row.get::<_, String>(5)?)
+                    .map_err(|_| rusqlite::Error::InvalidColumnType(5, "created_at".to_string(), rusqlite::types::Type::Text))?
+                    .with_timezone(&Utc),
+            })
+        })?;
+
+        let mut bookmarks = Vec::new();
+        for bookmark in bookmark_iter {
+            bookmarks.push(bookmark?);
+        }
+        Ok(bookmarks)
+    }
+
+    // Último marcador antes de `current_time` e primeiro depois; um marcador
+    // exatamente na posição atual não entra em nenhum dos dois
+    pub fn get_adjacent_bookmarks(&self, video_id: &str, current_time: f64) -> Result<AdjacentBookmarks> {
+        let bookmarks = self.get_video_bookmarks(video_id)?;
+        Ok(AdjacentBookmarks {
+            previous: bookmarks.iter().rev().find(|b| b.timestamp < current_time).cloned(),
+            next: bookmarks.into_iter().find(|b| b.timestamp > current_time),
+        })
+    }
+
+    pub fn get_video_bookmark(&self, bookmark_id: &str) -> Result<Option<VideoBookmark>> {
+        let result = self.conn.query_row(
+            "SELECT id, video_id, timestamp, title, description, created_at 
+             FROM video_bookmarks WHERE id = ?1 AND deleted_at IS NULL",
+            params![bookmark_id],
+            |row| {
+                Ok(VideoBookmark {
+                    id: row.get(0)?,
+                    video_id: row.get(1)?,
+                    timestamp: row.get(2)?,
+                    title: row.get(3)?,
+                    description: row.get(4)?,
+                    created_at: DateTime::parse_from_rfc3339(&row.get::<_, String>(5)?)
+                        .map_err(|_| rusqlite::Error::InvalidColumnType(5, "created_at".to_string(), rusqlite::types::Type::Text))?
+                        .with_timezone(&Utc),
+                })
+            },
+        );
+
+        match result {
+            Ok(bookmark) => Ok(Some(bookmark)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    // Todos os bookmarks do curso numa só consulta, na ordem das aulas e, dentro
+    // de cada vídeo, do início para o fim
+    pub fn get_course_bookmarks(&self, course_id: &str) -> Result<Vec<(Video, VideoBookmark)>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT v.id, v.module_id, v.course_id, v.name, v.path, v.duration, v.order_index,
+                    b.id, b.video_id, b.timestamp, b.title, b.description, b.created_at
+             FROM video_bookmarks b
+             INNER JOIN videos v ON v.id = b.video_id
+             INNER JOIN modules m ON m.id = v.module_id
+             WHERE v.course_id = ?1 AND b.deleted_at IS NULL
+             ORDER BY m.order_index, v.order_index, b.timestamp"
+        )?;
+
+        let bookmark_iter = stmt.query_map([course_id], |row| {
+            let video = Video {
+                id: row.get(0)?,
+                module_id: row.get(1)?,
+                course_id: row.get(2)?,
+                name: row.get(3)?,
+                path: row.get(4)?,
+                duration: row.get(5)?,
+                order_index: row.get(6)?,
+            };
+
+            let bookmark = VideoBookmark {
+                id: row.get(7)?,
+                video_id: row.get(8)?,
+                timestamp: row.get(9)?,
+                title: row.get(10)?,
+                description: row.get(11)?,
+                created_at: DateTime::parse_from_rfc3339(&row.get::<_, String>(12)?)
+                    .map_err(|_| rusqlite::Error::InvalidColumnType(12, "created_at".to_string(), rusqlite::types::Type::Text))?
+                    .with_timezone(&Utc),
+            };
+
+            Ok((video, bookmark))
+        })?;
+
+        let mut results = Vec::new();
+        for item in bookmark_iter {
+            results.push(item?);
+        }
+        Ok(results)
+    }
+
+    // ========== MÉTODOS PARA CONFIGURAÇÕES ==========
+    
+    pub fn set_user_setting(&self, setting: &UserSettings) -> std::result::Result<(), AppError> {
+        validate_setting(&setting.setting_key, &setting.setting_value, &setting.setting_type)?;
+        Ok(self.write_user_setting(setting)?)
+    }
+
+    fn write_user_setting(&self, setting: &UserSettings) -> Result<()> {
+        self.conn.execute(
+            "INSERT OR REPLACE INTO user_settings (id, setting_key, setting_value, setting_type, updated_at) 
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![
+                setting.id,
+                setting.setting_key,
+                setting.setting_value,
+                setting.setting_type,
+                setting.updated_at.to_rfc3339()
+            ],
+        )?;
+        Ok(())
+    }
+
+    pub fn get_user_setting(&self, key: &str) -> Result<Option<UserSettings>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, setting_key, setting_value, setting_type, updated_at 
+             FROM user_settings WHERE setting_key = ?1"
+        )?;
+        
+        let mut rows = stmt.query_map([key], |row| {
+            Ok(UserSettings {
+                id: row.get(0)?,
+                setting_key: row.get(1)?,
+                setting_value: row.get(2)?,
+                setting_type: row.get(3)?,
+                updated_at: DateTime::parse_from_rfc3339(&row.get::<_, String>(4)?)
+                    .map_err(|_| rusqlite::Error::InvalidColumnType(4, "updated_at".to_string(), rusqlite::types::Type::Text))?
+                    .with_timezone(&Utc),
+            })
+        })?;
+
+        match rows.next() {
+            Some(row) => Ok(Some(row?)),
+            None => Ok(None),
+        }
+    }
+
+    // Configuração do tipo json já desserializada; JSON malformado no banco vira
+    // InvalidInput em vez de chegar como texto ao chamador
+    pub fn get_setting_json<T: DeserializeOwned>(&self, key: &str) -> std::result::Result<Option<T>, AppError> {
+        let Some(setting) = self.get_user_setting(key)? else {
+            return Ok(None);
+        };
+        serde_json::from_str(&setting.setting_value).map(Some).map_err(|e| AppError::InvalidInput(format!(
+            "Configuração {} não contém JSON válido: {}", key, e
+        )))
+    }
+
+    pub fn set_setting_json<T: Serialize>(&self, key: &str, value: &T) -> std::result::Result<(), AppError> {
+        let setting_value = serde_json::to_string(value).map_err(|e| AppError::InvalidInput(format!(
+            "Não foi possível converter {} para JSON: {}", key, e
+        )))?;
+        self.set_user_setting(&UserSettings {
+            id: uuid::Uuid::new_v4().to_string(),
+            setting_key: key.to_string(),
+            setting_value,
+            setting_type: "json".to_string(),
+            updated_at: Utc::now(),
+        })
+    }
+
+    pub fn set_theme(&self, theme: Theme) -> std::result::Result<(), AppError> {
+        self.set_user_setting(&UserSettings {
+            id: uuid::Uuid::new_v4().to_string(),
+            setting_key: "theme".to_string(),
+            setting_value: theme.as_str().to_string(),
+            setting_type: "string".to_string(),
+            updated_at: Utc::now(),
+        })
+    }
+
+    // Tema salvo ou o padrão. Um valor desconhecido (gravado antes da
+    // validação) é trocado pelo padrão no banco
+    pub fn get_theme(&self) -> std::result::Result<Theme, AppError> {
+        let Some(setting) = self.get_user_setting("theme")? else {
+            return Ok(Theme::default());
+        };
+        if let Some(theme) = Theme::parse(&setting.setting_value) {
+            return Ok(theme);
+        }
+
+        log::warn!("⚠️ Tema inválido no banco ({}), voltando para o padrão", setting.setting_value);
+        self.set_theme(Theme::default())?;
+        Ok(Theme::default())
+    }
+
+    pub fn get_all_user_settings(&self) -> Result<Vec<UserSettings>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, setting_key, setting_value, setting_type, updated_at 
+             FROM user_settings ORDER BY setting_key"
+        )?;
+        
+        let setting_iter = stmt.query_map([], |row| {
+            Ok(UserSettings {
+                id: row.get(0)?,
+                setting_key: row.get(1)?,
+                setting_value: row.get(2)?,
+                setting_type: row.get(3)?,
+                updated_at: DateTime::parse_from_rfc3339(&row.get::<_, String>(4)?)
+                    .map_err(|_| rusqlite::Error::InvalidColumnType(4, "updated_at".to_string(), rusqlite::types::Type::Text))?
+                    .with_timezone(&Utc),
+            })
+        })?;
+
+        let mut settings = Vec::new();
+        for setting in setting_iter {
+            settings.push(setting?);
+        }
+        Ok(settings)
+    }
+
+    pub fn get_playback_settings(&self) -> Result<PlaybackSettings> {
+        let defaults = PlaybackSettings::default();
+        let value = |key: &str| -> Result<Option<String>> {
+            Ok(self.get_user_setting(key)?.map(|setting| setting.setting_value.trim().to_string()))
+        };
+
+        Ok(PlaybackSettings {
+            auto_play_next: value("auto_play_next")?
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(defaults.auto_play_next),
+            playback_speed: value("playback_speed")?
+                .and_then(|v| v.parse::<f64>().ok())
+                .filter(|speed| (0.25..=4.0).contains(speed))
+                .unwrap_or(defaults.playback_speed),
+            volume: value("volume")?
+                .and_then(|v| v.parse::<f64>().ok())
+                .filter(|volume| (0.0..=1.0).contains(volume))
+                .unwrap_or(defaults.volume),
+            auto_save_progress: value("auto_save_progress")?
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(defaults.auto_save_progress),
+            auto_save_interval_seconds: value("auto_save_interval_seconds")?
+                .and_then(|v| v.parse::<u32>().ok())
+                .filter(|seconds| *seconds >= 1)
+                .unwrap_or(defaults.auto_save_interval_seconds),
+            completion_threshold: value("completion_threshold")?
+                .and_then(|v| v.parse::<f64>().ok())
+                .filter(|threshold| *threshold > 0.0 && *threshold <= 1.0)
+                .unwrap_or(defaults.completion_threshold),
+        })
+    }
+
+    // ========== MÉTODOS PARA LOG DE ATIVIDADES ==========
+    
+    pub fn log_activity(&self, activity: &ActivityLog) -> Result<()> {
+        retry_on_busy(|| self.conn.execute(
+            "INSERT INTO activity_log (id, activity_type, entity_id, entity_type, details, created_at) 
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                activity.id,
+                activity.activity_type,
+                activity.entity_id,
+                activity.entity_type,
+                activity.details,
+                activity.created_at.to_rfc3339()
+            ],
+        ))?;
+        Ok(())
+    }
+
+    // Entrega a `f`, em ordem cronológica, as atividades com `since <= created_at < until`,
+    // uma linha por vez, sem carregar o log inteiro na memória
+    pub fn for_each_activity_between<E, F>(&self, since: DateTime<Utc>, until: DateTime<Utc>, mut f: F) -> std::result::Result<(), E>
+    where
+        E: From<rusqlite::Error>,
+        F: FnMut(ActivityLog) -> std::result::Result<(), E>,
+    {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, activity_type, entity_id, entity_type, details, created_at 
+             FROM activity_log WHERE created_at >= ?1 AND created_at < ?2 ORDER BY created_at, rowid"
+        )?;
+        let mut rows = stmt.query(params![since.to_rfc3339(), until.to_rfc3339()])?;
+
+        while let Some(row) = rows.next()? {
+            f(ActivityLog {
+                id: row.get(0)?,
+                activity_type: row.get(1)?,
+                entity_id: row.get(2)?,
+                entity_type: row.get(3)?,
+                details: row.get(4)?,
+                created_at: DateTime::parse_from_rfc3339(&row.get::<_, String>(5)?)
+                    .map_err(|_| rusqlite::Error::InvalidColumnType(5, "created_at".to_string(), rusqlite::types::Type::Text))?
+                    .with_timezone(&Utc),
+            })?;
+        }
+        Ok(())
+    }
+
+    pub fn get_recent_activities(&self, limit: usize) -> Result<Vec<ActivityLog>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, activity_type, entity_id, entity_type, details, created_at 
+             FROM activity_log ORDER BY created_at DESC LIMIT ?1"
+        )?;
+        
+        let activity_iter = stmt.query_map([limit], |row| {
+            Ok(ActivityLog {
+                id: row.get(0)?,
+                activity_type: row.get(1)?,
+                entity_id: row.get(2)?,
+                entity_type: row.get(3)?,
+                details: row.get(4)?,
+                created_at: DateTime::parse_from_rfc3339(&row.get::<_, String>(5)?)
+                    .map_err(|_| rusqlite::Error::InvalidColumnType(5, "created_at".to_string(), rusqlite::types::Type::Text))?
+                    .with_timezone(&Utc),
+            })
+        })?;
+
+        let mut activities = Vec::new();
+        for activity in activity_iter {
+            activities.push(activity?);
+        }
+        Ok(activities)
+    }
+
+    pub fn get_activities_by_type(&self, activity_type: &str, limit: usize) -> Result<Vec<ActivityLog>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, activity_type, entity_id, entity_type, details, created_at 
+             FROM activity_log WHERE activity_type = ?1 ORDER BY created_at DESC LIMIT ?2"
+        )?;
+        
+        let activity_iter = stmt.query_map(params![activity_type, limit], |row| {
+            Ok(ActivityLog {
+                id: row.get(0)?,
+                activity_type: row.get(1)?,
+                entity_id: row.get(2)?,
+                entity_type: row.get(3)?,
+                details: row.get(4)?,
+                created_at: DateTime::parse_from_rfc3339(&row.get::<_, String>(5)?)
+                    .map_err(|_| rusqlite::Error::InvalidColumnType(5, "created_at".to_string(), rusqlite::types::Type::Text))?
+                    .with_timezone(&Utc),
+            })
+        })?;
+
+        let mut activities = Vec::new();
+        for activity in activity_iter {
+            activities.push(activity?);
+        }
+        Ok(activities)
+    }
+
+    // ========== MÉTODOS UTILITÁRIOS ==========
+
+    // Transação sobre a conexão do banco; os métodos chamados enquanto ela
+    // estiver aberta fazem parte dela. Sem commit, é desfeita ao ser descartada
+    pub fn transaction(&self) -> Result<rusqlite::Transaction<'_>> {
+        self.conn.unchecked_transaction()
+    }
+    
+    // Caminho do arquivo do banco (None para bancos em memória)
+    pub fn path(&self) -> Option<String> {
+        self.conn.path()
+            .filter(|path| !path.is_empty())
+            .map(|path| path.to_string())
+    }
+
+    // Cópia consistente do banco em `path` (que não pode existir), feita pelo
+    // próprio SQLite sem bloquear as leituras
+    pub fn backup_to(&self, path: &Path) -> Result<()> {
+        self.conn.execute("VACUUM INTO ?1", params![path.to_string_lossy()])?;
+        Ok(())
+    }
+
+    // Total de cursos e de vídeos cadastrados
+    pub fn get_library_counts(&self) -> Result<(i64, i64)> {
+        self.conn.query_row(
+            "SELECT (SELECT COUNT(*) FROM courses), (SELECT COUNT(*) FROM videos)",
+            [],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+    }
+
+    pub fn initialize_default_settings(&self) -> Result<()> {
+        let default_settings = vec![
+            ("theme", "dark", "string"),
+            ("auto_play_next", "true", "boolean"),
+            ("playback_speed", "1.0", "number"),
+            ("volume", "0.8", "number"),
+            ("auto_save_progress", "true", "boolean"),
+            ("show_subtitles", "false", "boolean"),
+            ("language", "pt-BR", "string"),
+            ("external_player_path", "", "string"),
+            ("external_player_args", "", "string"),
+            ("log_level", "info", "string"),
+            ("auto_save_interval_seconds", "10", "number"),
+            ("completion_threshold", "0.9", "number"),
+            ("min_video_bytes", "1024", "number"),
+            ("course_detection_depth", "1", "number"),
+            ("watch_course_folders", "true", "boolean"),
+            ("custom_video_extensions", "", "string"),
+            ("flatten_single_video_dirs", "false", "boolean"),
+            ("home_decay_half_life_days", "7", "number"),
+            ("archive_suggestion_days", "90", "number"),
+            ("scan_hidden_files", "false", "boolean"),
+            ("backup_interval_hours", "24", "number"),
+            ("backup_keep_count", "7", "number"),
+        ];
+
+        for (key, value, setting_type) in default_settings {
+            // Só criar se não existir
+            if self.get_user_setting(key)?.is_none() {
+                let setting = UserSettings {
+                    id: uuid::Uuid::new_v4().to_string(),
+                    setting_key: key.to_string(),
+                    setting_value: value.to_string(),
+                    setting_type: setting_type.to_string(),
+                    updated_at: Utc::now(),
+                };
+                self.write_user_setting(&setting)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    // Métodos para gerenciar conclusão de vídeos
+    // Sem progresso anterior, o registro criado fica marcado como manual e as
+    // estatísticas de tempo assistido ignoram sua posição fictícia
+    pub fn mark_video_completed(&self, video_id: &str, completed: bool) -> Result<()> {
+        // Primeiro, verifica se já existe um registro de progresso
+        if let Some(mut progress) = self.get_video_progress(video_id)? {
+            let was_manual = self.is_completed_manually(video_id)?;
+            // Atualiza o registro existente
+            progress.completed = completed;
+            progress.last_watched = Utc::now();
+            if was_manual && !completed {
+                progress.current_time = 0.0;
+            }
+            self.update_video_progress(&progress)?;
+            self.set_completed_manually(video_id, was_manual && completed)?;
+        } else {
+            // Cria um novo registro de progresso
+            let progress = VideoProgress {
+                id: uuid::Uuid::new_v4().to_string(),
+                video_id: video_id.to_string(),
+                current_time: if completed { PLACEHOLDER_PROGRESS_DURATION } else { 0.0 }, // Assume 100% se completo
+                duration: PLACEHOLDER_PROGRESS_DURATION, // Valor padrão, será atualizado quando o vídeo for reproduzido
+                completed,
+                last_watched: Utc::now(),
+            };
+            self.update_video_progress(&progress)?;
+            self.set_completed_manually(video_id, completed)?;
+        }
+        Ok(())
+    }
+
+    pub fn is_completed_manually(&self, video_id: &str) -> Result<bool> {
+        let result = self.conn.query_row(
+            "SELECT completed_manually FROM video_progress WHERE video_id = ?1",
+            params![video_id],
+            |row| row.get(0),
+        );
+
+        match result {
+            Ok(manual) => Ok(manual),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn set_completed_manually(&self, video_id: &str, manual: bool) -> Result<()> {
+        self.conn.execute(
+            "UPDATE video_progress SET completed_manually = ?1 WHERE video_id = ?2",
+            params![manual, video_id],
+        )?;
+        Ok(())
+    }
+
+    // Apaga o progresso (e as amostras) dos vídeos do escopo; anotações e
+    // bookmarks ficam
+    pub fn reset_progress(&self, scope: &ResetScope) -> Result<usize> {
+        let (video_ids, id) = match scope {
+            ResetScope::Video(id) => ("?1", id),
+            ResetScope::Module(id) => ("SELECT id FROM videos WHERE module_id = ?1", id),
+            ResetScope::Course(id) => ("SELECT id FROM videos WHERE course_id = ?1", id),
+        };
+
+        let tx = self.transaction()?;
+        let cleared = self.conn.execute(
+            &format!("DELETE FROM video_progress WHERE video_id IN ({})", video_ids),
+            params![id],
+        )?;
+        self.conn.execute(
+            &format!("DELETE FROM progress_samples WHERE video_id IN ({})", video_ids),
+            params![id],
+        )?;
+        tx.commit()?;
+        Ok(cleared)
+    }
+
+    // Progresso com posição além da duração ou com a duração provisória de
+    // `mark_video_completed` quando o vídeo já tem a duração real. Só leitura
+    pub fn find_inconsistent_progress(&self) -> Result<Vec<InconsistentProgress>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT v.id, v.module_id, v.course_id, v.name, v.path, v.duration, v.order_index,
+                    p.id, p.video_id, p.current_time, p.duration, p.completed, p.last_watched
+             FROM video_progress p
+             INNER JOIN videos v ON v.id = p.video_id
+             WHERE p.current_time > p.duration
+                OR (p.duration = ?1 AND v.duration > 0 AND v.duration != ?1)
+             ORDER BY v.course_id, v.order_index"
+        )?;
+
+        let rows = stmt.query_map(params![PLACEHOLDER_PROGRESS_DURATION], |row| {
+            let video = Video {
+                id: row.get(0)?,
+                module_id: row.get(1)?,
+                course_id: row.get(2)?,
+                name: row.get(3)?,
+                path: row.get(4)?,
+                duration: row.get(5)?,
+                order_index: row.get(6)?,
+            };
+            let progress = VideoProgress {
+                id: row.get(7)?,
+                video_id: row.get(8)?,
+                current_time: row.get(9)?,
+                duration: row.get(10)?,
+                completed: row.get(11)?,
+                last_watched: DateTime::parse_from_rfc3339(&row.get::<_, String>(12)?)
+                    .map_err(|_| rusqlite::Error::InvalidColumnType(12, "last_watched".to_string(), rusqlite::types::Type::Text))?
+                    .with_timezone(&Utc),
+            };
+            let reason = if progress.current_time > progress.duration {
+                format!("Posição ({:.1}s) além da duração ({:.1}s)", progress.current_time, progress.duration)
+            } else {
+                format!("Duração provisória de {}s no progresso", PLACEHOLDER_PROGRESS_DURATION)
+            };
+            Ok(InconsistentProgress { video, progress, reason })
+        })?;
+
+        let mut results = Vec::new();
+        for row in rows {
+            results.push(row?);
+        }
+        Ok(results)
+    }
+
+    // Troca a duração provisória pela real (vídeos concluídos assim passam a
+    // estar no fim) e limita a posição à duração, numa única transação
+    pub fn repair_progress_durations(&self) -> Result<ProgressRepair> {
+        let tx = self.transaction()?;
+
+        let durations_fixed = self.conn.execute(
+            "UPDATE video_progress 
+             SET \"current_time\" = CASE 
+                    WHEN completed AND video_progress.current_time = ?1 THEN (SELECT v.duration FROM videos v WHERE v.id = video_progress.video_id) 
+                    ELSE video_progress.current_time END, 
+                 duration = (SELECT v.duration FROM videos v WHERE v.id = video_progress.video_id) 
+             WHERE duration = ?1 
+               AND EXISTS (SELECT 1 FROM videos v WHERE v.id = video_progress.video_id AND v.duration > 0 AND v.duration != ?1)",
+            params![PLACEHOLDER_PROGRESS_DURATION],
+        )?;
+        let positions_clamped = self.conn.execute(
+            "UPDATE video_progress SET \"current_time\" = duration WHERE video_progress.current_time > duration",
+            [],
+        )?;
+
+        tx.commit()?;
+        Ok(ProgressRepair { durations_fixed, positions_clamped })
+    }
+
+    // Vídeo de abertura do curso: o primeiro, na ordem do curso, com nome de
+    // introdução/boas-vindas; sem nenhum assim, o primeiro vídeo
+    pub fn get_course_intro_video(&self, course_id: &str) -> Result<Option<Video>> {
+        let videos = self.get_videos(Some(course_id))?;
+        let is_intro = |video: &Video| {
+            let file_name = Path::new(&video.path).file_name().map(|n| n.to_string_lossy().to_lowercase()).unwrap_or_default();
+            let name = video.name.to_lowercase();
+            INTRO_VIDEO_KEYWORDS.iter().any(|keyword| name.contains(keyword) || file_name.contains(keyword))
+        };
+
+        Ok(videos.iter().find(|video| is_intro(video)).or(videos.first()).cloned())
+    }
+
+    // Próximo vídeo não concluído depois de `current_video_id`, na ordem do
+    // curso. Retorna None se tudo depois dele já foi concluído ou se a
+    // reprodução automática (`auto_play_next`) estiver desativada
+    pub fn get_next_incomplete_video(&self, current_video_id: &str) -> std::result::Result<Option<Video>, AppError> {
+        let current = self.get_video_by_id(current_video_id)?
+            .ok_or_else(|| AppError::NotFound(format!("Vídeo não encontrado: {}", current_video_id)))?;
+
+        if !self.get_playback_settings()?.auto_play_next {
+            return Ok(None);
+        }
+
+        let mut stmt = self.conn.prepare(
+            "SELECT vp.video_id FROM video_progress vp 
+             INNER JOIN videos v ON v.id = vp.video_id 
+             WHERE vp.completed = 1 AND v.course_id = ?1"
+        )?;
+        let completed = stmt
+            .query_map(params![current.course_id], |row| row.get::<_, String>(0))?
+            .collect::<Result<HashSet<String>>>()?;
+
+        Ok(self.get_videos(Some(&current.course_id))?
+            .into_iter()
+            .skip_while(|video| video.id != current.id)
+            .skip(1)
+            .find(|video| !completed.contains(&video.id)))
+    }
+
+    // Onde retomar o curso: o vídeo em andamento assistido por último, na
+    // posição salva; sem nenhum em andamento, o primeiro não concluído do
+    // início; None se o curso estiver todo concluído
+    pub fn resume_course(&self, course_id: &str) -> std::result::Result<Option<(Video, f64)>, AppError> {
+        if self.get_course_by_id(course_id)?.is_none() {
+            return Err(AppError::NotFound(format!("Curso não encontrado: {}", course_id)));
+        }
+
+        let in_progress = self.conn.query_row(
+            "SELECT vp.video_id, vp.current_time FROM video_progress vp 
+             INNER JOIN videos v ON v.id = vp.video_id 
+             WHERE v.course_id = ?1 AND vp.completed = 0 AND vp.current_time > 0 
+             ORDER BY vp.last_watched DESC LIMIT 1",
+            params![course_id],
+            |row| Ok((row.get::<_, String>(0)?, row.get::<_, f64>(1)?)),
+        );
+        match in_progress {
+            Ok((video_id, current_time)) => {
+                if let Some(video) = self.get_video_by_id(&video_id)? {
+                    return Ok(Some((video, current_time)));
+                }
+            }
+            Err(rusqlite::Error::QueryReturnedNoRows) => {}
+            Err(e) => return Err(e.into()),
+        }
+
+        let mut stmt = self.conn.prepare(
+            "SELECT vp.video_id FROM video_progress vp 
+             INNER JOIN videos v ON v.id = vp.video_id 
+             WHERE vp.completed = 1 AND v.course_id = ?1"
+        )?;
+        let completed = stmt
+            .query_map(params![course_id], |row| row.get::<_, String>(0))?
+            .collect::<Result<HashSet<String>>>()?;
+
+        Ok(self.get_videos(Some(course_id))?
+            .into_iter()
+            .find(|video| !completed.contains(&video.id))
+            .map(|video| (video, 0.0)))
+    }
+
+    pub fn get_completed_videos(&self, course_id: Option<&str>) -> Result<Vec<(Video, VideoProgress)>> {
+        let mut videos = Vec::new();
+        
+        if let Some(course_id) = course_id {
+            let mut stmt = self.conn.prepare(
+                "SELECT v.id, v.module_id, v.course_id, v.name, v.path, v.duration, v.order_index,
+                        vp.id, vp.video_id, vp.current_time, vp.duration, vp.completed, vp.last_watched
+                 FROM videos v 
+                 INNER JOIN video_progress vp ON v.id = vp.video_id 
+                 WHERE vp.completed = 1 AND v.course_id = ?
+                 ORDER BY vp.last_watched DESC"
+            )?;
+            
+            let video_iter = stmt.query_map(params![course_id], |row| {
+                Ok((
+                    Video {
+                        id: row.get(0)?,
+                        module_id: row.get(1)?,
+                        course_id: row.get(2)?,
+                        name: row.get(3)?,
+                        path: row.get(4)?,
+                        duration: row.get(5)?,
+                        order_index: row.get(6)?,
+                    },
+                    VideoProgress {
+                        id: row.get(7)?,
+                        video_id: row.get(8)?,
+                        current_time: row.get(9)?,
+                        duration: row.get(10)?,
+                        completed: row.get(11)?,
+                        last_watched: DateTime::parse_from_rfc3339(&row.get::<_, String>(12)?)
+                            .map_err(|_| rusqlite::Error::InvalidColumnType(12, "last_watched".to_string(), rusqlite::types::Type::Text))?
+                            .with_timezone(&Utc),
+                    },
+                ))
+            })?;
+            
+            for video in video_iter {
+                videos.push(video?);
+            }
+        } else {
+            let mut stmt = self.conn.prepare(
+                "SELECT v.id, v.module_id, v.course_id, v.name, v.path, v.duration, v.order_index,
+                        vp.id, vp.video_id, vp.current_time, vp.duration, vp.completed, vp.last_watched
+                 FROM videos v 
+                 INNER JOIN video_progress vp ON v.id = vp.video_id 
+                 WHERE vp.completed = 1
+                 ORDER BY vp.last_watched DESC"
+            )?;
+            
+            let video_iter = stmt.query_map([], |row| {
+                Ok((
+                    Video {
+                        id: row.get(0)?,
+                        module_id: row.get(1)?,
+                        course_id: row.get(2)?,
+                        name: row.get(3)?,
+                        path: row.get(4)?,
+                        duration: row.get(5)?,
+                        order_index: row.get(6)?,
+                    },
+                    VideoProgress {
+                        id: row.get(7)?,
+                        video_id: row.get(8)?,
+                        current_time: row.get(9)?,
+                        duration: row.get(10)?,
+                        completed: row.get(11)?,
+                        last_watched: DateTime::parse_from_rfc3339(&row.get::<_, String>(12)?)
+                            .map_err(|_| rusqlite::Error::InvalidColumnType(12, "last_watched".to_string(), rusqlite::types::Type::Text))?
+                            .with_timezone(&Utc),
+                    },
+                ))
+            })?;
+            
+            for video in video_iter {
+                videos.push(video?);
+            }
+        }
+        
+        Ok(videos)
+    }
+
+    pub fn get_incomplete_videos(&self, course_id: Option<&str>) -> Result<Vec<(Video, Option<VideoProgress>)>> {
+        let mut videos = Vec::new();
+        
+        if let Some(course_id) = course_id {
+            let mut stmt = self.conn.prepare(
+                "SELECT v.id, v.module_id, v.course_id, v.name, v.path, v.duration, v.order_index,
+                        vp.id, vp.video_id, vp.current_time, vp.duration, vp.completed, vp.last_watched
+                 FROM videos v 
+                 LEFT JOIN video_progress vp ON v.id = vp.video_id 
+                 WHERE (vp.completed IS NULL OR vp.completed = 0) AND v.course_id = ?
+                 ORDER BY v.order_index"
+            )?;
+            
+            let video_iter = stmt.query_map(params![course_id], |row| {
+                let progress = if row.get::<_, Option<String>>(7)?.is_some() {
+                    Some(VideoProgress {
+                        id: row.get(7)?,
+                        video_id: row.get(8)?,
+                        current_time: row.get(9)?,
+                        duration: row.get(10)?,
+                        completed: row.get(11)?,
+                        last_watched: DateTime::parse_from_rfc3339(&row.get::<_, String>(12)?)
+                            .map_err(|_| rusqlite::Error::InvalidColumnType(12, "last_watched".to_string(), rusqlite::types::Type::Text))?
+                            .with_timezone(&Utc),
+                    })
+                } else {
+                    None
+                };
+
+                Ok((
+                    Video {
+                        id: row.get(0)?,
+                        module_id: row.get(1)?,
+                        course_id: row.get(2)?,
+                        name: row.get(3)?,
+                        path: row.get(4)?,
+                        duration: row.get(5)?,
+                        order_index: row.get(6)?,
+                    },
+                    progress,
+                ))
+            })?;
+            
+            for video in video_iter {
+                videos.push(video?);
+            }
+        } else {
+            let mut stmt = self.conn.prepare(
+                "SELECT v.id, v.module_id, v.course_id, v.name, v.path, v.duration, v.order_index,
+                        vp.id, vp.video_id, vp.current_time, vp.duration, vp.completed, vp.last_watched
+                 FROM videos v 
+                 LEFT JOIN video_progress vp ON v.id = vp.video_id 
+                 WHERE (vp.completed IS NULL OR vp.completed = 0)
+                 ORDER BY v.order_index"
+            )?;
+            
+            let video_iter = stmt.query_map([], |row| {
+                let progress = if row.get::<_, Option<String>>(7)?.is_some() {
+                    Some(VideoProgress {
+                        id: row.get(7)?,
+                        video_id: row.get(8)?,
+                        current_time: row.get(9)?,
+                        duration: row.get(10)?,
+                        completed: row.get(11)?,
+                        last_watched: DateTime::parse_from_rfc3339(&row.get::<_, String>(12)?)
+                            .map_err(|_| rusqlite::Error::InvalidColumnType(12, "last_watched".to_string(), rusqlite::types::Type::Text))?
+                            .with_timezone(&Utc),
+                    })
+                } else {
+                    None
+                };
+
+                Ok((
+                    Video {
+                        id: row.get(0)?,
+                        module_id: row.get(1)?,
+                        course_id: row.get(2)?,
+                        name: row.get(3)?,
+                        path: row.get(4)?,
+                        duration: row.get(5)?,
+                        order_index: row.get(6)?,
+                    },
+                    progress,
+                ))
+            })?;
+            
+            for video in video_iter {
+                videos.push(video?);
+            }
+        }
+        
+        Ok(videos)
+    }
+
+    // Sugestões do que assistir, entre os vídeos não concluídos:
+    // - curso recente: RECENT_COURSE_WEIGHT / posição do curso na ordem de atividade
+    //   (último acesso ou último vídeo assistido); cursos nunca abertos somam 0
+    // - módulo em andamento: MODULE_MOMENTUM_WEIGHT se um vídeo anterior do módulo foi concluído
+    // - já iniciado: IN_PROGRESS_WEIGHT se o vídeo tem progresso
+    // Empates seguem a ordem do curso. Cursos concluídos não têm vídeos elegíveis
+    pub fn get_recommendations(&self, limit: usize) -> Result<Vec<VideoRecommendation>> {
+        let mut course_activity: Vec<(String, String)> = {
+            let mut stmt = self.conn.prepare(
+                "SELECT c.id, MAX(COALESCE(c.last_accessed, ''), COALESCE(MAX(vp.last_watched), ''))
+                 FROM courses c
+                 LEFT JOIN videos v ON v.course_id = c.id
+                 LEFT JOIN video_progress vp ON vp.video_id = v.id
+                 GROUP BY c.id"
+            )?;
+            let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+            rows.collect::<Result<Vec<(String, String)>>>()?
+        };
+        course_activity.retain(|(_, last_activity)| !last_activity.is_empty());
+        course_activity.sort_by(|a, b| b.1.cmp(&a.1));
+
+        // Menor order_index concluído em cada módulo
+        let mut first_completed: std::collections::HashMap<String, i32> = std::collections::HashMap::new();
+        let mut completed_ids = HashSet::new();
+        for (video, _) in self.get_completed_videos(None)? {
+            let order = first_completed.entry(video.module_id.clone()).or_insert(video.order_index);
+            *order = (*order).min(video.order_index);
+            completed_ids.insert(video.id);
+        }
+
+        // Um vídeo tem um registro de progresso por salvamento: descarta os que já
+        // foram concluídos em algum deles e fica com o mais recente dos demais
+        let mut candidates: std::collections::HashMap<String, (Video, Option<VideoProgress>)> = std::collections::HashMap::new();
+        for (video, progress) in self.get_incomplete_videos(None)? {
+            if completed_ids.contains(&video.id) {
+                continue;
+            }
+            if let Some((_, Some(kept))) = candidates.get(&video.id) {
+                if progress.as_ref().is_none_or(|p| p.last_watched <= kept.last_watched) {
+                    continue;
+                }
+            }
+            candidates.insert(video.id.clone(), (video, progress));
+        }
+
+        let mut recommendations: Vec<VideoRecommendation> = candidates
+            .into_values()
+            .map(|(video, progress)| {
+                let mut score = 0.0;
+                let mut reasons = Vec::new();
+
+                if let Some(rank) = course_activity.iter().position(|(id, _)| *id == video.course_id) {
+                    score += RECENT_COURSE_WEIGHT / (rank + 1) as f64;
+                    reasons.push(format!("curso acessado recentemente (#{})", rank + 1));
+                }
+                if first_completed.get(&video.module_id).is_some_and(|order| *order < video.order_index) {
+                    score += MODULE_MOMENTUM_WEIGHT;
+                    reasons.push("módulo em andamento".to_string());
+                }
+                if progress.is_some_and(|p| p.current_time > 0.0) {
+                    score += IN_PROGRESS_WEIGHT;
+                    reasons.push("vídeo já iniciado".to_string());
+                }
+                if reasons.is_empty() {
+                    reasons.push("curso ainda não iniciado".to_string());
+                }
+
+                VideoRecommendation { video, score, reason: reasons.join(", ") }
+            })
+            .collect();
+
+        recommendations.sort_by(|a, b| {
+            b.score.total_cmp(&a.score)
+                .then_with(|| a.video.course_id.cmp(&b.video.course_id))
+                .then_with(|| a.video.order_index.cmp(&b.video.order_index))
+        });
+        recommendations.truncate(limit);
+        Ok(recommendations)
+    }
+
+    // Vídeos assistidos e segundos de progresso por dia (no fuso local), entre
+    // `from` e `to` inclusive. Usa o último `last_watched` de cada vídeo
+    pub fn get_watch_history(&self, from: NaiveDate, to: NaiveDate, include_empty_days: bool) -> Result<Vec<(NaiveDate, i32, f64)>> {
+        self.get_watch_history_in(from, to, include_empty_days, &Local)
+    }
+
+    fn get_watch_history_in<Tz: TimeZone>(
+        &self,
+        from: NaiveDate,
+        to: NaiveDate,
+        include_empty_days: bool,
+        tz: &Tz,
+    ) -> Result<Vec<(NaiveDate, i32, f64)>> {
+        // Margem de um dia em UTC para cobrir qualquer fuso; o corte exato é feito abaixo
+        let lower = from.pred_opt().unwrap_or(from).and_hms_opt(0, 0, 0).unwrap().and_utc().to_rfc3339();
+        let upper = to.succ_opt().and_then(|d| d.succ_opt()).unwrap_or(to).and_hms_opt(0, 0, 0).unwrap().and_utc().to_rfc3339();
+
+        let mut stmt = self.conn.prepare(
+            "SELECT vp.video_id, vp.last_watched, CASE WHEN vp.completed_manually THEN 0 ELSE vp.current_time END FROM video_progress vp 
+             WHERE vp.last_watched >= ?1 AND vp.last_watched < ?2"
+        )?;
+        let rows = stmt.query_map(params![lower, upper], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?, row.get::<_, f64>(2)?))
+        })?;
+
+        // Cada salvamento grava um novo registro; fica só o último de cada vídeo por dia
+        let mut latest: std::collections::HashMap<(NaiveDate, String), (DateTime<chrono::FixedOffset>, f64)> = std::collections::HashMap::new();
+        for row in rows {
+            let (video_id, last_watched, seconds) = row?;
+            let Ok(watched_at) = DateTime::parse_from_rfc3339(&last_watched) else {
+                continue;
+            };
+            let day = watched_at.with_timezone(tz).date_naive();
+            if day < from || day > to {
+                continue;
+            }
+            let entry = latest.entry((day, video_id)).or_insert((watched_at, seconds));
+            if watched_at > entry.0 {
+                *entry = (watched_at, seconds);
+            }
+        }
+
+        let mut days: std::collections::BTreeMap<NaiveDate, (i32, f64)> = std::collections::BTreeMap::new();
+        if include_empty_days {
+            for day in from.iter_days().take_while(|day| *day <= to) {
+                days.insert(day, (0, 0.0));
+            }
+        }
+
+        for ((day, _), (_, seconds)) in latest {
+            let entry = days.entry(day).or_insert((0, 0.0));
+            entry.0 += 1;
+            entry.1 += seconds;
+        }
+
+        Ok(days.into_iter().map(|(day, (videos, seconds))| (day, videos, seconds)).collect())
+    }
+
+    // Estimativa do tempo assistido por dia (no fuso local), entre `from` e `to`
+    // inclusive, somando os avanços de posição entre atualizações consecutivas
+    // de cada vídeo. Não há registro de sessões: voltar no vídeo não conta,
+    // mas pular para frente conta como assistido, então é só uma aproximação
+    pub fn estimate_daily_watch_time(&self, from: NaiveDate, to: NaiveDate) -> Result<Vec<(NaiveDate, f64)>> {
+        self.estimate_daily_watch_time_in(from, to, &Local)
+    }
+
+    fn estimate_daily_watch_time_in<Tz: TimeZone>(&self, from: NaiveDate, to: NaiveDate, tz: &Tz) -> Result<Vec<(NaiveDate, f64)>> {
+        // Mesma margem de `get_watch_history_in`. A amostra anterior ao período
+        // ainda entra no cálculo do primeiro avanço dentro dele
+        let lower = from.pred_opt().unwrap_or(from).and_hms_opt(0, 0, 0).unwrap().and_utc().to_rfc3339();
+        let upper = to.succ_opt().and_then(|d| d.succ_opt()).unwrap_or(to).and_hms_opt(0, 0, 0).unwrap().and_utc().to_rfc3339();
+
+        let mut stmt = self.conn.prepare(
+            "SELECT recorded_at, delta FROM (
+                SELECT recorded_at, position - LAG(position) OVER (PARTITION BY video_id ORDER BY recorded_at, rowid) AS delta 
+                FROM progress_samples WHERE recorded_at < ?2
+             ) WHERE recorded_at >= ?1 AND delta > 0"
+        )?;
+        let rows = stmt.query_map(params![lower, upper], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, f64>(1)?))
+        })?;
+
+        let mut days: std::collections::BTreeMap<NaiveDate, f64> = std::collections::BTreeMap::new();
+        for row in rows {
+            let (recorded_at, seconds) = row?;
+            let Ok(recorded_at) = DateTime::parse_from_rfc3339(&recorded_at) else {
+                continue;
+            };
+            let day = recorded_at.with_timezone(tz).date_naive();
+            if day < from || day > to {
+                continue;
+            }
+            *days.entry(day).or_insert(0.0) += seconds;
+        }
+
+        Ok(days.into_iter().collect())
+    }
+
+    // Vídeos concluídos por semana ISO (início na segunda-feira, no fuso local)
+    // nas últimas `weeks` semanas, incluindo a atual e semanas sem conclusões
+    pub fn get_completion_timeline(&self, weeks: usize) -> Result<Vec<(NaiveDate, i64)>> {
+        self.get_completion_timeline_in(Local::now().date_naive(), weeks, &Local)
+    }
+
+    fn get_completion_timeline_in<Tz: TimeZone>(&self, today: NaiveDate, weeks: usize, tz: &Tz) -> Result<Vec<(NaiveDate, i64)>> {
+        let current_week = today - chrono::Duration::days(today.weekday().num_days_from_monday() as i64);
+        let mut timeline: std::collections::BTreeMap<NaiveDate, i64> = (0..weeks)
+            .map(|offset| (current_week - chrono::Duration::weeks(offset as i64), 0))
+            .collect();
+        let Some(first_week) = timeline.keys().next().copied() else {
+            return Ok(Vec::new());
+        };
+
+        let mut stmt = self.conn.prepare("SELECT vp.last_watched FROM video_progress vp WHERE vp.completed = 1")?;
+        let rows = stmt.query_map([], |row| row.get::<_, String>(0))?;
+        for row in rows {
+            let Ok(watched_at) = DateTime::parse_from_rfc3339(&row?) else {
+                continue;
+            };
+            let day = watched_at.with_timezone(tz).date_naive();
+            let week = day - chrono::Duration::days(day.weekday().num_days_from_monday() as i64);
+            if week >= first_week {
+                if let Some(count) = timeline.get_mut(&week) {
+                    *count += 1;
+                }
+            }
+        }
+
+        Ok(timeline.into_iter().collect())
+    }
+
+    // Sequência de dias (no fuso local) com algum vídeo assistido
+    pub fn get_streak_status(&self) -> Result<StreakStatus> {
+        self.get_streak_status_in(Local::now().date_naive(), &Local)
+    }
+
+    fn get_streak_status_in<Tz: TimeZone>(&self, today: NaiveDate, tz: &Tz) -> Result<StreakStatus> {
+        let mut stmt = self.conn.prepare("SELECT last_watched FROM video_progress")?;
+        let rows = stmt.query_map([], |row| row.get::<_, String>(0))?;
+
+        let mut active_days = std::collections::BTreeSet::new();
+        for row in rows {
+            if let Ok(watched_at) = DateTime::parse_from_rfc3339(&row?) {
+                active_days.insert(watched_at.with_timezone(tz).date_naive());
+            }
+        }
+
+        let last_active_date = active_days.range(..=today).next_back().copied();
+        let yesterday = today.pred_opt().unwrap_or(today);
+
+        // A sequência só continua viva se o último dia ativo foi hoje ou ontem
+        let mut current_streak = 0;
+        if let Some(last_day) = last_active_date.filter(|day| *day >= yesterday) {
+            let mut day = last_day;
+            while active_days.contains(&day) {
+                current_streak += 1;
+                match day.pred_opt() {
+                    Some(previous) => day = previous,
+                    None => break,
+                }
+            }
+        }
+
+        Ok(StreakStatus {
+            current_streak,
+            last_active_date,
+            at_risk: current_streak > 0 && last_active_date != Some(today),
+        })
+    }
+
+    pub fn get_course_completion_stats(&self, course_id: &str) -> Result<(i32, i32, i32)> {
+        let total_videos: i32 = self.conn.query_row(
+            "SELECT COUNT(*) FROM videos WHERE course_id = ?",
+            params![course_id],
+            |row| row.get(0),
+        )?;
+
+        let completed_videos: i32 = self.conn.query_row(
+            "SELECT COUNT(*) FROM videos v INNER JOIN video_progress vp ON v.id = vp.video_id WHERE v.course_id = ? AND vp.completed = 1",
+            params![course_id],
+            |row| row.get(0),
+        )?;
+
+        let in_progress_videos: i32 = self.conn.query_row(
+            "SELECT COUNT(*) FROM videos v INNER JOIN video_progress vp ON v.id = vp.video_id WHERE v.course_id = ? AND vp.completed = 0 AND vp.current_time > 0",
+            params![course_id],
+            |row| row.get(0),
+        )?;
+
+        Ok((total_videos, completed_videos, in_progress_videos))
+    }
+
+    // Curso sem vídeos não tem nenhum marco
+    pub fn get_course_badges(&self, course_id: &str) -> std::result::Result<CourseBadges, AppError> {
+        if self.get_course_by_id(course_id)?.is_none() {
+            return Err(AppError::NotFound(format!("Curso não encontrado: {}", course_id)));
+        }
+        let (total_videos, completed_videos, in_progress_videos) = self.get_course_completion_stats(course_id)?;
+        let percent = if total_videos > 0 {
+            completed_videos as f64 / total_videos as f64 * 100.0
+        } else {
+            0.0
+        };
+        let completed = total_videos > 0 && completed_videos == total_videos;
+
+        let mut stmt = self.conn.prepare(
+            "SELECT a.activity_type, a.created_at FROM activity_log a 
+             INNER JOIN videos v ON v.id = a.entity_id 
+             WHERE a.entity_type = 'video' AND v.course_id = ?1"
+        )?;
+        let activities = stmt
+            .query_map(params![course_id], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))?
+            .collect::<Result<Vec<_>>>()?
+            .into_iter()
+            .filter_map(|(activity_type, created_at)| {
+                let created_at = DateTime::parse_from_rfc3339(&created_at).ok()?.with_timezone(&Utc);
+                Some((activity_type, created_at))
+            })
+            .collect::<Vec<_>>();
+
+        let started = completed_videos > 0 || in_progress_videos > 0;
+        Ok(CourseBadges {
+            total_videos,
+            completed_videos,
+            percent,
+            started,
+            halfway: total_videos > 0 && completed_videos * 2 >= total_videos,
+            completed,
+            started_at: activities.iter().map(|(_, at)| *at).min().filter(|_| started),
+            completed_at: activities.iter()
+                .filter(|(activity_type, _)| activity_type == "video_completed")
+                .map(|(_, at)| *at)
+                .max()
+                .filter(|_| completed),
+        })
+    }
+
+    // Anotações e marcadores do curso, primeira e última atividade (log dos
+    // vídeos, módulos e do próprio curso, mais o progresso) e conclusão
+    pub fn get_course_activity_summary(&self, course_id: &str) -> std::result::Result<CourseActivitySummary, AppError> {
+        if self.get_course_by_id(course_id)?.is_none() {
+            return Err(AppError::NotFound(format!("Curso não encontrado: {}", course_id)));
+        }
+
+        let note_count: i64 = self.conn.query_row(
+            "SELECT COUNT(*) FROM user_notes WHERE course_id = ?1 AND deleted_at IS NULL",
+            params![course_id],
+            |row| row.get(0),
+        )?;
+        let bookmark_count: i64 = self.conn.query_row(
+            "SELECT COUNT(*) FROM video_bookmarks b
+             INNER JOIN videos v ON v.id = b.video_id
+             WHERE v.course_id = ?1 AND b.deleted_at IS NULL",
+            params![course_id],
+            |row| row.get(0),
+        )?;
+
+        let mut stmt = self.conn.prepare(
+            "SELECT a.created_at FROM activity_log a
+             WHERE (a.entity_type = 'video' AND a.entity_id IN (SELECT id FROM videos WHERE course_id = ?1))
+                OR (a.entity_type = 'module' AND a.entity_id IN (SELECT id FROM modules WHERE course_id = ?1))
+                OR (a.entity_type = 'course' AND a.entity_id = ?1)
+             UNION ALL
+             SELECT p.last_watched FROM video_progress p
+             INNER JOIN videos v ON v.id = p.video_id
+             WHERE v.course_id = ?1"
+        )?;
+        let timestamps = stmt
+            .query_map(params![course_id], |row| row.get::<_, String>(0))?
+            .collect::<Result<Vec<_>>>()?
+            .into_iter()
+            .filter_map(|at| DateTime::parse_from_rfc3339(&at).ok().map(|at| at.with_timezone(&Utc)))
+            .collect::<Vec<_>>();
+
+        let (total_videos, completed_videos, _) = self.get_course_completion_stats(course_id)?;
+        Ok(CourseActivitySummary {
+            note_count,
+            bookmark_count,
+            first_activity: timestamps.iter().min().copied(),
+            last_activity: timestamps.iter().max().copied(),
+            percent: if total_videos > 0 { completed_videos as f64 / total_videos as f64 * 100.0 } else { 0.0 },
+        })
+    }
+
+    pub fn get_video_by_path(&self, file_path: &str) -> Result<Option<Video>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, module_id, course_id, name, path, duration, order_index 
+             FROM videos WHERE path = ?"
+        )?;
+
+        let result = stmt.query_row(params![file_path], |row| {
+            Ok(Video {
+                id: row.get(0)?,
+                module_id: row.get(1)?,
+                course_id: row.get(2)?,
+                name: row.get(3)?,
+                path: row.get(4)?,
+                duration: row.get(5)?,
+                order_index: row.get(6)?,
+            })
+        });
+
+        match result {
+            Ok(video) => Ok(Some(video)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    // Busca tolerante a diferenças de separador (`\` vs `/`) e, no Windows, de
+    // maiúsculas/minúsculas. Tenta primeiro o caminho exato
+    pub fn get_video_by_normalized_path(&self, file_path: &str) -> Result<Option<Video>> {
+        self.find_video_by_normalized_path(file_path, cfg!(windows))
+    }
+
+    fn find_video_by_normalized_path(&self, file_path: &str, case_insensitive: bool) -> Result<Option<Video>> {
+        if let Some(video) = self.get_video_by_path(file_path)? {
+            return Ok(Some(video));
+        }
+
+        let wanted = normalize_path_for_match(file_path, case_insensitive);
+        let mut stmt = self.conn.prepare("SELECT id, path FROM videos")?;
+        let rows = stmt.query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))?;
+
+        for row in rows {
+            let (video_id, path) = row?;
+            if normalize_path_for_match(&path, case_insensitive) == wanted {
+                return self.get_video_by_id(&video_id);
+            }
+        }
+        Ok(None)
+    }
+}
+
+// Repete `operation` com espera exponencial enquanto o SQLite responder que o
+// banco está ocupado ou bloqueado; qualquer outro erro é devolvido na hora
+pub fn retry_on_busy<T, F>(mut operation: F) -> Result<T>
+where
+    F: FnMut() -> Result<T>,
+{
+    let mut delay = BUSY_RETRY_INITIAL_DELAY;
+    let mut attempt = 1;
+    loop {
+        match operation() {
+            Err(e) if attempt < BUSY_RETRY_ATTEMPTS && is_busy_error(&e) => {
+                log::warn!("⏳ Banco ocupado (tentativa {} de {}), aguardando {:?}", attempt, BUSY_RETRY_ATTEMPTS, delay);
+                thread::sleep(delay);
+                delay *= 2;
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+fn is_busy_error(error: &rusqlite::Error) -> bool {
+    matches!(
+        error,
+        rusqlite::Error::SqliteFailure(e, _)
+            if matches!(e.code, rusqlite::ErrorCode::DatabaseBusy | rusqlite::ErrorCode::DatabaseLocked)
+    )
+}
+
+// Unifica separadores, remove separadores repetidos ou no final e,
+// opcionalmente, ignora maiúsculas/minúsculas
+fn normalize_path_for_match(path: &str, case_insensitive: bool) -> String {
+    let mut normalized = String::with_capacity(path.len());
+    for c in path.chars() {
+        let c = if c == '\\' { '/' } else { c };
+        if c == '/' && normalized.ends_with('/') {
+            continue;
+        }
+        normalized.push(c);
+    }
+    while normalized.len() > 1 && normalized.ends_with('/') {
+        normalized.pop();
+    }
+
+    if case_insensitive {
+        normalized.to_lowercase()
+    } else {
+        normalized
+    }
+}
+
+// "[x] Nome" ou "[ ] Nome", opcionalmente como item de lista ("- [x] Nome")
+fn parse_checklist_line(line: &str) -> Option<(bool, &str)> {
+    let line = line.trim();
+    let line = line.strip_prefix("- ").or_else(|| line.strip_prefix("* ")).unwrap_or(line).trim_start();
+    let (checked, name) = if let Some(name) = line.strip_prefix("[x]").or_else(|| line.strip_prefix("[X]")) {
+        (true, name)
+    } else {
+        (false, line.strip_prefix("[ ]")?)
+    };
+    let name = name.trim();
+    (!name.is_empty()).then_some((checked, name))
+}
+
+// Minúsculas sem acentos, com a pontuação trocada por um espaço
+fn checklist_key(name: &str) -> String {
+    let folded: String = name.to_lowercase().chars().map(|c| match c {
+        'á' | 'à' | 'â' | 'ã' | 'ä' => 'a',
+        'é' | 'è' | 'ê' | 'ë' => 'e',
+        'í' | 'ì' | 'î' | 'ï' => 'i',
+        'ó' | 'ò' | 'ô' | 'õ' | 'ö' => 'o',
+        'ú' | 'ù' | 'û' | 'ü' => 'u',
+        'ç' => 'c',
+        'ñ' => 'n',
+        c if c.is_alphanumeric() => c,
+        _ => ' ',
+    }).collect();
+    folded.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+// "01 introducao" -> "introducao", para listas escritas sem a numeração dos arquivos
+fn strip_numbering(key: &str) -> &str {
+    match key.split_once(' ') {
+        Some((number, rest)) if number.chars().all(|c| c.is_ascii_digit()) => rest,
+        _ => key,
+    }
+}
+
+// Primeiro número no nome ("Section 10" -> 10); nomes sem número vão para o fim
+fn leading_number(name: &str) -> u64 {
+    name.split(|c: char| !c.is_ascii_digit())
+        .find(|part| !part.is_empty())
+        .and_then(|digits| digits.parse().ok())
+        .unwrap_or(u64::MAX)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_db() -> Database {
+        Database::new(Path::new(":memory:")).unwrap()
+    }
+
+    fn seed_course(db: &Database, course_id: &str) {
+        db.insert_course(&Course {
+            id: course_id.to_string(),
+            name: course_id.to_string(),
+            path: format!("/cursos/{}", course_id),
+            created_at: Utc::now(),
+            last_accessed: None,
+            cover_path: None,
+            display_name: None,
+            description: None,
+        }).unwrap();
+    }
+
+    fn seed_module(db: &Database, course_id: &str, module_id: &str, order_index: i32) {
+        db.insert_module(&Module {
+            id: module_id.to_string(),
+            course_id: course_id.to_string(),
+            name: module_id.to_string(),
+            path: format!("/cursos/{}/{}", course_id, module_id),
+            order_index,
+        }).unwrap();
+    }
+
+    fn seed_video(db: &Database, course_id: &str, module_id: &str, video_id: &str, order_index: i32) -> Video {
+        let video = Video {
+            id: video_id.to_string(),
+            module_id: module_id.to_string(),
+            course_id: course_id.to_string(),
+            name: video_id.to_string(),
+            path: format!("/cursos/{}/{}/{}.mp4", course_id, module_id, video_id),
+            duration: None,
+            order_index,
+        };
+        db.insert_video(&video).unwrap();
+        video
+    }
+
+    // Grava o progresso como o comando `update_video_progress`: um registro
+    // novo a cada chamada, então o mesmo vídeo pode ter vários
+    fn save_progress(db: &Database, video_id: &str, current_time: f64, duration: f64, completed: bool, last_watched: DateTime<Utc>) {
+        db.update_video_progress(&VideoProgress {
+            id: uuid::Uuid::new_v4().to_string(),
+            video_id: video_id.to_string(),
+            current_time,
+            duration,
+            completed,
+            last_watched,
+        }).unwrap();
+    }
+
+    // Curso "course-1" com um módulo "module-1" e os vídeos informados
+    fn seed_hierarchy(db: &Database, video_ids: &[&str]) {
+        seed_course(db, "course-1");
+        seed_module(db, "course-1", "module-1", 0);
+        for (index, video_id) in video_ids.iter().enumerate() {
+            seed_video(db, "course-1", "module-1", video_id, index as i32);
+        }
+    }
+
+    fn sample_note(video_id: &str, title: &str, note_type: &str) -> UserNote {
+        UserNote {
+            id: uuid::Uuid::new_v4().to_string(),
+            video_id: Some(video_id.to_string()),
+            course_id: Some("course-1".to_string()),
+            module_id: Some("module-1".to_string()),
+            timestamp: Some(42.5),
+            title: title.to_string(),
+            content: "conteúdo".to_string(),
+            note_type: note_type.to_string(),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            color: None,
+            bookmark_id: None,
+        }
+    }
+
+    #[test]
+    fn test_note_associations_are_validated_and_completed() {
+        let db = test_db();
+        seed_hierarchy(&db, &["video-1"]);
+        seed_course(&db, "course-2");
+        seed_module(&db, "course-2", "module-2", 0);
+        let note = |note_type: &str, video_id: Option<&str>, module_id: Option<&str>, course_id: Option<&str>| UserNote {
+            video_id: video_id.map(str::to_string),
+            module_id: module_id.map(str::to_string),
+            course_id: course_id.map(str::to_string),
+            timestamp: None,
+            ..sample_note("video-1", "Anotação", note_type)
+        };
+
+        // Anotação de curso sem vídeo nem módulo
+        let mut course_note = note("course", None, None, Some("course-1"));
+        db.resolve_note_associations(&mut course_note).unwrap();
+        db.create_user_note(&course_note).unwrap();
+        let saved = db.get_note_by_id(&course_note.id).unwrap().unwrap();
+        assert_eq!((saved.video_id, saved.module_id, saved.timestamp), (None, None, None));
+        assert_eq!(db.get_course_level_notes("course-1").unwrap().len(), 1);
+
+        // Módulo e curso vêm do vídeo
+        let mut video_note = note("video", Some("video-1"), None, Some(""));
+        db.resolve_note_associations(&mut video_note).unwrap();
+        assert_eq!(video_note.module_id.as_deref(), Some("module-1"));
+        assert_eq!(video_note.course_id.as_deref(), Some("course-1"));
+
+        let resolve = |mut note: UserNote| db.resolve_note_associations(&mut note);
+        assert!(matches!(resolve(note("video", Some("video-1"), Some("module-2"), None)), Err(AppError::InvalidInput(_))));
+        assert!(matches!(resolve(note("module", None, Some("module-2"), Some("course-1"))), Err(AppError::InvalidInput(_))));
+        assert!(matches!(resolve(note("video", None, Some("module-1"), None)), Err(AppError::InvalidInput(_))));
+        assert!(matches!(resolve(note("video", Some("inexistente"), None, None)), Err(AppError::NotFound(_))));
+        assert!(matches!(resolve(note("course", None, None, Some("inexistente"))), Err(AppError::NotFound(_))));
+        assert!(resolve(note("general", None, None, None)).is_ok());
+    }
+
+    #[test]
+    fn test_clone_note_keeps_associations() {
+        let db = test_db();
+        seed_hierarchy(&db, &["video-1"]);
+        let note = sample_note("video-1", "Template", "video");
+        db.create_user_note(&note).unwrap();
+
+        let clone = db.clone_note(&note.id).unwrap();
+        assert_ne!(clone.id, note.id);
+        assert_eq!(clone.title, "Template (copy)");
+
+        let stored = db.get_note_by_id(&clone.id).unwrap().unwrap();
+        assert_eq!(stored.video_id, note.video_id);
+        assert_eq!(stored.course_id, note.course_id);
+        assert_eq!(stored.module_id, note.module_id);
+        assert_eq!(stored.timestamp, note.timestamp);
+        assert_eq!(stored.content, note.content);
+        assert_eq!(db.get_notes_by_video("video-1").unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_course_duration_mixes_known_and_unknown() {
+        let db = test_db();
+        seed_hierarchy(&db, &["video-1", "video-2", "video-3"]);
+        for (video_id, duration) in [("video-1", 100.0), ("video-2", 50.5)] {
+            let mut video = db.get_video_by_path(&format!("/cursos/course-1/module-1/{}.mp4", video_id)).unwrap().unwrap();
+            video.duration = Some(duration);
+            db.insert_video(&video).unwrap();
+        }
+
+        let expected = CourseDuration { total_seconds: 150.5, unknown_duration_count: 1 };
+        assert_eq!(db.get_course_duration("course-1").unwrap(), expected);
+        let cached: Option<f64> = db.conn
+            .query_row("SELECT total_duration FROM courses WHERE id = 'course-1'", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(cached, Some(150.5));
+
+        // Inserir/reescanear vídeos invalida o cache
+        let mut video = seed_video(&db, "course-1", "module-1", "video-4", 3);
+        video.duration = Some(9.5);
+        db.insert_video(&video).unwrap();
+        assert_eq!(db.get_course_duration("course-1").unwrap().total_seconds, 160.0);
+
+        assert_eq!(db.get_course_duration("sem-videos").unwrap(), CourseDuration { total_seconds: 0.0, unknown_duration_count: 0 });
+    }
+
+    #[cfg(feature = "cipher")]
+    #[test]
+    fn test_encrypted_database_requires_key() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("encrypted.db");
+
+        {
+            let db = Database::new_with_key(&db_path, Some("segredo")).unwrap();
+            seed_course(&db, "course-1");
+        }
+
+        assert!(Database::new_with_key(&db_path, Some("errada")).is_err());
+        assert!(Database::new(&db_path).is_err());
+
+        let db = Database::new_with_key(&db_path, Some("segredo")).unwrap();
+        assert_eq!(db.get_all_courses().unwrap().len(), 1);
+
+        assert!(matches!(db.change_key("errada", "nova"), Err(AppError::InvalidInput(_))));
+        db.change_key("segredo", "nova").unwrap();
+        drop(db);
+
+        assert!(Database::new_with_key(&db_path, Some("segredo")).is_err());
+        assert!(Database::new_with_key(&db_path, Some("nova")).is_ok());
+    }
+
+    #[test]
+    fn test_note_type_counts() {
+        let db = test_db();
+        assert!(db.get_note_type_counts().unwrap().is_empty());
+
+        seed_hierarchy(&db, &["video-1"]);
+        for title in ["A", "B", "C"] {
+            db.create_user_note(&sample_note("video-1", title, "video")).unwrap();
+        }
+        db.create_user_note(&sample_note("video-1", "D", "general")).unwrap();
+
+        assert_eq!(
+            db.get_note_type_counts().unwrap(),
+            vec![("video".to_string(), 3), ("general".to_string(), 1)]
+        );
+    }
+
+    #[test]
+    fn test_note_keyword_frequencies() {
+        let db = test_db();
+        seed_hierarchy(&db, &["video-1", "video-2"]);
+        let contents = [
+            "Closures em Rust: a closure captura o ambiente.",
+            "Rust, ownership e borrowing. O borrowing é verificado!",
+            "Revisar closures e ownership de novo",
+        ];
+        for (index, content) in contents.iter().enumerate() {
+            let mut note = sample_note(if index == 0 { "video-1" } else { "video-2" }, "Nota", "general");
+            note.content = content.to_string();
+            db.create_user_note(&note).unwrap();
+        }
+        let deleted = sample_note("video-1", "Excluída", "general");
+        db.create_user_note(&UserNote { content: "rust rust rust".to_string(), ..deleted.clone() }).unwrap();
+        db.delete_user_note(&deleted.id).unwrap();
+
+        assert_eq!(
+            db.get_note_keyword_frequencies(4, 3).unwrap(),
+            vec![
+                ("borrowing".to_string(), 2),
+                ("closures".to_string(), 2),
+                ("ownership".to_string(), 2),
+                ("rust".to_string(), 2),
+            ]
+        );
+
+        // Stopwords ficam de fora mesmo com tamanho mínimo baixo
+        let all = db.get_note_keyword_frequencies(100, 1).unwrap();
+        assert!(all.iter().all(|(word, _)| !["a", "o", "em", "e", "de", "é"].contains(&word.as_str())));
+        assert!(all.contains(&("closure".to_string(), 1)));
+        assert!(!db.get_note_keyword_frequencies(100, 6).unwrap().iter().any(|(word, _)| word == "rust"));
+    }
+
+    #[test]
+    fn test_progress_since_returns_only_newer_rows() {
+        let db = test_db();
+        seed_hierarchy(&db, &["video-1", "video-2", "video-3"]);
+        seed_course(&db, "course-2");
+        seed_module(&db, "course-2", "module-2", 0);
+        seed_video(&db, "course-2", "module-2", "video-4", 0);
+
+        let cutoff = Utc.with_ymd_and_hms(2024, 3, 10, 12, 0, 0).unwrap();
+        let sessions = [
+            ("video-1", "2024-03-09T12:00:00+00:00"),
+            ("video-2", "2024-03-10T13:00:00+00:00"),
+            // 09:30 em UTC-3 = 12:30 UTC, depois do corte
+            ("video-3", "2024-03-10T09:30:00-03:00"),
+            ("video-4", "2024-03-11T08:00:00+00:00"),
+        ];
+        for (video_id, watched_at) in sessions {
+            db.update_video_progress(&VideoProgress {
+                id: format!("progress-{}", video_id),
+                video_id: video_id.to_string(),
+                current_time: 60.0,
+                duration: 600.0,
+                completed: false,
+                last_watched: DateTime::parse_from_rfc3339(watched_at).unwrap().with_timezone(&Utc),
+            }).unwrap();
+        }
+
+        let ids = |rows: Vec<(Course, Video, VideoProgress)>| -> Vec<String> {
+            rows.into_iter().map(|(_, video, _)| video.id).collect()
+        };
+        assert_eq!(ids(db.get_progress_since(cutoff, None).unwrap()), vec!["video-4", "video-2", "video-3"]);
+        assert_eq!(ids(db.get_progress_since(cutoff, Some("course-1")).unwrap()), vec!["video-2", "video-3"]);
+        let rows = db.get_progress_since(cutoff, Some("course-2")).unwrap();
+        assert_eq!(rows[0].0.id, "course-2");
+        assert!(db.get_progress_since(Utc.with_ymd_and_hms(2024, 3, 12, 0, 0, 0).unwrap(), None).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_recent_videos_include_completed() {
+        let db = test_db();
+        seed_hierarchy(&db, &["video-1", "video-2"]);
+
+        let now = Utc::now();
+        for (video_id, completed, minutes_ago) in [("video-1", true, 1), ("video-2", false, 5)] {
+            db.update_video_progress(&VideoProgress {
+                id: format!("progress-{}", video_id),
+                video_id: video_id.to_string(),
+                current_time: 30.0,
+                duration: 60.0,
+                completed,
+                last_watched: now - chrono::Duration::minutes(minutes_ago),
+            }).unwrap();
+        }
+
+        let ids = |include_completed: bool| -> Vec<String> {
+            db.get_recent_videos(10, include_completed).unwrap()
+                .into_iter()
+                .map(|(video, _)| video.id)
+                .collect()
+        };
+        assert_eq!(ids(false), vec!["video-2"]);
+        assert_eq!(ids(true), vec!["video-1", "video-2"]);
+    }
+
+    #[test]
+    fn test_recommendations_follow_weighting() {
+        let db = test_db();
+        // Curso D: concluído há pouco
+        seed_course(&db, "course-d");
+        seed_module(&db, "course-d", "module-d", 0);
+        seed_video(&db, "course-d", "module-d", "d-1", 0);
+        db.mark_video_completed("d-1", true).unwrap();
+
+        // Curso A: acessado agora, com o primeiro vídeo do módulo concluído
+        seed_course(&db, "course-a");
+        seed_module(&db, "course-a", "module-a", 0);
+        for (index, video_id) in ["a-1", "a-2", "a-3"].iter().enumerate() {
+            seed_video(&db, "course-a", "module-a", video_id, index as i32);
+        }
+        db.mark_video_completed("a-1", true).unwrap();
+        db.update_course_last_accessed("course-a").unwrap();
+
+        // Curso B: vídeo iniciado ontem
+        seed_course(&db, "course-b");
+        seed_module(&db, "course-b", "module-b", 0);
+        seed_video(&db, "course-b", "module-b", "b-1", 0);
+        db.update_video_progress(&VideoProgress {
+            id: "progress-b-1".to_string(),
+            video_id: "b-1".to_string(),
+            current_time: 30.0,
+            duration: 60.0,
+            completed: false,
+            last_watched: Utc::now() - chrono::Duration::days(1),
+        }).unwrap();
+
+        // Curso C: nunca aberto
+        seed_course(&db, "course-c");
+        seed_module(&db, "course-c", "module-c", 0);
+        seed_video(&db, "course-c", "module-c", "c-1", 0);
+
+        let recommendations = db.get_recommendations(10).unwrap();
+        let ranked: Vec<(&str, f64)> = recommendations.iter()
+            .map(|r| (r.video.id.as_str(), r.score))
+            .collect();
+
+        // Curso D é o segundo mais recente, mas não tem vídeos pendentes
+        assert_eq!(ranked, vec![
+            ("a-2", RECENT_COURSE_WEIGHT + MODULE_MOMENTUM_WEIGHT),
+            ("a-3", RECENT_COURSE_WEIGHT + MODULE_MOMENTUM_WEIGHT),
+            ("b-1", RECENT_COURSE_WEIGHT / 3.0 + IN_PROGRESS_WEIGHT),
+            ("c-1", 0.0),
+        ]);
+        assert!(recommendations[0].reason.contains("módulo em andamento"));
+        assert_eq!(db.get_recommendations(2).unwrap().len(), 2);
+
+        // Vários salvamentos do mesmo vídeo: b-1 aparece uma vez e a-2, concluído
+        // depois de um salvamento parcial, sai da lista
+        save_progress(&db, "b-1", 10.0, 60.0, false, Utc::now() - chrono::Duration::days(2));
+        save_progress(&db, "a-2", 20.0, 60.0, false, Utc::now() - chrono::Duration::hours(1));
+        save_progress(&db, "a-2", 60.0, 60.0, true, Utc::now());
+        let ranked: Vec<String> = db.get_recommendations(10).unwrap().into_iter().map(|r| r.video.id).collect();
+        assert_eq!(ranked, vec!["a-3", "b-1", "c-1"]);
+    }
+
+    #[test]
+    fn test_video_lookup_tolerates_separators_and_case() {
+        let db = test_db();
+        seed_course(&db, "course-1");
+        seed_module(&db, "course-1", "module-1", 0);
+        let mut video = seed_video(&db, "course-1", "module-1", "video-1", 0);
+        video.path = "C:\\Cursos\\Rust\\Módulo 1\\Aula Introdução.mp4".to_string();
+        db.insert_video(&video).unwrap();
+
+        let found = |path: &str, case_insensitive: bool| {
+            db.find_video_by_normalized_path(path, case_insensitive).unwrap().map(|v| v.id)
+        };
+
+        assert_eq!(found(&video.path, false).as_deref(), Some("video-1"));
+        assert_eq!(found("C:/Cursos/Rust/Módulo 1/Aula Introdução.mp4", false).as_deref(), Some("video-1"));
+        assert_eq!(found("C:\\Cursos//Rust/Módulo 1\\Aula Introdução.mp4", false).as_deref(), Some("video-1"));
+
+        // Maiúsculas/minúsculas só são ignoradas quando pedido (Windows)
+        assert_eq!(found("c:/cursos/rust/MÓDULO 1/aula introdução.mp4", false), None);
+        assert_eq!(found("c:/cursos/rust/MÓDULO 1/aula introdução.mp4", true).as_deref(), Some("video-1"));
+        assert_eq!(found("C:/Cursos/Rust/Outro.mp4", true), None);
+    }
+
+    #[test]
+    fn test_courses_with_progress_matches_per_course_stats() {
+        let db = test_db();
+        seed_hierarchy(&db, &["video-1", "video-2", "video-3"]);
+        db.mark_video_completed("video-1", true).unwrap();
+        db.mark_video_completed("video-2", true).unwrap();
+        db.mark_video_completed("video-2", false).unwrap();
+
+        seed_course(&db, "course-2");
+        seed_module(&db, "course-2", "module-2", 0);
+        seed_video(&db, "course-2", "module-2", "video-4", 0);
+        db.mark_video_completed("video-4", true).unwrap();
+        db.update_course_last_accessed("course-2").unwrap();
+
+        seed_course(&db, "course-vazio");
+
+        let courses = db.get_courses_with_progress().unwrap();
+        assert_eq!(courses.len(), 3);
+        assert_eq!(courses[0].0.id, "course-2");
+
+        for (course, total, completed, percent) in &courses {
+            let (expected_total, expected_completed, _) = db.get_course_completion_stats(&course.id).unwrap();
+            assert_eq!((*total, *completed), (expected_total, expected_completed));
+            assert!(percent.is_finite());
+        }
+
+        let percent_of = |course_id: &str| courses.iter().find(|c| c.0.id == course_id).unwrap().3;
+        assert!((percent_of("course-1") - 100.0 / 3.0).abs() < 1e-9);
+        assert_eq!(percent_of("course-2"), 100.0);
+        assert_eq!(percent_of("course-vazio"), 0.0);
+    }
+
+    #[test]
+    fn test_deleted_note_and_bookmark_are_restorable_until_purged() {
+        let db = test_db();
+        seed_hierarchy(&db, &["video-1"]);
+        let note = sample_note("video-1", "Nota", "video");
+        db.create_user_note(&note).unwrap();
+        let bookmark = VideoBookmark {
+            id: "bookmark-1".to_string(),
+            video_id: "video-1".to_string(),
+            timestamp: 10.0,
+            title: "Marcador".to_string(),
+            description: None,
+            created_at: Utc::now(),
+        };
+        db.create_video_bookmark(&bookmark).unwrap();
+
+        db.delete_user_note(&note.id).unwrap();
+        db.delete_video_bookmark(&bookmark.id).unwrap();
+        assert!(db.get_notes_by_video("video-1").unwrap().is_empty());
+        assert!(db.get_notes_by_course("course-1").unwrap().is_empty());
+        assert!(db.get_all_notes().unwrap().is_empty());
+        assert!(db.get_note_by_id(&note.id).unwrap().is_none());
+        assert!(db.get_note_type_counts().unwrap().is_empty());
+        assert!(db.get_video_bookmarks("video-1").unwrap().is_empty());
+        assert!(db.get_video_bookmark(&bookmark.id).unwrap().is_none());
+
+        db.restore_user_note(&note.id).unwrap();
+        db.restore_video_bookmark(&bookmark.id).unwrap();
+        assert_eq!(db.get_notes_by_video("video-1").unwrap().len(), 1);
+        assert_eq!(db.get_video_bookmarks("video-1").unwrap().len(), 1);
+        assert!(matches!(db.restore_user_note(&note.id), Err(AppError::NotFound(_))));
+
+        // Dentro da janela nada é purgado; excluídos há 40 dias são
+        db.delete_user_note(&note.id).unwrap();
+        db.delete_video_bookmark(&bookmark.id).unwrap();
+        assert_eq!(db.purge_deleted(30).unwrap(), 0);
+
+        let long_ago = (Utc::now() - chrono::Duration::days(40)).to_rfc3339();
+        db.conn.execute("UPDATE user_notes SET deleted_at = ?1", params![long_ago]).unwrap();
+        db.conn.execute("UPDATE video_bookmarks SET deleted_at = ?1", params![long_ago]).unwrap();
+        assert_eq!(db.purge_deleted(30).unwrap(), 2);
+        assert!(matches!(db.restore_user_note(&note.id), Err(AppError::NotFound(_))));
+        assert!(matches!(db.restore_video_bookmark(&bookmark.id), Err(AppError::NotFound(_))));
+    }
+
+    #[test]
+    fn test_watch_history_groups_by_local_day() {
+        let db = test_db();
+        seed_hierarchy(&db, &["video-1", "video-2", "video-3", "video-4"]);
+
+        // Fuso de São Paulo (UTC-3): 01:30 UTC do dia 11 ainda é dia 10 no horário local
+        let tz = chrono::FixedOffset::west_opt(3 * 3600).unwrap();
+        let sessions = [
+            ("video-1", "2024-03-10T13:00:00+00:00", 120.0),
+            ("video-2", "2024-03-10T20:00:00+00:00", 60.0),
+            ("video-3", "2024-03-11T01:30:00+00:00", 30.0),
+            ("video-4", "2024-03-12T15:00:00+00:00", 300.0),
+        ];
+        for (video_id, watched_at, seconds) in sessions {
+            db.update_video_progress(&VideoProgress {
+                id: format!("progress-{}", video_id),
+                video_id: video_id.to_string(),
+                current_time: seconds,
+                duration: 600.0,
+                completed: false,
+                last_watched: DateTime::parse_from_rfc3339(watched_at).unwrap().with_timezone(&Utc),
+            }).unwrap();
+        }
+
+        // Salvamentos anteriores no mesmo dia não contam o vídeo de novo
+        for (watched_at, seconds) in [("2024-03-10T12:00:00+00:00", 40.0), ("2024-03-10T12:30:00+00:00", 80.0)] {
+            save_progress(&db, "video-1", seconds, 600.0, false, DateTime::parse_from_rfc3339(watched_at).unwrap().with_timezone(&Utc));
+        }
+
+        let day = |d: u32| NaiveDate::from_ymd_opt(2024, 3, d).unwrap();
+        assert_eq!(
+            db.get_watch_history_in(day(10), day(12), false, &tz).unwrap(),
+            vec![(day(10), 3, 210.0), (day(12), 1, 300.0)]
+        );
+        assert_eq!(
+            db.get_watch_history_in(day(10), day(11), true, &tz).unwrap(),
+            vec![(day(10), 3, 210.0), (day(11), 0, 0.0)]
+        );
+    }
+
+    #[test]
+    fn test_daily_watch_time_sums_forward_progress() {
+        let db = test_db();
+        seed_hierarchy(&db, &["video-1", "video-2"]);
+
+        // UTC-3, como em `test_watch_history_groups_by_local_day`
+        let tz = chrono::FixedOffset::west_opt(3 * 3600).unwrap();
+        let updates = [
+            // Avança 50s no dia 9; a primeira amostra do dia 10 conta a partir dela
+            ("video-1", "2024-03-09T22:00:00+00:00", 10.0),
+            ("video-1", "2024-03-09T22:01:00+00:00", 60.0),
+            ("video-1", "2024-03-10T13:00:00+00:00", 90.0),
+            ("video-1", "2024-03-10T13:01:00+00:00", 150.0),
+            // Voltar no vídeo não conta, e o avanço seguinte parte da nova posição
+            ("video-1", "2024-03-10T13:02:00+00:00", 40.0),
+            ("video-1", "2024-03-10T13:03:00+00:00", 100.0),
+            ("video-2", "2024-03-10T20:00:00+00:00", 0.0),
+            ("video-2", "2024-03-10T20:05:00+00:00", 300.0),
+            // 01:30 UTC do dia 11 ainda é dia 10 no horário local
+            ("video-2", "2024-03-11T01:30:00+00:00", 330.0),
+            ("video-2", "2024-03-12T15:00:00+00:00", 400.0),
+        ];
+        for (video_id, recorded_at, position) in updates {
+            db.update_video_progress(&VideoProgress {
+                id: format!("progress-{}", video_id),
+                video_id: video_id.to_string(),
+                current_time: position,
+                duration: 600.0,
+                completed: false,
+                last_watched: DateTime::parse_from_rfc3339(recorded_at).unwrap().with_timezone(&Utc),
+            }).unwrap();
+        }
+
+        let day = |d: u32| NaiveDate::from_ymd_opt(2024, 3, d).unwrap();
+        assert_eq!(
+            db.estimate_daily_watch_time_in(day(9), day(12), &tz).unwrap(),
+            vec![(day(9), 50.0), (day(10), 30.0 + 60.0 + 60.0 + 300.0 + 30.0), (day(12), 70.0)]
+        );
+        assert_eq!(
+            db.estimate_daily_watch_time_in(day(10), day(11), &tz).unwrap(),
+            vec![(day(10), 480.0)]
+        );
+    }
+
+    #[test]
+    fn test_progress_samples_are_capped_per_video() {
+        let db = test_db();
+        seed_hierarchy(&db, &["video-1", "video-2"]);
+        let start = Utc.with_ymd_and_hms(2024, 3, 10, 12, 0, 0).unwrap();
+        let save = |video_id: &str, second: i64| {
+            db.update_video_progress(&VideoProgress {
+                id: format!("progress-{}", video_id),
+                video_id: video_id.to_string(),
+                current_time: second as f64,
+                duration: 3600.0,
+                completed: false,
+                last_watched: start + chrono::Duration::seconds(second),
+            }).unwrap();
+        };
+        for second in 0..MAX_PROGRESS_SAMPLES_PER_VIDEO + 20 {
+            save("video-1", second);
+        }
+        save("video-2", 0);
+
+        let (count, oldest): (i64, f64) = db.conn.query_row(
+            "SELECT COUNT(*), MIN(position) FROM progress_samples WHERE video_id = 'video-1'",
+            [],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        ).unwrap();
+        assert_eq!((count, oldest), (MAX_PROGRESS_SAMPLES_PER_VIDEO, 20.0));
+        let others: i64 = db.conn.query_row(
+            "SELECT COUNT(*) FROM progress_samples WHERE video_id = 'video-2'", [], |row| row.get(0),
+        ).unwrap();
+        assert_eq!(others, 1);
+    }
+
+    #[test]
+    fn test_completion_timeline_by_local_week() {
+        let db = test_db();
+        let video_ids = ["video-1", "video-2", "video-3", "video-4", "video-5", "video-6"];
+        seed_hierarchy(&db, &video_ids);
+
+        // Semanas de 26/02, 04/03, 11/03 e 18/03/2024; 23:30 de domingo em
+        // UTC-3 já é segunda-feira em UTC, mas conta na semana anterior
+        let tz = chrono::FixedOffset::west_opt(3 * 3600).unwrap();
+        let sessions = [
+            ("video-1", "2024-02-27T12:00:00+00:00", true),
+            ("video-2", "2024-03-05T12:00:00+00:00", true),
+            ("video-3", "2024-03-11T02:30:00+00:00", true),
+            ("video-4", "2024-03-19T12:00:00+00:00", true),
+            ("video-5", "2024-03-20T12:00:00+00:00", true),
+            ("video-6", "2024-03-20T13:00:00+00:00", false),
+        ];
+        for (video_id, watched_at, completed) in sessions {
+            db.update_video_progress(&VideoProgress {
+                id: format!("progress-{}", video_id),
+                video_id: video_id.to_string(),
+                current_time: 600.0,
+                duration: 600.0,
+                completed,
+                last_watched: DateTime::parse_from_rfc3339(watched_at).unwrap().with_timezone(&Utc),
+            }).unwrap();
+        }
+
+        let day = |m: u32, d: u32| NaiveDate::from_ymd_opt(2024, m, d).unwrap();
+        assert_eq!(
+            db.get_completion_timeline_in(day(3, 21), 3, &tz).unwrap(),
+            vec![(day(3, 4), 2), (day(3, 11), 0), (day(3, 18), 2)]
+        );
+        assert!(db.get_completion_timeline_in(day(3, 21), 0, &tz).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_streak_status_at_risk_until_watched_today() {
+        let db = test_db();
+        seed_hierarchy(&db, &["video-1", "video-2", "video-3", "video-4"]);
+
+        let tz = chrono::FixedOffset::west_opt(3 * 3600).unwrap();
+        let day = |d: u32| NaiveDate::from_ymd_opt(2024, 3, d).unwrap();
+        let watch = |video_id: &str, watched_at: &str| {
+            db.update_video_progress(&VideoProgress {
+                id: format!("progress-{}", video_id),
+                video_id: video_id.to_string(),
+                current_time: 60.0,
+                duration: 600.0,
+                completed: false,
+                last_watched: DateTime::parse_from_rfc3339(watched_at).unwrap().with_timezone(&Utc),
+            }).unwrap();
+        };
+
+        assert_eq!(db.get_streak_status_in(day(12), &tz).unwrap(), StreakStatus {
+            current_streak: 0,
+            last_active_date: None,
+            at_risk: false,
+        });
+
+        // Dias 8 (isolado), 10 e 11; 01:30 UTC do dia 11 ainda é dia 10 no horário local
+        watch("video-1", "2024-03-08T15:00:00+00:00");
+        watch("video-2", "2024-03-11T01:30:00+00:00");
+        watch("video-3", "2024-03-11T15:00:00+00:00");
+
+        assert_eq!(db.get_streak_status_in(day(12), &tz).unwrap(), StreakStatus {
+            current_streak: 2,
+            last_active_date: Some(day(11)),
+            at_risk: true,
+        });
+
+        watch("video-4", "2024-03-12T15:00:00+00:00");
+        assert_eq!(db.get_streak_status_in(day(12), &tz).unwrap(), StreakStatus {
+            current_streak: 3,
+            last_active_date: Some(day(12)),
+            at_risk: false,
+        });
+
+        // Sem atividade ontem nem hoje, a sequência já foi quebrada
+        assert_eq!(db.get_streak_status_in(day(14), &tz).unwrap(), StreakStatus {
+            current_streak: 0,
+            last_active_date: Some(day(12)),
+            at_risk: false,
+        });
+    }
+
+    #[test]
+    fn test_playback_settings_substitute_defaults() {
+        let db = test_db();
+        db.initialize_default_settings().unwrap();
+        assert_eq!(db.get_playback_settings().unwrap(), PlaybackSettings::default());
+
+        // Grava sem validar, como nos bancos de versões anteriores à validação
+        let set = |key: &str, value: &str| {
+            db.write_user_setting(&UserSettings {
+                id: uuid::Uuid::new_v4().to_string(),
+                setting_key: key.to_string(),
+                setting_value: value.to_string(),
+                setting_type: "number".to_string(),
+                updated_at: Utc::now(),
+            }).unwrap();
+        };
+        set("playback_speed", "1.5");
+        set("auto_play_next", "false");
+        set("volume", "7");
+        set("auto_save_interval_seconds", "0");
+        set("completion_threshold", "abc");
+
+        let settings = db.get_playback_settings().unwrap();
+        assert_eq!(settings.playback_speed, 1.5);
+        assert!(!settings.auto_play_next);
+        assert_eq!(settings.volume, PlaybackSettings::default().volume);
+        assert_eq!(settings.auto_save_interval_seconds, 10);
+        assert_eq!(settings.completion_threshold, 0.9);
+    }
+
+    #[test]
+    fn test_import_progress_keeps_newer_last_watched() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let old_path = temp_dir.path().join("antigo.db");
+        let now = Utc::now();
+        let progress = |video_id: &str, current_time: f64, days_ago: i64| VideoProgress {
+            id: uuid::Uuid::new_v4().to_string(),
+            video_id: video_id.to_string(),
+            current_time,
+            duration: 600.0,
+            completed: false,
+            last_watched: now - chrono::Duration::days(days_ago),
+        };
+
+        {
+            let old = Database::new(&old_path).unwrap();
+            seed_hierarchy(&old, &["video-1", "video-2", "video-3"]);
+            let mut orphan = seed_video(&old, "course-1", "module-1", "video-orfao", 9);
+            orphan.path = "/cursos/removido.mp4".to_string();
+            old.insert_video(&orphan).unwrap();
+
+            old.update_video_progress(&progress("video-1", 300.0, 1)).unwrap();
+            old.update_video_progress(&progress("video-2", 100.0, 10)).unwrap();
+            old.update_video_progress(&progress("video-3", 50.0, 3)).unwrap();
+            old.update_video_progress(&progress("video-orfao", 10.0, 1)).unwrap();
+
+            // Data ilegível: conta como pulado, não como encontrado
+            seed_video(&old, "course-1", "module-1", "video-4", 3);
+            old.update_video_progress(&progress("video-4", 20.0, 1)).unwrap();
+            old.conn.execute("UPDATE video_progress SET last_watched = 'ontem' WHERE video_id = 'video-4'", []).unwrap();
+        }
+
+        // No banco atual os mesmos caminhos têm outros ids
+        let db = test_db();
+        seed_course(&db, "course-novo");
+        seed_module(&db, "course-novo", "module-novo", 0);
+        for (index, video_id) in ["video-1", "video-2", "video-3", "video-4"].iter().enumerate() {
+            let mut video = seed_video(&db, "course-novo", "module-novo", &format!("novo-{}", video_id), index as i32);
+            video.path = format!("/cursos/course-1/module-1/{}.mp4", video_id);
+            db.insert_video(&video).unwrap();
+        }
+        db.update_video_progress(&progress("novo-video-1", 120.0, 5)).unwrap();
+        db.update_video_progress(&progress("novo-video-2", 200.0, 2)).unwrap();
+
+        let result = db.import_progress_from(&old_path).unwrap();
+        assert_eq!(result, ProgressImport { matched: 3, updated: 2, skipped: 2 });
+
+        // video-1: o antigo é mais recente; video-2: o local é mais recente; video-3: só existia no antigo
+        assert_eq!(db.get_video_progress("novo-video-1").unwrap().unwrap().current_time, 300.0);
+        assert_eq!(db.get_video_progress("novo-video-2").unwrap().unwrap().current_time, 200.0);
+        assert_eq!(db.get_video_progress("novo-video-3").unwrap().unwrap().current_time, 50.0);
+        assert!(db.get_video_progress("novo-video-4").unwrap().is_none());
+
+        let missing = temp_dir.path().join("inexistente.db");
+        assert!(matches!(db.import_progress_from(&missing), Err(AppError::NotFound(_))));
+    }
+
+    #[test]
+    fn test_import_completion_checklist() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let db = test_db();
+        seed_course(&db, "course-1");
+        seed_module(&db, "course-1", "module-1", 0);
+        for (index, (video_id, name)) in [
+            ("video-1", "01 - Introdução"),
+            ("video-2", "02 - Ownership & Borrowing"),
+            ("video-3", "03 - Traits"),
+            ("video-4", "04 - Lifetimes"),
+        ].iter().enumerate() {
+            let mut video = seed_video(&db, "course-1", "module-1", video_id, index as i32);
+            video.name = name.to_string();
+            db.insert_video(&video).unwrap();
+        }
+        db.mark_video_completed("video-3", true).unwrap();
+        db.mark_video_completed("video-4", true).unwrap();
+
+        let checklist = temp_dir.path().join("checklist.txt");
+        std::fs::write(&checklist, [
+            "# Curso de Rust",
+            "[x] Introducao",
+            "- [X] 02 ownership borrowing",
+            "[ ] Traits",
+            "[x] 04 - Lifetimes",
+            "[ ] Macros",
+            "anotação solta",
+        ].join("\n")).unwrap();
+
+        let result = db.import_completion_checklist("course-1", &checklist).unwrap();
+        assert_eq!(result, ChecklistImport { matched: 4, changed: 3, unmatched: vec!["Macros".to_string()] });
+
+        let completed = |video_id: &str| db.get_video_progress(video_id).unwrap().is_some_and(|p| p.completed);
+        assert!(completed("video-1"));
+        assert!(completed("video-2"));
+        assert!(!completed("video-3"));
+        assert!(completed("video-4"));
+
+        assert!(matches!(db.import_completion_checklist("inexistente", &checklist), Err(AppError::NotFound(_))));
+        let missing = temp_dir.path().join("inexistente.txt");
+        assert!(matches!(db.import_completion_checklist("course-1", &missing), Err(AppError::NotFound(_))));
+    }
+
+    #[test]
+    fn test_note_colors_validate_and_filter() {
+        assert!(validate_note_color(None).is_ok());
+        assert!(validate_note_color(Some("#FFD700")).is_ok());
+        assert!(validate_note_color(Some("#a1b2c3")).is_ok());
+        for invalid in ["FFD700", "#FFF", "#GGGGGG", "#FFD7000", "amarelo", ""] {
+            assert!(matches!(validate_note_color(Some(invalid)), Err(AppError::InvalidInput(_))), "{}", invalid);
+        }
+
+        let db = test_db();
+        seed_hierarchy(&db, &["video-1"]);
+        let mut question = sample_note("video-1", "Dúvida", "video");
+        question.color = Some("#FFD700".to_string());
+        let mut summary = sample_note("video-1", "Resumo", "video");
+        summary.color = Some("#00FF00".to_string());
+        db.create_user_note(&question).unwrap();
+        db.create_user_note(&summary).unwrap();
+        db.create_user_note(&sample_note("video-1", "Sem cor", "video")).unwrap();
+
+        let yellow = db.get_notes_by_color("#ffd700").unwrap();
+        assert_eq!(yellow.len(), 1);
+        assert_eq!(yellow[0].title, "Dúvida");
+
+        let by_video = db.get_notes_by_video("video-1").unwrap();
+        assert_eq!(by_video.iter().filter(|n| n.color.is_some()).count(), 2);
+
+        summary.color = Some("#FFD700".to_string());
+        db.update_user_note(&summary).unwrap();
+        assert_eq!(db.get_notes_by_color("#FFD700").unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_merge_modules_appends_videos_and_moves_notes() {
+        let db = test_db();
+        seed_course(&db, "course-1");
+        seed_module(&db, "course-1", "module-1", 0);
+        seed_module(&db, "course-1", "module-2", 1);
+        for (index, video_id) in ["m1-a", "m1-b"].iter().enumerate() {
+            seed_video(&db, "course-1", "module-1", video_id, index as i32);
+        }
+        for (index, video_id) in ["m2-a", "m2-b", "m2-c"].iter().enumerate() {
+            seed_video(&db, "course-1", "module-2", video_id, index as i32);
+        }
+        let mut note = sample_note("m2-a", "Nota do módulo 2", "module");
+        note.module_id = Some("module-2".to_string());
+        db.create_user_note(&note).unwrap();
+
+        assert_eq!(db.merge_modules("course-1", "module-1", "module-2").unwrap(), 3);
+
+        let videos = db.get_module_videos("module-1").unwrap();
+        let order: Vec<(&str, i32)> = videos.iter().map(|v| (v.id.as_str(), v.order_index)).collect();
+        assert_eq!(order, vec![("m1-a", 0), ("m1-b", 1), ("m2-a", 2), ("m2-b", 3), ("m2-c", 4)]);
+        assert!(videos.iter().all(|v| v.module_id == "module-1"));
+
+        let modules = db.get_course_modules("course-1").unwrap();
+        assert_eq!(modules.len(), 1);
+        assert_eq!(db.get_note_by_id(&note.id).unwrap().unwrap().module_id.as_deref(), Some("module-1"));
+
+        assert!(matches!(db.merge_modules("course-1", "module-1", "module-2"), Err(AppError::NotFound(_))));
+        assert!(matches!(db.merge_modules("course-1", "module-1", "module-1"), Err(AppError::InvalidInput(_))));
+    }
+
+    #[test]
+    fn test_move_video_to_module_resequences_both_modules() {
+        let db = test_db();
+        seed_course(&db, "course-1");
+        seed_module(&db, "course-1", "module-1", 0);
+        seed_module(&db, "course-1", "module-2", 1);
+        for (index, video_id) in ["m1-a", "m1-b", "m1-c"].iter().enumerate() {
+            seed_video(&db, "course-1", "module-1", video_id, index as i32);
+        }
+        for (index, video_id) in ["m2-a", "m2-b"].iter().enumerate() {
+            seed_video(&db, "course-1", "module-2", video_id, index as i32);
+        }
+        let note = sample_note("m1-b", "Nota da aula", "video");
+        db.create_user_note(&note).unwrap();
+
+        let moved = db.move_video_to_module("m1-b", "module-2").unwrap();
+        assert_eq!((moved.module_id.as_str(), moved.order_index), ("module-2", 2));
+
+        let order = |module_id: &str| -> Vec<(String, i32)> {
+            db.get_module_videos(module_id).unwrap().into_iter().map(|v| (v.id, v.order_index)).collect()
+        };
+        assert_eq!(order("module-1"), vec![("m1-a".to_string(), 0), ("m1-c".to_string(), 1)]);
+        assert_eq!(
+            order("module-2"),
+            vec![("m2-a".to_string(), 0), ("m2-b".to_string(), 1), ("m1-b".to_string(), 2)]
+        );
+        assert_eq!(db.get_note_by_id(&note.id).unwrap().unwrap().module_id.as_deref(), Some("module-2"));
+
+        seed_course(&db, "course-2");
+        seed_module(&db, "course-2", "module-x", 0);
+        assert!(matches!(db.move_video_to_module("m1-a", "module-x"), Err(AppError::InvalidInput(_))));
+        assert!(matches!(db.move_video_to_module("m1-a", "module-1"), Err(AppError::InvalidInput(_))));
+        assert!(matches!(db.move_video_to_module("m1-a", "inexistente"), Err(AppError::NotFound(_))));
+        assert!(matches!(db.move_video_to_module("inexistente", "module-2"), Err(AppError::NotFound(_))));
+    }
+
+    #[test]
+    fn test_toggle_and_list_favorite_videos() {
+        let db = test_db();
+        seed_course(&db, "course-b");
+        seed_module(&db, "course-b", "module-b", 0);
+        seed_course(&db, "course-a");
+        seed_module(&db, "course-a", "module-a", 0);
+        seed_video(&db, "course-b", "module-b", "b-0", 0);
+        seed_video(&db, "course-a", "module-a", "a-0", 0);
+        let a1 = seed_video(&db, "course-a", "module-a", "a-1", 1);
+        db.rename_course("course-a", "A primeiro").unwrap();
+        db.rename_course("course-b", "B depois").unwrap();
+
+        assert!(db.toggle_video_favorite("b-0").unwrap());
+        assert!(db.toggle_video_favorite("a-1").unwrap());
+        assert!(db.toggle_video_favorite("a-0").unwrap());
+        assert!(!db.toggle_video_favorite("a-0").unwrap());
+
+        // Reinserir o vídeo (como num rescan) mantém a marcação
+        db.insert_video(&a1).unwrap();
+
+        let favorites = db.get_favorite_videos().unwrap();
+        let ids: Vec<(&str, &str)> = favorites.iter().map(|f| (f.course_name.as_str(), f.video.id.as_str())).collect();
+        assert_eq!(ids, vec![("A primeiro", "a-1"), ("B depois", "b-0")]);
+
+        assert!(matches!(db.toggle_video_favorite("inexistente"), Err(AppError::NotFound(_))));
+    }
+
+    #[test]
+    fn test_largest_videos_by_file_size() {
+        let db = test_db();
+        seed_hierarchy(&db, &["pequeno", "grande", "sem-tamanho", "medio"]);
+        db.rename_course("course-1", "Rust").unwrap();
+        db.set_video_file_size("pequeno", Some(10_000)).unwrap();
+        db.set_video_file_size("grande", Some(3_000_000_000)).unwrap();
+        db.set_video_file_size("medio", Some(500_000)).unwrap();
+
+        let largest: Vec<(String, u64, String)> = db.get_largest_videos(10).unwrap()
+            .into_iter()
+            .map(|(video, size, course_name)| (video.id, size, course_name))
+            .collect();
+        assert_eq!(largest, vec![
+            ("grande".to_string(), 3_000_000_000, "Rust".to_string()),
+            ("medio".to_string(), 500_000, "Rust".to_string()),
+            ("pequeno".to_string(), 10_000, "Rust".to_string()),
+            ("sem-tamanho".to_string(), 0, "Rust".to_string()),
+        ]);
+
+        let ids: Vec<String> = db.get_largest_videos(2).unwrap().into_iter().map(|(video, _, _)| video.id).collect();
+        assert_eq!(ids, vec!["grande", "medio"]);
+    }
+
+    #[test]
+    fn test_rate_videos_and_list_top_rated() {
+        let db = test_db();
+        seed_course(&db, "course-b");
+        seed_module(&db, "course-b", "module-b", 0);
+        seed_course(&db, "course-a");
+        seed_module(&db, "course-a", "module-a", 0);
+        seed_video(&db, "course-b", "module-b", "b-0", 0);
+        seed_video(&db, "course-a", "module-a", "a-0", 0);
+        let a1 = seed_video(&db, "course-a", "module-a", "a-1", 1);
+        db.rename_course("course-a", "A primeiro").unwrap();
+
+        assert!(matches!(db.set_video_rating("a-0", 6), Err(AppError::InvalidInput(_))));
+        assert!(matches!(db.set_video_rating("inexistente", 3), Err(AppError::NotFound(_))));
+
+        db.set_video_rating("b-0", 4).unwrap();
+        db.set_video_rating("a-1", 4).unwrap();
+        db.set_video_rating("a-0", 5).unwrap();
+        db.set_video_rating("a-0", 0).unwrap();
+        db.set_video_rating("a-0", 2).unwrap();
+
+        // Reinserir o vídeo (como num rescan) mantém a avaliação
+        db.insert_video(&a1).unwrap();
+
+        let rated = db.get_top_rated_videos(10).unwrap();
+        let ids: Vec<(&str, &str, u8)> = rated.iter()
+            .map(|r| (r.course_name.as_str(), r.video.id.as_str(), r.rating))
+            .collect();
+        assert_eq!(ids, vec![("A primeiro", "a-1", 4), ("course-b", "b-0", 4), ("A primeiro", "a-0", 2)]);
+        assert_eq!(db.get_top_rated_videos(1).unwrap().len(), 1);
+
+        // 0 remove a avaliação
+        db.set_video_rating("a-0", 0).unwrap();
+        assert!(db.get_top_rated_videos(10).unwrap().iter().all(|r| r.video.id != "a-0"));
+    }
+
+    #[test]
+    fn test_retry_on_busy_retries_only_busy_errors() {
+        let busy = || rusqlite::Error::SqliteFailure(rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_BUSY), None);
+
+        let mut calls = 0;
+        let result = retry_on_busy(|| {
+            calls += 1;
+            if calls == 1 { Err(busy()) } else { Ok(42) }
+        });
+        assert_eq!(result.unwrap(), 42);
+        assert_eq!(calls, 2);
+
+        // Erros que não são de banco ocupado não são repetidos
+        let mut calls = 0;
+        let result: Result<()> = retry_on_busy(|| {
+            calls += 1;
+            Err(rusqlite::Error::QueryReturnedNoRows)
+        });
+        assert!(matches!(result, Err(rusqlite::Error::QueryReturnedNoRows)));
+        assert_eq!(calls, 1);
+
+        // Desiste depois do limite de tentativas
+        let mut calls = 0;
+        let result: Result<()> = retry_on_busy(|| {
+            calls += 1;
+            Err(busy())
+        });
+        assert!(is_busy_error(&result.unwrap_err()));
+        assert_eq!(calls, BUSY_RETRY_ATTEMPTS);
+    }
+
+    #[test]
+    fn test_next_incomplete_video_skips_completed() {
+        let db = test_db();
+        seed_course(&db, "course-1");
+        seed_module(&db, "course-1", "module-1", 0);
+        seed_module(&db, "course-1", "module-2", 1);
+        seed_video(&db, "course-1", "module-1", "v0", 0);
+        seed_video(&db, "course-1", "module-1", "v1", 1);
+        seed_video(&db, "course-1", "module-1", "v2", 2);
+        seed_video(&db, "course-1", "module-2", "v3", 0);
+        seed_video(&db, "course-1", "module-2", "v4", 1);
+
+        db.mark_video_completed("v1", true).unwrap();
+        db.mark_video_completed("v2", true).unwrap();
+        db.mark_video_completed("v4", true).unwrap();
+
+        let next = |id: &str| db.get_next_incomplete_video(id).unwrap().map(|v| v.id);
+        assert_eq!(next("v0").as_deref(), Some("v3"));
+        // Vídeos anteriores ao atual não contam, mesmo incompletos
+        assert_eq!(next("v2").as_deref(), Some("v3"));
+        assert_eq!(next("v3"), None);
+
+        db.mark_video_completed("v2", false).unwrap();
+        assert_eq!(next("v0").as_deref(), Some("v2"));
+
+        db.set_user_setting(&UserSettings {
+            id: uuid::Uuid::new_v4().to_string(),
+            setting_key: "auto_play_next".to_string(),
+            setting_value: "false".to_string(),
+            setting_type: "boolean".to_string(),
+            updated_at: Utc::now(),
+        }).unwrap();
+        assert_eq!(next("v0"), None);
+
+        assert!(matches!(db.get_next_incomplete_video("inexistente"), Err(AppError::NotFound(_))));
+    }
+
+    #[test]
+    fn test_activity_details_round_trip() {
+        let db = test_db();
+        let cases = vec![
+            ("video_completed", ActivityDetails::VideoCompleted { percent: 100.0 }),
+            ("video_marked_incomplete", ActivityDetails::VideoMarkedIncomplete {}),
+            ("note_created", ActivityDetails::NoteCreated { title: "Resumo".to_string(), duplicated_from: None }),
+            ("note_created", ActivityDetails::NoteCreated { title: "Cópia".to_string(), duplicated_from: Some("note-1".to_string()) }),
+            ("modules_merged", ActivityDetails::ModulesMerged { merged_module_id: "module-2".to_string(), videos_moved: 3 }),
+            ("bookmark_deleted", ActivityDetails::BookmarkDeleted {}),
+        ];
+
+        for (activity_type, details) in &cases {
+            db.log_activity(&ActivityLog {
+                id: uuid::Uuid::new_v4().to_string(),
+                activity_type: activity_type.to_string(),
+                entity_id: "entity-1".to_string(),
+                entity_type: "video".to_string(),
+                details: Some(details.to_details()),
+                created_at: Utc::now(),
+            }).unwrap();
+        }
+
+        let mut parsed: Vec<(String, ActivityDetails)> = db.get_recent_activities(100).unwrap()
+            .into_iter()
+            .map(|a| (a.activity_type.clone(), a.parsed_details().unwrap()))
+            .collect();
+        let mut expected: Vec<(String, ActivityDetails)> = cases.into_iter()
+            .map(|(activity_type, details)| (activity_type.to_string(), details))
+            .collect();
+        let key = |entry: &(String, ActivityDetails)| format!("{:?}", entry);
+        parsed.sort_by_key(key);
+        expected.sort_by_key(key);
+        assert_eq!(parsed, expected);
+    }
+
+    #[test]
+    fn test_activity_details_fall_back_to_raw() {
+        let activity = |activity_type: &str, details: Option<&str>| ActivityLog {
+            id: "activity-1".to_string(),
+            activity_type: activity_type.to_string(),
+            entity_id: "note-1".to_string(),
+            entity_type: "note".to_string(),
+            details: details.map(str::to_string),
+            created_at: Utc::now(),
+        };
+
+        // Texto livre gravado por versões anteriores
+        assert_eq!(
+            activity("note_deleted", Some("Anotação deletada")).parsed_details(),
+            Some(ActivityDetails::Raw("Anotação deletada".to_string()))
+        );
+        // JSON que não corresponde ao tipo
+        assert_eq!(
+            activity("note_created", Some(r#"{"percent": 50}"#)).parsed_details(),
+            Some(ActivityDetails::Raw(r#"{"percent": 50}"#.to_string()))
+        );
+        // Tipo desconhecido, como os registrados por `log_user_activity`
+        assert_eq!(
+            activity("video_watched", Some("{}")).parsed_details(),
+            Some(ActivityDetails::Raw("{}".to_string()))
+        );
+        assert_eq!(activity("note_deleted", None).parsed_details(), None);
+    }
+
+    #[test]
+    fn test_course_modules_with_counts() {
+        let db = test_db();
+        seed_course(&db, "course-1");
+        seed_module(&db, "course-1", "module-2", 1);
+        seed_module(&db, "course-1", "module-1", 0);
+        seed_video(&db, "course-1", "module-1", "v0", 0);
+        seed_video(&db, "course-1", "module-1", "v1", 1);
+        seed_video(&db, "course-1", "module-1", "v2", 2);
+        db.mark_video_completed("v0", true).unwrap();
+        db.mark_video_completed("v2", true).unwrap();
+        db.mark_video_completed("v1", false).unwrap();
+
+        let counts: Vec<(String, i32, i32)> = db.get_course_modules_with_counts("course-1").unwrap()
+            .into_iter()
+            .map(|(module, total, completed)| (module.id, total, completed))
+            .collect();
+        assert_eq!(counts, vec![
+            ("module-1".to_string(), 3, 2),
+            ("module-2".to_string(), 0, 0),
+        ]);
+
+        // Vários registros de progresso do mesmo vídeo contam uma vez só
+        seed_video(&db, "course-1", "module-2", "v3", 0);
+        seed_video(&db, "course-1", "module-2", "v4", 1);
+        save_progress(&db, "v3", 30.0, 100.0, false, Utc::now());
+        save_progress(&db, "v3", 60.0, 100.0, false, Utc::now());
+        save_progress(&db, "v3", 100.0, 100.0, true, Utc::now());
+        let module_2 = db.get_course_modules_with_counts("course-1").unwrap().into_iter()
+            .find(|(module, _, _)| module.id == "module-2")
+            .map(|(_, total, completed)| (total, completed));
+        assert_eq!(module_2, Some((2, 1)));
+    }
+
+    #[test]
+    fn test_selected_subtitle_falls_back_when_file_is_removed() {
+        let db = test_db();
+        seed_hierarchy(&db, &["video-1"]);
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let subtitle = temp_dir.path().join("aula.pt-BR.srt");
+        std::fs::write(&subtitle, "1\n00:00:01,000 --> 00:00:02,000\nOlá\n").unwrap();
+        let subtitle_path = subtitle.to_string_lossy().to_string();
+
+        assert_eq!(db.get_selected_subtitle("video-1").unwrap(), None);
+        db.set_selected_subtitle("video-1", Some(&subtitle_path)).unwrap();
+        assert_eq!(db.get_selected_subtitle("video-1").unwrap(), Some(subtitle_path.clone()));
+        assert_eq!(db.get_video_detail("video-1").unwrap().selected_subtitle_path, Some(subtitle_path.clone()));
+
+        // Salvar o progresso não apaga a escolha
+        db.mark_video_completed("video-1", true).unwrap();
+        assert_eq!(db.get_selected_subtitle("video-1").unwrap(), Some(subtitle_path.clone()));
+
+        std::fs::remove_file(&subtitle).unwrap();
+        assert_eq!(db.get_selected_subtitle("video-1").unwrap(), None);
+        assert_eq!(db.get_video_detail("video-1").unwrap().selected_subtitle_path, None);
+
+        assert!(matches!(db.set_selected_subtitle("video-1", Some(&subtitle_path)), Err(AppError::InvalidInput(_))));
+        assert!(matches!(db.set_selected_subtitle("inexistente", None), Err(AppError::NotFound(_))));
+        db.set_selected_subtitle("video-1", None).unwrap();
+    }
+
+    #[test]
+    fn test_video_detail_assembles_full_context() {
+        let db = test_db();
+        seed_course(&db, "course-1");
+        seed_module(&db, "course-1", "module-1", 0);
+        seed_video(&db, "course-1", "module-1", "video-1", 0);
+        seed_video(&db, "course-1", "module-1", "video-2", 1);
+
+        let detail = db.get_video_detail("video-2").unwrap();
+        assert_eq!(detail.video.id, "video-2");
+        assert!(detail.progress.is_none());
+        assert!(detail.bookmarks.is_empty());
+        assert!(detail.notes.is_empty());
+
+        db.mark_video_completed("video-1", true).unwrap();
+        db.create_user_note(&sample_note("video-1", "Resumo", "general")).unwrap();
+        db.create_video_bookmark(&VideoBookmark {
+            id: "bookmark-1".to_string(),
+            video_id: "video-1".to_string(),
+            timestamp: 42.0,
+            title: "Ponto importante".to_string(),
+            description: None,
+            created_at: Utc::now(),
+        }).unwrap();
+
+        let detail = db.get_video_detail("video-1").unwrap();
+        assert_eq!(detail.video.id, "video-1");
+        assert_eq!(detail.module.id, "module-1");
+        assert_eq!(detail.course.id, "course-1");
+        assert!(detail.progress.unwrap().completed);
+        assert_eq!(detail.bookmarks.len(), 1);
+        assert_eq!(detail.notes.len(), 1);
+        assert_eq!(detail.notes[0].title, "Resumo");
+
+        assert!(matches!(db.get_video_detail("inexistente"), Err(AppError::NotFound(_))));
+    }
+
+    #[test]
+    fn test_reset_progress_per_scope() {
+        let db = test_db();
+        seed_course(&db, "course-1");
+        seed_course(&db, "course-2");
+        seed_module(&db, "course-1", "module-1", 0);
+        seed_module(&db, "course-1", "module-2", 1);
+        seed_module(&db, "course-2", "module-3", 0);
+        let videos = [
+            ("module-1", "course-1", "v1"),
+            ("module-1", "course-1", "v2"),
+            ("module-2", "course-1", "v3"),
+            ("module-3", "course-2", "v4"),
+        ];
+        for (index, (module_id, course_id, video_id)) in videos.iter().enumerate() {
+            seed_video(&db, course_id, module_id, video_id, index as i32);
+            db.mark_video_completed(video_id, true).unwrap();
+        }
+        db.create_user_note(&sample_note("v1", "Resumo", "general")).unwrap();
+
+        let has_progress = |video_id: &str| db.get_video_progress(video_id).unwrap().is_some();
+
+        assert_eq!(db.reset_progress(&ResetScope::Video("v1".to_string())).unwrap(), 1);
+        assert!(!has_progress("v1") && has_progress("v2"));
+
+        assert_eq!(db.reset_progress(&ResetScope::Module("module-1".to_string())).unwrap(), 1);
+        assert!(!has_progress("v2") && has_progress("v3"));
+
+        assert_eq!(db.reset_progress(&ResetScope::Course("course-1".to_string())).unwrap(), 1);
+        assert!(!has_progress("v3") && has_progress("v4"));
+
+        assert_eq!(db.reset_progress(&ResetScope::Course("course-1".to_string())).unwrap(), 0);
+        assert_eq!(db.get_notes_by_video("v1").unwrap().len(), 1);
+
+        // As amostras de progresso saem junto
+        let samples = |video_id: &str| -> i64 {
+            db.conn.query_row("SELECT COUNT(*) FROM progress_samples WHERE video_id = ?1", [video_id], |row| row.get(0)).unwrap()
+        };
+        assert_eq!((samples("v1"), samples("v3"), samples("v4")), (0, 0, 1));
+    }
+
+    #[test]
+    fn test_clear_course_annotations_only_touches_target_course() {
+        let db = test_db();
+        seed_hierarchy(&db, &["video-1", "video-2"]);
+        seed_course(&db, "course-2");
+        seed_module(&db, "course-2", "module-2", 0);
+        seed_video(&db, "course-2", "module-2", "video-3", 0);
+
+        let bookmark = |id: &str, video_id: &str| {
+            db.create_video_bookmark(&VideoBookmark {
+                id: id.to_string(),
+                video_id: video_id.to_string(),
+                timestamp: 10.0,
+                title: "Marcador".to_string(),
+                description: None,
+                created_at: Utc::now(),
+            }).unwrap();
+        };
+        db.create_user_note(&sample_note("video-1", "Nota 1", "general")).unwrap();
+        db.create_user_note(&sample_note("video-2", "Nota 2", "general")).unwrap();
+        let mut other = sample_note("video-3", "Outro curso", "general");
+        other.course_id = Some("course-2".to_string());
+        other.module_id = Some("module-2".to_string());
+        db.create_user_note(&other).unwrap();
+        bookmark("b1", "video-1");
+        bookmark("b2", "video-3");
+        db.mark_video_completed("video-1", true).unwrap();
+
+        // Sem os bookmarks
+        let cleared = db.clear_course_annotations("course-1", false).unwrap();
+        assert_eq!(cleared, ClearedAnnotations { notes_deleted: 2, bookmarks_deleted: 0 });
+        assert!(db.get_notes_by_course("course-1").unwrap().is_empty());
+        assert_eq!(db.get_video_bookmarks("video-1").unwrap().len(), 1);
+
+        let cleared = db.clear_course_annotations("course-1", true).unwrap();
+        assert_eq!(cleared, ClearedAnnotations { notes_deleted: 0, bookmarks_deleted: 1 });
+        assert!(db.get_video_bookmarks("video-1").unwrap().is_empty());
+
+        // Outro curso e progresso ficam intactos
+        assert_eq!(db.get_notes_by_course("course-2").unwrap().len(), 1);
+        assert_eq!(db.get_video_bookmarks("video-3").unwrap().len(), 1);
+        assert!(db.get_video_progress("video-1").unwrap().unwrap().completed);
+
+        assert!(matches!(db.clear_course_annotations("inexistente", true), Err(AppError::NotFound(_))));
+    }
+
+    #[test]
+    fn test_adjacent_bookmarks_around_current_time() {
+        let db = test_db();
+        seed_hierarchy(&db, &["video-1", "video-2"]);
+        for (id, video_id, timestamp) in [
+            ("b-90", "video-1", 90.0),
+            ("b-10", "video-1", 10.0),
+            ("b-60", "video-1", 60.0),
+            ("b-30", "video-1", 30.0),
+            ("b-outro", "video-2", 45.0),
+        ] {
+            db.create_video_bookmark(&VideoBookmark {
+                id: id.to_string(),
+                video_id: video_id.to_string(),
+                timestamp,
+                title: "Marcador".to_string(),
+                description: None,
+                created_at: Utc::now(),
+            }).unwrap();
+        }
+
+        let ids = |current_time: f64| {
+            let adjacent = db.get_adjacent_bookmarks("video-1", current_time).unwrap();
+            (adjacent.previous.map(|b| b.id), adjacent.next.map(|b| b.id))
+        };
+        assert_eq!(ids(45.0), (Some("b-30".to_string()), Some("b-60".to_string())));
+        // Marcador exatamente na posição atual fica de fora
+        assert_eq!(ids(60.0), (Some("b-30".to_string()), Some("b-90".to_string())));
+        assert_eq!(ids(5.0), (None, Some("b-10".to_string())));
+        assert_eq!(ids(120.0), (Some("b-90".to_string()), None));
+    }
+
+    #[test]
+    fn test_course_bookmarks_follow_lesson_order() {
+        let db = test_db();
+        seed_course(&db, "course-1");
+        // Módulo inserido antes, mas que vem depois na ordem do curso
+        seed_module(&db, "course-1", "module-2", 1);
+        seed_module(&db, "course-1", "module-1", 0);
+        seed_video(&db, "course-1", "module-2", "m2-a", 0);
+        seed_video(&db, "course-1", "module-1", "m1-b", 1);
+        seed_video(&db, "course-1", "module-1", "m1-a", 0);
+        seed_course(&db, "course-2");
+        seed_module(&db, "course-2", "module-x", 0);
+        seed_video(&db, "course-2", "module-x", "x-a", 0);
+
+        let bookmark = |id: &str, video_id: &str, timestamp: f64| {
+            db.create_video_bookmark(&VideoBookmark {
+                id: id.to_string(),
+                video_id: video_id.to_string(),
+                timestamp,
+                title: "Marcador".to_string(),
+                description: None,
+                created_at: Utc::now(),
+            }).unwrap();
+        };
+        bookmark("b-m2a", "m2-a", 5.0);
+        bookmark("b-m1b", "m1-b", 1.0);
+        bookmark("b-m1a-late", "m1-a", 90.0);
+        bookmark("b-m1a-early", "m1-a", 30.0);
+        bookmark("b-x", "x-a", 1.0);
+        bookmark("b-deleted", "m1-a", 60.0);
+        db.delete_video_bookmark("b-deleted").unwrap();
+
+        let order: Vec<(String, String)> = db.get_course_bookmarks("course-1").unwrap()
+            .into_iter()
+            .map(|(video, bookmark)| (video.id, bookmark.id))
+            .collect();
+        let expected: Vec<(String, String)> = [
+            ("m1-a", "b-m1a-early"),
+            ("m1-a", "b-m1a-late"),
+            ("m1-b", "b-m1b"),
+            ("m2-a", "b-m2a"),
+        ].iter().map(|(video, bookmark)| (video.to_string(), bookmark.to_string())).collect();
+        assert_eq!(order, expected);
+    }
+
+    #[test]
+    fn test_most_annotated_videos_ranking() {
+        let db = test_db();
+        seed_hierarchy(&db, &["video-1", "video-2", "video-3", "video-4"]);
+
+        let bookmark = |id: &str, video_id: &str| {
+            db.create_video_bookmark(&VideoBookmark {
+                id: id.to_string(),
+                video_id: video_id.to_string(),
+                timestamp: 10.0,
+                title: "Marcador".to_string(),
+                description: None,
+                created_at: Utc::now(),
+            }).unwrap();
+        };
+        db.create_user_note(&sample_note("video-1", "Nota 1", "general")).unwrap();
+        bookmark("b1", "video-1");
+        for title in ["A", "B", "C"] {
+            db.create_user_note(&sample_note("video-2", title, "general")).unwrap();
+        }
+        bookmark("b2", "video-3");
+        // Anotações excluídas não contam
+        let deleted = sample_note("video-4", "Excluída", "general");
+        db.create_user_note(&deleted).unwrap();
+        db.delete_user_note(&deleted.id).unwrap();
+
+        let ranking: Vec<(String, i64, i64)> = db.get_most_annotated_videos(10).unwrap()
+            .into_iter()
+            .map(|a| (a.video.id, a.note_count, a.bookmark_count))
+            .collect();
+        assert_eq!(ranking, vec![
+            ("video-2".to_string(), 3, 0),
+            ("video-1".to_string(), 1, 1),
+            ("video-3".to_string(), 0, 1),
+        ]);
+
+        assert_eq!(db.get_most_annotated_videos(1).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_normalize_ordering_fixes_gaps_and_ties() {
+        let db = test_db();
+        seed_course(&db, "course-1");
+        seed_module(&db, "course-1", "module-b", 5);
+        seed_module(&db, "course-1", "module-a", 2);
+        // Vídeos com buraco (0, 4) e empate (4, 4) resolvido pelo nome
+        seed_video(&db, "course-1", "module-a", "v-first", 0);
+        seed_video(&db, "course-1", "module-a", "v-zeta", 4);
+        seed_video(&db, "course-1", "module-a", "v-alfa", 4);
+        seed_video(&db, "course-1", "module-b", "v-only", 0);
+
+        assert_eq!(db.normalize_ordering("course-1").unwrap(), 4);
+
+        let modules: Vec<(String, i32)> = db.get_course_modules("course-1").unwrap()
+            .into_iter()
+            .map(|m| (m.id, m.order_index))
+            .collect();
+        assert_eq!(modules, vec![("module-a".to_string(), 0), ("module-b".to_string(), 1)]);
+
+        let videos: Vec<(String, i32)> = db.get_module_videos("module-a").unwrap()
+            .into_iter()
+            .map(|v| (v.id, v.order_index))
+            .collect();
+        assert_eq!(videos, vec![
+            ("v-first".to_string(), 0),
+            ("v-alfa".to_string(), 1),
+            ("v-zeta".to_string(), 2),
+        ]);
+
+        // Já normalizado: nada muda
+        assert_eq!(db.normalize_ordering("course-1").unwrap(), 0);
+    }
+
+    #[test]
+    fn test_search_notes_in_video_matches_title_and_content() {
+        let db = test_db();
+        seed_hierarchy(&db, &["video-1", "video-2"]);
+        let notes = [
+            ("video-1", 300.0, "Borrowing", "Regras de OWNERSHIP e empréstimo"),
+            ("video-1", 120.0, "Ownership", "move semantics"),
+            ("video-1", 60.0, "Introdução", "visão geral"),
+            ("video-2", 10.0, "Ownership em outro vídeo", ""),
+        ];
+        for (video_id, timestamp, title, content) in notes {
+            let mut note = sample_note(video_id, title, "video");
+            note.timestamp = Some(timestamp);
+            note.content = content.to_string();
+            db.create_user_note(&note).unwrap();
+        }
+
+        // Título e conteúdo, ordenados pelo tempo no vídeo
+        let titles: Vec<String> = db.search_notes_in_video("video-1", "ownership").unwrap()
+            .into_iter()
+            .map(|n| n.title)
+            .collect();
+        assert_eq!(titles, vec!["Ownership", "Borrowing"]);
+
+        let titles: Vec<String> = db.search_notes_in_video("video-1", "VISÃO").unwrap()
+            .into_iter()
+            .map(|n| n.title)
+            .collect();
+        assert_eq!(titles, vec!["Introdução"]);
+        assert!(db.search_notes_in_video("video-1", "lifetimes").unwrap().is_empty());
+
+        let all: Vec<String> = db.search_notes_in_video("video-1", "  ").unwrap().into_iter().map(|n| n.id).collect();
+        let by_video: Vec<String> = db.get_notes_by_video("video-1").unwrap().into_iter().map(|n| n.id).collect();
+        assert_eq!(all.len(), 3);
+        assert_eq!(all, by_video);
+    }
+
+    #[test]
+    fn test_last_watched_per_course() {
+        let db = test_db();
+        for course_id in ["course-a", "course-b", "course-c"] {
+            seed_course(&db, course_id);
+            seed_module(&db, course_id, &format!("{}-m", course_id), 0);
+            for index in 0..3 {
+                seed_video(&db, course_id, &format!("{}-m", course_id), &format!("{}-v{}", course_id, index), index);
+            }
+        }
+
+        let now = Utc::now();
+        let watched = [
+            ("course-a-v0", 30),
+            ("course-a-v2", 5),
+            ("course-a-v1", 20),
+            ("course-b-v1", 60),
+            ("course-b-v0", 1),
+        ];
+        for (video_id, minutes_ago) in watched {
+            db.update_video_progress(&VideoProgress {
+                id: format!("progress-{}", video_id),
+                video_id: video_id.to_string(),
+                current_time: 12.0,
+                duration: 60.0,
+                completed: false,
+                last_watched: now - chrono::Duration::minutes(minutes_ago),
+            }).unwrap();
+        }
+
+        // Mais recente primeiro; course-c nunca foi assistido
+        let resume: Vec<(String, String, String)> = db.get_last_watched_per_course().unwrap()
+            .into_iter()
+            .map(|(course, video, progress)| (course.id, video.id, progress.video_id))
+            .collect();
+        assert_eq!(resume, vec![
+            ("course-b".to_string(), "course-b-v0".to_string(), "course-b-v0".to_string()),
+            ("course-a".to_string(), "course-a-v2".to_string(), "course-a-v2".to_string()),
+        ]);
+    }
+
+    #[test]
+    fn test_bulk_update_notes_changes_only_given_fields() {
+        let db = test_db();
+        seed_hierarchy(&db, &["video-1"]);
+        let mut notes = Vec::new();
+        for (title, color) in [("A", Some("#112233")), ("B", None), ("C", Some("#445566"))] {
+            let mut note = sample_note("video-1", title, "video");
+            note.color = color.map(|c| c.to_string());
+            note.updated_at = Utc::now() - chrono::Duration::days(1);
+            db.create_user_note(&note).unwrap();
+            notes.push(note);
+        }
+        let ids = vec![notes[0].id.clone(), notes[1].id.clone(), "inexistente".to_string()];
+
+        // Só o tipo: as cores ficam como estavam
+        let result = db.bulk_update_notes(&ids, Some("general"), None).unwrap();
+        assert_eq!(result, BulkNoteUpdate { updated: 2, missing_ids: vec!["inexistente".to_string()] });
+        let a = db.get_note_by_id(&notes[0].id).unwrap().unwrap();
+        assert_eq!((a.note_type.as_str(), a.color.as_deref()), ("general", Some("#112233")));
+        assert_eq!((a.title.as_str(), a.content.as_str()), ("A", "conteúdo"));
+        assert!(a.updated_at > notes[0].updated_at);
+
+        // Só a cor: o tipo fica como estava
+        db.bulk_update_notes(&ids[..2], None, Some(Some("#ABCDEF"))).unwrap();
+        let b = db.get_note_by_id(&notes[1].id).unwrap().unwrap();
+        assert_eq!((b.note_type.as_str(), b.color.as_deref()), ("general", Some("#ABCDEF")));
+
+        db.bulk_update_notes(&ids[..1], None, Some(None)).unwrap();
+        assert!(db.get_note_by_id(&notes[0].id).unwrap().unwrap().color.is_none());
+
+        // Fora da seleção, nada muda
+        let c = db.get_note_by_id(&notes[2].id).unwrap().unwrap();
+        assert_eq!((c.note_type.as_str(), c.color.as_deref()), ("video", Some("#445566")));
+        assert_eq!(c.updated_at, notes[2].updated_at);
+
+        assert!(matches!(db.bulk_update_notes(&ids, Some("lembrete"), None), Err(AppError::InvalidInput(_))));
+        assert!(matches!(db.bulk_update_notes(&ids, None, Some(Some("vermelho"))), Err(AppError::InvalidInput(_))));
+    }
+
+    #[test]
+    fn test_course_time_remaining() {
+        let db = test_db();
+        seed_hierarchy(&db, &["parcial", "sem-progresso", "concluido", "sem-duracao", "adiantado"]);
+        for (video_id, duration) in [("parcial", 600.0), ("sem-progresso", 300.0), ("concluido", 900.0), ("adiantado", 100.0)] {
+            let mut video = db.get_video_by_id(video_id).unwrap().unwrap();
+            video.duration = Some(duration);
+            db.insert_video(&video).unwrap();
+        }
+        for (video_id, current_time, completed) in [("parcial", 240.0, false), ("concluido", 100.0, true), ("adiantado", 150.0, false)] {
+            db.update_video_progress(&VideoProgress {
+                id: format!("progress-{}", video_id),
+                video_id: video_id.to_string(),
+                current_time,
+                duration: 0.0,
+                completed,
+                last_watched: Utc::now(),
+            }).unwrap();
+        }
+        // Salvamentos parciais anteriores à conclusão não voltam a contar
+        let earlier = Utc::now() - chrono::Duration::hours(1);
+        save_progress(&db, "concluido", 50.0, 900.0, false, earlier);
+        save_progress(&db, "concluido", 80.0, 900.0, false, earlier);
+
+        // 360 do parcial + 300 sem progresso; o concluído não conta e o sem duração é sinalizado
+        let expected = CourseTimeRemaining { remaining_seconds: 660.0, unknown_duration_count: 1 };
+        assert_eq!(db.get_course_time_remaining("course-1").unwrap(), expected);
+
+        db.set_user_setting(&UserSettings {
+            id: uuid::Uuid::new_v4().to_string(),
+            setting_key: "playback_speed".to_string(),
+            setting_value: "1.5".to_string(),
+            setting_type: "number".to_string(),
+            updated_at: Utc::now(),
+        }).unwrap();
+        assert_eq!(db.get_course_time_remaining("course-1").unwrap().remaining_seconds, 440.0);
+    }
+
+    #[test]
+    fn test_force_migrate_from_stored_version() {
+        let db = test_db();
+        seed_hierarchy(&db, &["video-1"]);
+        db.conn.execute_batch(
+            "DROP TABLE user_settings;
+             DROP TABLE activity_log;
+             DELETE FROM database_version;
+             INSERT INTO database_version (version) VALUES (1);",
+        ).unwrap();
+        assert_eq!(db.get_database_version().unwrap(), 1);
+
+        assert_eq!(db.force_migrate().unwrap(), DATABASE_VERSION);
+        db.initialize_default_settings().unwrap();
+        assert!(db.get_user_setting("theme").unwrap().is_some());
+        let tables: i64 = db.conn.query_row(
+            "SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name IN ('user_settings', 'activity_log')",
+            [],
+            |row| row.get(0),
+        ).unwrap();
+        assert_eq!(tables, 2);
+
+        // Rodar de novo não muda nada
+        assert_eq!(db.force_migrate().unwrap(), DATABASE_VERSION);
+        assert!(db.get_video_by_id("video-1").unwrap().is_some());
+    }
+
+    #[test]
+    fn test_version_from_metadata_table_is_reconciled() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("test.db");
+        {
+            let db = Database::new(&db_path).unwrap();
+            seed_hierarchy(&db, &["video-1"]);
+        }
+
+        // Banco criado pelo script antigo: versão 8 só em database_metadata
+        {
+            let conn = Connection::open(&db_path).unwrap();
+            conn.execute_batch(
+                "DROP TABLE database_version;
+                 ALTER TABLE videos DROP COLUMN description;
+                 CREATE TABLE database_metadata (key TEXT PRIMARY KEY, value TEXT NOT NULL, updated_at TEXT NOT NULL);
+                 INSERT INTO database_metadata VALUES ('version', '8', '2024-01-01T00:00:00Z');",
+            ).unwrap();
+        }
+
+        let db = Database { conn: Connection::open(&db_path).unwrap() };
+        db.conn.execute("CREATE TABLE IF NOT EXISTS database_version (version INTEGER PRIMARY KEY)", []).unwrap();
+        db.reconcile_version_tables().unwrap();
+        assert_eq!(db.get_database_version().unwrap(), 8);
+        drop(db);
+
+        // Ao abrir normalmente, migra da 8 em diante em vez de recriar o banco
+        let db = Database::new(&db_path).unwrap();
+        assert_eq!(db.get_database_version().unwrap(), DATABASE_VERSION);
+        db.set_video_description("video-1", Some("Descrição")).unwrap();
+        assert!(db.get_video_by_id("video-1").unwrap().is_some());
+        let leftover: i64 = db.conn.query_row(
+            "SELECT COUNT(*) FROM database_metadata WHERE key = 'version'", [], |row| row.get(0),
+        ).unwrap();
+        assert_eq!(leftover, 0);
+    }
+
+    #[test]
+    fn test_organize_videos_by_pattern() {
+        let db = test_db();
+        seed_course(&db, "course-1");
+        seed_module(&db, "course-1", "module-1", 0);
+        let files = [
+            "Section 10 - Lesson 1 - Encerramento.mp4",
+            "Section 2 - Lesson 5 - Traits.mp4",
+            "Section 2 - Lesson 1 - Tipos.mp4",
+            "Section 1 - Lesson 3 - Instalação.mp4",
+            "Bônus - entrevista.mp4",
+        ];
+        for (index, file) in files.iter().enumerate() {
+            let mut video = seed_video(&db, "course-1", "module-1", &format!("video-{}", index), index as i32);
+            video.path = format!("/cursos/course-1/{}", file);
+            db.insert_video(&video).unwrap();
+        }
+        let mut note = sample_note("video-1", "Traits", "video");
+        note.module_id = Some("module-1".to_string());
+        db.create_user_note(&note).unwrap();
+
+        let result = db.organize_videos_by_pattern("course-1", r"^(Section \d+) - Lesson (\d+)", 1, 2).unwrap();
+        assert_eq!(result, VideoOrganization { modules_created: 3, videos_moved: 4, unmatched: 1 });
+
+        let layout: Vec<(String, Vec<(String, i32)>)> = db.get_course_modules("course-1").unwrap()
+            .into_iter()
+            .map(|m| {
+                let videos = db.get_module_videos(&m.id).unwrap().into_iter().map(|v| (v.id, v.order_index)).collect();
+                (m.name, videos)
+            })
+            .collect();
+        assert_eq!(layout, vec![
+            ("module-1".to_string(), vec![("video-4".to_string(), 4)]),
+            ("Section 1".to_string(), vec![("video-3".to_string(), 3)]),
+            ("Section 2".to_string(), vec![("video-2".to_string(), 1), ("video-1".to_string(), 5)]),
+            ("Section 10".to_string(), vec![("video-0".to_string(), 1)]),
+        ]);
+
+        // As anotações acompanham o vídeo
+        let section_2 = db.get_video_by_id("video-1").unwrap().unwrap().module_id;
+        assert_eq!(db.get_note_by_id(&note.id).unwrap().unwrap().module_id, Some(section_2));
+
+        // Rodar de novo não muda nada
+        let again = db.organize_videos_by_pattern("course-1", r"^(Section \d+) - Lesson (\d+)", 1, 2).unwrap();
+        assert_eq!(again, VideoOrganization { modules_created: 0, videos_moved: 0, unmatched: 1 });
+
+        assert!(matches!(db.organize_videos_by_pattern("course-1", "(", 1, 2), Err(AppError::InvalidInput(_))));
+        assert!(matches!(db.organize_videos_by_pattern("course-1", r"(\d+)", 1, 2), Err(AppError::InvalidInput(_))));
+        assert!(matches!(db.organize_videos_by_pattern("inexistente", r"(\d+)-(\d+)", 1, 2), Err(AppError::NotFound(_))));
+    }
+
+    #[test]
+    fn test_global_summary() {
+        let db = test_db();
+        let empty = GlobalSummary { total_videos: 0, completed_videos: 0, percent: 0.0, total_watch_seconds: 0.0, course_count: 0 };
+        assert_eq!(db.get_global_summary().unwrap(), empty);
+
+        seed_hierarchy(&db, &["video-1", "video-2", "video-3", "video-4"]);
+        seed_course(&db, "course-2");
+        for (video_id, current_time, completed) in [("video-1", 600.0, true), ("video-2", 120.5, false), ("video-3", 300.0, true)] {
+            db.update_video_progress(&VideoProgress {
+                id: format!("progress-{}", video_id),
+                video_id: video_id.to_string(),
+                current_time,
+                duration: 600.0,
+                completed,
+                last_watched: Utc::now(),
+            }).unwrap();
+        }
+
+        assert_eq!(db.get_global_summary().unwrap(), GlobalSummary {
+            total_videos: 4,
+            completed_videos: 2,
+            percent: 50.0,
+            total_watch_seconds: 1020.5,
+            course_count: 2,
+        });
+
+        // Salvamentos anteriores do mesmo vídeo não contam de novo
+        let earlier = Utc::now() - chrono::Duration::hours(1);
+        save_progress(&db, "video-2", 30.0, 600.0, false, earlier);
+        save_progress(&db, "video-1", 600.0, 600.0, true, earlier);
+        let summary = db.get_global_summary().unwrap();
+        assert_eq!((summary.total_videos, summary.completed_videos), (4, 2));
+        assert_eq!(summary.total_watch_seconds, 1020.5);
+    }
+
+    #[test]
+    fn test_manual_completion_counts_as_completed_but_not_watch_time() {
+        let db = test_db();
+        seed_hierarchy(&db, &["assistido", "manual", "parcial"]);
+        db.update_video_progress(&VideoProgress {
+            id: "progress-assistido".to_string(),
+            video_id: "assistido".to_string(),
+            current_time: 600.0,
+            duration: 600.0,
+            completed: true,
+            last_watched: Utc::now(),
+        }).unwrap();
+        db.update_video_progress(&VideoProgress {
+            id: "progress-parcial".to_string(),
+            video_id: "parcial".to_string(),
+            current_time: 200.0,
+            duration: 600.0,
+            completed: false,
+            last_watched: Utc::now(),
+        }).unwrap();
+        db.mark_video_completed("manual", true).unwrap();
+
+        assert!(db.is_completed_manually("manual").unwrap());
+        assert!(!db.is_completed_manually("assistido").unwrap());
+        assert_eq!(db.get_course_completion_stats("course-1").unwrap(), (3, 2, 1));
+
+        let summary = db.get_global_summary().unwrap();
+        assert_eq!((summary.completed_videos, summary.total_watch_seconds), (2, 800.0));
+        let today = Local::now().date_naive();
+        let history = db.get_watch_history(today, today, false).unwrap();
+        assert_eq!(history.iter().map(|(_, _, seconds)| seconds).sum::<f64>(), 800.0);
+
+        // Marcar à mão um vídeo já assistido em parte mantém o tempo real
+        db.mark_video_completed("parcial", true).unwrap();
+        assert!(!db.is_completed_manually("parcial").unwrap());
+        assert_eq!(db.get_global_summary().unwrap().total_watch_seconds, 800.0);
+
+        // Desmarcar volta ao início, sem a posição fictícia
+        db.mark_video_completed("manual", false).unwrap();
+        assert!(!db.is_completed_manually("manual").unwrap());
+        assert_eq!(db.get_video_progress("manual").unwrap().unwrap().current_time, 0.0);
+        assert_eq!(db.get_course_completion_stats("course-1").unwrap(), (3, 2, 0));
+    }
+
+    #[test]
+    fn test_note_linked_to_bookmark() {
+        let db = test_db();
+        seed_hierarchy(&db, &["video-1"]);
+        db.create_video_bookmark(&VideoBookmark {
+            id: "bookmark-1".to_string(),
+            video_id: "video-1".to_string(),
+            timestamp: 42.5,
+            title: "Ownership".to_string(),
+            description: None,
+            created_at: Utc::now(),
+        }).unwrap();
+
+        let mut linked = sample_note("video-1", "Resumo do trecho", "video");
+        linked.bookmark_id = Some("bookmark-1".to_string());
+        db.create_user_note(&linked).unwrap();
+        db.create_user_note(&sample_note("video-1", "Solta", "video")).unwrap();
+
+        let notes = db.get_notes_for_bookmark("bookmark-1").unwrap();
+        assert_eq!(notes.len(), 1);
+        assert_eq!(notes[0].id, linked.id);
+        assert_eq!(notes[0].bookmark_id.as_deref(), Some("bookmark-1"));
+
+        // Na lixeira a ligação continua; removido de vez, a anotação fica sem bookmark
+        db.delete_video_bookmark("bookmark-1").unwrap();
+        assert_eq!(db.get_notes_for_bookmark("bookmark-1").unwrap().len(), 1);
+        db.conn.execute("UPDATE video_bookmarks SET deleted_at = ?1", params![(Utc::now() - chrono::Duration::days(60)).to_rfc3339()]).unwrap();
+        db.purge_deleted(30).unwrap();
+
+        assert!(db.get_notes_for_bookmark("bookmark-1").unwrap().is_empty());
+        let note = db.get_note_by_id(&linked.id).unwrap().unwrap();
+        assert_eq!(note.title, "Resumo do trecho");
+        assert!(note.bookmark_id.is_none());
+    }
+
+    #[test]
+    fn test_course_level_notes_exclude_video_notes() {
+        let db = test_db();
+        seed_hierarchy(&db, &["video-1"]);
+        let now = Utc::now();
+        for (minutes_ago, title, note_type) in [(3, "Plano de estudo", "course"), (2, "Dúvida da aula", "video"), (1, "Resumo final", "course")] {
+            let mut note = sample_note("video-1", title, note_type);
+            note.created_at = now - chrono::Duration::minutes(minutes_ago);
+            db.create_user_note(&note).unwrap();
+        }
+
+        let titles: Vec<String> = db.get_course_level_notes("course-1").unwrap().into_iter().map(|n| n.title).collect();
+        assert_eq!(titles, vec!["Resumo final", "Plano de estudo"]);
+        assert_eq!(db.get_notes_by_course("course-1").unwrap().len(), 3);
+    }
+
+    #[test]
+    fn test_notes_by_module_exclude_other_modules() {
+        let db = test_db();
+        seed_hierarchy(&db, &["video-1"]);
+        seed_module(&db, "course-1", "module-2", 1);
+        seed_video(&db, "course-1", "module-2", "video-2", 0);
+        let now = Utc::now();
+        let notes = [(3, "Visão geral", "video-1", "module-1"), (2, "Outro módulo", "video-2", "module-2"), (1, "Revisão", "video-1", "module-1")];
+        for (minutes_ago, title, video_id, module_id) in notes {
+            let mut note = sample_note(video_id, title, "module");
+            note.module_id = Some(module_id.to_string());
+            note.created_at = now - chrono::Duration::minutes(minutes_ago);
+            db.create_user_note(&note).unwrap();
+        }
+
+        let titles: Vec<String> = db.get_notes_by_module("module-1").unwrap().into_iter().map(|n| n.title).collect();
+        assert_eq!(titles, vec!["Revisão", "Visão geral"]);
+        assert!(db.get_notes_by_module("module-3").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_course_intro_video() {
+        let db = test_db();
+        for course_id in ["course-1", "course-2"] {
+            seed_course(&db, course_id);
+            seed_module(&db, course_id, &format!("{}-m0", course_id), 0);
+            seed_module(&db, course_id, &format!("{}-m1", course_id), 1);
+        }
+        let videos = [
+            ("course-1", "course-1-m1", "c1-welcome", "Welcome", 0),
+            ("course-1", "course-1-m0", "c1-setup", "Instalando o Rust", 0),
+            ("course-1", "course-1-m0", "c1-intro", "00 - Introdução", 1),
+            ("course-2", "course-2-m1", "c2-b", "Traits", 0),
+            ("course-2", "course-2-m0", "c2-a", "Tipos", 2),
+            ("course-2", "course-2-m0", "c2-first", "Variáveis", 1),
+        ];
+        for (course_id, module_id, video_id, name, order) in videos {
+            let mut video = seed_video(&db, course_id, module_id, video_id, order);
+            video.name = name.to_string();
+            db.insert_video(&video).unwrap();
+        }
+
+        // O primeiro nome de introdução na ordem do curso, não o primeiro vídeo
+        assert_eq!(db.get_course_intro_video("course-1").unwrap().unwrap().id, "c1-intro");
+        // Sem introdução: o primeiro vídeo do primeiro módulo
+        assert_eq!(db.get_course_intro_video("course-2").unwrap().unwrap().id, "c2-first");
+        assert!(db.get_course_intro_video("inexistente").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_validate_setting_per_type() {
+        assert!(validate_setting("volume", "0.8", "number").is_ok());
+        assert!(validate_setting("volume", " 12 ", "number").is_ok());
+        assert!(matches!(validate_setting("volume", "alto", "number"), Err(AppError::InvalidInput(_))));
+        assert!(matches!(validate_setting("volume", "NaN", "number"), Err(AppError::InvalidInput(_))));
+        assert!(matches!(validate_setting("volume", "inf", "number"), Err(AppError::InvalidInput(_))));
+
+        assert!(validate_setting("auto_play_next", "false", "boolean").is_ok());
+        assert!(matches!(validate_setting("auto_play_next", "sim", "boolean"), Err(AppError::InvalidInput(_))));
+
+        assert!(validate_setting("layout", r#"{"sidebar": true}"#, "json").is_ok());
+        assert!(matches!(validate_setting("layout", "{sidebar", "json"), Err(AppError::InvalidInput(_))));
+
+        assert!(validate_setting("theme", "light", "string").is_ok());
+        assert!(matches!(validate_setting("theme", "roxo", "string"), Err(AppError::InvalidInput(_))));
+        assert!(matches!(validate_setting("language", "pt-BR", "texto"), Err(AppError::InvalidInput(_))));
+
+        // `set_user_setting` não grava valores inválidos
+        let db = test_db();
+        db.initialize_default_settings().unwrap();
+        let setting = |value: &str| UserSettings {
+            id: uuid::Uuid::new_v4().to_string(),
+            setting_key: "theme".to_string(),
+            setting_value: value.to_string(),
+            setting_type: "string".to_string(),
+            updated_at: Utc::now(),
+        };
+        assert!(matches!(db.set_user_setting(&setting("roxo")), Err(AppError::InvalidInput(_))));
+        assert_eq!(db.get_user_setting("theme").unwrap().unwrap().setting_value, "dark");
+        db.set_user_setting(&setting("light")).unwrap();
+        assert_eq!(db.get_user_setting("theme").unwrap().unwrap().setting_value, "light");
+    }
+
+    #[test]
+    fn test_theme_round_trip_and_repair() {
+        let db = test_db();
+        assert_eq!(db.get_theme().unwrap(), Theme::Dark);
+
+        for theme in [Theme::Light, Theme::System, Theme::Dark] {
+            db.set_theme(theme).unwrap();
+            assert_eq!(db.get_theme().unwrap(), theme);
+        }
+        assert_eq!(Theme::parse(" Light "), Some(Theme::Light));
+        assert_eq!(Theme::parse("roxo"), None);
+
+        // Valor gravado sem validação volta para o padrão e é corrigido no banco
+        db.write_user_setting(&UserSettings {
+            id: uuid::Uuid::new_v4().to_string(),
+            setting_key: "theme".to_string(),
+            setting_value: "roxo".to_string(),
+            setting_type: "string".to_string(),
+            updated_at: Utc::now(),
+        }).unwrap();
+        assert_eq!(db.get_theme().unwrap(), Theme::Dark);
+        assert_eq!(db.get_user_setting("theme").unwrap().unwrap().setting_value, "dark");
+    }
+
+    #[test]
+    fn test_repair_inconsistent_progress() {
+        let db = test_db();
+        seed_hierarchy(&db, &["video-1", "video-2", "video-3", "video-4"]);
+        for video_id in ["video-1", "video-2", "video-3"] {
+            db.set_video_duration(video_id, 600.0).unwrap();
+        }
+
+        // video-1: concluído à mão antes de ser reproduzido (100/100)
+        db.mark_video_completed("video-1", true).unwrap();
+        let progress = |video_id: &str, current_time: f64, duration: f64| {
+            db.update_video_progress(&VideoProgress {
+                id: format!("progress-{}", video_id),
+                video_id: video_id.to_string(),
+                current_time,
+                duration,
+                completed: false,
+                last_watched: Utc::now(),
+            }).unwrap();
+        };
+        progress("video-2", 700.0, 600.0);
+        progress("video-3", 120.0, 600.0);
+        // Sem duração real ainda: nada a corrigir
+        db.mark_video_completed("video-4", true).unwrap();
+
+        let found: Vec<String> = db.find_inconsistent_progress().unwrap().into_iter().map(|row| row.video.id).collect();
+        assert_eq!(found, vec!["video-1", "video-2"]);
+
+        assert_eq!(db.repair_progress_durations().unwrap(), ProgressRepair { durations_fixed: 1, positions_clamped: 1 });
+        let fixed = db.get_video_progress("video-1").unwrap().unwrap();
+        assert_eq!((fixed.current_time, fixed.duration, fixed.completed), (600.0, 600.0, true));
+        assert_eq!(db.get_video_progress("video-2").unwrap().unwrap().current_time, 600.0);
+        assert_eq!(db.get_video_progress("video-3").unwrap().unwrap().current_time, 120.0);
+        assert_eq!(db.get_video_progress("video-4").unwrap().unwrap().duration, 100.0);
+
+        assert!(db.find_inconsistent_progress().unwrap().is_empty());
+        assert_eq!(db.repair_progress_durations().unwrap(), ProgressRepair { durations_fixed: 0, positions_clamped: 0 });
+    }
+
+    #[test]
+    fn test_dangling_note_timestamps_are_found_and_clamped() {
+        let db = test_db();
+        seed_hierarchy(&db, &["video-1", "video-2"]);
+        db.set_video_duration("video-1", 300.0).unwrap();
+        let note = |video_id: &str, title: &str, timestamp: Option<f64>| {
+            let mut note = sample_note(video_id, title, "video");
+            note.timestamp = timestamp;
+            db.create_user_note(&note).unwrap();
+            note.id
+        };
+        let past_end = note("video-1", "Depois do fim", Some(420.0));
+        note("video-1", "No fim", Some(300.0));
+        note("video-1", "Sem timestamp", None);
+        // Duração desconhecida: não dá para saber se passou do fim
+        note("video-2", "Vídeo sem duração", Some(9999.0));
+
+        let found: Vec<String> = db.find_dangling_note_timestamps().unwrap().into_iter().map(|n| n.title).collect();
+        assert_eq!(found, vec!["Depois do fim"]);
+
+        assert_eq!(db.clamp_note_timestamps().unwrap(), 1);
+        assert_eq!(db.get_note_by_id(&past_end).unwrap().unwrap().timestamp, Some(300.0));
+        assert!(db.find_dangling_note_timestamps().unwrap().is_empty());
+        assert_eq!(db.clamp_note_timestamps().unwrap(), 0);
+    }
+
+    #[test]
+    fn test_find_and_delete_orphans() {
+        let db = test_db();
+        seed_hierarchy(&db, &["video-1"]);
+        db.create_user_note(&sample_note("video-1", "Nota", "general")).unwrap();
+
+        // Órfãos como os de bancos antigos, sem as chaves estrangeiras valendo
+        db.conn.execute("PRAGMA foreign_keys = OFF", []).unwrap();
+        seed_module(&db, "curso-apagado", "module-orfao", 0);
+        seed_video(&db, "curso-apagado", "module-orfao", "video-do-modulo-orfao", 0);
+        seed_video(&db, "course-1", "module-apagado", "video-sem-modulo", 1);
+        db.mark_video_completed("video-sem-modulo", true).unwrap();
+        db.create_user_note(&sample_note("video-sem-modulo", "Nota órfã", "general")).unwrap();
+        db.conn.execute("PRAGMA foreign_keys = ON", []).unwrap();
+
+        let report = db.find_orphans().unwrap();
+        assert_eq!((report.module_count, report.video_count), (1, 2));
+        assert_eq!(report.sample_modules[0].id, "module-orfao");
+        let mut sample_ids: Vec<&str> = report.sample_videos.iter().map(|v| v.id.as_str()).collect();
+        sample_ids.sort();
+        assert_eq!(sample_ids, vec!["video-do-modulo-orfao", "video-sem-modulo"]);
+
+        assert_eq!(db.delete_orphans().unwrap(), (1, 2));
+        let report = db.find_orphans().unwrap();
+        assert_eq!((report.module_count, report.video_count), (0, 0));
+        assert!(db.get_video_progress("video-sem-modulo").unwrap().is_none());
+
+        // O resto do acervo fica intacto
+        assert!(db.get_video_by_id("video-1").unwrap().is_some());
+        assert_eq!(db.get_notes_by_video("video-1").unwrap().len(), 1);
+        assert_eq!(db.get_course_modules("course-1").unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_course_outline_interleaves_modules_and_videos() {
+        let db = test_db();
+        seed_course(&db, "course-1");
+        // Inseridos fora de ordem, como num scan
+        seed_module(&db, "course-1", "module-2", 1);
+        seed_module(&db, "course-1", "module-1", 0);
+        seed_video(&db, "course-1", "module-2", "m2-a", 0);
+        seed_video(&db, "course-1", "module-1", "m1-b", 1);
+        seed_video(&db, "course-1", "module-1", "m1-a", 0);
+        db.mark_video_completed("m1-a", true).unwrap();
+        db.update_video_progress(&VideoProgress {
+            id: "progress-m2-a".to_string(),
+            video_id: "m2-a".to_string(),
+            current_time: 42.0,
+            duration: 600.0,
+            completed: false,
+            last_watched: Utc::now(),
+        }).unwrap();
+
+        let outline = |db: &Database| -> Vec<String> {
+            db.get_course_outline("course-1").unwrap()
+                .into_iter()
+                .map(|item| match item {
+                    OutlineItem::Module { module, video_count } => format!("{} ({})", module.id, video_count),
+                    OutlineItem::Video { video, completed, current_time } => format!("  {} {} {}", video.id, completed, current_time),
+                })
+                .collect()
+        };
+        assert_eq!(outline(&db), vec![
+            "module-1 (2)",
+            "  m1-a true 0",
+            "  m1-b false 0",
+            "module-2 (1)",
+            "  m2-a false 42",
+        ]);
+
+        // Com vários registros de progresso, vale o mais recente
+        let earlier = Utc::now() - chrono::Duration::minutes(10);
+        save_progress(&db, "m1-b", 30.0, 600.0, false, earlier);
+        save_progress(&db, "m1-b", 90.0, 600.0, false, earlier + chrono::Duration::minutes(5));
+        save_progress(&db, "m1-b", 60.0, 600.0, false, earlier - chrono::Duration::minutes(5));
+        assert_eq!(outline(&db), vec![
+            "module-1 (2)",
+            "  m1-a true 0",
+            "  m1-b false 90",
+            "module-2 (1)",
+            "  m2-a false 42",
+        ]);
+        assert!(db.get_course_outline("inexistente").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_resume_course_picks_latest_in_progress_video() {
+        let db = test_db();
+        seed_hierarchy(&db, &["v0", "v1", "v2", "v3"]);
+        let resumed = |db: &Database| db.resume_course("course-1").unwrap().map(|(video, time)| (video.id, time));
+
+        // Nada assistido: começa do primeiro vídeo
+        assert_eq!(resumed(&db), Some(("v0".to_string(), 0.0)));
+
+        let now = Utc::now();
+        for (video_id, current_time, completed, minutes_ago) in [("v0", 600.0, true, 30), ("v3", 90.0, false, 5), ("v1", 250.0, false, 20)] {
+            db.update_video_progress(&VideoProgress {
+                id: format!("progress-{}", video_id),
+                video_id: video_id.to_string(),
+                current_time,
+                duration: 600.0,
+                completed,
+                last_watched: now - chrono::Duration::minutes(minutes_ago),
+            }).unwrap();
+        }
+        assert_eq!(resumed(&db), Some(("v3".to_string(), 90.0)));
+
+        // Sem vídeos em andamento: o primeiro não concluído, do início
+        db.mark_video_completed("v3", true).unwrap();
+        db.mark_video_completed("v1", true).unwrap();
+        assert_eq!(resumed(&db), Some(("v2".to_string(), 0.0)));
+
+        db.mark_video_completed("v2", true).unwrap();
+        assert_eq!(resumed(&db), None);
+
+        assert!(matches!(db.resume_course("inexistente"), Err(AppError::NotFound(_))));
+    }
+
+    #[test]
+    fn test_recommended_next_module_is_first_incomplete() {
+        let db = test_db();
+        seed_course(&db, "course-1");
+        for (module_id, order) in [("module-1", 0), ("module-2", 1), ("module-3", 2), ("module-vazio", 3)] {
+            seed_module(&db, "course-1", module_id, order);
+        }
+        for (module_id, videos) in [("module-1", ["a1", "a2"]), ("module-2", ["b1", "b2"]), ("module-3", ["c1", "c2"])] {
+            for (order, video_id) in videos.into_iter().enumerate() {
+                seed_video(&db, "course-1", module_id, video_id, order as i32);
+            }
+        }
+        for video_id in ["a1", "a2", "b2", "c1"] {
+            db.mark_video_completed(video_id, true).unwrap();
+        }
+
+        let (module, fraction) = db.get_recommended_next_module("course-1").unwrap().unwrap();
+        assert_eq!(module.id, "module-2");
+        assert_eq!(fraction, 0.5);
+
+        for video_id in ["b1", "c2"] {
+            db.mark_video_completed(video_id, true).unwrap();
+        }
+        assert!(db.get_recommended_next_module("course-1").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_json_setting_round_trip() {
+        #[derive(Debug, PartialEq, Serialize, Deserialize)]
+        struct PlayerLayout {
+            sidebar_width: u32,
+            panels: Vec<String>,
+        }
+
+        let db = test_db();
+        let layout = PlayerLayout { sidebar_width: 320, panels: vec!["notas".to_string(), "bookmarks".to_string()] };
+        assert_eq!(db.get_setting_json::<PlayerLayout>("player_layout").unwrap(), None);
+
+        db.set_setting_json("player_layout", &layout).unwrap();
+        assert_eq!(db.get_setting_json::<PlayerLayout>("player_layout").unwrap(), Some(layout));
+        assert_eq!(db.get_user_setting("player_layout").unwrap().unwrap().setting_type, "json");
+
+        db.write_user_setting(&UserSettings {
+            id: "quebrada".to_string(),
+            setting_key: "player_layout".to_string(),
+            setting_value: "{sidebar_width: 320".to_string(),
+            setting_type: "json".to_string(),
+            updated_at: Utc::now(),
+        }).unwrap();
+        assert!(matches!(db.get_setting_json::<PlayerLayout>("player_layout"), Err(AppError::InvalidInput(_))));
+    }
+
+    #[test]
+    fn test_course_badges_follow_completion() {
+        let db = test_db();
+        seed_hierarchy(&db, &["v1", "v2", "v3", "v4"]);
+        let badges = |db: &Database| {
+            let b = db.get_course_badges("course-1").unwrap();
+            (b.started, b.halfway, b.completed)
+        };
+        let complete = |db: &Database, video_id: &str, day: u32| {
+            db.mark_video_completed(video_id, true).unwrap();
+            db.log_activity(&ActivityLog {
+                id: uuid::Uuid::new_v4().to_string(),
+                activity_type: "video_completed".to_string(),
+                entity_id: video_id.to_string(),
+                entity_type: "video".to_string(),
+                details: None,
+                created_at: Utc.with_ymd_and_hms(2024, 5, day, 12, 0, 0).unwrap(),
+            }).unwrap();
+        };
+
+        let empty = db.get_course_badges("course-1").unwrap();
+        assert_eq!((empty.started, empty.halfway, empty.completed), (false, false, false));
+        assert_eq!((empty.percent, empty.started_at, empty.completed_at), (0.0, None, None));
+
+        complete(&db, "v1", 3);
+        complete(&db, "v2", 4);
+        assert_eq!(badges(&db), (true, true, false));
+        let halfway = db.get_course_badges("course-1").unwrap();
+        assert_eq!(halfway.percent, 50.0);
+        assert_eq!(halfway.started_at, Some(Utc.with_ymd_and_hms(2024, 5, 3, 12, 0, 0).unwrap()));
+        assert_eq!(halfway.completed_at, None);
+
+        complete(&db, "v3", 9);
+        complete(&db, "v4", 7);
+        assert_eq!(badges(&db), (true, true, true));
+        let done = db.get_course_badges("course-1").unwrap();
+        assert_eq!(done.completed_at, Some(Utc.with_ymd_and_hms(2024, 5, 9, 12, 0, 0).unwrap()));
+
+        // Curso sem vídeos: nenhum marco
+        seed_course(&db, "course-vazio");
+        let no_videos = db.get_course_badges("course-vazio").unwrap();
+        assert_eq!((no_videos.total_videos, no_videos.started, no_videos.completed), (0, false, false));
+    }
+
+    #[test]
+    fn test_course_activity_summary_aggregates_notes_bookmarks_and_activity() {
+        let db = test_db();
+        seed_hierarchy(&db, &["video-1", "video-2"]);
+        seed_course(&db, "course-2");
+        seed_module(&db, "course-2", "module-2", 0);
+        seed_video(&db, "course-2", "module-2", "video-outro", 0);
+
+        let empty = db.get_course_activity_summary("course-1").unwrap();
+        assert_eq!(empty, CourseActivitySummary {
+            note_count: 0,
+            bookmark_count: 0,
+            first_activity: None,
+            last_activity: None,
+            percent: 0.0,
+        });
+
+        db.create_user_note(&sample_note("video-1", "Ownership", "video")).unwrap();
+        db.create_user_note(&sample_note("video-2", "Traits", "video")).unwrap();
+        let deleted = sample_note("video-2", "Apagada", "video");
+        db.create_user_note(&deleted).unwrap();
+        db.delete_user_note(&deleted.id).unwrap();
+        for (video_id, timestamp) in [("video-1", 10.0), ("video-outro", 20.0)] {
+            db.create_video_bookmark(&VideoBookmark {
+                id: uuid::Uuid::new_v4().to_string(),
+                video_id: video_id.to_string(),
+                timestamp,
+                title: "Marcador".to_string(),
+                description: None,
+                created_at: Utc::now(),
+            }).unwrap();
+        }
+
+        let day = |day: u32| Utc.with_ymd_and_hms(2024, 5, day, 12, 0, 0).unwrap();
+        for (entity_id, entity_type, created_at) in [
+            ("course-1", "course", day(2)),
+            ("video-2", "video", day(5)),
+            ("module-1", "module", day(8)),
+            ("video-outro", "video", day(1)),
+            ("course-2", "course", day(20)),
+        ] {
+            db.log_activity(&ActivityLog {
+                id: uuid::Uuid::new_v4().to_string(),
+                activity_type: "video_watched".to_string(),
+                entity_id: entity_id.to_string(),
+                entity_type: entity_type.to_string(),
+                details: None,
+                created_at,
+            }).unwrap();
+        }
+        db.update_video_progress(&VideoProgress {
+            id: "progress-1".to_string(),
+            video_id: "video-1".to_string(),
+            current_time: 600.0,
+            duration: 600.0,
+            completed: true,
+            last_watched: day(12),
+        }).unwrap();
+
+        assert_eq!(db.get_course_activity_summary("course-1").unwrap(), CourseActivitySummary {
+            note_count: 2,
+            bookmark_count: 1,
+            first_activity: Some(day(2)),
+            last_activity: Some(day(12)),
+            percent: 50.0,
+        });
+        assert!(matches!(db.get_course_activity_summary("nao-existe"), Err(AppError::NotFound(_))));
+    }
+
+    #[test]
+    fn test_video_location_counts_modules_and_lessons() {
+        let db = test_db();
+        seed_course(&db, "course-1");
+        // Módulos inseridos fora de ordem; vale o `order_index`
+        for (module_id, order, lessons) in [("module-c", 2, 3), ("module-a", 0, 2), ("module-b", 1, 4)] {
+            seed_module(&db, "course-1", module_id, order);
+            for lesson in (0..lessons).rev() {
+                seed_video(&db, "course-1", module_id, &format!("{}-{}", module_id, lesson), lesson);
+            }
+        }
+
+        assert_eq!(db.get_video_location("module-b-2").unwrap(), VideoLocation {
+            module_index: 2,
+            module_count: 3,
+            video_index_in_module: 3,
+            videos_in_module: 4,
+            global_index: 5,
+            global_count: 9,
+        });
+        let last = db.get_video_location("module-c-2").unwrap();
+        assert_eq!((last.module_index, last.video_index_in_module, last.global_index), (3, 3, 9));
+
+        // Pasta sem vídeos que só agrupa submódulos não conta como módulo
+        seed_module(&db, "course-1", "module-d", 3);
+        seed_module(&db, "course-1", "module-d-1", 4);
+        db.set_module_parent("module-d-1", Some("module-d")).unwrap();
+        seed_video(&db, "course-1", "module-d-1", "module-d-1-0", 0);
+        assert_eq!(db.get_video_location("module-d-1-0").unwrap(), VideoLocation {
+            module_index: 4,
+            module_count: 4,
+            video_index_in_module: 1,
+            videos_in_module: 1,
+            global_index: 10,
+            global_count: 10,
+        });
+        assert_eq!(db.get_video_location("module-b-2").unwrap().module_count, 4);
+        assert!(matches!(db.get_video_location("nao-existe"), Err(AppError::NotFound(_))));
+    }
+
+    #[test]
+    fn test_archive_candidates_are_finished_and_stale() {
+        let db = test_db();
+        let now = Utc.with_ymd_and_hms(2024, 6, 1, 12, 0, 0).unwrap();
+        // (curso, dias desde o acesso, vídeos concluídos de 3)
+        let courses = [
+            ("concluido-antigo", Some(200), 3),
+            ("concluido-recente", Some(10), 3),
+            ("em-andamento-antigo", Some(200), 2),
+            ("concluido-nunca-aberto", None, 3),
+        ];
+        for (course_id, days_ago, completed) in courses {
+            db.insert_course(&Course {
+                id: course_id.to_string(),
+                name: course_id.to_string(),
+                path: format!("/cursos/{}", course_id),
+                created_at: now - chrono::Duration::days(120),
+                last_accessed: days_ago.map(|days| now - chrono::Duration::days(days)),
+                cover_path: None,
+                display_name: None,
+                description: None,
+            }).unwrap();
+            let module_id = format!("{}-modulo", course_id);
+            seed_module(&db, course_id, &module_id, 0);
+            for index in 0..3 {
+                let video_id = format!("{}-{}", course_id, index);
+                seed_video(&db, course_id, &module_id, &video_id, index);
+                if index < completed {
+                    db.mark_video_completed(&video_id, true).unwrap();
+                }
+            }
+        }
+
+        let candidates: Vec<(String, f64, i64)> = db.get_archive_candidates_at(now, 90).unwrap()
+            .into_iter()
+            .map(|c| (c.course.id, c.percent, c.days_since_access))
+            .collect();
+        assert_eq!(candidates, vec![
+            ("concluido-antigo".to_string(), 100.0, 200),
+            ("concluido-nunca-aberto".to_string(), 100.0, 120),
+        ]);
+
+        let ids: Vec<String> = db.get_archive_candidates_at(now, 150).unwrap().into_iter().map(|c| c.course.id).collect();
+        assert_eq!(ids, vec!["concluido-antigo"]);
+    }
+
+    #[test]
+    fn test_home_courses_rank_recent_unfinished_first() {
+        let db = test_db();
+        let now = Utc.with_ymd_and_hms(2024, 6, 1, 12, 0, 0).unwrap();
+        // (curso, dias desde o acesso, vídeos concluídos de 10)
+        let courses = [
+            ("em-andamento", Some(1), 5),
+            ("concluido", Some(1), 10),
+            ("esquecido", Some(30), 2),
+            ("novo", Some(3), 0),
+            ("nunca-aberto", None, 0),
+        ];
+        for (course_id, days_ago, completed) in courses {
+            db.insert_course(&Course {
+                id: course_id.to_string(),
+                name: course_id.to_string(),
+                path: format!("/cursos/{}", course_id),
+                created_at: now,
+                last_accessed: days_ago.map(|days| now - chrono::Duration::days(days)),
+                cover_path: None,
+                display_name: None,
+                description: None,
+            }).unwrap();
+            let module_id = format!("{}-modulo", course_id);
+            seed_module(&db, course_id, &module_id, 0);
+            for index in 0..10 {
+                let video_id = format!("{}-{}", course_id, index);
+                seed_video(&db, course_id, &module_id, &video_id, index);
+                if index < completed {
+                    db.mark_video_completed(&video_id, true).unwrap();
+                }
+            }
+        }
+
+        let ranking = |limit: usize| -> Vec<String> {
+            db.get_home_courses_at(now, limit, 7.0).unwrap().into_iter().map(|c| c.course.id).collect()
+        };
+        assert_eq!(ranking(10), vec!["em-andamento", "novo", "concluido", "esquecido", "nunca-aberto"]);
+        assert_eq!(ranking(2), vec!["em-andamento", "novo"]);
+
+        // Meia-vida longa: o curso antigo pouco concluído passa o concluído recente
+        let long: Vec<String> = db.get_home_courses_at(now, 10, 365.0).unwrap().into_iter().map(|c| c.course.id).collect();
+        assert_eq!(long, vec!["em-andamento", "esquecido", "novo", "concluido", "nunca-aberto"]);
+    }
+
+    #[test]
+    fn test_clone_missing_note_is_not_found() {
+        let db = test_db();
+        assert!(matches!(db.clone_note("inexistente"), Err(AppError::NotFound(_))));
+    }
+}
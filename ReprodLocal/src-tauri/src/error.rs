@@ -0,0 +1,38 @@
+use std::fmt;
+
+// Erros tipados da aplicação, convertidos em String na fronteira dos comandos
+#[derive(Debug)]
+pub enum AppError {
+    Database(rusqlite::Error),
+    NotFound(String),
+    InvalidInput(String),
+    ToolNotFound(String),
+    ToolFailed(String),
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AppError::Database(e) => write!(f, "Erro de banco de dados: {}", e),
+            AppError::NotFound(message) => write!(f, "{}", message),
+            AppError::InvalidInput(message) => write!(f, "{}", message),
+            AppError::ToolNotFound(message) => write!(f, "{}", message),
+            AppError::ToolFailed(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl std::error::Error for AppError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            AppError::Database(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<rusqlite::Error> for AppError {
+    fn from(e: rusqlite::Error) -> Self {
+        AppError::Database(e)
+    }
+}
@@ -1,400 +1,1937 @@
+use std::cell::RefCell;
+use std::collections::HashSet;
 use std::path::{Path, PathBuf};
+use std::sync::{mpsc, Mutex};
 use walkdir::WalkDir;
 use uuid::Uuid;
-use chrono::Utc;
+use chrono::{DateTime, Utc};
 use anyhow::{Result, anyhow};
+use serde::Serialize;
 use crate::db::{Course, Module, Video, Database};
+use crate::error::AppError;
+use crate::media;
+use crate::metadata::{find_course_metadata, find_video_metadata};
 
 const VIDEO_EXTENSIONS: &[&str] = &[
     "mp4", "mkv", "avi", "ts", "mov", "wmv", "flv", "webm", "m4v", "3gp", "ogv"
 ];
 
+const IMAGE_EXTENSIONS: &[&str] = &["jpg", "jpeg", "png", "webp", "bmp", "gif"];
+
+// Nomes convencionais de capa, em ordem de preferência
+const COVER_NAMES: &[&str] = &["cover", "folder", "poster"];
+
+// Módulo único dos cursos criados a partir de vídeos soltos numa pasta raiz
+const ROOT_MODULE_NAME: &str = "Vídeos";
+
+// Máximo de cursos lidos do disco ao mesmo tempo por `rescan_courses`
+const SCAN_WORKERS: usize = 4;
+
+// Ids de módulos e vídeos encontrados no disco durante um escaneamento
+#[derive(Default)]
+struct ScannedEntries {
+    module_ids: HashSet<String>,
+    video_ids: HashSet<String>,
+}
+
+// Curso encontrado por `detect_courses`, ainda sem nada gravado
+struct CourseCandidate {
+    name: String,
+    path: PathBuf,
+    root_only: bool, // Apenas vídeos soltos, sem subpastas
+}
+
+#[derive(Default)]
+struct DetectedCourses {
+    courses: Vec<CourseCandidate>,
+    directories_found: usize,
+    files_found: usize,
+    root_videos: usize,
+}
+
+// Diretório com vídeos de um curso, já com os vídeos ordenados por nome
+struct DetectedModule {
+    name: String,
+    path: PathBuf,
+    parent_path: Option<PathBuf>, // Pasta do módulo pai; None no primeiro nível
+    videos: Vec<DetectedVideo>,
+}
+
+struct DetectedVideo {
+    path: PathBuf,
+    folder_title: Option<String>, // Pasta de um vídeo só achatada no módulo pai
+}
+
+impl DetectedVideo {
+    // Vídeos achatados são ordenados pelo nome da pasta de origem
+    fn sort_key(&self) -> String {
+        self.folder_title.clone().unwrap_or_else(|| {
+            self.path.file_name().unwrap_or_default().to_string_lossy().to_string()
+        })
+    }
+}
+
+impl DetectedModule {
+    fn new(name: String, path: &Path, parent_path: Option<&Path>, mut videos: Vec<DetectedVideo>) -> Self {
+        videos.sort_by_key(|video| video.sort_key());
+        Self { name, path: path.to_path_buf(), parent_path: parent_path.map(Path::to_path_buf), videos }
+    }
+}
+
+// Resultado de `preview_scan`: o que um escaneamento criaria, sem gravar nada
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct ScanPreview {
+    pub base_path: String,
+    pub courses: Vec<CoursePreview>,
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct CoursePreview {
+    pub name: String,
+    pub path: String,
+    pub cover_path: Option<String>,
+    pub already_imported: bool, // Já existe curso cadastrado neste caminho
+    pub modules: Vec<ModulePreview>,
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct ModulePreview {
+    pub name: String,
+    pub path: String,
+    pub parent_path: Option<String>,
+    pub order_index: i32,
+    pub videos: Vec<VideoPreview>,
+}
+
+// Resultado de `scan_directory_with_report`: os cursos e as entradas que não
+// puderam ser lidas (permissão negada, links quebrados...) e foram puladas
+#[derive(Debug, Serialize, Clone)]
+pub struct ScanReport {
+    pub courses: Vec<Course>,
+    pub skipped_paths: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct VideoPreview {
+    pub name: String,
+    pub description: Option<String>,
+    pub path: String,
+    pub order_index: i32,
+}
+
 pub struct FileSystemScanner<'a> {
     db: &'a Database,
+    walker: DiskWalker,
 }
 
 impl<'a> FileSystemScanner<'a> {
     pub fn new(db: &'a Database) -> Self {
-        Self { db }
+        let custom = match db.get_user_setting("custom_video_extensions") {
+            Ok(setting) => setting.map(|s| s.setting_value).unwrap_or_default(),
+            Err(e) => {
+                log::warn!("⚠️ Erro ao ler extensões de vídeo personalizadas: {}", e);
+                String::new()
+            }
+        };
+        let scan_hidden_files = match db.get_user_setting("scan_hidden_files") {
+            Ok(setting) => setting.is_some_and(|s| s.setting_value.trim() == "true"),
+            Err(e) => {
+                log::warn!("⚠️ Erro ao ler configuração de arquivos ocultos: {}", e);
+                false
+            }
+        };
+        Self {
+            db,
+            walker: DiskWalker {
+                video_extensions: video_extensions_with(&custom),
+                skipped_paths: RefCell::new(Vec::new()),
+                scan_hidden_files,
+            },
+        }
+    }
+
+    // Arquivos e pastas começando com `.` (`.AppleDouble`, `._video.mp4`...)
+    // ficam de fora, a menos que `scan_hidden_files` esteja ativa
+    pub fn is_ignored(&self, path: &Path) -> bool {
+        self.walker.is_ignored(path)
+    }
+
+    pub fn is_video_file(&self, path: &Path) -> bool {
+        self.walker.is_video_file(path)
     }
 
     pub fn scan_directory(&self, base_path: &Path) -> Result<Vec<Course>> {
-        if !base_path.exists() {
-            return Err(anyhow!("Diretório não existe: {}", base_path.display()));
-        }
+        Ok(self.scan_directory_with_report(base_path)?.courses)
+    }
 
-        println!("🔍 Escaneando diretório: {}", base_path.display());
+    // Como `scan_directory`, mas também lista as entradas puladas por erro de leitura
+    pub fn scan_directory_with_report(&self, base_path: &Path) -> Result<ScanReport> {
+        self.walker.skipped_paths.borrow_mut().clear();
+        let detected = self.detect_courses(base_path)?;
         let mut courses = Vec::new();
-        let mut directories_found = 0;
-        let mut files_found = 0;
-        let mut root_videos = Vec::new();
-        
-        // Procura por diretórios que contenham vídeos (cursos)
-        for entry in std::fs::read_dir(base_path)? {
-            let entry = entry?;
-            let path = entry.path();
-            
-            if path.is_dir() {
-                directories_found += 1;
-                println!("📁 Diretório encontrado: {}", path.display());
-                
-                match self.scan_course_directory(&path) {
-                    Ok(course) => {
-                        println!("✅ Curso criado: {} (ID: {})", course.name, course.id);
-                        courses.push(course);
-                    }
-                    Err(e) => {
-                        println!("❌ Erro ao escanear diretório {}: {}", path.display(), e);
-                        println!("🔍 Detalhes do erro: {:?}", e);
-                        // Continua para o próximo diretório em vez de parar
-                    }
-                }
-            } else {
-                files_found += 1;
-                println!("📄 Arquivo encontrado: {}", path.display());
-                if self.is_video_file(&path) {
-                    println!("🎬 Arquivo de vídeo detectado na raiz: {}", path.display());
-                    root_videos.push(path);
-                }
-            }
-        }
 
-        // Se encontramos vídeos na pasta raiz, criar um curso para eles
-        if !root_videos.is_empty() {
-            println!("📹 Criando curso para {} vídeos encontrados na pasta raiz", root_videos.len());
-            let folder_name = base_path
-                .file_name()
-                .and_then(|n| n.to_str())
-                .unwrap_or("Curso")
-                .to_string();
-            
-            match self.create_root_course(base_path, &folder_name) {
+        for candidate in &detected.courses {
+            let result = self.course_for_path(&candidate.path, &candidate.name)
+                .and_then(|course| self.sync_course(course, candidate.root_only));
+
+            match result {
+                Ok(course) if candidate.root_only => {
+                    log::info!("✅ Curso da pasta raiz criado: {} (ID: {})", course.name, course.id);
+                    courses.push(course);
+                }
                 Ok(course) => {
-                    println!("✅ Curso da pasta raiz criado: {} (ID: {})", course.name, course.id);
+                    log::info!("✅ Curso criado: {} (ID: {})", course.name, course.id);
                     courses.push(course);
                 }
                 Err(e) => {
-                    println!("❌ Erro ao criar curso da pasta raiz: {}", e);
+                    log::error!("❌ Erro ao escanear diretório {}: {}", candidate.path.display(), e);
+                    log::debug!("🔍 Detalhes do erro: {:?}", e);
+                    // Continua para o próximo diretório em vez de parar
                 }
             }
         }
 
-        println!("📊 Resumo do escaneamento:");
-        println!("   - Diretórios encontrados: {}", directories_found);
-        println!("   - Arquivos encontrados: {}", files_found);
-        println!("   - Vídeos na raiz: {}", root_videos.len());
-        println!("   - Cursos criados: {}", courses.len());
+        log::info!(
+            "📊 Resumo do escaneamento de {}: {} diretórios, {} arquivos, {} vídeos na raiz, {} cursos",
+            base_path.display(), detected.directories_found, detected.files_found, detected.root_videos, courses.len()
+        );
 
-        Ok(courses)
+        let skipped_paths: Vec<String> = self.walker.skipped_paths.take()
+            .into_iter()
+            .map(|path| path.to_string_lossy().to_string())
+            .collect();
+        if !skipped_paths.is_empty() {
+            log::warn!("⚠️ {} entradas ilegíveis foram puladas em {}", skipped_paths.len(), base_path.display());
+        }
+
+        Ok(ScanReport { courses, skipped_paths })
     }
 
-    fn scan_course_directory(&self, course_path: &Path) -> Result<Course> {
-        let course_name = course_path
-            .file_name()
-            .and_then(|n| n.to_str())
-            .unwrap_or("Curso Sem Nome")
-            .to_string();
-
-        let course_id = Uuid::new_v4().to_string();
-        let course = Course {
-            id: course_id.clone(),
-            name: course_name,
-            path: course_path.to_string_lossy().to_string(),
-            created_at: Utc::now(),
-            last_accessed: None,
-        };
+    // Mostra o que `scan_directory` criaria, sem gravar nada no banco
+    pub fn preview_scan(&self, base_path: &Path) -> Result<ScanPreview> {
+        let detected = self.detect_courses(base_path)?;
+        let flatten = self.flatten_single_video_dirs()?;
+        let mut courses = Vec::new();
 
-        // Salva o curso no banco
-        self.db.insert_course(&course)?;
+        for candidate in detected.courses {
+            let path = candidate.path.to_string_lossy().to_string();
+            let mut modules = Vec::new();
 
-        // Escaneia módulos e vídeos
-        self.scan_course_content(&course_id, course_path)?;
+            self.walker.visit_course_modules(&candidate.path, candidate.root_only, flatten, |module_order, module| {
+                // Como em `get_course_modules`, pastas que só agrupam submódulos ficam de fora
+                if module.videos.is_empty() {
+                    return Ok(());
+                }
+                modules.push(ModulePreview {
+                    name: module.name,
+                    path: module.path.to_string_lossy().to_string(),
+                    parent_path: module.parent_path.map(|p| p.to_string_lossy().to_string()),
+                    order_index: module_order,
+                    videos: module.videos.iter().enumerate().map(|(video_order, video)| {
+                        let (name, description) = video_details(&video.path, video.folder_title.as_deref());
+                        VideoPreview {
+                            name,
+                            description,
+                            path: video.path.to_string_lossy().to_string(),
+                            order_index: video_order as i32,
+                        }
+                    }).collect(),
+                });
+                Ok(())
+            })?;
 
-        Ok(course)
+            courses.push(CoursePreview {
+                already_imported: self.db.get_course_by_path(&path)?.is_some(),
+                name: candidate.name,
+                cover_path: find_cover_image(&candidate.path).map(|p| p.to_string_lossy().to_string()),
+                path,
+                modules,
+            });
+        }
+
+        Ok(ScanPreview {
+            base_path: base_path.to_string_lossy().to_string(),
+            courses,
+        })
     }
 
-    fn create_root_course(&self, course_path: &Path, course_name: &str) -> Result<Course> {
-        let course_id = Uuid::new_v4().to_string();
-        let course = Course {
-            id: course_id.clone(),
-            name: course_name.to_string(),
-            path: course_path.to_string_lossy().to_string(),
-            created_at: Utc::now(),
-            last_accessed: None,
-        };
+    // Lista as pastas de curso dentro de `base_path`. Os cursos ficam no nível
+    // definido por `course_detection_depth` (1 = cada subdiretório é um curso) e
+    // os vídeos soltos nos níveis acima formam cursos à parte
+    fn detect_courses(&self, base_path: &Path) -> Result<DetectedCourses> {
+        if !base_path.exists() {
+            return Err(anyhow!("Diretório não existe: {}", base_path.display()));
+        }
+
+        // Um arquivo de vídeo selecionado vira o curso da pasta onde ele está
+        if base_path.is_file() {
+            let Some(dir) = base_path.parent().filter(|_| self.is_video_file(base_path)) else {
+                return Err(AppError::InvalidInput(format!(
+                    "Selecione uma pasta ou um arquivo de vídeo: {}", base_path.display()
+                )).into());
+            };
+            log::info!("🎬 Arquivo de vídeo selecionado, escaneando a pasta {}", dir.display());
+            let mut detected = DetectedCourses { files_found: 1, root_videos: 1, ..Default::default() };
+            detected.courses.push(CourseCandidate {
+                name: course_name(dir, "Curso"),
+                path: dir.to_path_buf(),
+                root_only: true,
+            });
+            return Ok(detected);
+        }
+
+        let depth = self.course_detection_depth()?;
+        log::info!("🔍 Escaneando diretório: {} (cursos no nível {})", base_path.display(), depth);
+        let mut detected = DetectedCourses::default();
+        self.walker.detect_courses_in(base_path, depth, &mut detected)?;
+        Ok(detected)
+    }
+
+    // Configuração `course_detection_depth`; valores inválidos voltam ao padrão 1
+    fn course_detection_depth(&self) -> Result<usize> {
+        Ok(self.db.get_user_setting("course_detection_depth")?
+            .and_then(|setting| setting.setting_value.trim().parse::<usize>().ok())
+            .filter(|depth| *depth >= 1)
+            .unwrap_or(1))
+    }
+
+    // Reaproveita o curso já cadastrado no mesmo caminho (id, criação e último
+    // acesso), para que progresso e anotações sobrevivam a um novo escaneamento
+    fn course_for_path(&self, course_path: &Path, course_name: &str) -> Result<Course> {
+        let path = course_path.to_string_lossy().to_string();
+        let cover_path = find_cover_image(course_path).map(|p| p.to_string_lossy().to_string());
+
+        Ok(match self.db.get_course_by_path(&path)? {
+            Some(existing) => Course {
+                name: course_name.to_string(),
+                cover_path,
+                ..existing
+            },
+            None => Course {
+                id: Uuid::new_v4().to_string(),
+                name: course_name.to_string(),
+                path,
+                created_at: Utc::now(),
+                last_accessed: None,
+                cover_path,
+                display_name: None,
+                description: None,
+            },
+        })
+    }
+
+    // Reescaneia apenas a pasta de um curso já cadastrado: adiciona vídeos novos
+    // mantendo os ids existentes e remove os que não estão mais no disco
+    pub fn rescan_course(&self, course_id: &str, mark_accessed: bool) -> Result<Course> {
+        let mut course = self.db.get_course_by_id(course_id)?
+            .ok_or_else(|| anyhow!("Curso não encontrado: {}", course_id))?;
+
+        let course_path = PathBuf::from(&course.path);
+        if !course_path.is_dir() {
+            return Err(anyhow!("Diretório do curso não existe: {}", course.path));
+        }
+
+        // Cursos criados a partir de vídeos soltos numa pasta raiz não incluem subpastas
+        let root_only = self.db.get_course_modules(course_id)?
+            .iter()
+            .any(|m| m.name == ROOT_MODULE_NAME && Path::new(&m.path) == course_path);
+
+        course.name = course_name(&course_path, "Curso Sem Nome");
+        course.cover_path = find_cover_image(&course_path).map(|p| p.to_string_lossy().to_string());
+        if mark_accessed {
+            course.last_accessed = Some(Utc::now());
+        }
+
+        self.sync_course(course, root_only)
+    }
+
+    // Grava o curso e seu conteúdo numa única transação e remove as entradas
+    // de arquivos que sumiram
+    fn sync_course(&self, course: Course, root_only: bool) -> Result<Course> {
+        let course_path = PathBuf::from(&course.path);
+        let flatten = self.flatten_single_video_dirs()?;
+        self.write_course(course, |visit| self.walker.visit_course_modules(&course_path, root_only, flatten, visit))
+    }
+
+    // Como `sync_course`, mas com os módulos entregues por `modules`, que pode
+    // lê-los do disco ou de uma lista já pronta
+    fn write_course<M>(&self, course: Course, modules: M) -> Result<Course>
+    where
+        M: FnOnce(&mut dyn FnMut(i32, DetectedModule) -> Result<()>) -> Result<()>,
+    {
+        let tx = self.db.transaction()?;
 
-        // Salva o curso no banco
         self.db.insert_course(&course)?;
 
-        // Escaneia vídeos diretamente na pasta raiz
-        self.scan_root_videos(&course_id, course_path)?;
+        let mut scanned = ScannedEntries::default();
+        modules(&mut |module_order, module| {
+            self.insert_module_videos(&course.id, module_order, module, &mut scanned)
+        })?;
 
+        let removed = self.db.remove_missing_course_entries(&course.id, &scanned.video_ids, &scanned.module_ids)?;
+        if removed > 0 {
+            log::info!("🗑️ {} vídeos removidos do curso {}", removed, course.name);
+        }
+
+        tx.commit()?;
         Ok(course)
     }
 
-    fn scan_root_videos(&self, course_id: &str, course_path: &Path) -> Result<()> {
-        println!("🎬 Escaneando vídeos na pasta raiz: {}", course_path.display());
-        
-        let mut videos_found = 0;
-        let mut files_scanned = 0;
+    // Configuração `flatten_single_video_dirs`: pastas com um único vídeo (e sem
+    // subpastas) entram no módulo da pasta pai, com o nome da pasta como título
+    fn flatten_single_video_dirs(&self) -> Result<bool> {
+        Ok(self.db.get_user_setting("flatten_single_video_dirs")?
+            .is_some_and(|setting| setting.setting_value.trim() == "true"))
+    }
 
-        // Cria um módulo padrão para os vídeos da raiz
-        let module_id = Uuid::new_v4().to_string();
+    // Grava um módulo detectado e seus vídeos, reaproveitando os ids já
+    // cadastrados para o mesmo caminho
+    fn insert_module_videos(
+        &self,
+        course_id: &str,
+        module_order: i32,
+        detected: DetectedModule,
+        scanned: &mut ScannedEntries,
+    ) -> Result<()> {
+        let module_path = detected.path.to_string_lossy().to_string();
+        let module_id = self.db.get_module_by_path(course_id, &module_path)?
+            .map(|m| m.id)
+            .unwrap_or_else(|| Uuid::new_v4().to_string());
         let module = Module {
             id: module_id.clone(),
             course_id: course_id.to_string(),
-            name: "Vídeos".to_string(),
-            path: course_path.to_string_lossy().to_string(),
-            order_index: 0,
+            name: detected.name,
+            path: module_path,
+            order_index: module_order,
         };
-        self.db.insert_module(&module)?;
 
-        for entry in std::fs::read_dir(course_path)? {
-            let entry = entry?;
-            let path = entry.path();
-            
-            if path.is_file() {
-                files_scanned += 1;
-                println!("📄 Arquivo encontrado: {}", path.display());
-                
-                if self.is_video_file(&path) {
-                    videos_found += 1;
-                    println!("🎥 Vídeo detectado: {}", path.display());
-                    
-                    let video_name = path
-                        .file_stem()
-                        .and_then(|n| n.to_str())
-                        .unwrap_or("Vídeo")
-                        .to_string();
-
-                    let video = Video {
-                        id: Uuid::new_v4().to_string(),
-                        module_id: module_id.clone(),
-                        course_id: course_id.to_string(),
-                        name: video_name,
-                        path: path.to_string_lossy().to_string(),
-                        duration: None,
-                        order_index: videos_found as i32 - 1,
-                    };
-
-                    self.db.insert_video(&video)?;
-                }
+        log::debug!("🔧 Tentando inserir módulo: {} (course_id: {})", module.name, module.course_id);
+        match self.db.insert_module(&module) {
+            Ok(_) => log::debug!("✅ Módulo inserido com sucesso: {}", module.name),
+            Err(e) => {
+                log::error!("❌ Erro ao inserir módulo {}: {}", module.name, e);
+                log::debug!("🔍 Detalhes do módulo: {:?}", module);
+                return Err(e.into());
             }
         }
+        scanned.module_ids.insert(module_id.clone());
 
-        println!("📊 Escaneamento de vídeos da raiz concluído:");
-        println!("   - Arquivos escaneados: {}", files_scanned);
-        println!("   - Vídeos encontrados: {}", videos_found);
-
-        Ok(())
-    }
-
-    fn scan_course_content(&self, course_id: &str, course_path: &Path) -> Result<()> {
-        println!("🎬 Escaneando conteúdo do curso: {}", course_path.display());
-        let mut videos_found: Vec<PathBuf> = Vec::new();
-        let _modules_found: Vec<PathBuf> = Vec::new();
-        let mut files_scanned = 0;
-
-        // Coleta todos os vídeos recursivamente
-        for entry in WalkDir::new(course_path)
-            .follow_links(false)
-            .into_iter()
-            .filter_map(|e| e.ok())
-        {
-            let path = entry.path();
-            files_scanned += 1;
-            
-            if path.is_file() {
-                println!("📄 Arquivo encontrado: {}", path.display());
-                if self.is_video_file(path) {
-                    println!("🎥 Vídeo detectado: {}", path.display());
-                    videos_found.push(path.to_path_buf());
-                } else {
-                    println!("❌ Não é vídeo: {}", path.display());
-                }
-            }
-        }
+        let parent_id = match &detected.parent_path {
+            Some(parent_path) => self.db.get_module_by_path(course_id, &parent_path.to_string_lossy())?.map(|m| m.id),
+            None => None,
+        };
+        self.db.set_module_parent(&module_id, parent_id.as_deref())?;
 
-        println!("📊 Escaneamento do curso concluído:");
-        println!("   - Arquivos escaneados: {}", files_scanned);
-        println!("   - Vídeos encontrados: {}", videos_found.len());
+        // Adiciona vídeos do módulo
+        for (video_order, detected_video) in detected.videos.iter().enumerate() {
+            let path = detected_video.path.to_string_lossy().to_string();
+            let video_id = self.db.get_video_by_path(&path)?
+                .map(|v| v.id)
+                .unwrap_or_else(|| Uuid::new_v4().to_string());
+            let (name, description) = video_details(&detected_video.path, detected_video.folder_title.as_deref());
+            let video = Video {
+                id: video_id.clone(),
+                module_id: module_id.clone(),
+                course_id: course_id.to_string(),
+                name,
+                path,
+                duration: None, // Será preenchido quando o vídeo for reproduzido
+                order_index: video_order as i32,
+            };
 
-        if videos_found.is_empty() {
-            println!("⚠️ Nenhum vídeo encontrado no curso: {}", course_path.display());
-            return Ok(());
+            self.db.insert_video(&video)?;
+            self.db.set_video_description(&video_id, description.as_deref())?;
+            let file_size = std::fs::metadata(&detected_video.path).ok().map(|m| m.len());
+            self.db.set_video_file_size(&video_id, file_size)?;
+            scanned.video_ids.insert(video_id);
         }
 
-        // Organiza vídeos por diretório (módulos)
-        let mut modules_map: std::collections::HashMap<PathBuf, Vec<PathBuf>> = 
-            std::collections::HashMap::new();
+        Ok(())
+    }
 
-        for video_path in videos_found {
-            let parent_dir = video_path.parent().unwrap_or(course_path);
-            modules_map.entry(parent_dir.to_path_buf())
-                .or_insert_with(Vec::new)
-                .push(video_path);
+    // Escaneia várias pastas base. A leitura do disco roda em até
+    // `SCAN_WORKERS` threads, um curso por vez em cada uma, e as gravações ficam
+    // nesta thread, numa transação por curso. Os cursos voltam ordenados por nome
+    pub fn rescan_courses(&self, base_paths: &[PathBuf]) -> Result<Vec<Course>> {
+        self.walker.skipped_paths.borrow_mut().clear();
+        let mut candidates = Vec::new();
+        for base_path in base_paths {
+            candidates.extend(self.detect_courses(base_path)?.courses);
         }
+        let flatten = self.flatten_single_video_dirs()?;
 
-        // Cria módulos e vídeos
-        let mut module_order = 0;
-        for (module_path, mut videos) in modules_map {
-            // Ordena vídeos por nome
-            videos.sort_by(|a, b| {
-                let a_name = a.file_name().unwrap_or_default();
-                let b_name = b.file_name().unwrap_or_default();
-                a_name.cmp(b_name)
-            });
+        let total = candidates.len();
+        let queue = Mutex::new(candidates.into_iter());
+        // Canal limitado: quem termina de ler espera a gravação em vez de acumular cursos
+        let (tx, rx) = mpsc::sync_channel(SCAN_WORKERS);
+        let mut courses = Vec::new();
 
-            let module_name = if module_path == course_path {
-                "Aulas".to_string()
-            } else {
-                module_path
-                    .file_name()
-                    .and_then(|n| n.to_str())
-                    .unwrap_or("Módulo")
-                    .to_string()
-            };
+        std::thread::scope(|scope| {
+            for _ in 0..SCAN_WORKERS.min(total) {
+                let tx = tx.clone();
+                let queue = &queue;
+                let walker = self.walker.detached();
+                scope.spawn(move || {
+                    while let Some(candidate) = queue.lock().ok().and_then(|mut q| q.next()) {
+                        // O curso inteiro fica em memória até ser gravado
+                        let mut modules = Vec::new();
+                        let result = walker.visit_course_modules(&candidate.path, candidate.root_only, flatten, |module_order, module| {
+                            modules.push((module_order, module));
+                            Ok(())
+                        });
+                        if tx.send((candidate, result.map(|_| modules), walker.skipped_paths.take())).is_err() {
+                            break;
+                        }
+                    }
+                });
+            }
+            drop(tx);
 
-            let module_id = Uuid::new_v4().to_string();
-            let module = Module {
-                id: module_id.clone(),
-                course_id: course_id.to_string(),
-                name: module_name,
-                path: module_path.to_string_lossy().to_string(),
-                order_index: module_order,
-            };
+            for (candidate, modules, skipped) in rx {
+                self.walker.skipped_paths.borrow_mut().extend(skipped);
+                let result = modules.and_then(|modules| {
+                    let course = self.course_for_path(&candidate.path, &candidate.name)?;
+                    self.write_course(course, |visit| {
+                        modules.into_iter().try_for_each(|(module_order, module)| visit(module_order, module))
+                    })
+                });
 
-            println!("🔧 Tentando inserir módulo: {} (course_id: {})", module.name, module.course_id);
-            match self.db.insert_module(&module) {
-                Ok(_) => println!("✅ Módulo inserido com sucesso: {}", module.name),
-                Err(e) => {
-                    println!("❌ Erro ao inserir módulo {}: {}", module.name, e);
-                    println!("🔍 Detalhes do módulo: {:?}", module);
-                    return Err(e.into());
+                match result {
+                    Ok(course) => {
+                        log::info!("✅ Curso criado: {} (ID: {})", course.name, course.id);
+                        courses.push(course);
+                    }
+                    Err(e) => {
+                        log::error!("❌ Erro ao escanear diretório {}: {}", candidate.path.display(), e);
+                        log::debug!("🔍 Detalhes do erro: {:?}", e);
+                    }
                 }
             }
-            module_order += 1;
+        });
 
-            // Adiciona vídeos do módulo
-            for (video_order, video_path) in videos.iter().enumerate() {
-                let video_name = video_path
-                    .file_stem()
-                    .and_then(|n| n.to_str())
-                    .unwrap_or("Vídeo")
-                    .to_string();
-
-                let video_id = Uuid::new_v4().to_string();
-                let video = Video {
-                    id: video_id,
-                    module_id: module_id.clone(),
-                    course_id: course_id.to_string(),
-                    name: video_name,
-                    path: video_path.to_string_lossy().to_string(),
-                    duration: None, // Será preenchido quando o vídeo for reproduzido
-                    order_index: video_order as i32,
-                };
+        let skipped = self.walker.skipped_paths.take().len();
+        if skipped > 0 {
+            log::warn!("⚠️ {} entradas ilegíveis foram puladas", skipped);
+        }
+        log::info!("📊 {} cursos escaneados em {} pastas", courses.len(), base_paths.len());
 
-                self.db.insert_video(&video)?;
-            }
+        courses.sort_by(|a, b| a.name.cmp(&b.name).then_with(|| a.path.cmp(&b.path)));
+        Ok(courses)
+    }
+}
+
+// Leitura do disco durante o escaneamento, sem acesso ao banco, para poder
+// rodar nas threads de `rescan_courses`
+struct DiskWalker {
+    video_extensions: Vec<String>, // Padrões mais os da configuração `custom_video_extensions`
+    skipped_paths: RefCell<Vec<PathBuf>>, // Entradas ilegíveis desde o último relatório
+    scan_hidden_files: bool, // Configuração `scan_hidden_files`
+}
+
+impl DiskWalker {
+    // Cópia para outra thread, com a própria lista de entradas puladas
+    fn detached(&self) -> Self {
+        Self {
+            video_extensions: self.video_extensions.clone(),
+            skipped_paths: RefCell::new(Vec::new()),
+            scan_hidden_files: self.scan_hidden_files,
         }
+    }
 
-        Ok(())
+    fn is_ignored(&self, path: &Path) -> bool {
+        !self.scan_hidden_files
+            && path.file_name().is_some_and(|name| name.to_string_lossy().starts_with('.'))
     }
 
-    pub fn is_video_file(&self, path: &Path) -> bool {
+    fn is_video_file(&self, path: &Path) -> bool {
         if let Some(extension) = path.extension() {
             if let Some(ext_str) = extension.to_str() {
                 let ext_lower = ext_str.to_lowercase();
-                let is_video = VIDEO_EXTENSIONS.contains(&ext_lower.as_str());
-                println!("🔍 Verificando arquivo: {} | Extensão: {} | É vídeo: {}", 
+                let is_video = self.video_extensions.contains(&ext_lower);
+                log::trace!("🔍 Verificando arquivo: {} | Extensão: {} | É vídeo: {}", 
                     path.display(), ext_lower, is_video);
                 return is_video;
             } else {
-                println!("⚠️ Não foi possível converter extensão para string: {}", path.display());
+                log::warn!("⚠️ Não foi possível converter extensão para string: {}", path.display());
             }
         } else {
-            println!("⚠️ Arquivo sem extensão: {}", path.display());
+            log::trace!("⚠️ Arquivo sem extensão: {}", path.display());
         }
         false
     }
 
-    pub fn rescan_courses(&self, base_paths: &[PathBuf]) -> Result<Vec<Course>> {
-        let mut all_courses = Vec::new();
-        
-        for base_path in base_paths {
-            let courses = self.scan_directory(base_path)?;
-            all_courses.extend(courses);
+    // Metadados da entrada; se não der para lê-los, registra o caminho como pulado
+    fn readable_metadata(&self, path: &Path) -> Option<std::fs::Metadata> {
+        match std::fs::metadata(path) {
+            Ok(metadata) => Some(metadata),
+            Err(e) => {
+                log::warn!("⚠️ Entrada ilegível ignorada {}: {}", path.display(), e);
+                self.skipped_paths.borrow_mut().push(path.to_path_buf());
+                None
+            }
         }
+    }
 
-        Ok(all_courses)
+    // Entrada de `read_dir` com erro: não há caminho, só o diretório onde ocorreu
+    fn skip_unreadable_entry(&self, dir: &Path, error: std::io::Error) {
+        log::warn!("⚠️ Entrada ilegível ignorada em {}: {}", dir.display(), error);
+        self.skipped_paths.borrow_mut().push(dir.to_path_buf());
     }
 
-}
+    fn detect_courses_in(&self, dir: &Path, depth: usize, detected: &mut DetectedCourses) -> Result<()> {
+        let mut loose_videos = 0;
 
-pub fn get_default_course_directories() -> Vec<PathBuf> {
-    let mut dirs = Vec::new();
-    
-    // Pasta base especificada pelo usuário
-    let main_course_dir = PathBuf::from("C:\\MeusCursos");
-    if main_course_dir.exists() {
-        dirs.push(main_course_dir);
-    }
-    
-    // Diretórios comuns onde usuários podem ter cursos
-    if let Some(home) = dirs::home_dir() {
-        dirs.push(home.join("Cursos"));
-        dirs.push(home.join("Videos").join("Cursos"));
-        dirs.push(home.join("Documents").join("Cursos"));
-        dirs.push(home.join("Downloads"));
+        // Procura por diretórios que contenham vídeos (cursos)
+        for entry in std::fs::read_dir(dir)? {
+            let entry = match entry {
+                Ok(entry) => entry,
+                Err(e) => {
+                    self.skip_unreadable_entry(dir, e);
+                    continue;
+                }
+            };
+            let path = entry.path();
+            if self.is_ignored(&path) {
+                continue;
+            }
+            let Some(metadata) = self.readable_metadata(&path) else {
+                continue;
+            };
+
+            if metadata.is_dir() {
+                detected.directories_found += 1;
+                log::debug!("📁 Diretório encontrado: {}", path.display());
+                if depth > 1 {
+                    if let Err(e) = self.detect_courses_in(&path, depth - 1, detected) {
+                        log::error!("❌ Erro ao escanear diretório {}: {}", path.display(), e);
+                    }
+                } else {
+                    detected.courses.push(CourseCandidate {
+                        name: course_name(&path, "Curso Sem Nome"),
+                        path,
+                        root_only: false,
+                    });
+                }
+            } else {
+                detected.files_found += 1;
+                log::debug!("📄 Arquivo encontrado: {}", path.display());
+                if self.is_video_file(&path) {
+                    log::debug!("🎬 Arquivo de vídeo detectado na raiz: {}", path.display());
+                    loose_videos += 1;
+                }
+            }
+        }
+
+        // Se encontramos vídeos soltos neste nível, criar um curso para eles
+        if loose_videos > 0 {
+            log::info!("📹 Criando curso para {} vídeos encontrados em {}", loose_videos, dir.display());
+            detected.root_videos += loose_videos;
+            detected.courses.push(CourseCandidate {
+                name: course_name(dir, "Curso"),
+                path: dir.to_path_buf(),
+                root_only: true,
+            });
+        }
+
+        Ok(())
     }
 
-    // Adiciona drives comuns no Windows
-    #[cfg(windows)]
+    // Detecta os módulos de um curso e os entrega um a um, em ordem, para `visit`
+    fn visit_course_modules<F>(&self, course_path: &Path, root_only: bool, flatten: bool, visit: F) -> Result<()>
+    where
+        F: FnMut(i32, DetectedModule) -> Result<()>,
     {
-        for drive in ['C', 'D', 'E', 'F'] {
-            let drive_path = PathBuf::from(format!("{}:\\Cursos", drive));
-            if drive_path.exists() {
-                dirs.push(drive_path);
-            }
+        if root_only {
+            self.visit_root_videos(course_path, visit)
+        } else {
+            self.visit_course_content(course_path, flatten, visit)
         }
     }
 
-    dirs.into_iter().filter(|p| p.exists()).collect()
-}
+    fn visit_root_videos<F>(&self, course_path: &Path, mut visit: F) -> Result<()>
+    where
+        F: FnMut(i32, DetectedModule) -> Result<()>,
+    {
+        log::debug!("🎬 Escaneando vídeos na pasta raiz: {}", course_path.display());
+        
+        let mut files_scanned = 0;
+        let mut videos = Vec::new();
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use tempfile::TempDir;
-    use std::fs;
+        for entry in std::fs::read_dir(course_path)? {
+            let entry = match entry {
+                Ok(entry) => entry,
+                Err(e) => {
+                    self.skip_unreadable_entry(course_path, e);
+                    continue;
+                }
+            };
+            let path = entry.path();
+            if self.is_ignored(&path) {
+                continue;
+            }
+            let Some(metadata) = self.readable_metadata(&path) else {
+                continue;
+            };
 
-    #[test]
-    fn test_video_file_detection() {
-        let temp_dir = TempDir::new().unwrap();
-        let db = Database::new(&temp_dir.path().join("test.db")).unwrap();
-        let scanner = FileSystemScanner::new(db);
+            if metadata.is_file() {
+                files_scanned += 1;
+                log::debug!("📄 Arquivo encontrado: {}", path.display());
+                
+                if self.is_video_file(&path) {
+                    log::debug!("🎥 Vídeo detectado: {}", path.display());
+                    videos.push(DetectedVideo { path, folder_title: None });
+                }
+            }
+        }
 
-        assert!(scanner.is_video_file(Path::new("video.mp4")));
-        assert!(scanner.is_video_file(Path::new("movie.mkv")));
-        assert!(!scanner.is_video_file(Path::new("document.txt")));
-        assert!(!scanner.is_video_file(Path::new("image.jpg")));
+        log::debug!(
+            "📊 Escaneamento de vídeos da raiz concluído: {} arquivos escaneados, {} vídeos encontrados",
+            files_scanned, videos.len()
+        );
+
+        // Cria um módulo padrão para os vídeos da raiz
+        visit(0, DetectedModule::new(ROOT_MODULE_NAME.to_string(), course_path, None, videos))
     }
 
-    #[test]
-    fn test_course_scanning() {
-        let temp_dir = TempDir::new().unwrap();
-        let course_dir = temp_dir.path().join("Curso Teste");
+    fn visit_course_content<F>(&self, course_path: &Path, flatten: bool, mut visit: F) -> Result<()>
+    where
+        F: FnMut(i32, DetectedModule) -> Result<()>,
+    {
+        log::debug!("🎬 Escaneando conteúdo do curso: {}", course_path.display());
+        let mut files_scanned = 0;
+        let mut videos_found = 0;
+        let mut module_order = 0;
+        let mut flattened_dirs: HashSet<PathBuf> = HashSet::new();
+        let mut module_dirs: HashSet<PathBuf> = HashSet::new();
+
+        // Percorre os diretórios em ordem alfabética; os vídeos de cada um são
+        // entregues como um módulo e descartados antes de seguir para o próximo,
+        // então a memória fica limitada ao maior diretório e não à árvore inteira
+        for entry in WalkDir::new(course_path)
+            .follow_links(false)
+            .sort_by_file_name()
+            .into_iter()
+            .filter_entry(|e| e.depth() == 0 || !self.is_ignored(e.path()))
+            .filter_map(|e| match e {
+                Ok(entry) => Some(entry),
+                Err(e) => {
+                    let path = e.path().unwrap_or(course_path).to_path_buf();
+                    log::warn!("⚠️ Entrada ilegível ignorada {}: {}", path.display(), e);
+                    self.skipped_paths.borrow_mut().push(path);
+                    None
+                }
+            })
+            .filter(|e| e.file_type().is_dir())
+        {
+            let module_path = entry.path();
+            if flattened_dirs.contains(module_path) {
+                continue;
+            }
+            let dir_entries = match std::fs::read_dir(module_path) {
+                Ok(entries) => entries,
+                Err(e) => {
+                    log::warn!("⚠️ Não foi possível ler o diretório {}: {}", module_path.display(), e);
+                    continue;
+                }
+            };
+
+            let mut videos: Vec<DetectedVideo> = Vec::new();
+            for dir_entry in dir_entries {
+                let dir_entry = match dir_entry {
+                    Ok(dir_entry) => dir_entry,
+                    Err(e) => {
+                        self.skip_unreadable_entry(module_path, e);
+                        continue;
+                    }
+                };
+                let path = dir_entry.path();
+                if self.is_ignored(&path) {
+                    continue;
+                }
+                let Some(metadata) = self.readable_metadata(&path) else {
+                    continue;
+                };
+                if metadata.is_file() {
+                    files_scanned += 1;
+                    log::debug!("📄 Arquivo encontrado: {}", path.display());
+                    if self.is_video_file(&path) {
+                        log::debug!("🎥 Vídeo detectado: {}", path.display());
+                        videos.push(DetectedVideo { path, folder_title: None });
+                    } else {
+                        log::debug!("❌ Não é vídeo: {}", path.display());
+                    }
+                } else if flatten && metadata.is_dir() {
+                    if let Some(video_path) = self.single_video_in(&path) {
+                        log::debug!("📦 Pasta com um único vídeo incorporada ao módulo pai: {}", path.display());
+                        files_scanned += 1;
+                        videos.push(DetectedVideo { path: video_path, folder_title: Some(folder_name(&path, "Vídeo")) });
+                        flattened_dirs.insert(path);
+                    }
+                }
+            }
+
+            if videos.is_empty() {
+                continue;
+            }
+
+            let module_name = if module_path == course_path {
+                "Aulas".to_string()
+            } else {
+                folder_name(module_path, "Módulo")
+            };
+
+            // Pastas intermediárias sem vídeos viram módulos vazios, para que
+            // os submódulos tenham a quem se ligar. O percurso visita cada pasta
+            // antes das de dentro, então os pais já foram entregues
+            let mut missing_parents: Vec<&Path> = module_path.ancestors()
+                .skip(1)
+                .take_while(|dir| *dir != course_path && dir.starts_with(course_path))
+                .filter(|dir| !module_dirs.contains(*dir))
+                .collect();
+            missing_parents.reverse();
+            for dir in missing_parents {
+                log::debug!("📁 Módulo sem vídeos com submódulos: {}", dir.display());
+                visit(module_order, DetectedModule::new(folder_name(dir, "Módulo"), dir, parent_module_dir(dir, course_path), Vec::new()))?;
+                module_dirs.insert(dir.to_path_buf());
+                module_order += 1;
+            }
+
+            videos_found += videos.len();
+            visit(module_order, DetectedModule::new(module_name, module_path, parent_module_dir(module_path, course_path), videos))?;
+            module_dirs.insert(module_path.to_path_buf());
+            module_order += 1;
+        }
+
+        log::debug!(
+            "📊 Escaneamento do curso concluído: {} arquivos escaneados, {} vídeos encontrados",
+            files_scanned, videos_found
+        );
+
+        if videos_found == 0 {
+            log::warn!("⚠️ Nenhum vídeo encontrado no curso: {}", course_path.display());
+        }
+
+        Ok(())
+    }
+
+    // O vídeo da pasta, se ela tiver exatamente um e nenhuma subpasta
+    fn single_video_in(&self, dir: &Path) -> Option<PathBuf> {
+        let mut videos = Vec::new();
+        for entry in std::fs::read_dir(dir).ok()?.filter_map(|e| e.ok()) {
+            let path = entry.path();
+            if self.is_ignored(&path) {
+                continue;
+            }
+            if path.is_dir() {
+                return None;
+            }
+            if self.is_video_file(&path) {
+                videos.push(path);
+            }
+        }
+        if videos.len() == 1 { videos.pop() } else { None }
+    }
+}
+
+
+// Pasta do módulo pai dentro do curso; None para módulos de primeiro nível
+fn parent_module_dir<'p>(dir: &'p Path, course_path: &Path) -> Option<&'p Path> {
+    dir.parent().filter(|parent| *parent != course_path && dir != course_path && parent.starts_with(course_path))
+}
+
+fn folder_name(path: &Path, fallback: &str) -> String {
+    path.file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or(fallback)
+        .to_string()
+}
+
+// Título do arquivo de metadados do curso ou, sem ele, o nome da pasta
+fn course_name(path: &Path, fallback: &str) -> String {
+    find_course_metadata(path)
+        .and_then(|metadata| metadata.title)
+        .unwrap_or_else(|| folder_name(path, fallback))
+}
+
+// Nome e descrição do vídeo: os do arquivo de metadados ao lado dele, se
+// houver, ou `folder_title` ou ainda o nome do arquivo sem extensão
+fn video_details(path: &Path, folder_title: Option<&str>) -> (String, Option<String>) {
+    let metadata = find_video_metadata(path).unwrap_or_default();
+    let name = metadata.title.or_else(|| folder_title.map(str::to_string)).unwrap_or_else(|| {
+        path.file_stem()
+            .and_then(|n| n.to_str())
+            .unwrap_or("Vídeo")
+            .to_string()
+    });
+    (name, metadata.description)
+}
+
+fn is_image_file(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| IMAGE_EXTENSIONS.contains(&ext.to_lowercase().as_str()))
+        .unwrap_or(false)
+}
+
+// Procura a capa na raiz do curso: primeiro pelos nomes convencionais
+// (cover, folder, poster), depois a primeira imagem em ordem alfabética
+pub fn find_cover_image(course_path: &Path) -> Option<PathBuf> {
+    let mut images: Vec<PathBuf> = std::fs::read_dir(course_path)
+        .ok()?
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| p.is_file() && is_image_file(p))
+        .collect();
+    images.sort();
+
+    for cover_name in COVER_NAMES {
+        let found = images.iter().find(|p| {
+            p.file_stem()
+                .and_then(|n| n.to_str())
+                .map(|n| n.eq_ignore_ascii_case(cover_name))
+                .unwrap_or(false)
+        });
+        if let Some(path) = found {
+            return Some(path.clone());
+        }
+    }
+
+    images.into_iter().next()
+}
+
+// Vídeos cadastrados cujo arquivo sumiu, está vazio, é menor que `min_bytes`
+// ou, com ffprobe disponível, não pode ser lido. Não altera o banco
+pub fn find_suspect_videos(
+    db: &Database,
+    course_id: Option<&str>,
+    min_bytes: u64,
+    ffprobe: Option<&Path>,
+) -> Result<Vec<(Video, u64, String)>> {
+    let mut suspects = Vec::new();
+
+    for video in db.get_videos(course_id)? {
+        let path = Path::new(&video.path);
+        let size = match std::fs::metadata(path) {
+            Ok(metadata) => metadata.len(),
+            Err(_) => {
+                suspects.push((video, 0, "Arquivo não encontrado".to_string()));
+                continue;
+            }
+        };
+
+        let reason = if size == 0 {
+            Some("Arquivo vazio (0 bytes)".to_string())
+        } else if size < min_bytes {
+            Some(format!("Arquivo menor que {} bytes", min_bytes))
+        } else if let Some(ffprobe) = ffprobe {
+            media::probe_duration(ffprobe, path).err().map(|e| e.to_string())
+        } else {
+            None
+        };
+
+        if let Some(reason) = reason {
+            log::debug!("⚠️ Vídeo suspeito: {} ({})", video.path, reason);
+            suspects.push((video, size, reason));
+        }
+    }
+
+    Ok(suspects)
+}
+
+// Vídeos de cursos diferentes com o mesmo nome de arquivo (sem extensão e sem
+// diferenciar maiúsculas), provavelmente a mesma aula baixada mais de uma vez
+#[derive(Debug, Serialize, Clone)]
+pub struct DuplicateVideoGroup {
+    pub file_name: String, // Nome normalizado que os vídeos compartilham
+    pub videos: Vec<DuplicateVideo>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct DuplicateVideo {
+    pub video: Video,
+    pub course_name: String,
+    pub size: Option<u64>, // None se o arquivo não estiver no disco
+}
+
+// Só leitura: a limpeza fica a cargo do usuário
+pub fn find_duplicate_videos_by_name(db: &Database) -> Result<Vec<DuplicateVideoGroup>> {
+    let course_names: std::collections::HashMap<String, String> = db.get_all_courses()?
+        .into_iter()
+        .map(|course| (course.id, course.display_name.unwrap_or(course.name)))
+        .collect();
+
+    let mut by_name: std::collections::BTreeMap<String, Vec<Video>> = std::collections::BTreeMap::new();
+    for video in db.get_videos(None)? {
+        let Some(stem) = Path::new(&video.path).file_stem() else {
+            continue;
+        };
+        by_name.entry(stem.to_string_lossy().to_lowercase()).or_default().push(video);
+    }
+
+    let groups = by_name.into_iter()
+        .filter(|(_, videos)| videos.iter().map(|v| &v.course_id).collect::<HashSet<_>>().len() > 1)
+        .map(|(file_name, videos)| {
+            let mut videos: Vec<DuplicateVideo> = videos.into_iter()
+                .map(|video| DuplicateVideo {
+                    course_name: course_names.get(&video.course_id).cloned().unwrap_or_default(),
+                    size: std::fs::metadata(&video.path).ok().map(|m| m.len()),
+                    video,
+                })
+                .collect();
+            videos.sort_by(|a, b| a.course_name.cmp(&b.course_name).then_with(|| a.video.path.cmp(&b.video.path)));
+            DuplicateVideoGroup { file_name, videos }
+        })
+        .collect::<Vec<_>>();
+
+    log::debug!("🔁 {} grupos de vídeos com o mesmo nome em cursos diferentes", groups.len());
+    Ok(groups)
+}
+
+// Como mostrar um arquivo no gerenciador de arquivos do sistema
+#[derive(Debug, PartialEq)]
+pub enum RevealAction {
+    // Comando que abre a pasta já com o arquivo selecionado
+    Command { program: String, args: Vec<String> },
+    // Sem suporte a seleção: abre apenas a pasta
+    OpenDirectory(PathBuf),
+}
+
+// `os` segue `std::env::consts::OS` ("windows", "macos", "linux"...)
+pub fn reveal_action(file_path: &Path, os: &str) -> Result<RevealAction, AppError> {
+    if !file_path.exists() {
+        return Err(AppError::NotFound(format!("Arquivo não encontrado: {}", file_path.display())));
+    }
+
+    let path = file_path.to_string_lossy().to_string();
+    Ok(match os {
+        "windows" => RevealAction::Command {
+            program: "explorer".to_string(),
+            args: vec!["/select,".to_string(), path],
+        },
+        "macos" => RevealAction::Command {
+            program: "open".to_string(),
+            args: vec!["-R".to_string(), path],
+        },
+        _ => RevealAction::OpenDirectory(
+            file_path.parent().map(Path::to_path_buf).unwrap_or_else(|| file_path.to_path_buf()),
+        ),
+    })
+}
+
+// Tamanho e datas do arquivo no disco. `created` fica None nos sistemas de
+// arquivos que não guardam a data de criação
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct FileMetadata {
+    pub size: u64,
+    pub modified: DateTime<Utc>,
+    pub created: Option<DateTime<Utc>>,
+}
+
+pub fn file_metadata(path: &Path) -> Result<FileMetadata, AppError> {
+    let metadata = std::fs::metadata(path).map_err(|e| match e.kind() {
+        std::io::ErrorKind::NotFound => AppError::NotFound(format!("Arquivo não encontrado: {}", path.display())),
+        _ => AppError::InvalidInput(format!("Não foi possível ler {}: {}", path.display(), e)),
+    })?;
+    let modified = metadata.modified()
+        .map_err(|e| AppError::InvalidInput(format!("Data de modificação indisponível para {}: {}", path.display(), e)))?;
+
+    Ok(FileMetadata {
+        size: metadata.len(),
+        modified: modified.into(),
+        created: metadata.created().ok().map(DateTime::<Utc>::from),
+    })
+}
+
+// Tamanho dos blocos lidos ao calcular o hash, para não carregar o vídeo inteiro
+const HASH_CHUNK_SIZE: usize = 1024 * 1024;
+
+// Hash do conteúdo do arquivo em hexadecimal; `algorithm` é "md5" ou "sha256"
+pub fn compute_file_hash(path: &Path, algorithm: &str) -> Result<String, AppError> {
+    use sha2::Digest;
+    use std::io::Read;
+
+    enum Hasher {
+        Md5(md5::Context),
+        Sha256(sha2::Sha256),
+    }
+    let mut hasher = match algorithm {
+        "md5" => Hasher::Md5(md5::Context::new()),
+        "sha256" => Hasher::Sha256(sha2::Sha256::new()),
+        other => return Err(AppError::InvalidInput(format!("Algoritmo de hash inválido (use md5 ou sha256): {}", other))),
+    };
+
+    let mut file = std::fs::File::open(path).map_err(|e| match e.kind() {
+        std::io::ErrorKind::NotFound => AppError::NotFound(format!("Arquivo não encontrado: {}", path.display())),
+        _ => AppError::InvalidInput(format!("Não foi possível ler {}: {}", path.display(), e)),
+    })?;
+    let mut buffer = vec![0u8; HASH_CHUNK_SIZE];
+    loop {
+        let read = file.read(&mut buffer)
+            .map_err(|e| AppError::InvalidInput(format!("Erro ao ler {}: {}", path.display(), e)))?;
+        if read == 0 {
+            break;
+        }
+        match &mut hasher {
+            Hasher::Md5(context) => context.consume(&buffer[..read]),
+            Hasher::Sha256(sha) => sha.update(&buffer[..read]),
+        }
+    }
+
+    Ok(match hasher {
+        Hasher::Md5(context) => format!("{:x}", context.compute()),
+        Hasher::Sha256(sha) => sha.finalize().iter().map(|byte| format!("{:02x}", byte)).collect(),
+    })
+}
+
+// Extensões padrão mais as da lista separada por vírgulas (ex.: "mpg, divx"),
+// em minúsculas e sem repetição; entradas com ponto ou espaço são ignoradas
+fn video_extensions_with(custom: &str) -> Vec<String> {
+    let mut extensions: Vec<String> = VIDEO_EXTENSIONS.iter().map(|ext| ext.to_string()).collect();
+    for entry in custom.split(',').map(str::trim).filter(|entry| !entry.is_empty()) {
+        if entry.contains('.') || entry.contains(char::is_whitespace) {
+            log::warn!("⚠️ Extensão de vídeo inválida ignorada: {:?}", entry);
+            continue;
+        }
+        let entry = entry.to_lowercase();
+        if !extensions.contains(&entry) {
+            extensions.push(entry);
+        }
+    }
+    extensions
+}
+
+pub fn get_default_course_directories() -> Vec<PathBuf> {
+    let mut dirs = Vec::new();
+    
+    // Pasta base especificada pelo usuário
+    let main_course_dir = PathBuf::from("C:\\MeusCursos");
+    if main_course_dir.exists() {
+        dirs.push(main_course_dir);
+    }
+    
+    // Diretórios comuns onde usuários podem ter cursos
+    if let Some(home) = dirs::home_dir() {
+        dirs.push(home.join("Cursos"));
+        dirs.push(home.join("Videos").join("Cursos"));
+        dirs.push(home.join("Documents").join("Cursos"));
+        dirs.push(home.join("Downloads"));
+    }
+
+    // Adiciona drives comuns no Windows
+    #[cfg(windows)]
+    {
+        for drive in ['C', 'D', 'E', 'F'] {
+            let drive_path = PathBuf::from(format!("{}:\\Cursos", drive));
+            if drive_path.exists() {
+                dirs.push(drive_path);
+            }
+        }
+    }
+
+    dirs.into_iter().filter(|p| p.exists()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+    use std::fs;
+
+    #[test]
+    fn test_video_file_detection() {
+        let temp_dir = TempDir::new().unwrap();
+        let db = Database::new(&temp_dir.path().join("test.db")).unwrap();
+        let scanner = FileSystemScanner::new(&db);
+
+        assert!(scanner.is_video_file(Path::new("video.mp4")));
+        assert!(scanner.is_video_file(Path::new("movie.mkv")));
+        assert!(!scanner.is_video_file(Path::new("document.txt")));
+        assert!(!scanner.is_video_file(Path::new("image.jpg")));
+    }
+
+    #[test]
+    fn test_course_scanning() {
+        let temp_dir = TempDir::new().unwrap();
+        let course_dir = temp_dir.path().join("Curso Teste");
         fs::create_dir_all(&course_dir).unwrap();
         
         // Cria alguns arquivos de vídeo de teste
         fs::write(course_dir.join("aula1.mp4"), "fake video content").unwrap();
-        fs::write(course_dir.join("aula2.mkv"), "fake video content").unwrap();
+        fs::write(course_dir.join("aula2.mkv"), "fake video content, maior").unwrap();
         
         let db = Database::new(&temp_dir.path().join("test.db")).unwrap();
-        let scanner = FileSystemScanner::new(db);
+        let scanner = FileSystemScanner::new(&db);
         
         let courses = scanner.scan_directory(temp_dir.path()).unwrap();
         assert_eq!(courses.len(), 1);
         assert_eq!(courses[0].name, "Curso Teste");
+
+        // O scan grava o tamanho dos arquivos
+        let sizes: Vec<(String, u64)> = db.get_largest_videos(10).unwrap()
+            .into_iter()
+            .map(|(video, size, _)| (video.name, size))
+            .collect();
+        assert_eq!(sizes, vec![("aula2".to_string(), 25), ("aula1".to_string(), 18)]);
+    }
+
+    #[test]
+    fn test_course_cover_detection() {
+        let temp_dir = TempDir::new().unwrap();
+        let library = temp_dir.path().join("cursos");
+        let with_cover = library.join("Com Capa");
+        let without_cover = library.join("Sem Capa");
+        fs::create_dir_all(&with_cover).unwrap();
+        fs::create_dir_all(&without_cover).unwrap();
+
+        fs::write(with_cover.join("aula1.mp4"), "fake video content").unwrap();
+        fs::write(with_cover.join("a-screenshot.png"), "fake image").unwrap();
+        fs::write(with_cover.join("Cover.JPG"), "fake image").unwrap();
+        fs::write(without_cover.join("aula1.mp4"), "fake video content").unwrap();
+
+        let db = Database::new(&temp_dir.path().join("test.db")).unwrap();
+        let scanner = FileSystemScanner::new(&db);
+        let courses = scanner.scan_directory(&library).unwrap();
+
+        let course = courses.iter().find(|c| c.name == "Com Capa").unwrap();
+        let expected = with_cover.join("Cover.JPG").to_string_lossy().to_string();
+        assert_eq!(course.cover_path.as_deref(), Some(expected.as_str()));
+        assert_eq!(db.get_course_cover(&course.id).unwrap(), Some(expected));
+
+        let course = courses.iter().find(|c| c.name == "Sem Capa").unwrap();
+        assert!(course.cover_path.is_none());
+        assert_eq!(db.get_course_cover(&course.id).unwrap(), None);
+    }
+
+    #[test]
+    fn test_cover_falls_back_to_first_image() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(temp_dir.path().join("b.png"), "fake image").unwrap();
+        fs::write(temp_dir.path().join("a.webp"), "fake image").unwrap();
+        fs::write(temp_dir.path().join("notes.txt"), "not an image").unwrap();
+
+        assert_eq!(find_cover_image(temp_dir.path()), Some(temp_dir.path().join("a.webp")));
+    }
+
+    #[test]
+    fn test_streaming_scan_matches_grouped_layout() {
+        let temp_dir = TempDir::new().unwrap();
+        let course_dir = temp_dir.path().join("cursos").join("Curso Grande");
+
+        // Árvore sintética: vídeos na raiz, em módulos e em submódulos,
+        // misturados com arquivos que não são vídeo
+        let mut all_videos = Vec::new();
+        for module in 0..12 {
+            let module_dir = if module == 0 {
+                course_dir.clone()
+            } else if module % 4 == 0 {
+                course_dir.join(format!("Modulo {:02}", module - 1)).join(format!("Extra {:02}", module))
+            } else {
+                course_dir.join(format!("Modulo {:02}", module))
+            };
+            fs::create_dir_all(&module_dir).unwrap();
+            for lesson in (0..40).rev() {
+                let video = module_dir.join(format!("aula{:03}.mp4", lesson));
+                fs::write(&video, "fake video content").unwrap();
+                all_videos.push(video);
+            }
+            fs::write(module_dir.join("leia-me.txt"), "texto").unwrap();
+        }
+        fs::create_dir_all(course_dir.join("Vazio")).unwrap();
+
+        // Agrupamento de referência (implementação anterior): vídeos por diretório pai
+        let mut expected: std::collections::HashMap<String, Vec<String>> = std::collections::HashMap::new();
+        for video in &all_videos {
+            expected
+                .entry(video.parent().unwrap().to_string_lossy().to_string())
+                .or_default()
+                .push(video.file_stem().unwrap().to_string_lossy().to_string());
+        }
+        for videos in expected.values_mut() {
+            videos.sort();
+        }
+
+        let db = Database::new(&temp_dir.path().join("test.db")).unwrap();
+        let scanner = FileSystemScanner::new(&db);
+        let courses = scanner.scan_directory(&temp_dir.path().join("cursos")).unwrap();
+        assert_eq!(courses.len(), 1);
+
+        let modules = db.get_course_modules(&courses[0].id).unwrap();
+        assert_eq!(modules.len(), expected.len());
+        assert_eq!(modules[0].name, "Aulas");
+
+        for (index, module) in modules.iter().enumerate() {
+            assert_eq!(module.order_index, index as i32);
+            let videos = db.get_module_videos(&module.id).unwrap();
+            let names: Vec<String> = videos.iter().map(|v| v.name.clone()).collect();
+            assert_eq!(&names, expected.get(&module.path).unwrap());
+            for (order, video) in videos.iter().enumerate() {
+                assert_eq!(video.order_index, order as i32);
+            }
+        }
+    }
+
+    #[test]
+    fn test_rescan_course_only_changes_that_course() {
+        let temp_dir = TempDir::new().unwrap();
+        let library = temp_dir.path().join("cursos");
+        let course_a = library.join("Curso A");
+        let course_b = library.join("Curso B");
+        fs::create_dir_all(course_a.join("Modulo 1")).unwrap();
+        fs::create_dir_all(&course_b).unwrap();
+        fs::write(course_a.join("Modulo 1").join("aula1.mp4"), "fake video content").unwrap();
+        fs::write(course_a.join("Modulo 1").join("aula2.mp4"), "fake video content").unwrap();
+        fs::write(course_b.join("aula1.mp4"), "fake video content").unwrap();
+
+        let db = Database::new(&temp_dir.path().join("test.db")).unwrap();
+        let scanner = FileSystemScanner::new(&db);
+        let courses = scanner.scan_directory(&library).unwrap();
+        let id_a = courses.iter().find(|c| c.name == "Curso A").unwrap().id.clone();
+        let id_b = courses.iter().find(|c| c.name == "Curso B").unwrap().id.clone();
+
+        let course_videos = |course_id: &str| -> Vec<Video> {
+            db.get_course_modules(course_id).unwrap()
+                .iter()
+                .flat_map(|m| db.get_module_videos(&m.id).unwrap())
+                .collect()
+        };
+        let aula1 = course_videos(&id_a).into_iter().find(|v| v.name == "aula1").unwrap();
+        db.mark_video_completed(&aula1.id, true).unwrap();
+        let before_b: Vec<String> = course_videos(&id_b).iter().map(|v| v.id.clone()).collect();
+
+        // Novo vídeo no curso A, um removido e um novo vídeo (ainda não escaneado) no curso B
+        fs::write(course_a.join("Modulo 1").join("aula3.mp4"), "fake video content").unwrap();
+        fs::remove_file(course_a.join("Modulo 1").join("aula2.mp4")).unwrap();
+        fs::write(course_b.join("aula2.mp4"), "fake video content").unwrap();
+
+        let rescanned = scanner.rescan_course(&id_a, true).unwrap();
+        assert_eq!(rescanned.id, id_a);
+        assert!(rescanned.last_accessed.is_some());
+
+        let after_a = course_videos(&id_a);
+        let names: Vec<&str> = after_a.iter().map(|v| v.name.as_str()).collect();
+        assert_eq!(names, vec!["aula1", "aula3"]);
+
+        // O vídeo existente mantém id e progresso
+        let kept = after_a.iter().find(|v| v.name == "aula1").unwrap();
+        assert_eq!(kept.id, aula1.id);
+        assert!(db.get_video_progress(&aula1.id).unwrap().unwrap().completed);
+
+        // O curso B não foi tocado
+        let after_b: Vec<String> = course_videos(&id_b).iter().map(|v| v.id.clone()).collect();
+        assert_eq!(after_b, before_b);
+        assert!(db.get_course_by_id(&id_b).unwrap().unwrap().last_accessed.is_none());
+    }
+
+    #[test]
+    fn test_course_display_name_survives_rescan() {
+        let temp_dir = TempDir::new().unwrap();
+        let library = temp_dir.path().join("cursos");
+        let course_dir = library.join("udemy_rust_complete_2024_downloaded");
+        fs::create_dir_all(&course_dir).unwrap();
+        fs::write(course_dir.join("aula1.mp4"), "fake video content").unwrap();
+
+        let db = Database::new(&temp_dir.path().join("test.db")).unwrap();
+        let scanner = FileSystemScanner::new(&db);
+        let course_id = scanner.scan_directory(&library).unwrap()[0].id.clone();
+
+        db.rename_course(&course_id, "Rust 2024").unwrap();
+        scanner.scan_directory(&library).unwrap();
+        scanner.rescan_course(&course_id, false).unwrap();
+
+        let courses = db.get_all_courses().unwrap();
+        assert_eq!(courses.len(), 1);
+        assert_eq!(courses[0].id, course_id);
+        assert_eq!(courses[0].display_name.as_deref(), Some("Rust 2024"));
+        assert_eq!(courses[0].name, "udemy_rust_complete_2024_downloaded");
+
+        // Nome vazio volta para o nome da pasta
+        db.rename_course(&course_id, "  ").unwrap();
+        assert!(db.get_course_by_id(&course_id).unwrap().unwrap().display_name.is_none());
+        assert!(matches!(db.rename_course("inexistente", "X"), Err(crate::error::AppError::NotFound(_))));
+    }
+
+    #[test]
+    fn test_course_description_survives_rescan() {
+        let temp_dir = TempDir::new().unwrap();
+        let library = temp_dir.path().join("cursos");
+        fs::create_dir_all(library.join("Curso Rust")).unwrap();
+        fs::write(library.join("Curso Rust").join("aula1.mp4"), "fake video content").unwrap();
+
+        let db = Database::new(&temp_dir.path().join("test.db")).unwrap();
+        let scanner = FileSystemScanner::new(&db);
+        let course_id = scanner.scan_directory(&library).unwrap()[0].id.clone();
+        assert_eq!(db.get_course_by_id(&course_id).unwrap().unwrap().description, None);
+
+        db.set_course_description(&course_id, "  Do básico a lifetimes  ").unwrap();
+        fs::write(library.join("Curso Rust").join("aula2.mp4"), "fake video content").unwrap();
+        scanner.rescan_course(&course_id, true).unwrap();
+        scanner.scan_directory(&library).unwrap();
+
+        let course = db.get_course_by_id(&course_id).unwrap().unwrap();
+        assert_eq!(course.description.as_deref(), Some("Do básico a lifetimes"));
+        assert_eq!(db.get_all_courses().unwrap()[0].description.as_deref(), Some("Do básico a lifetimes"));
+        assert_eq!(db.get_courses_with_progress().unwrap()[0].0.description.as_deref(), Some("Do básico a lifetimes"));
+
+        db.set_course_description(&course_id, "").unwrap();
+        assert_eq!(db.get_course_by_id(&course_id).unwrap().unwrap().description, None);
+        assert!(matches!(db.set_course_description("inexistente", "x"), Err(AppError::NotFound(_))));
+    }
+
+    #[test]
+    fn test_reveal_action_per_platform() {
+        let temp_dir = TempDir::new().unwrap();
+        let video = temp_dir.path().join("aula 1.mp4");
+        fs::write(&video, "fake video content").unwrap();
+        let video_str = video.to_string_lossy().to_string();
+
+        assert_eq!(
+            reveal_action(&video, "windows").unwrap(),
+            RevealAction::Command { program: "explorer".to_string(), args: vec!["/select,".to_string(), video_str.clone()] }
+        );
+        assert_eq!(
+            reveal_action(&video, "macos").unwrap(),
+            RevealAction::Command { program: "open".to_string(), args: vec!["-R".to_string(), video_str] }
+        );
+        assert_eq!(
+            reveal_action(&video, "linux").unwrap(),
+            RevealAction::OpenDirectory(temp_dir.path().to_path_buf())
+        );
+
+        let missing = temp_dir.path().join("inexistente.mp4");
+        assert!(matches!(reveal_action(&missing, "linux"), Err(AppError::NotFound(_))));
+    }
+
+    #[test]
+    fn test_suspect_videos_reports_empty_and_tiny_files() {
+        let temp_dir = TempDir::new().unwrap();
+        let library = temp_dir.path().join("cursos");
+        let course_dir = library.join("Curso");
+        fs::create_dir_all(&course_dir).unwrap();
+        fs::write(course_dir.join("aula1.mp4"), vec![0u8; 4096]).unwrap();
+        fs::write(course_dir.join("aula2.mp4"), "").unwrap();
+        fs::write(course_dir.join("aula3.mp4"), "pequeno").unwrap();
+
+        let db = Database::new(&temp_dir.path().join("test.db")).unwrap();
+        let course_id = FileSystemScanner::new(&db).scan_directory(&library).unwrap()[0].id.clone();
+
+        let suspects = find_suspect_videos(&db, Some(&course_id), 1024, None).unwrap();
+        let reported: Vec<(&str, u64)> = suspects.iter().map(|(v, size, _)| (v.name.as_str(), *size)).collect();
+        assert_eq!(reported, vec![("aula2", 0), ("aula3", 7)]);
+        assert!(suspects[0].2.contains("0 bytes"));
+
+        // Arquivo apagado depois do escaneamento também é reportado, sem mexer no banco
+        fs::remove_file(course_dir.join("aula1.mp4")).unwrap();
+        let suspects = find_suspect_videos(&db, None, 1024, None).unwrap();
+        assert_eq!(suspects.len(), 3);
+        assert_eq!(db.get_videos(Some(&course_id)).unwrap().len(), 3);
+    }
+
+    #[test]
+    fn test_preview_scan_matches_scan_without_writing() {
+        let temp_dir = TempDir::new().unwrap();
+        let library = temp_dir.path().join("cursos");
+        fs::create_dir_all(library.join("Curso A").join("Modulo 2")).unwrap();
+        fs::create_dir_all(library.join("Curso A").join("Modulo 1")).unwrap();
+        fs::write(library.join("Curso A").join("intro.mp4"), "fake video content").unwrap();
+        fs::write(library.join("Curso A").join("Modulo 1").join("b.mp4"), "fake video content").unwrap();
+        fs::write(library.join("Curso A").join("Modulo 1").join("a.mkv"), "fake video content").unwrap();
+        fs::write(library.join("Curso A").join("Modulo 2").join("c.mp4"), "fake video content").unwrap();
+        fs::write(library.join("Curso A").join("Modulo 2").join("leia-me.txt"), "texto").unwrap();
+        fs::write(library.join("solto.mp4"), "fake video content").unwrap();
+
+        let db = Database::new(&temp_dir.path().join("test.db")).unwrap();
+        let scanner = FileSystemScanner::new(&db);
+
+        let preview = scanner.preview_scan(&library).unwrap();
+        assert!(db.get_all_courses().unwrap().is_empty());
+        assert_eq!(preview.courses.len(), 2);
+        assert!(preview.courses.iter().all(|c| !c.already_imported));
+
+        let courses = scanner.scan_directory(&library).unwrap();
+        assert_eq!(courses.len(), preview.courses.len());
+
+        for course in &courses {
+            let expected = preview.courses.iter().find(|c| c.path == course.path).unwrap();
+            assert_eq!(expected.name, course.name);
+            assert_eq!(expected.cover_path, course.cover_path);
+
+            let modules = db.get_course_modules(&course.id).unwrap();
+            assert_eq!(modules.len(), expected.modules.len());
+            for (module, expected_module) in modules.iter().zip(&expected.modules) {
+                assert_eq!(
+                    (&module.name, &module.path, module.order_index),
+                    (&expected_module.name, &expected_module.path, expected_module.order_index)
+                );
+
+                let videos: Vec<(String, String, i32)> = db.get_module_videos(&module.id).unwrap()
+                    .into_iter()
+                    .map(|v| (v.name, v.path, v.order_index))
+                    .collect();
+                let expected_videos: Vec<(String, String, i32)> = expected_module.videos.iter()
+                    .map(|v| (v.name.clone(), v.path.clone(), v.order_index))
+                    .collect();
+                assert_eq!(videos, expected_videos);
+            }
+        }
+
+        // Depois do escaneamento, a prévia indica que os cursos já existem
+        let preview = scanner.preview_scan(&library).unwrap();
+        assert!(preview.courses.iter().all(|c| c.already_imported));
+    }
+
+    #[test]
+    fn test_course_detection_depth() {
+        let temp_dir = TempDir::new().unwrap();
+        let library = temp_dir.path().join("cursos");
+        for (provider, course) in [("Provedor A", "Rust"), ("Provedor A", "SQL"), ("Provedor B", "Go")] {
+            let module_dir = library.join(provider).join(course).join("Modulo 1");
+            fs::create_dir_all(&module_dir).unwrap();
+            fs::write(module_dir.join("aula1.mp4"), "fake video content").unwrap();
+        }
+        fs::write(library.join("Provedor B").join("boas-vindas.mp4"), "fake video content").unwrap();
+
+        let db = Database::new(&temp_dir.path().join("test.db")).unwrap();
+        let scanner = FileSystemScanner::new(&db);
+        let course_names = |preview: &ScanPreview| {
+            let mut names: Vec<(String, usize)> = preview.courses.iter()
+                .map(|c| (c.name.clone(), c.modules.len()))
+                .collect();
+            names.sort();
+            names
+        };
+
+        // Padrão: cada subdiretório de primeiro nível é um curso, e as pastas
+        // de curso do nível de baixo viram módulos com "Modulo 1" dentro
+        let preview = scanner.preview_scan(&library).unwrap();
+        assert_eq!(course_names(&preview), vec![("Provedor A".to_string(), 2), ("Provedor B".to_string(), 2)]);
+
+        db.set_user_setting(&crate::db::UserSettings {
+            id: Uuid::new_v4().to_string(),
+            setting_key: "course_detection_depth".to_string(),
+            setting_value: "2".to_string(),
+            setting_type: "number".to_string(),
+            updated_at: Utc::now(),
+        }).unwrap();
+
+        // Nível 2: os provedores só agrupam; vídeos soltos num provedor viram um curso próprio
+        let preview = scanner.preview_scan(&library).unwrap();
+        assert_eq!(course_names(&preview), vec![
+            ("Go".to_string(), 1),
+            ("Provedor B".to_string(), 1),
+            ("Rust".to_string(), 1),
+            ("SQL".to_string(), 1),
+        ]);
+
+        let courses = scanner.scan_directory(&library).unwrap();
+        let rust = courses.iter().find(|c| c.name == "Rust").unwrap();
+        let modules = db.get_course_modules(&rust.id).unwrap();
+        assert_eq!(modules.len(), 1);
+        assert_eq!(modules[0].name, "Modulo 1");
+    }
+
+    #[test]
+    fn test_sidecar_metadata_overrides_names() {
+        let temp_dir = TempDir::new().unwrap();
+        let library = temp_dir.path().join("cursos");
+        let course_dir = library.join("rust-2024");
+        fs::create_dir_all(&course_dir).unwrap();
+        fs::write(course_dir.join("info.json"), r#"{"title": "Rust do Zero", "description": "Curso completo"}"#).unwrap();
+        fs::write(course_dir.join("01-intro.mp4"), "fake video content").unwrap();
+        fs::write(course_dir.join("01-intro.nfo"), "title: Boas-vindas ao curso\nplot: O que vamos construir\n").unwrap();
+        fs::write(course_dir.join("02-tipos.mp4"), "fake video content").unwrap();
+
+        let db = Database::new(&temp_dir.path().join("test.db")).unwrap();
+        let scanner = FileSystemScanner::new(&db);
+        let courses = scanner.scan_directory(&library).unwrap();
+        assert_eq!(courses[0].name, "Rust do Zero");
+
+        let modules = db.get_course_modules(&courses[0].id).unwrap();
+        let videos = db.get_module_videos(&modules[0].id).unwrap();
+        let names: Vec<&str> = videos.iter().map(|v| v.name.as_str()).collect();
+        assert_eq!(names, vec!["Boas-vindas ao curso", "02-tipos"]);
+
+        let detail = db.get_video_detail(&videos[0].id).unwrap();
+        assert_eq!(detail.description.as_deref(), Some("O que vamos construir"));
+        assert!(db.get_video_detail(&videos[1].id).unwrap().description.is_none());
+
+        // Sem o .nfo, o rescan volta ao nome do arquivo e limpa a descrição
+        fs::remove_file(course_dir.join("01-intro.nfo")).unwrap();
+        scanner.rescan_course(&courses[0].id, false).unwrap();
+        let detail = db.get_video_detail(&videos[0].id).unwrap();
+        assert_eq!(detail.video.name, "01-intro");
+        assert!(detail.description.is_none());
+    }
+
+    #[test]
+    fn test_scan_single_video_file_uses_parent_folder() {
+        let temp_dir = TempDir::new().unwrap();
+        let course_dir = temp_dir.path().join("Palestra");
+        fs::create_dir_all(course_dir.join("extras")).unwrap();
+        let video = course_dir.join("palestra.mp4");
+        fs::write(&video, "fake video content").unwrap();
+        fs::write(course_dir.join("extras").join("bonus.mp4"), "fake video content").unwrap();
+
+        let db = Database::new(&temp_dir.path().join("test.db")).unwrap();
+        let courses = FileSystemScanner::new(&db).scan_directory(&video).unwrap();
+        assert_eq!(courses.len(), 1);
+        assert_eq!(courses[0].name, "Palestra");
+        assert_eq!(courses[0].path, course_dir.to_string_lossy());
+
+        // Só os vídeos da própria pasta, sem subpastas
+        let modules = db.get_course_modules(&courses[0].id).unwrap();
+        assert_eq!(modules.len(), 1);
+        let videos = db.get_module_videos(&modules[0].id).unwrap();
+        assert_eq!(videos.len(), 1);
+        assert_eq!(videos[0].path, video.to_string_lossy());
+    }
+
+    #[test]
+    fn test_scan_non_video_file_is_invalid_input() {
+        let temp_dir = TempDir::new().unwrap();
+        let notes = temp_dir.path().join("notas.txt");
+        fs::write(&notes, "não é vídeo").unwrap();
+
+        let db = Database::new(&temp_dir.path().join("test.db")).unwrap();
+        let err = FileSystemScanner::new(&db).scan_directory(&notes).unwrap_err();
+        assert!(matches!(err.downcast_ref::<AppError>(), Some(AppError::InvalidInput(_))));
+        assert!(db.get_all_courses().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_custom_video_extensions() {
+        assert_eq!(
+            video_extensions_with(" MPG, divx,.avi, mp4, dv x,,mpg ").split_off(VIDEO_EXTENSIONS.len()),
+            vec!["mpg", "divx"]
+        );
+
+        let temp_dir = TempDir::new().unwrap();
+        let course_dir = temp_dir.path().join("cursos").join("Antigo");
+        fs::create_dir_all(&course_dir).unwrap();
+        fs::write(course_dir.join("aula1.MPG"), "fake video content").unwrap();
+        fs::write(course_dir.join("aula2.mp4"), "fake video content").unwrap();
+
+        let db = Database::new(&temp_dir.path().join("test.db")).unwrap();
+        assert!(!FileSystemScanner::new(&db).is_video_file(&course_dir.join("aula1.MPG")));
+
+        db.set_user_setting(&crate::db::UserSettings {
+            id: Uuid::new_v4().to_string(),
+            setting_key: "custom_video_extensions".to_string(),
+            setting_value: "mpg, .divx".to_string(),
+            setting_type: "string".to_string(),
+            updated_at: Utc::now(),
+        }).unwrap();
+        let scanner = FileSystemScanner::new(&db);
+        assert!(scanner.is_video_file(&course_dir.join("aula1.MPG")));
+        assert!(!scanner.is_video_file(&course_dir.join("aula3.divx")));
+
+        let courses = scanner.scan_directory(&temp_dir.path().join("cursos")).unwrap();
+        let modules = db.get_course_modules(&courses[0].id).unwrap();
+        assert_eq!(db.get_module_videos(&modules[0].id).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_flatten_single_video_dirs_into_parent_module() {
+        let temp_dir = TempDir::new().unwrap();
+        let library = temp_dir.path().join("cursos");
+        let course_dir = library.join("Curso Rust");
+        for lesson in ["02 - Variáveis", "01 - Instalação", "03 - Funções"] {
+            fs::create_dir_all(course_dir.join(lesson)).unwrap();
+            fs::write(course_dir.join(lesson).join("video.mp4"), "fake video content").unwrap();
+        }
+        // Pasta com dois vídeos continua sendo um módulo próprio
+        let exercises = course_dir.join("04 - Exercícios");
+        fs::create_dir_all(&exercises).unwrap();
+        fs::write(exercises.join("ex1.mp4"), "fake video content").unwrap();
+        fs::write(exercises.join("ex2.mp4"), "fake video content").unwrap();
+
+        let db = Database::new(&temp_dir.path().join("test.db")).unwrap();
+        let scanner = FileSystemScanner::new(&db);
+        let preview = scanner.preview_scan(&library).unwrap();
+        assert_eq!(preview.courses[0].modules.len(), 4);
+
+        db.set_user_setting(&crate::db::UserSettings {
+            id: Uuid::new_v4().to_string(),
+            setting_key: "flatten_single_video_dirs".to_string(),
+            setting_value: "true".to_string(),
+            setting_type: "boolean".to_string(),
+            updated_at: Utc::now(),
+        }).unwrap();
+
+        let courses = scanner.scan_directory(&library).unwrap();
+        let modules = db.get_course_modules(&courses[0].id).unwrap();
+        let names: Vec<&str> = modules.iter().map(|m| m.name.as_str()).collect();
+        assert_eq!(names, vec!["Aulas", "04 - Exercícios"]);
+
+        let lessons: Vec<(String, i32)> = db.get_module_videos(&modules[0].id).unwrap()
+            .into_iter()
+            .map(|v| (v.name, v.order_index))
+            .collect();
+        assert_eq!(lessons, vec![
+            ("01 - Instalação".to_string(), 0),
+            ("02 - Variáveis".to_string(), 1),
+            ("03 - Funções".to_string(), 2),
+        ]);
+    }
+
+    #[test]
+    fn test_hidden_entries_skipped_unless_enabled() {
+        let temp_dir = TempDir::new().unwrap();
+        let library = temp_dir.path().join("cursos");
+        let course_dir = library.join("Curso Rust");
+        fs::create_dir_all(course_dir.join(".AppleDouble")).unwrap();
+        fs::create_dir_all(library.join(".amostras")).unwrap();
+        fs::write(course_dir.join("aula.mp4"), "fake video content").unwrap();
+        fs::write(course_dir.join("._aula.mp4"), "resource fork").unwrap();
+        fs::write(course_dir.join(".AppleDouble").join("aula.mp4"), "resource fork").unwrap();
+        fs::write(library.join(".amostras").join("amostra.mp4"), "fake video content").unwrap();
+
+        let db = Database::new(&temp_dir.path().join("test.db")).unwrap();
+        let courses = FileSystemScanner::new(&db).scan_directory(&library).unwrap();
+        assert_eq!(courses.len(), 1);
+        assert_eq!(db.get_videos(Some(&courses[0].id)).unwrap().len(), 1);
+
+        db.set_user_setting(&crate::db::UserSettings {
+            id: Uuid::new_v4().to_string(),
+            setting_key: "scan_hidden_files".to_string(),
+            setting_value: "true".to_string(),
+            setting_type: "boolean".to_string(),
+            updated_at: Utc::now(),
+        }).unwrap();
+
+        let courses = FileSystemScanner::new(&db).scan_directory(&library).unwrap();
+        let mut counts: Vec<(String, usize)> = courses.into_iter()
+            .map(|c| (c.name, db.get_videos(Some(&c.id)).unwrap().len()))
+            .collect();
+        counts.sort();
+        assert_eq!(counts, vec![(".amostras".to_string(), 1), ("Curso Rust".to_string(), 3)]);
+    }
+
+    #[test]
+    fn test_nested_folders_become_submodules() {
+        let temp_dir = TempDir::new().unwrap();
+        let library = temp_dir.path().join("cursos");
+        let course_dir = library.join("Curso Rust");
+        for (dir, video) in [
+            ("Parte 1", "intro.mp4"),
+            ("Parte 1/Capítulo A", "aula.mp4"),
+            ("Parte 2/Capítulo B", "aula.mp4"),
+            ("Parte 2/Capítulo C", "aula.mp4"),
+        ] {
+            fs::create_dir_all(course_dir.join(dir)).unwrap();
+            fs::write(course_dir.join(dir).join(video), "fake video content").unwrap();
+        }
+
+        let db = Database::new(&temp_dir.path().join("test.db")).unwrap();
+        let courses = FileSystemScanner::new(&db).scan_directory(&library).unwrap();
+
+        // A lista plana só tem módulos com vídeos, na ordem do percurso
+        let flat: Vec<String> = db.get_course_modules(&courses[0].id).unwrap().into_iter().map(|m| m.name).collect();
+        assert_eq!(flat, vec!["Parte 1", "Capítulo A", "Capítulo B", "Capítulo C"]);
+
+        let tree = db.get_course_module_tree(&courses[0].id).unwrap();
+        let shape: Vec<(String, Vec<String>)> = tree.iter()
+            .map(|node| (node.module.name.clone(), node.children.iter().map(|c| c.module.name.clone()).collect()))
+            .collect();
+        assert_eq!(shape, vec![
+            ("Parte 1".to_string(), vec!["Capítulo A".to_string()]),
+            ("Parte 2".to_string(), vec!["Capítulo B".to_string(), "Capítulo C".to_string()]),
+        ]);
+        assert_eq!(tree[0].parent_module_id, None);
+        assert_eq!(tree[1].children[0].parent_module_id.as_deref(), Some(tree[1].module.id.as_str()));
+        // Pasta só com subpastas entra como módulo vazio
+        assert!(db.get_module_videos(&tree[1].module.id).unwrap().is_empty());
+
+        // Reescanear mantém as ligações
+        FileSystemScanner::new(&db).rescan_course(&courses[0].id, false).unwrap();
+        assert_eq!(db.get_course_module_tree(&courses[0].id).unwrap()[1].children.len(), 2);
+    }
+
+    #[test]
+    fn test_duplicate_videos_grouped_across_courses() {
+        let temp_dir = TempDir::new().unwrap();
+        let library = temp_dir.path().join("cursos");
+        for (course, video, content) in [
+            ("Curso Go", "intro.mp4", "12345"),
+            ("Curso Rust", "Intro.MKV", "123"),
+            ("Curso Rust", "traits.mp4", "123"),
+            ("Curso SQL", "traits.mp4", "123"),
+            ("Curso SQL", "joins.mp4", "123"),
+        ] {
+            fs::create_dir_all(library.join(course)).unwrap();
+            fs::write(library.join(course).join(video), content).unwrap();
+        }
+        // Mesmo nome dentro de um só curso não conta
+        fs::create_dir_all(library.join("Curso SQL").join("Extra")).unwrap();
+        fs::write(library.join("Curso SQL").join("Extra").join("joins.mp4"), "123").unwrap();
+
+        let db = Database::new(&temp_dir.path().join("test.db")).unwrap();
+        FileSystemScanner::new(&db).scan_directory(&library).unwrap();
+
+        let groups = find_duplicate_videos_by_name(&db).unwrap();
+        let names: Vec<&str> = groups.iter().map(|g| g.file_name.as_str()).collect();
+        assert_eq!(names, vec!["intro", "traits"]);
+        let members = |group: &DuplicateVideoGroup| -> Vec<(String, Option<u64>)> {
+            group.videos.iter().map(|v| (v.course_name.clone(), v.size)).collect()
+        };
+        assert_eq!(members(&groups[0]), vec![("Curso Go".to_string(), Some(5)), ("Curso Rust".to_string(), Some(3))]);
+        assert_eq!(members(&groups[1]), vec![("Curso Rust".to_string(), Some(3)), ("Curso SQL".to_string(), Some(3))]);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_parallel_rescan_matches_serial_scan() {
+        let temp_dir = TempDir::new().unwrap();
+        let bases = [temp_dir.path().join("cursos"), temp_dir.path().join("mais cursos")];
+        for (base, names) in bases.iter().zip([["Rust", "Go", "Python"], ["Zig", "Elixir", "C"]]) {
+            for (index, name) in names.iter().enumerate() {
+                for module in 0..=index {
+                    let dir = base.join(name).join(format!("Módulo {}", module));
+                    fs::create_dir_all(&dir).unwrap();
+                    fs::write(dir.join("aula1.mp4"), "fake video content").unwrap();
+                    fs::write(dir.join("aula2.mkv"), "fake video content").unwrap();
+                }
+            }
+        }
+        fs::write(bases[1].join("avulso.mp4"), "fake video content").unwrap();
+
+        let summary = |db: &Database, courses: Vec<Course>| -> Vec<(String, String, usize, usize)> {
+            courses.into_iter().map(|c| (
+                db.get_course_modules(&c.id).unwrap().len(),
+                db.get_videos(Some(&c.id)).unwrap().len(),
+                c,
+            )).map(|(modules, videos, c)| (c.name, c.path, modules, videos)).collect()
+        };
+
+        let serial_db = Database::new(&temp_dir.path().join("serial.db")).unwrap();
+        let serial_scanner = FileSystemScanner::new(&serial_db);
+        let serial_courses: Vec<Course> = bases.iter()
+            .flat_map(|base| serial_scanner.scan_directory(base).unwrap())
+            .collect();
+        let mut serial = summary(&serial_db, serial_courses);
+        serial.sort();
+
+        let parallel_db = Database::new(&temp_dir.path().join("parallel.db")).unwrap();
+        let parallel_courses = FileSystemScanner::new(&parallel_db).rescan_courses(&bases).unwrap();
+        let parallel = summary(&parallel_db, parallel_courses);
+
+        assert_eq!(parallel.len(), 7);
+        assert_eq!(parallel, serial);
+        assert_eq!(parallel_db.get_all_courses().unwrap().len(), 7);
+
+        // Reescanear reaproveita os cursos já gravados
+        let again = FileSystemScanner::new(&parallel_db).rescan_courses(&bases).unwrap();
+        assert_eq!(summary(&parallel_db, again), parallel);
+        assert_eq!(parallel_db.get_all_courses().unwrap().len(), 7);
+    }
+
+    #[test]
+    fn test_file_metadata_reports_size_and_dates() {
+        let temp_dir = TempDir::new().unwrap();
+        let video = temp_dir.path().join("aula.mp4");
+        fs::write(&video, "fake video content").unwrap();
+
+        let metadata = file_metadata(&video).unwrap();
+        assert_eq!(metadata.size, 18);
+        let age = Utc::now() - metadata.modified;
+        assert!(age >= chrono::Duration::seconds(-5) && age < chrono::Duration::minutes(5));
+        if let Some(created) = metadata.created {
+            assert!(created <= Utc::now() + chrono::Duration::seconds(5));
+        }
+
+        fs::remove_file(&video).unwrap();
+        assert!(matches!(file_metadata(&video), Err(AppError::NotFound(_))));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_scan_skips_unreadable_entries() {
+        let temp_dir = TempDir::new().unwrap();
+        let library = temp_dir.path().join("cursos");
+        let course_dir = library.join("Curso Rust");
+        fs::create_dir_all(&course_dir).unwrap();
+        fs::write(course_dir.join("aula1.mp4"), "fake video content").unwrap();
+        // Links quebrados: a leitura dos metadados falha
+        let broken_course = library.join("Curso Sumido");
+        let broken_video = course_dir.join("aula2.mp4");
+        std::os::unix::fs::symlink(temp_dir.path().join("inexistente"), &broken_course).unwrap();
+        std::os::unix::fs::symlink(temp_dir.path().join("inexistente.mp4"), &broken_video).unwrap();
+
+        let db = Database::new(&temp_dir.path().join("test.db")).unwrap();
+        let report = FileSystemScanner::new(&db).scan_directory_with_report(&library).unwrap();
+
+        assert_eq!(report.courses.len(), 1);
+        assert_eq!(report.courses[0].name, "Curso Rust");
+        assert_eq!(db.get_videos(Some(&report.courses[0].id)).unwrap().len(), 1);
+        let mut skipped = report.skipped_paths.clone();
+        skipped.sort();
+        assert_eq!(skipped, vec![
+            broken_video.to_string_lossy().to_string(),
+            broken_course.to_string_lossy().to_string(),
+        ]);
+    }
+
+    #[test]
+    fn test_compute_file_hash_known_digests() {
+        let temp_dir = TempDir::new().unwrap();
+        let file = temp_dir.path().join("fixture.bin");
+        fs::write(&file, "The quick brown fox jumps over the lazy dog").unwrap();
+
+        assert_eq!(compute_file_hash(&file, "md5").unwrap(), "9e107d9d372bb6826bd81d3542a419d6");
+        assert_eq!(
+            compute_file_hash(&file, "sha256").unwrap(),
+            "d7a8fbb307d7809469ca9abcb0082e4f8d5651e46d3cdb762d02d0bf37c9e592"
+        );
+
+        // Maior que um bloco de leitura
+        let large = temp_dir.path().join("grande.bin");
+        fs::write(&large, vec![0u8; HASH_CHUNK_SIZE + 1]).unwrap();
+        assert_eq!(compute_file_hash(&large, "md5").unwrap(), format!("{:x}", md5::compute(vec![0u8; HASH_CHUNK_SIZE + 1])));
+
+        assert!(matches!(compute_file_hash(&file, "sha1"), Err(AppError::InvalidInput(_))));
+        assert!(matches!(compute_file_hash(&temp_dir.path().join("nada"), "md5"), Err(AppError::NotFound(_))));
+    }
+
+    // Logger que guarda os registros da thread atual, para inspecionar nos testes
+    struct CapturingLogger;
+
+    thread_local! {
+        static CAPTURED_LOGS: std::cell::RefCell<Vec<(log::Level, String)>> = const { std::cell::RefCell::new(Vec::new()) };
+    }
+
+    impl log::Log for CapturingLogger {
+        fn enabled(&self, _metadata: &log::Metadata) -> bool {
+            true
+        }
+
+        fn log(&self, record: &log::Record) {
+            CAPTURED_LOGS.with(|logs| logs.borrow_mut().push((record.level(), record.args().to_string())));
+        }
+
+        fn flush(&self) {}
+    }
+
+    static CAPTURING_LOGGER: CapturingLogger = CapturingLogger;
+
+    #[test]
+    fn test_scan_logs_info_summary() {
+        let _ = log::set_logger(&CAPTURING_LOGGER);
+        log::set_max_level(log::LevelFilter::Info);
+
+        let temp_dir = TempDir::new().unwrap();
+        let library = temp_dir.path().join("cursos");
+        fs::create_dir_all(library.join("Curso")).unwrap();
+        fs::write(library.join("Curso").join("aula1.mp4"), "fake video content").unwrap();
+
+        let db = Database::new(&temp_dir.path().join("test.db")).unwrap();
+        FileSystemScanner::new(&db).scan_directory(&library).unwrap();
+
+        let logs = CAPTURED_LOGS.with(|logs| logs.borrow().clone());
+        let summary = logs.iter()
+            .find(|(_, message)| message.contains("Resumo do escaneamento"))
+            .expect("resumo do escaneamento não foi registrado");
+        assert_eq!(summary.0, log::Level::Info);
+        assert!(summary.1.contains("1 cursos"));
+
+        // Mensagens por arquivo ficam em debug e não passam do filtro info
+        assert!(logs.iter().all(|(level, _)| *level <= log::Level::Info));
+    }
+}
@@ -1,117 +1,320 @@
+mod activity_export;
+mod backfill;
+mod backup;
+mod cache;
+mod captions;
 mod commands;
 mod db;
+mod error;
 mod fs;
+mod logging;
+mod media;
+mod metadata;
+mod sprites;
+mod structure;
+mod video_player;
+mod watcher;
 
 use commands::{
     create_app_state,
+    get_app_info,
+    get_schema_version,
+    force_migrate,
     scan_courses,
     get_all_courses,
+    get_courses_with_progress,
+    get_home_courses,
+    get_archive_candidates,
     get_course_modules,
+    get_course_module_tree,
+    get_course_modules_with_counts,
+    get_course_outline,
+    get_video_location,
+    get_recommended_next_module,
     get_module_videos,
+    merge_modules,
+    move_video_to_module,
+    organize_videos_by_pattern,
+    normalize_ordering,
     get_video_progress,
     update_video_progress,
     get_recent_videos,
+    get_progress_since,
+    get_last_watched_per_course,
+    import_progress_from,
+    import_completion_checklist,
+    get_recommendations,
+    get_watch_history,
+    estimate_daily_watch_time,
+    get_completion_timeline,
+    get_streak_status,
     play_video,
     pause_video,
     resume_video,
     seek_video,
     stop_video,
     get_video_status,
+    get_suspect_videos,
+    find_duplicate_videos_by_name,
+    backfill_durations,
+    cancel_backfill,
+    open_containing_folder,
+    get_database_location,
+    open_database_folder,
+    get_file_metadata,
+    compute_file_hash,
     select_course_directory,
     scan_custom_directory,
+    scan_custom_directory_with_report,
+    preview_scan,
+    rescan_course,
     update_course_last_accessed,
+    rename_course,
+    set_course_description,
+    get_course_detail,
+    get_video_detail,
+    set_selected_subtitle,
+    toggle_video_favorite,
+    get_favorite_videos,
+    get_largest_videos,
+    set_video_rating,
+    get_top_rated_videos,
+    get_course_cover,
+    get_course_duration,
+    get_course_time_remaining,
+    get_global_summary,
     scan_folder_content,
+    scan_folder_page,
     get_folder_playlist,
+    get_course_folder_tree,
     // Novos comandos para anotações
     create_user_note,
     update_user_note,
+    bulk_update_notes,
     delete_user_note,
+    clear_course_annotations,
+    restore_note,
     get_notes_by_video,
+    search_notes_in_video,
+    get_notes_for_bookmark,
     get_notes_by_course,
+    get_notes_by_module,
+    get_course_level_notes,
     get_all_notes,
+    get_notes_by_color,
+    clone_note,
+    get_note_type_counts,
+    get_note_keyword_frequencies,
+    get_most_annotated_videos,
     // Novos comandos para bookmarks
     create_video_bookmark,
     delete_video_bookmark,
+    restore_bookmark,
+    purge_deleted,
     get_video_bookmarks,
+    get_adjacent_bookmarks,
+    get_course_bookmarks,
+    export_bookmark_clip,
+    export_course_structure,
+    export_notes_vtt,
+    generate_scrub_sprites,
+    backup_database,
+    // Comando para criptografia do banco
+    change_database_key,
     // Novos comandos para configurações
     set_user_setting,
+    validate_setting,
     get_user_setting,
+    get_json_setting,
+    set_json_setting,
     get_all_user_settings,
+    get_playback_settings,
+    set_theme,
+    get_theme,
     initialize_default_settings,
     // Novos comandos para logs de atividade
     get_recent_activities,
+    export_activity_log,
     get_activities_by_type,
     log_user_activity,
     // Comandos para conclusão de vídeos
     mark_video_completed,
     mark_video_incomplete,
+    reset_progress,
+    find_inconsistent_progress,
+    find_dangling_note_timestamps,
+    clamp_note_timestamps,
+    repair_progress_durations,
+    find_orphans,
+    delete_orphans,
     get_completed_videos,
+    get_next_incomplete_video,
+    resume_course,
+    get_course_intro_video,
     get_incomplete_videos,
     get_course_completion_stats,
+    get_course_badges,
+    get_course_activity_summary,
     get_video_by_path,
 };
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
+    logging::init();
     let app_state = create_app_state().expect("Falha ao criar estado da aplicação");
     
     tauri::Builder::default()
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_dialog::init())
         .manage(app_state)
+        .setup(|app| {
+            // Reescaneia cursos quando arquivos de vídeo mudam nas pastas monitoradas
+            if let Err(e) = watcher::start(app.handle().clone()) {
+                log::warn!("⚠️ Não foi possível iniciar o monitoramento de pastas: {}", e);
+            }
+            Ok(())
+        })
         .invoke_handler(tauri::generate_handler![
-            greet,
+            get_app_info,
+            get_schema_version,
+            force_migrate,
             scan_courses,
             get_all_courses,
+            get_courses_with_progress,
+            get_home_courses,
+            get_archive_candidates,
             get_course_modules,
+            get_course_module_tree,
+            get_course_modules_with_counts,
+            get_course_outline,
+            get_video_location,
+            get_recommended_next_module,
             get_module_videos,
+            merge_modules,
+            move_video_to_module,
+            organize_videos_by_pattern,
+            normalize_ordering,
             get_video_progress,
             update_video_progress,
             get_recent_videos,
+            get_progress_since,
+            get_last_watched_per_course,
+            import_progress_from,
+            import_completion_checklist,
+            get_recommendations,
+            get_watch_history,
+            estimate_daily_watch_time,
+            get_completion_timeline,
+            get_streak_status,
             play_video,
             pause_video,
             resume_video,
             seek_video,
             stop_video,
             get_video_status,
+            get_suspect_videos,
+            find_duplicate_videos_by_name,
+            backfill_durations,
+            cancel_backfill,
+            open_containing_folder,
+            get_database_location,
+            open_database_folder,
+            get_file_metadata,
+            compute_file_hash,
             select_course_directory,
             scan_custom_directory,
+            scan_custom_directory_with_report,
+            preview_scan,
+            rescan_course,
             update_course_last_accessed,
+            rename_course,
+            set_course_description,
+            get_course_detail,
+            get_video_detail,
+            set_selected_subtitle,
+            toggle_video_favorite,
+            get_favorite_videos,
+            get_largest_videos,
+            set_video_rating,
+            get_top_rated_videos,
+            get_course_cover,
+            get_course_duration,
+            get_course_time_remaining,
+            get_global_summary,
             scan_folder_content,
+            scan_folder_page,
             get_folder_playlist,
+            get_course_folder_tree,
             // Comandos para anotações
             create_user_note,
             update_user_note,
+            bulk_update_notes,
             delete_user_note,
+            clear_course_annotations,
+            restore_note,
             get_notes_by_video,
+            search_notes_in_video,
+            get_notes_for_bookmark,
             get_notes_by_course,
+            get_notes_by_module,
+            get_course_level_notes,
             get_all_notes,
+            get_notes_by_color,
+            clone_note,
+            get_note_type_counts,
+            get_note_keyword_frequencies,
+            get_most_annotated_videos,
             // Comandos para bookmarks
             create_video_bookmark,
             delete_video_bookmark,
+            restore_bookmark,
+            purge_deleted,
             get_video_bookmarks,
+            get_adjacent_bookmarks,
+            get_course_bookmarks,
+            export_bookmark_clip,
+            export_course_structure,
+            export_notes_vtt,
+            generate_scrub_sprites,
+            backup_database,
+            // Comando para criptografia do banco
+            change_database_key,
             // Comandos para configurações
             set_user_setting,
+            validate_setting,
             get_user_setting,
+            get_json_setting,
+            set_json_setting,
             get_all_user_settings,
+            get_playback_settings,
+            set_theme,
+            get_theme,
             initialize_default_settings,
             // Comandos para logs de atividade
             get_recent_activities,
+            export_activity_log,
             get_activities_by_type,
             log_user_activity,
             // Comandos para conclusão de vídeos
             mark_video_completed,
             mark_video_incomplete,
+            reset_progress,
+            find_inconsistent_progress,
+            find_dangling_note_timestamps,
+            clamp_note_timestamps,
+            repair_progress_durations,
+            find_orphans,
+            delete_orphans,
             get_completed_videos,
+            get_next_incomplete_video,
+            resume_course,
+            get_course_intro_video,
             get_incomplete_videos,
             get_course_completion_stats,
+            get_course_badges,
+            get_course_activity_summary,
             get_video_by_path
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
 }
-
-#[tauri::command]
-fn greet(name: &str) -> String {
-    format!("Hello, {}! You've been greeted from Rust!", name)
-}
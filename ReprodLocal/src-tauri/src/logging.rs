@@ -0,0 +1,36 @@
+use log::LevelFilter;
+use std::str::FromStr;
+
+const DEFAULT_LEVEL: LevelFilter = LevelFilter::Info;
+
+// Inicializa o logger global. Com RUST_LOG definido, ele manda nos filtros;
+// caso contrário o nível vem da configuração `log_level` do usuário,
+// aplicada depois com `apply_user_level`
+pub fn init() {
+    let mut builder = env_logger::Builder::new();
+    if env_overrides_level() {
+        builder.parse_env("RUST_LOG");
+    } else {
+        builder.filter_level(LevelFilter::Trace);
+    }
+
+    if builder.try_init().is_ok() && !env_overrides_level() {
+        log::set_max_level(DEFAULT_LEVEL);
+    }
+}
+
+// Aplica o nível salvo em `log_level` ("error", "warn", "info", "debug", "trace", "off")
+pub fn apply_user_level(level: &str) {
+    if env_overrides_level() {
+        return;
+    }
+
+    match LevelFilter::from_str(level.trim()) {
+        Ok(filter) => log::set_max_level(filter),
+        Err(_) => log::warn!("Nível de log inválido em log_level: {}", level),
+    }
+}
+
+fn env_overrides_level() -> bool {
+    std::env::var_os("RUST_LOG").is_some_and(|value| !value.is_empty())
+}
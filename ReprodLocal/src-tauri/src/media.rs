@@ -0,0 +1,140 @@
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use crate::error::AppError;
+
+// Procura um executável (ffmpeg, ffprobe...) nos diretórios do PATH
+pub fn find_executable(name: &str) -> Result<PathBuf, AppError> {
+    let path_var = std::env::var_os("PATH").unwrap_or_default();
+    find_executable_in(name, &path_var)
+}
+
+fn find_executable_in(name: &str, path_var: &OsString) -> Result<PathBuf, AppError> {
+    let candidates: Vec<String> = if cfg!(windows) {
+        vec![format!("{}.exe", name), name.to_string()]
+    } else {
+        vec![name.to_string()]
+    };
+
+    std::env::split_paths(path_var)
+        .flat_map(|dir| candidates.iter().map(move |c| dir.join(c)))
+        .find(|p| p.is_file())
+        .ok_or_else(|| AppError::ToolNotFound(format!("{} não encontrado no PATH", name)))
+}
+
+// Duração do vídeo em segundos lida pelo ffprobe; falha se o arquivo não puder ser lido
+pub fn probe_duration(ffprobe: &Path, video_path: &Path) -> Result<f64, AppError> {
+    let output = Command::new(ffprobe)
+        .args(["-v", "error", "-show_entries", "format=duration", "-of", "default=noprint_wrappers=1:nokey=1"])
+        .arg(video_path)
+        .output()
+        .map_err(|e| AppError::ToolFailed(format!("Erro ao executar ffprobe: {}", e)))?;
+
+    if !output.status.success() {
+        return Err(AppError::ToolFailed(format!(
+            "ffprobe não conseguiu ler {}: {}",
+            video_path.display(),
+            String::from_utf8_lossy(&output.stderr).lines().last().unwrap_or("")
+        )));
+    }
+
+    String::from_utf8_lossy(&output.stdout)
+        .trim()
+        .parse::<f64>()
+        .map_err(|_| AppError::ToolFailed(format!("ffprobe não retornou a duração de {}", video_path.display())))
+}
+
+// Trecho a ser recortado de um vídeo, em segundos
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClipRange {
+    pub start: f64,
+    pub duration: f64,
+}
+
+impl ClipRange {
+    // Intervalo [timestamp - pre, timestamp + post], com início limitado a 0
+    pub fn around(timestamp: f64, pre_seconds: f64, post_seconds: f64) -> Result<Self, AppError> {
+        if pre_seconds < 0.0 || post_seconds < 0.0 {
+            return Err(AppError::InvalidInput("Os intervalos antes/depois não podem ser negativos".to_string()));
+        }
+
+        let start = (timestamp - pre_seconds).max(0.0);
+        let end = timestamp + post_seconds;
+        Ok(Self { start, duration: end - start })
+    }
+
+    pub fn ffmpeg_args(&self, input: &str, output: &str) -> Vec<String> {
+        vec![
+            "-y".to_string(),
+            "-ss".to_string(),
+            format!("{:.3}", self.start),
+            "-i".to_string(),
+            input.to_string(),
+            "-t".to_string(),
+            format!("{:.3}", self.duration),
+            output.to_string(),
+        ]
+    }
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct ClipExport {
+    pub out_path: String,
+    pub duration: f64,
+}
+
+pub fn export_clip(video_path: &str, range: &ClipRange, out_path: &str) -> Result<ClipExport, AppError> {
+    if !Path::new(video_path).exists() {
+        return Err(AppError::NotFound(format!("Arquivo de vídeo não encontrado: {}", video_path)));
+    }
+
+    let ffmpeg = find_executable("ffmpeg")?;
+    let output = Command::new(ffmpeg)
+        .args(range.ffmpeg_args(video_path, out_path))
+        .output()
+        .map_err(|e| AppError::ToolFailed(format!("Erro ao executar ffmpeg: {}", e)))?;
+
+    if !output.status.success() {
+        return Err(AppError::ToolFailed(format!(
+            "ffmpeg falhou: {}",
+            String::from_utf8_lossy(&output.stderr).lines().last().unwrap_or("")
+        )));
+    }
+
+    Ok(ClipExport {
+        out_path: out_path.to_string(),
+        duration: range.duration,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clip_args_seek_and_duration() {
+        let range = ClipRange::around(120.0, 5.0, 10.0).unwrap();
+        assert_eq!(range, ClipRange { start: 115.0, duration: 15.0 });
+        assert_eq!(
+            range.ffmpeg_args("/cursos/aula.mp4", "/tmp/clip.mp4"),
+            vec!["-y", "-ss", "115.000", "-i", "/cursos/aula.mp4", "-t", "15.000", "/tmp/clip.mp4"]
+        );
+
+        // O início é limitado a 0 perto do começo do vídeo
+        let range = ClipRange::around(3.0, 5.0, 2.0).unwrap();
+        assert_eq!(range, ClipRange { start: 0.0, duration: 5.0 });
+    }
+
+    #[test]
+    fn test_clip_rejects_negative_offsets() {
+        assert!(matches!(ClipRange::around(10.0, -1.0, 5.0), Err(AppError::InvalidInput(_))));
+        assert!(matches!(ClipRange::around(10.0, 1.0, -5.0), Err(AppError::InvalidInput(_))));
+    }
+
+    #[test]
+    fn test_missing_ffmpeg_is_tool_not_found() {
+        let empty_dir = tempfile::TempDir::new().unwrap();
+        let path_var = OsString::from(empty_dir.path());
+        assert!(matches!(find_executable_in("ffmpeg", &path_var), Err(AppError::ToolNotFound(_))));
+    }
+}
@@ -0,0 +1,122 @@
+use std::path::{Path, PathBuf};
+use serde::Deserialize;
+
+// Arquivos de metadados de um curso, procurados na pasta do curso
+const COURSE_METADATA_FILES: &[&str] = &["info.json", "course.json", "course.nfo", "info.nfo"];
+
+// Título e descrição lidos de um arquivo de metadados (.nfo ou .json)
+#[derive(Debug, Default, Clone, PartialEq, Deserialize)]
+pub struct SidecarMetadata {
+    #[serde(default)]
+    pub title: Option<String>,
+    #[serde(default)]
+    pub description: Option<String>,
+}
+
+impl SidecarMetadata {
+    fn cleaned(self) -> Self {
+        let clean = |value: Option<String>| value.map(|v| v.trim().to_string()).filter(|v| !v.is_empty());
+        Self {
+            title: clean(self.title),
+            description: clean(self.description),
+        }
+    }
+}
+
+// Metadados de um vídeo: `aula.nfo`, `aula.info.json` ou `aula.json` ao lado de `aula.mp4`
+pub fn find_video_metadata(video_path: &Path) -> Option<SidecarMetadata> {
+    let stem = video_path.file_stem()?.to_string_lossy();
+    let dir = video_path.parent()?;
+    let candidates: Vec<PathBuf> = ["nfo", "info.json", "json"]
+        .iter()
+        .map(|ext| dir.join(format!("{}.{}", stem, ext)))
+        .collect();
+    read_first(&candidates)
+}
+
+// Metadados do curso, em `info.json`, `course.json`, `course.nfo` ou `info.nfo`
+pub fn find_course_metadata(course_path: &Path) -> Option<SidecarMetadata> {
+    let candidates: Vec<PathBuf> = COURSE_METADATA_FILES.iter().map(|name| course_path.join(name)).collect();
+    read_first(&candidates)
+}
+
+fn read_first(candidates: &[PathBuf]) -> Option<SidecarMetadata> {
+    candidates.iter().filter(|path| path.is_file()).find_map(|path| {
+        let text = match std::fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(e) => {
+                log::warn!("⚠️ Não foi possível ler metadados {}: {}", path.display(), e);
+                return None;
+            }
+        };
+        let is_json = path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("json"));
+        let metadata = if is_json { parse_json(&text) } else { Some(parse_nfo(&text)) };
+        if metadata.is_none() {
+            log::warn!("⚠️ Metadados inválidos ignorados: {}", path.display());
+        }
+        metadata.filter(|m| m.title.is_some() || m.description.is_some())
+    })
+}
+
+// JSON no formato `{ "title": ..., "description": ... }`
+pub fn parse_json(text: &str) -> Option<SidecarMetadata> {
+    serde_json::from_str::<SidecarMetadata>(text).ok().map(SidecarMetadata::cleaned)
+}
+
+// .nfo simples com linhas `chave: valor` ou `chave = valor`; chaves
+// desconhecidas e linhas sem separador são ignoradas
+pub fn parse_nfo(text: &str) -> SidecarMetadata {
+    let mut metadata = SidecarMetadata::default();
+
+    for line in text.lines() {
+        let Some((key, value)) = line.split_once([':', '=']) else {
+            continue;
+        };
+        let value = Some(value.to_string());
+        match key.trim().to_lowercase().as_str() {
+            "title" | "titulo" | "título" if metadata.title.is_none() => metadata.title = value,
+            "description" | "descricao" | "descrição" | "plot" if metadata.description.is_none() => {
+                metadata.description = value
+            }
+            _ => {}
+        }
+    }
+
+    metadata.cleaned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_parse_nfo_key_values() {
+        let metadata = parse_nfo("# exportado\nTitle: Introdução ao Rust \nplot = Tipos, traits e lifetimes\ntitle: ignorado\nsem separador\n");
+        assert_eq!(metadata.title.as_deref(), Some("Introdução ao Rust"));
+        assert_eq!(metadata.description.as_deref(), Some("Tipos, traits e lifetimes"));
+
+        assert_eq!(parse_nfo("title:\n"), SidecarMetadata::default());
+    }
+
+    #[test]
+    fn test_parse_json_metadata() {
+        let metadata = parse_json(r#"{"title": "Aula 1", "description": "", "extra": 1}"#).unwrap();
+        assert_eq!(metadata.title.as_deref(), Some("Aula 1"));
+        assert!(metadata.description.is_none());
+
+        assert!(parse_json("não é json").is_none());
+    }
+
+    #[test]
+    fn test_find_video_metadata_next_to_video() {
+        let temp_dir = TempDir::new().unwrap();
+        let video = temp_dir.path().join("01-intro.mp4");
+        std::fs::write(&video, "fake video content").unwrap();
+        assert!(find_video_metadata(&video).is_none());
+
+        std::fs::write(temp_dir.path().join("01-intro.info.json"), r#"{"title": "Do JSON"}"#).unwrap();
+        std::fs::write(temp_dir.path().join("01-intro.nfo"), "title: Do NFO").unwrap();
+        assert_eq!(find_video_metadata(&video).unwrap().title.as_deref(), Some("Do NFO"));
+    }
+}
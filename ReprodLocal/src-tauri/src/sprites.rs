@@ -0,0 +1,225 @@
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use serde::Serialize;
+use crate::error::AppError;
+
+// Tamanho de cada miniatura e quantas cabem por linha na imagem
+const THUMB_WIDTH: u32 = 160;
+const THUMB_HEIGHT: u32 = 90;
+const SPRITE_COLUMNS: u32 = 10;
+
+// Disposição das miniaturas de um vídeo na imagem de sprites
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpriteLayout {
+    pub duration: f64,
+    pub interval: f64,
+    pub frame_count: u32,
+    pub columns: u32,
+    pub rows: u32,
+}
+
+impl SpriteLayout {
+    // Uma miniatura a cada `interval` segundos, cobrindo o vídeo inteiro
+    pub fn new(duration: f64, interval: f64) -> Result<Self, AppError> {
+        if !interval.is_finite() || interval <= 0.0 {
+            return Err(AppError::InvalidInput("O intervalo entre miniaturas deve ser positivo".to_string()));
+        }
+        if !duration.is_finite() || duration <= 0.0 {
+            return Err(AppError::InvalidInput("Duração do vídeo desconhecida".to_string()));
+        }
+
+        let frame_count = (duration / interval).ceil() as u32;
+        let columns = frame_count.min(SPRITE_COLUMNS);
+        Ok(Self {
+            duration,
+            interval,
+            frame_count,
+            columns,
+            rows: frame_count.div_ceil(columns),
+        })
+    }
+
+    // Canto superior esquerdo da miniatura `index` na imagem
+    pub fn frame_position(&self, index: u32) -> (u32, u32) {
+        ((index % self.columns) * THUMB_WIDTH, (index / self.columns) * THUMB_HEIGHT)
+    }
+
+    // Índice WebVTT: cada trecho do vídeo aponta para a região da sua miniatura
+    pub fn to_vtt(&self, sprite_file_name: &str) -> String {
+        let mut vtt = String::from("WEBVTT\n");
+        for index in 0..self.frame_count {
+            let start = index as f64 * self.interval;
+            let end = (start + self.interval).min(self.duration);
+            let (x, y) = self.frame_position(index);
+            vtt.push_str(&format!(
+                "\n{} --> {}\n{}#xywh={},{},{},{}\n",
+                vtt_timestamp(start), vtt_timestamp(end), sprite_file_name, x, y, THUMB_WIDTH, THUMB_HEIGHT
+            ));
+        }
+        vtt
+    }
+
+    pub fn ffmpeg_args(&self, input: &Path, output: &Path) -> Vec<String> {
+        vec![
+            "-y".to_string(),
+            "-i".to_string(),
+            input.to_string_lossy().to_string(),
+            "-vf".to_string(),
+            format!(
+                "fps=1/{},scale={}:{},tile={}x{}",
+                self.interval, THUMB_WIDTH, THUMB_HEIGHT, self.columns, self.rows
+            ),
+            "-frames:v".to_string(),
+            "1".to_string(),
+            output.to_string_lossy().to_string(),
+        ]
+    }
+}
+
+// HH:MM:SS.mmm
+fn vtt_timestamp(seconds: f64) -> String {
+    let millis = (seconds * 1000.0).round() as u64;
+    format!(
+        "{:02}:{:02}:{:02}.{:03}",
+        millis / 3_600_000, (millis / 60_000) % 60, (millis / 1000) % 60, millis % 1000
+    )
+}
+
+// Gera a imagem de sprites; trocado por um falso nos testes
+pub trait FrameExtractor {
+    fn extract_sprite(&self, video_path: &Path, layout: &SpriteLayout, out_path: &Path) -> Result<(), AppError>;
+}
+
+pub struct FfmpegExtractor {
+    pub ffmpeg: PathBuf,
+}
+
+impl FrameExtractor for FfmpegExtractor {
+    fn extract_sprite(&self, video_path: &Path, layout: &SpriteLayout, out_path: &Path) -> Result<(), AppError> {
+        let output = Command::new(&self.ffmpeg)
+            .args(layout.ffmpeg_args(video_path, out_path))
+            .output()
+            .map_err(|e| AppError::ToolFailed(format!("Erro ao executar ffmpeg: {}", e)))?;
+
+        if !output.status.success() {
+            return Err(AppError::ToolFailed(format!(
+                "ffmpeg falhou: {}",
+                String::from_utf8_lossy(&output.stderr).lines().last().unwrap_or("")
+            )));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct ScrubSprites {
+    pub sprite_path: String,
+    pub vtt_path: String,
+    pub frame_count: u32,
+    pub cached: bool,
+}
+
+// Gera (ou reaproveita do cache) os sprites de um vídeo. Os arquivos ficam em
+// `cache_dir`, nomeados pelo id do vídeo e pelo intervalo
+pub fn generate_scrub_sprites(
+    extractor: &dyn FrameExtractor,
+    video_id: &str,
+    video_path: &Path,
+    duration: f64,
+    interval: f64,
+    cache_dir: &Path,
+) -> Result<ScrubSprites, AppError> {
+    let layout = SpriteLayout::new(duration, interval)?;
+    let base_name = format!("{}-{}", video_id, interval);
+    let sprite_name = format!("{}.jpg", base_name);
+    let sprite_path = cache_dir.join(&sprite_name);
+    let vtt_path = cache_dir.join(format!("{}.vtt", base_name));
+
+    let cached = sprite_path.is_file() && vtt_path.is_file();
+    if cached {
+        log::debug!("🖼️ Sprites em cache: {}", sprite_path.display());
+    } else {
+        if !video_path.exists() {
+            return Err(AppError::NotFound(format!("Arquivo de vídeo não encontrado: {}", video_path.display())));
+        }
+        std::fs::create_dir_all(cache_dir)
+            .map_err(|e| AppError::ToolFailed(format!("Erro ao criar {}: {}", cache_dir.display(), e)))?;
+
+        log::info!("🖼️ Gerando {} miniaturas de {} (a cada {}s)", layout.frame_count, video_path.display(), interval);
+        extractor.extract_sprite(video_path, &layout, &sprite_path)?;
+        // O índice é gravado por último: sem ele, a próxima chamada gera tudo de novo
+        std::fs::write(&vtt_path, layout.to_vtt(&sprite_name))
+            .map_err(|e| AppError::ToolFailed(format!("Erro ao gravar {}: {}", vtt_path.display(), e)))?;
+    }
+
+    Ok(ScrubSprites {
+        sprite_path: sprite_path.to_string_lossy().to_string(),
+        vtt_path: vtt_path.to_string_lossy().to_string(),
+        frame_count: layout.frame_count,
+        cached,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+    use tempfile::TempDir;
+
+    // Extrator falso: grava um arquivo vazio e conta as chamadas
+    #[derive(Default)]
+    struct FakeExtractor {
+        calls: Cell<u32>,
+    }
+
+    impl FrameExtractor for FakeExtractor {
+        fn extract_sprite(&self, _video_path: &Path, _layout: &SpriteLayout, out_path: &Path) -> Result<(), AppError> {
+            self.calls.set(self.calls.get() + 1);
+            std::fs::write(out_path, "").map_err(|e| AppError::ToolFailed(e.to_string()))
+        }
+    }
+
+    #[test]
+    fn test_vtt_index_maps_timestamps_to_tiles() {
+        // 125s a cada 10s: 13 miniaturas, a última cobre só 5s
+        let layout = SpriteLayout::new(125.0, 10.0).unwrap();
+        assert_eq!((layout.frame_count, layout.columns, layout.rows), (13, 10, 2));
+
+        let vtt = layout.to_vtt("aula.jpg");
+        let cues: Vec<&str> = vtt.split("\n\n").collect();
+        assert_eq!(cues[0], "WEBVTT");
+        assert_eq!(cues.len(), 14);
+        assert_eq!(cues[1], "00:00:00.000 --> 00:00:10.000\naula.jpg#xywh=0,0,160,90");
+        assert_eq!(cues[10], "00:01:30.000 --> 00:01:40.000\naula.jpg#xywh=1440,0,160,90");
+        assert_eq!(cues[11], "00:01:40.000 --> 00:01:50.000\naula.jpg#xywh=0,90,160,90");
+        assert_eq!(cues[13], "00:02:00.000 --> 00:02:05.000\naula.jpg#xywh=320,90,160,90\n");
+
+        assert!(matches!(SpriteLayout::new(125.0, 0.0), Err(AppError::InvalidInput(_))));
+        assert!(matches!(SpriteLayout::new(0.0, 10.0), Err(AppError::InvalidInput(_))));
+    }
+
+    #[test]
+    fn test_sprites_are_cached_per_video_and_interval() {
+        let temp_dir = TempDir::new().unwrap();
+        let video = temp_dir.path().join("aula.mp4");
+        std::fs::write(&video, "fake video content").unwrap();
+        let cache_dir = temp_dir.path().join("sprites");
+        let extractor = FakeExtractor::default();
+
+        let first = generate_scrub_sprites(&extractor, "video-1", &video, 25.0, 10.0, &cache_dir).unwrap();
+        assert!(!first.cached);
+        assert_eq!(first.frame_count, 3);
+        assert!(std::fs::read_to_string(&first.vtt_path).unwrap().contains("video-1-10.jpg#xywh=320,0,160,90"));
+
+        let again = generate_scrub_sprites(&extractor, "video-1", &video, 25.0, 10.0, &cache_dir).unwrap();
+        assert!(again.cached);
+        assert_eq!(again.sprite_path, first.sprite_path);
+        assert_eq!(extractor.calls.get(), 1);
+
+        // Outro intervalo gera outro par de arquivos
+        let other = generate_scrub_sprites(&extractor, "video-1", &video, 25.0, 5.0, &cache_dir).unwrap();
+        assert!(!other.cached);
+        assert_ne!(other.sprite_path, first.sprite_path);
+        assert_eq!(extractor.calls.get(), 2);
+    }
+}
@@ -0,0 +1,175 @@
+use std::path::Path;
+use anyhow::{Result, anyhow};
+use serde::{Deserialize, Serialize};
+use crate::db::Database;
+
+// Versão do formato do arquivo exportado
+pub const STRUCTURE_FORMAT_VERSION: u32 = 1;
+
+// Estrutura de um curso para compartilhar, sem os arquivos de vídeo. Os
+// caminhos são relativos à pasta do curso, sem a raiz desta máquina
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct CourseStructure {
+    pub format_version: u32,
+    pub name: String,
+    pub module_count: usize,
+    pub video_count: usize,
+    pub total_duration: f64, // Soma das durações conhecidas, em segundos
+    pub modules: Vec<ModuleStructure>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ModuleStructure {
+    pub name: String,
+    pub order_index: i32,
+    pub relative_path: String,
+    pub videos: Vec<VideoStructure>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct VideoStructure {
+    pub name: String,
+    pub order_index: i32,
+    pub duration: Option<f64>,
+    pub relative_path: String,
+}
+
+pub fn build_course_structure(db: &Database, course_id: &str) -> Result<CourseStructure> {
+    let course = db.get_course_by_id(course_id)?
+        .ok_or_else(|| anyhow!("Curso não encontrado: {}", course_id))?;
+    let course_root = Path::new(&course.path);
+
+    let mut modules = Vec::new();
+    for module in db.get_course_modules(course_id)? {
+        let videos = db.get_module_videos(&module.id)?
+            .into_iter()
+            .map(|video| VideoStructure {
+                relative_path: relative_to(course_root, &video.path),
+                name: video.name,
+                order_index: video.order_index,
+                duration: video.duration,
+            })
+            .collect();
+
+        modules.push(ModuleStructure {
+            relative_path: relative_to(course_root, &module.path),
+            name: module.name,
+            order_index: module.order_index,
+            videos,
+        });
+    }
+
+    let videos = modules.iter().flat_map(|m| &m.videos);
+    Ok(CourseStructure {
+        format_version: STRUCTURE_FORMAT_VERSION,
+        name: course.display_name.unwrap_or(course.name),
+        module_count: modules.len(),
+        video_count: videos.clone().count(),
+        total_duration: videos.filter_map(|v| v.duration).sum(),
+        modules,
+    })
+}
+
+// Grava a estrutura do curso como JSON em `out_path`
+pub fn export_course_structure(db: &Database, course_id: &str, out_path: &Path) -> Result<CourseStructure> {
+    let structure = build_course_structure(db, course_id)?;
+    let json = serde_json::to_string_pretty(&structure)?;
+    std::fs::write(out_path, json)
+        .map_err(|e| anyhow!("Erro ao gravar {}: {}", out_path.display(), e))?;
+
+    log::info!(
+        "📤 Estrutura do curso {} exportada para {} ({} módulos, {} vídeos)",
+        structure.name, out_path.display(), structure.module_count, structure.video_count
+    );
+    Ok(structure)
+}
+
+// Caminho relativo à pasta do curso, sempre com `/`; fora dela, só o nome do arquivo
+fn relative_to(root: &Path, path: &str) -> String {
+    let path = Path::new(path);
+    let relative = path.strip_prefix(root).unwrap_or_else(|_| {
+        path.file_name().map(Path::new).unwrap_or(path)
+    });
+    relative
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{Course, Module, Video};
+    use chrono::Utc;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_export_matches_course_hierarchy() {
+        let temp_dir = TempDir::new().unwrap();
+        let db = Database::new(&temp_dir.path().join("test.db")).unwrap();
+        db.insert_course(&Course {
+            id: "course-1".to_string(),
+            name: "Rust".to_string(),
+            path: "/home/aluno/cursos/Rust".to_string(),
+            created_at: Utc::now(),
+            last_accessed: None,
+            cover_path: None,
+            display_name: None,
+            description: None,
+        }).unwrap();
+
+        let layout = [
+            ("Aulas", "/home/aluno/cursos/Rust", vec![("intro", Some(60.0))]),
+            ("01 Básico", "/home/aluno/cursos/Rust/01 Básico", vec![("tipos", Some(300.0)), ("traits", None)]),
+        ];
+        for (module_order, (module_name, module_path, videos)) in layout.iter().enumerate() {
+            let module_id = format!("module-{}", module_order);
+            db.insert_module(&Module {
+                id: module_id.clone(),
+                course_id: "course-1".to_string(),
+                name: module_name.to_string(),
+                path: module_path.to_string(),
+                order_index: module_order as i32,
+            }).unwrap();
+            for (video_order, (video_name, duration)) in videos.iter().enumerate() {
+                db.insert_video(&Video {
+                    id: format!("{}-{}", module_id, video_name),
+                    module_id: module_id.clone(),
+                    course_id: "course-1".to_string(),
+                    name: video_name.to_string(),
+                    path: format!("{}/{}.mp4", module_path, video_name),
+                    duration: *duration,
+                    order_index: video_order as i32,
+                }).unwrap();
+            }
+        }
+
+        let out_path = temp_dir.path().join("rust.json");
+        let exported = export_course_structure(&db, "course-1", &out_path).unwrap();
+        let from_file: CourseStructure = serde_json::from_str(&std::fs::read_to_string(&out_path).unwrap()).unwrap();
+        assert_eq!(from_file, exported);
+
+        assert_eq!(exported.name, "Rust");
+        assert_eq!((exported.module_count, exported.video_count), (2, 3));
+        assert_eq!(exported.total_duration, 360.0);
+
+        let modules: Vec<(&str, &str)> = exported.modules.iter()
+            .map(|m| (m.name.as_str(), m.relative_path.as_str()))
+            .collect();
+        assert_eq!(modules, vec![("Aulas", ""), ("01 Básico", "01 Básico")]);
+
+        let videos: Vec<(&str, i32, &str)> = exported.modules.iter()
+            .flat_map(|m| &m.videos)
+            .map(|v| (v.name.as_str(), v.order_index, v.relative_path.as_str()))
+            .collect();
+        assert_eq!(videos, vec![
+            ("intro", 0, "intro.mp4"),
+            ("tipos", 0, "01 Básico/tipos.mp4"),
+            ("traits", 1, "01 Básico/traits.mp4"),
+        ]);
+        assert!(!std::fs::read_to_string(&out_path).unwrap().contains("/home/aluno"));
+
+        assert!(export_course_structure(&db, "inexistente", &out_path).is_err());
+    }
+}
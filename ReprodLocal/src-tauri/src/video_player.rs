@@ -2,10 +2,58 @@ use anyhow::{Result, anyhow};
 use std::process::{Command, Child};
 use std::path::Path;
 use crate::commands::VideoStatus;
+use crate::error::AppError;
+
+// Player externo configurado pelas configurações `external_player_path`
+// e `external_player_args`; sem caminho, usa o player padrão do sistema
+#[derive(Debug, Clone, Default)]
+pub struct ExternalPlayerConfig {
+    pub path: Option<String>,
+    pub args: Option<String>,
+}
+
+impl ExternalPlayerConfig {
+    // Monta o comando do player externo. Nos argumentos, `{file}` é trocado
+    // pelo caminho do vídeo (que vai no final se não houver o marcador) e
+    // `--start=<segundos>` é adicionado quando há posição para retomar
+    pub fn build_command(&self, video_path: &str, start_time: Option<f64>) -> std::result::Result<Option<Command>, AppError> {
+        let player_path = match self.path.as_deref().map(str::trim) {
+            Some(path) if !path.is_empty() => path,
+            _ => return Ok(None),
+        };
+
+        if !Path::new(player_path).is_file() {
+            return Err(AppError::ToolNotFound(format!("Player externo não encontrado: {}", player_path)));
+        }
+
+        let mut args: Vec<String> = Vec::new();
+        if let Some(start) = start_time.filter(|t| *t > 0.0) {
+            args.push(format!("--start={}", start));
+        }
+
+        let mut has_file_placeholder = false;
+        for arg in self.args.as_deref().unwrap_or("").split_whitespace() {
+            if arg.contains("{file}") {
+                has_file_placeholder = true;
+                args.push(arg.replace("{file}", video_path));
+            } else {
+                args.push(arg.to_string());
+            }
+        }
+        if !has_file_placeholder {
+            args.push(video_path.to_string());
+        }
+
+        let mut cmd = Command::new(player_path);
+        cmd.args(&args);
+        Ok(Some(cmd))
+    }
+}
 
 pub struct VideoPlayer {
     current_file: Option<String>,
     process: Option<Child>,
+    external_player: ExternalPlayerConfig,
     is_playing: bool,
     current_time: f64,
     duration: f64,
@@ -17,6 +65,7 @@ impl VideoPlayer {
         Self {
             current_file: None,
             process: None,
+            external_player: ExternalPlayerConfig::default(),
             is_playing: false,
             current_time: 0.0,
             duration: 0.0,
@@ -24,6 +73,10 @@ impl VideoPlayer {
         }
     }
 
+    pub fn set_external_player(&mut self, config: ExternalPlayerConfig) {
+        self.external_player = config;
+    }
+
     pub fn play(&mut self, video_path: &str, start_time: Option<f64>) -> Result<()> {
         let path = Path::new(video_path);
         if !path.exists() {
@@ -33,11 +86,13 @@ impl VideoPlayer {
         // Para por qualquer reprodução anterior
         self.stop()?;
 
-        // Por enquanto, usa o player padrão do sistema
+        // Usa o player externo configurado ou, sem configuração, o player padrão do sistema
         // Futuramente será substituído por mpv ou VLC integrado
-        let mut cmd = if cfg!(target_os = "windows") {
+        let mut cmd = if let Some(cmd) = self.external_player.build_command(video_path, start_time)? {
+            cmd
+        } else if cfg!(target_os = "windows") {
             let mut c = Command::new("cmd");
-            c.args(&["/C", "start", "", video_path]);
+            c.args(["/C", "start", "", video_path]);
             c
         } else if cfg!(target_os = "macos") {
             let mut c = Command::new("open");
@@ -105,16 +160,19 @@ impl VideoPlayer {
         })
     }
 
+    #[allow(dead_code)] // Ainda não exposto como comando
     pub fn set_volume(&mut self, volume: f64) -> Result<()> {
         self.volume = volume.clamp(0.0, 1.0);
         log::info!("Volume definido para: {}", self.volume);
         Ok(())
     }
 
+    #[allow(dead_code)]
     pub fn get_current_file(&self) -> Option<&String> {
         self.current_file.as_ref()
     }
 
+    #[allow(dead_code)]
     pub fn is_playing(&self) -> bool {
         self.is_playing
     }
@@ -216,6 +274,40 @@ mod tests {
         assert_eq!(status.volume, 1.0);
     }
 
+    #[test]
+    fn test_external_player_argv_with_start_time() {
+        let mut fake_player = NamedTempFile::new().unwrap();
+        writeln!(fake_player, "#!/bin/sh").unwrap();
+        let player_path = fake_player.path().to_string_lossy().to_string();
+
+        let config = ExternalPlayerConfig {
+            path: Some(player_path.clone()),
+            args: Some("--fullscreen --title={file}".to_string()),
+        };
+        let cmd = config.build_command("/cursos/aula 1.mp4", Some(90.5)).unwrap().unwrap();
+        let args: Vec<String> = cmd.get_args().map(|a| a.to_string_lossy().to_string()).collect();
+
+        assert_eq!(cmd.get_program().to_string_lossy(), player_path);
+        assert_eq!(args, vec!["--start=90.5", "--fullscreen", "--title=/cursos/aula 1.mp4"]);
+
+        // Sem marcador {file} e sem posição inicial, o arquivo vai no final
+        let config = ExternalPlayerConfig { path: Some(player_path), args: None };
+        let cmd = config.build_command("/cursos/aula.mp4", Some(0.0)).unwrap().unwrap();
+        let args: Vec<String> = cmd.get_args().map(|a| a.to_string_lossy().to_string()).collect();
+        assert_eq!(args, vec!["/cursos/aula.mp4"]);
+    }
+
+    #[test]
+    fn test_external_player_unset_or_missing() {
+        assert!(ExternalPlayerConfig::default().build_command("/cursos/aula.mp4", None).unwrap().is_none());
+
+        let config = ExternalPlayerConfig {
+            path: Some("/caminho/inexistente/mpv".to_string()),
+            args: None,
+        };
+        assert!(matches!(config.build_command("/cursos/aula.mp4", None), Err(AppError::ToolNotFound(_))));
+    }
+
     #[test]
     fn test_volume_control() {
         let mut player = VideoPlayer::new();
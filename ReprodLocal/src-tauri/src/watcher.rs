@@ -0,0 +1,228 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::time::{Duration, Instant};
+use anyhow::{Result, anyhow};
+use notify::event::ModifyKind;
+use notify::{Event, EventKind, RecursiveMode, Watcher};
+use tauri::{AppHandle, Emitter, Manager};
+use crate::commands::AppState;
+use crate::db::{Course, Database};
+use crate::fs::{FileSystemScanner, get_default_course_directories};
+
+// Evento enviado ao frontend com os ids dos cursos reescaneados
+pub const COURSES_CHANGED_EVENT: &str = "courses://changed";
+
+// Tempo sem novos eventos antes de reescanear um curso, para que uma cópia
+// grande gere um único reescaneamento
+const DEBOUNCE_DELAY: Duration = Duration::from_secs(2);
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+// Agrupa eventos em sequência por curso
+pub struct RescanDebouncer {
+    delay: Duration,
+    pending: HashMap<String, Instant>,
+}
+
+impl RescanDebouncer {
+    pub fn new(delay: Duration) -> Self {
+        Self { delay, pending: HashMap::new() }
+    }
+
+    // Cada novo evento adia o reescaneamento do curso
+    pub fn record(&mut self, course_id: &str, at: Instant) {
+        self.pending.insert(course_id.to_string(), at);
+    }
+
+    // Cursos sem eventos há pelo menos `delay`, removidos da fila
+    pub fn take_ready(&mut self, now: Instant) -> Vec<String> {
+        let ready: Vec<String> = self.pending
+            .iter()
+            .filter(|(_, last_event)| now.saturating_duration_since(**last_event) >= self.delay)
+            .map(|(course_id, _)| course_id.clone())
+            .collect();
+        for course_id in &ready {
+            self.pending.remove(course_id);
+        }
+        ready
+    }
+}
+
+// Inicia o monitoramento das pastas de cursos numa thread própria, se a
+// configuração `watch_course_folders` estiver ativa
+pub fn start(app: AppHandle) -> Result<()> {
+    let roots = {
+        let state = app.state::<AppState>();
+        let db = state.db.lock().map_err(|e| anyhow!("Erro ao acessar banco: {}", e))?;
+
+        let enabled = db.get_user_setting("watch_course_folders")?
+            .map(|setting| setting.setting_value.trim() != "false")
+            .unwrap_or(true);
+        if !enabled {
+            log::info!("👀 Monitoramento de pastas desativado");
+            return Ok(());
+        }
+        watch_roots(&db)?
+    };
+
+    let (tx, rx) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(tx)?;
+    for root in &roots {
+        match watcher.watch(root, RecursiveMode::Recursive) {
+            Ok(_) => log::info!("👀 Monitorando pasta: {}", root.display()),
+            Err(e) => log::warn!("⚠️ Não foi possível monitorar {}: {}", root.display(), e),
+        }
+    }
+
+    std::thread::spawn(move || {
+        // O watcher para de enviar eventos quando é descartado
+        let _watcher = watcher;
+        let mut debouncer = RescanDebouncer::new(DEBOUNCE_DELAY);
+
+        loop {
+            match rx.recv_timeout(POLL_INTERVAL) {
+                Ok(Ok(event)) => {
+                    let state = app.state::<AppState>();
+                    let Ok(db) = state.db.lock() else { break };
+                    if let Err(e) = record_event(&db, &event, Instant::now(), &mut debouncer) {
+                        log::warn!("⚠️ Erro ao processar evento de arquivo: {}", e);
+                    }
+                }
+                Ok(Err(e)) => log::warn!("⚠️ Erro do monitoramento de pastas: {}", e),
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => break,
+            }
+
+            let ready = debouncer.take_ready(Instant::now());
+            if ready.is_empty() {
+                continue;
+            }
+
+            let changed = {
+                let state = app.state::<AppState>();
+                let Ok(db) = state.db.lock() else { break };
+                state.cache.invalidate();
+                rescan_courses(&db, &ready)
+            };
+            if !changed.is_empty() {
+                app.emit(COURSES_CHANGED_EVENT, changed).ok();
+            }
+        }
+    });
+
+    Ok(())
+}
+
+// Pastas padrão de cursos mais as pastas de cursos cadastrados fora delas
+fn watch_roots(db: &Database) -> Result<Vec<PathBuf>> {
+    let mut roots = get_default_course_directories();
+    for course in db.get_all_courses()? {
+        let path = PathBuf::from(&course.path);
+        if path.is_dir() && !roots.iter().any(|root| path.starts_with(root)) {
+            roots.push(path);
+        }
+    }
+    Ok(roots)
+}
+
+// Registra no debouncer os cursos afetados por criação, remoção ou renomeação
+// de arquivos de vídeo
+fn record_event(db: &Database, event: &Event, at: Instant, debouncer: &mut RescanDebouncer) -> Result<()> {
+    if !matches!(event.kind, EventKind::Create(_) | EventKind::Remove(_) | EventKind::Modify(ModifyKind::Name(_))) {
+        return Ok(());
+    }
+
+    let scanner = FileSystemScanner::new(db);
+    let video_paths: Vec<&PathBuf> = event.paths.iter().filter(|path| scanner.is_video_file(path)).collect();
+    if video_paths.is_empty() {
+        return Ok(());
+    }
+
+    let courses = db.get_all_courses()?;
+    for path in video_paths {
+        match course_for_path(&courses, path) {
+            Some(course) => {
+                log::debug!("📂 Mudança em {} (curso {})", path.display(), course.name);
+                debouncer.record(&course.id, at);
+            }
+            None => log::debug!("📂 Mudança fora de cursos cadastrados: {}", path.display()),
+        }
+    }
+    Ok(())
+}
+
+// Curso que contém o caminho; com cursos aninhados, vence o mais específico
+fn course_for_path<'c>(courses: &'c [Course], path: &Path) -> Option<&'c Course> {
+    courses
+        .iter()
+        .filter(|course| path.starts_with(&course.path))
+        .max_by_key(|course| course.path.len())
+}
+
+// Reescaneia os cursos e retorna os ids dos que foram atualizados
+fn rescan_courses(db: &Database, course_ids: &[String]) -> Vec<String> {
+    let scanner = FileSystemScanner::new(db);
+    let mut changed = Vec::new();
+
+    for course_id in course_ids {
+        match scanner.rescan_course(course_id, false) {
+            Ok(course) => {
+                log::info!("🔄 Curso atualizado após mudança nas pastas: {}", course.name);
+                changed.push(course.id);
+            }
+            Err(e) => log::warn!("⚠️ Erro ao reescanear curso {}: {}", course_id, e),
+        }
+    }
+    changed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use notify::event::{CreateKind, ModifyKind, RenameMode};
+    use tempfile::TempDir;
+    use std::fs;
+
+    #[test]
+    fn test_burst_of_events_triggers_single_rescan() {
+        let temp_dir = TempDir::new().unwrap();
+        let library = temp_dir.path().join("cursos");
+        let course_dir = library.join("Curso");
+        fs::create_dir_all(&course_dir).unwrap();
+        fs::write(course_dir.join("aula1.mp4"), "fake video content").unwrap();
+
+        let db = Database::new(&temp_dir.path().join("test.db")).unwrap();
+        let courses = FileSystemScanner::new(&db).scan_directory(&library).unwrap();
+        let course_id = courses[0].id.clone();
+
+        // Cópia de vários arquivos: eventos em sequência, inclusive de arquivos que não são vídeo
+        let start = Instant::now();
+        let mut debouncer = RescanDebouncer::new(Duration::from_secs(2));
+        for (index, name) in ["aula2.mp4", "aula3.mp4", "aula3.srt", "aula4.mkv"].iter().enumerate() {
+            let path = course_dir.join(name);
+            fs::write(&path, "fake video content").unwrap();
+            let event = Event::new(EventKind::Create(CreateKind::File)).add_path(path);
+            record_event(&db, &event, start + Duration::from_millis(300 * index as u64), &mut debouncer).unwrap();
+        }
+        let renamed = Event::new(EventKind::Modify(ModifyKind::Name(RenameMode::Any)))
+            .add_path(course_dir.join("aula4.mkv"));
+        record_event(&db, &renamed, start + Duration::from_millis(1000), &mut debouncer).unwrap();
+
+        // Eventos fora de cursos cadastrados ou que não mudam a lista de arquivos são ignorados
+        let outside = Event::new(EventKind::Create(CreateKind::File)).add_path(library.join("solto.mp4"));
+        record_event(&db, &outside, start, &mut debouncer).unwrap();
+        let modified = Event::new(EventKind::Modify(ModifyKind::Any)).add_path(course_dir.join("aula1.mp4"));
+        record_event(&db, &modified, start, &mut debouncer).unwrap();
+
+        // Ainda dentro da janela de espera do último evento
+        assert!(debouncer.take_ready(start + Duration::from_millis(2500)).is_empty());
+
+        let ready = debouncer.take_ready(start + Duration::from_millis(3000));
+        assert_eq!(ready, vec![course_id.clone()]);
+        assert!(debouncer.take_ready(start + Duration::from_secs(10)).is_empty());
+
+        assert_eq!(rescan_courses(&db, &ready), vec![course_id.clone()]);
+        let modules = db.get_course_modules(&course_id).unwrap();
+        assert_eq!(db.get_module_videos(&modules[0].id).unwrap().len(), 4);
+    }
+}